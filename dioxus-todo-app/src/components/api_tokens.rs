@@ -0,0 +1,116 @@
+use dioxus::prelude::*;
+use crate::models::ApiToken;
+use crate::utils::{caldav_url, create_api_token, delete_api_token, load_api_tokens};
+
+fn last_used(token: &ApiToken) -> String {
+    match token.last_used_at {
+        Some(at) => format!("last used {}", at.format("%Y-%m-%d")),
+        None => "never used".to_string(),
+    }
+}
+
+// Creates and revokes the personal tokens task apps sign in to CalDAV with
+#[component]
+pub fn ApiTokensPanel() -> Element {
+    let mut tokens = use_resource(load_api_tokens);
+    let mut name = use_signal(String::new);
+    // Shown once, right after it is created
+    let mut created = use_signal(|| None::<String>);
+    let mut error = use_signal(|| None::<String>);
+
+    let create = move |_| {
+        let token_name = name().trim().to_string();
+        if token_name.is_empty() {
+            return;
+        }
+        spawn(async move {
+            match create_api_token(&token_name).await {
+                Ok(token) => {
+                    error.set(None);
+                    name.set(String::new());
+                    created.set(token.token);
+                    tokens.restart();
+                }
+                Err(e) => error.set(Some(e)),
+            }
+        });
+    };
+
+    let button = "px-4 py-2 rounded text-sm font-medium disabled:opacity-50";
+
+    rsx! {
+        div {
+            class: "space-y-3",
+            p {
+                class: "text-sm text-gray-600",
+                "Server address: "
+                code { class: "font-mono text-gray-800", "{caldav_url()}" }
+                ". Use any user name and a token as the password."
+            }
+            div {
+                class: "flex gap-2",
+                input {
+                    class: "flex-1 border rounded px-2 py-1 text-sm",
+                    placeholder: "Token name, e.g. Phone",
+                    value: "{name}",
+                    oninput: move |evt| name.set(evt.value()),
+                }
+                button {
+                    class: "{button} bg-blue-600 text-white hover:bg-blue-700",
+                    disabled: name().trim().is_empty(),
+                    onclick: create,
+                    "Create token"
+                }
+            }
+            if let Some(token) = created() {
+                div {
+                    class: "text-sm",
+                    p { class: "text-gray-700", "Copy this token now; it will not be shown again." }
+                    input {
+                        class: "w-full border rounded px-2 py-1 font-mono text-gray-700",
+                        readonly: true,
+                        value: token,
+                    }
+                }
+            }
+            match &*tokens.read() {
+                Some(Ok(list)) => rsx! {
+                    ul {
+                        class: "divide-y text-sm",
+                        for token in list.iter().cloned() {
+                            li {
+                                key: "{token.id}",
+                                class: "flex justify-between items-center py-2",
+                                div {
+                                    span { class: "font-medium text-gray-900", "{token.name}" }
+                                    span { class: "ml-2 text-gray-500", "{last_used(&token)}" }
+                                }
+                                button {
+                                    class: "text-red-600 hover:text-red-800 text-sm",
+                                    onclick: move |_| {
+                                        spawn(async move {
+                                            match delete_api_token(token.id).await {
+                                                Ok(()) => tokens.restart(),
+                                                Err(e) => error.set(Some(e)),
+                                            }
+                                        });
+                                    },
+                                    "Revoke"
+                                }
+                            }
+                        }
+                    }
+                },
+                Some(Err(e)) => rsx! {
+                    p { class: "text-red-500 text-sm", "{e}" }
+                },
+                None => rsx! {
+                    p { class: "text-gray-500 text-sm", "Loading..." }
+                },
+            }
+            if let Some(e) = error() {
+                p { class: "text-red-500 text-sm", "{e}" }
+            }
+        }
+    }
+}
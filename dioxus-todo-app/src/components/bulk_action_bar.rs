@@ -0,0 +1,112 @@
+use dioxus::prelude::*;
+use crate::models::{BulkOperation, Priority};
+use crate::utils::load_projects;
+use uuid::Uuid;
+
+#[derive(Props, Clone, PartialEq)]
+pub struct BulkActionBarProps {
+    pub count: usize,
+    pub on_action: EventHandler<BulkOperation>,
+    pub on_clear: EventHandler<()>,
+}
+
+#[component]
+pub fn BulkActionBar(props: BulkActionBarProps) -> Element {
+    let button_class = "px-3 py-1 text-sm font-medium rounded-md bg-white border border-gray-300 text-gray-700 hover:bg-gray-50";
+    let projects = use_resource(load_projects);
+    let mut tag = use_signal(String::new);
+    let mut tag_operation = move |add: bool| {
+        let value = tag().trim().trim_start_matches('#').to_string();
+        if value.is_empty() {
+            return;
+        }
+        props.on_action.call(if add { BulkOperation::AddTag { tag: value } } else { BulkOperation::RemoveTag { tag: value } });
+        tag.set(String::new());
+    };
+
+    rsx! {
+        div {
+            class: "flex flex-wrap items-center gap-2 p-3 mb-4 bg-blue-50 border border-blue-200 rounded-lg",
+            span {
+                class: "text-sm font-medium text-blue-800 mr-2",
+                "{props.count} selected"
+            }
+            button {
+                class: button_class,
+                onclick: move |_| props.on_action.call(BulkOperation::Complete),
+                "Complete"
+            }
+            button {
+                class: button_class,
+                onclick: move |_| props.on_action.call(BulkOperation::Uncomplete),
+                "Mark active"
+            }
+            select {
+                class: "px-2 py-1 text-sm border border-gray-300 rounded-md bg-white",
+                value: "",
+                onchange: move |evt: FormEvent| {
+                    let priority = match evt.value().as_str() {
+                        "High" => Priority::High,
+                        "Medium" => Priority::Medium,
+                        "Low" => Priority::Low,
+                        _ => return,
+                    };
+                    props.on_action.call(BulkOperation::SetPriority { priority });
+                },
+                option { value: "", "Set priority…" }
+                option { value: "Low", "🟢 Low" }
+                option { value: "Medium", "🟡 Medium" }
+                option { value: "High", "🔴 High" }
+            }
+            select {
+                class: "px-2 py-1 text-sm border border-gray-300 rounded-md bg-white",
+                value: "",
+                onchange: move |evt: FormEvent| {
+                    let project_id = match evt.value().as_str() {
+                        "" => return,
+                        "none" => None,
+                        id => match Uuid::parse_str(id) {
+                            Ok(id) => Some(id),
+                            Err(_) => return,
+                        },
+                    };
+                    props.on_action.call(BulkOperation::MoveToProject { project_id });
+                },
+                option { value: "", "Move to project…" }
+                option { value: "none", "No project" }
+                if let Some(Ok(list)) = projects.read().as_ref() {
+                    for project in list.clone() {
+                        option { key: "{project.id}", value: "{project.id}", "{project.name}" }
+                    }
+                }
+            }
+            input {
+                r#type: "text",
+                class: "w-28 px-2 py-1 text-sm border border-gray-300 rounded-md",
+                placeholder: "tag",
+                value: "{tag}",
+                oninput: move |evt: FormEvent| tag.set(evt.value())
+            }
+            button {
+                class: button_class,
+                onclick: move |_| tag_operation(true),
+                "Add tag"
+            }
+            button {
+                class: button_class,
+                onclick: move |_| tag_operation(false),
+                "Remove tag"
+            }
+            button {
+                class: "px-3 py-1 text-sm font-medium rounded-md bg-red-600 text-white hover:bg-red-700",
+                onclick: move |_| props.on_action.call(BulkOperation::Delete),
+                "Delete"
+            }
+            button {
+                class: "ml-auto text-sm text-gray-600 hover:text-gray-800",
+                onclick: move |_| props.on_clear.call(()),
+                "Clear selection"
+            }
+        }
+    }
+}
@@ -0,0 +1,79 @@
+use dioxus::prelude::*;
+use crate::utils::{calendar_feed_url, delete_calendar_feed, load_calendar_feed, reset_calendar_feed};
+
+// Shows the private calendar subscription URL and turns the feed on, off or to a new URL
+#[component]
+pub fn CalendarFeedPanel() -> Element {
+    let mut feed = use_resource(load_calendar_feed);
+    let mut error = use_signal(|| None::<String>);
+
+    let reset = move |_| {
+        spawn(async move {
+            match reset_calendar_feed().await {
+                Ok(_) => {
+                    error.set(None);
+                    feed.restart();
+                }
+                Err(e) => error.set(Some(e)),
+            }
+        });
+    };
+
+    let turn_off = move |_| {
+        spawn(async move {
+            match delete_calendar_feed().await {
+                Ok(()) => {
+                    error.set(None);
+                    feed.restart();
+                }
+                Err(e) => error.set(Some(e)),
+            }
+        });
+    };
+
+    let button = "px-4 py-2 rounded text-sm font-medium";
+
+    rsx! {
+        div {
+            class: "space-y-3",
+            match &*feed.read() {
+                Some(Ok(Some(current))) => rsx! {
+                    input {
+                        class: "w-full border rounded px-2 py-1 text-sm font-mono text-gray-700",
+                        readonly: true,
+                        value: calendar_feed_url(current),
+                    }
+                    div {
+                        class: "flex gap-2",
+                        button {
+                            class: "{button} bg-gray-100 text-gray-800 hover:bg-gray-200",
+                            onclick: reset,
+                            "New link"
+                        }
+                        button {
+                            class: "{button} text-red-600 hover:bg-red-50",
+                            onclick: turn_off,
+                            "Turn off"
+                        }
+                    }
+                },
+                Some(Ok(None)) => rsx! {
+                    button {
+                        class: "{button} bg-blue-600 text-white hover:bg-blue-700",
+                        onclick: reset,
+                        "Create subscription link"
+                    }
+                },
+                Some(Err(e)) => rsx! {
+                    p { class: "text-red-500 text-sm", "{e}" }
+                },
+                None => rsx! {
+                    p { class: "text-gray-500 text-sm", "Loading..." }
+                },
+            }
+            if let Some(e) = error() {
+                p { class: "text-red-500 text-sm", "{e}" }
+            }
+        }
+    }
+}
@@ -0,0 +1,83 @@
+use dioxus::prelude::*;
+use crate::models::EditConflict;
+
+#[derive(Props, Clone, PartialEq)]
+pub struct ConflictDialogProps {
+    pub conflict: EditConflict,
+    pub on_keep_mine: EventHandler<EditConflict>,
+    pub on_use_server: EventHandler<()>,
+    pub on_merge: EventHandler<EditConflict>,
+}
+
+#[component]
+pub fn ConflictDialog(props: ConflictDialogProps) -> Element {
+    let mine = props.conflict.form.clone();
+    let server = props.conflict.server.clone();
+    let server_description = server.description.clone().unwrap_or_default();
+    let rows = [
+        ("Title", mine.title.clone(), server.title.clone()),
+        ("Description", mine.description.clone(), server_description),
+        ("Priority", mine.priority.to_string(), server.priority.to_string()),
+    ];
+
+    rsx! {
+        div {
+            class: "fixed inset-0 bg-black bg-opacity-40 flex items-center justify-center p-4 z-50",
+            div {
+                class: "bg-white rounded-lg shadow-xl max-w-xl w-full p-6",
+                h2 {
+                    class: "text-xl font-semibold text-gray-900 mb-2",
+                    "This todo was changed elsewhere"
+                }
+                p {
+                    class: "text-sm text-gray-600 mb-4",
+                    "Someone saved a newer version while you were editing. Choose which changes to keep."
+                }
+                table {
+                    class: "w-full text-sm mb-6",
+                    thead {
+                        tr {
+                            th { class: "text-left text-gray-500 pb-2", "" }
+                            th { class: "text-left text-gray-500 pb-2", "Your version" }
+                            th { class: "text-left text-gray-500 pb-2", "Server version" }
+                        }
+                    }
+                    tbody {
+                        for (label, yours, theirs) in rows {
+                            tr {
+                                class: if yours != theirs { "bg-yellow-50" } else { "" },
+                                td { class: "py-1 pr-2 font-medium text-gray-700", "{label}" }
+                                td { class: "py-1 pr-2 text-gray-900", "{yours}" }
+                                td { class: "py-1 text-gray-900", "{theirs}" }
+                            }
+                        }
+                    }
+                }
+                div {
+                    class: "flex justify-end space-x-3",
+                    button {
+                        class: "px-4 py-2 text-sm font-medium text-gray-700 bg-white border border-gray-300 rounded-md hover:bg-gray-50",
+                        onclick: move |_| props.on_use_server.call(()),
+                        "Use server version"
+                    }
+                    button {
+                        class: "px-4 py-2 text-sm font-medium text-gray-700 bg-white border border-gray-300 rounded-md hover:bg-gray-50",
+                        onclick: {
+                            let conflict = props.conflict.clone();
+                            move |_| props.on_merge.call(conflict.clone())
+                        },
+                        "Merge manually"
+                    }
+                    button {
+                        class: "px-4 py-2 text-sm font-medium text-white bg-blue-600 rounded-md hover:bg-blue-700",
+                        onclick: {
+                            let conflict = props.conflict.clone();
+                            move |_| props.on_keep_mine.call(conflict.clone())
+                        },
+                        "Keep mine"
+                    }
+                }
+            }
+        }
+    }
+}
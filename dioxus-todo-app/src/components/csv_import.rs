@@ -0,0 +1,149 @@
+use dioxus::prelude::*;
+use std::collections::BTreeMap;
+use crate::models::CsvImportReport;
+use crate::utils::{import_csv, load_custom_fields};
+
+const TARGETS: &[&str] = &["title", "description", "priority", "completed"];
+
+// Column names from the first line, for the mapping form
+fn first_line_columns(csv: &str) -> Vec<String> {
+    csv.trim_start_matches('\u{feff}')
+        .lines()
+        .next()
+        .unwrap_or_default()
+        .split(',')
+        .map(|name| name.trim().trim_matches('"').to_string())
+        .collect()
+}
+
+#[component]
+pub fn CsvImportPanel() -> Element {
+    let fields = use_resource(load_custom_fields);
+    let mut csv = use_signal(|| None::<String>);
+    let mut columns = use_signal(Vec::<String>::new);
+    let mut mapping = use_signal(BTreeMap::<String, String>::new);
+    let mut report = use_signal(|| None::<CsvImportReport>);
+    let mut error = use_signal(|| None::<String>);
+
+    let run_import = move |dry_run: bool| {
+        let Some(contents) = csv() else { return };
+        spawn(async move {
+            match import_csv(contents, &mapping(), dry_run).await {
+                Ok(result) => {
+                    error.set(None);
+                    // The first preview fills the form with the mapping the server guessed.
+                    if mapping.read().is_empty() {
+                        mapping.set(result.mapping.clone());
+                    }
+                    report.set(Some(result));
+                }
+                Err(e) => error.set(Some(e)),
+            }
+        });
+    };
+
+    let handle_file = move |evt: Event<FormData>| {
+        report.set(None);
+        error.set(None);
+        mapping.set(BTreeMap::new());
+        spawn(async move {
+            let Some(files) = evt.files() else { return };
+            let Some(name) = files.files().into_iter().next() else { return };
+            match files.read_file_to_string(&name).await {
+                Some(contents) => {
+                    columns.set(first_line_columns(&contents));
+                    csv.set(Some(contents));
+                    run_import(true);
+                }
+                None => error.set(Some(format!("Could not read {}", name))),
+            }
+        });
+    };
+
+    let mut targets: Vec<String> = TARGETS.iter().map(|t| t.to_string()).collect();
+    if let Some(Ok(fields)) = &*fields.read() {
+        targets.extend(fields.iter().map(|field| format!("cf.{}", field.key)));
+    }
+    let button = "px-4 py-2 rounded text-sm font-medium disabled:opacity-50";
+
+    rsx! {
+        div {
+            class: "space-y-4",
+            input {
+                r#type: "file",
+                accept: ".csv,text/csv",
+                onchange: handle_file,
+            }
+            if csv.read().is_some() {
+                table {
+                    class: "text-sm text-gray-700",
+                    for column in columns() {
+                        tr {
+                            td { class: "pr-4 py-1", "{column}" }
+                            td {
+                                select {
+                                    class: "border rounded px-2 py-1",
+                                    value: mapping.read().get(&column).cloned().unwrap_or_default(),
+                                    onchange: {
+                                        let column = column.clone();
+                                        move |evt: Event<FormData>| {
+                                            let target = evt.value();
+                                            let mut mapping = mapping.write();
+                                            if target.is_empty() {
+                                                mapping.remove(&column);
+                                            } else {
+                                                mapping.insert(column.clone(), target);
+                                            }
+                                        }
+                                    },
+                                    option { value: "", "(ignore)" }
+                                    for target in targets.clone() {
+                                        option { value: "{target}", "{target}" }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+                div {
+                    class: "flex gap-2",
+                    button {
+                        class: "{button} bg-gray-100 text-gray-800 hover:bg-gray-200",
+                        onclick: move |_| run_import(true),
+                        "Preview"
+                    }
+                    button {
+                        class: "{button} bg-blue-600 text-white hover:bg-blue-700",
+                        disabled: mapping.read().is_empty(),
+                        onclick: move |_| run_import(false),
+                        "Import"
+                    }
+                }
+            }
+            if let Some(e) = error() {
+                p { class: "text-red-500 text-sm", "{e}" }
+            }
+            if let Some(result) = report() {
+                div {
+                    class: "text-sm text-gray-700",
+                    p {
+                        class: "font-medium text-gray-900",
+                        if result.dry_run {
+                            "{result.imported} rows would be imported."
+                        } else {
+                            "Imported {result.imported} rows."
+                        }
+                    }
+                    if !result.errors.is_empty() {
+                        ul {
+                            class: "mt-2 text-red-600",
+                            for row in result.errors.iter() {
+                                li { "Line {row.line}: {row.message}" }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
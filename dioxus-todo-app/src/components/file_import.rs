@@ -0,0 +1,105 @@
+use dioxus::prelude::*;
+use crate::models::TextImportReport;
+use crate::utils::import_file;
+
+#[derive(Props, Clone, PartialEq)]
+pub struct FileImportPanelProps {
+    // Importer path under the API, e.g. `import/todotxt`
+    pub path: &'static str,
+    // File types offered by the file picker
+    pub accept: &'static str,
+    // What errors are numbered by: lines for text files, items for JSON
+    #[props(default = "Line")]
+    pub unit: &'static str,
+}
+
+// Picks a file, previews the import with a dry run and then imports it
+#[component]
+pub fn FileImportPanel(props: FileImportPanelProps) -> Element {
+    let mut contents = use_signal(|| None::<String>);
+    let mut report = use_signal(|| None::<TextImportReport>);
+    let mut error = use_signal(|| None::<String>);
+    let path = props.path;
+
+    let run_import = move |dry_run: bool| {
+        let Some(file) = contents() else { return };
+        spawn(async move {
+            match import_file(path, file, dry_run).await {
+                Ok(result) => {
+                    error.set(None);
+                    report.set(Some(result));
+                }
+                Err(e) => error.set(Some(e)),
+            }
+        });
+    };
+
+    let handle_file = move |evt: Event<FormData>| {
+        report.set(None);
+        error.set(None);
+        spawn(async move {
+            let Some(files) = evt.files() else { return };
+            let Some(name) = files.files().into_iter().next() else { return };
+            match files.read_file_to_string(&name).await {
+                Some(file) => {
+                    contents.set(Some(file));
+                    run_import(true);
+                }
+                None => error.set(Some(format!("Could not read {}", name))),
+            }
+        });
+    };
+
+    let button = "px-4 py-2 rounded text-sm font-medium disabled:opacity-50";
+
+    rsx! {
+        div {
+            class: "space-y-4",
+            input {
+                r#type: "file",
+                accept: props.accept,
+                onchange: handle_file,
+            }
+            if let Some(result) = report() {
+                div {
+                    class: "text-sm text-gray-700",
+                    p {
+                        class: "font-medium text-gray-900",
+                        if result.dry_run {
+                            "{result.imported} todos would be imported."
+                        } else {
+                            "Imported {result.imported} todos."
+                        }
+                    }
+                    if !result.errors.is_empty() {
+                        ul {
+                            class: "mt-2 text-red-600",
+                            for row in result.errors.iter() {
+                                li { "{props.unit} {row.line}: {row.message}" }
+                            }
+                        }
+                    }
+                    if !result.unmapped.is_empty() {
+                        p { class: "mt-2 text-gray-600", "Left out:" }
+                        ul {
+                            class: "list-disc ml-5 text-gray-600",
+                            for left_out in result.unmapped.iter() {
+                                li { "{left_out.count} {left_out.what}" }
+                            }
+                        }
+                    }
+                }
+                if result.dry_run {
+                    button {
+                        class: "{button} bg-blue-600 text-white hover:bg-blue-700",
+                        onclick: move |_| run_import(false),
+                        "Import"
+                    }
+                }
+            }
+            if let Some(e) = error() {
+                p { class: "text-red-500 text-sm", "{e}" }
+            }
+        }
+    }
+}
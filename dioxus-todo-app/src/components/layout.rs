@@ -1,6 +1,10 @@
 use dioxus::prelude::*;
 use dioxus_router::prelude::{Outlet, Link};
-use crate::{Route, models::AuthState, utils::{load_user, clear_user}};
+use crate::{
+    Route,
+    models::{AuthState, TimerState},
+    utils::{load_user, clear_user, format_duration, load_running_timer, stop_timer},
+};
 
 #[component]
 pub fn Layout() -> Element {
@@ -16,6 +20,29 @@ pub fn Layout() -> Element {
         }
     });
 
+    let TimerState(mut timer) = use_context::<TimerState>();
+    let mut now = use_signal(chrono::Utc::now);
+
+    // Pick up a timer started in another tab or before a reload, then tick the display.
+    use_future(move || async move {
+        if let Ok(running) = load_running_timer().await {
+            timer.set(running);
+        }
+        loop {
+            gloo::timers::future::TimeoutFuture::new(1_000).await;
+            now.set(chrono::Utc::now());
+        }
+    });
+
+    let handle_stop_timer = move |_| {
+        spawn(async move {
+            match stop_timer().await {
+                Ok(_) => timer.set(None),
+                Err(e) => log::error!("Failed to stop timer: {}", e),
+            }
+        });
+    };
+
     let handle_logout = move |_| {
         clear_user();
         *auth_state.write() = AuthState::Unknown;
@@ -47,6 +74,24 @@ pub fn Layout() -> Element {
                         div { 
                             class: "hidden md:flex items-center space-x-6",
                             
+                            // Running timer indicator
+                            if let Some(entry) = timer.read().clone() {
+                                div {
+                                    class: "flex items-center space-x-2 px-3 py-1 rounded-full bg-green-50 border border-green-200",
+                                    span { class: "text-green-700 text-sm font-mono", "⏱ {format_duration((*now.read() - entry.started_at).num_seconds())}" }
+                                    Link {
+                                        to: Route::TodoDetail { id: entry.todo_id },
+                                        class: "text-xs text-green-700 underline",
+                                        "View"
+                                    }
+                                    button {
+                                        class: "text-xs text-red-600 hover:text-red-800",
+                                        onclick: handle_stop_timer,
+                                        "Stop"
+                                    }
+                                }
+                            }
+
                             div {
                                 class: "flex items-center space-x-4",
                                 match &*auth_state.read() {
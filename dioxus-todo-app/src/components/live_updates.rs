@@ -0,0 +1,72 @@
+use dioxus::prelude::*;
+use futures_util::StreamExt;
+use gloo::net::websocket::{futures::WebSocket, Message};
+use crate::{models::{Todo, TodoEvent}, utils::live_updates_url};
+
+// Pause before reconnecting after the connection drops
+const RECONNECT_DELAY_MS: u32 = 5_000;
+
+// Calls `on_event` with every change the server pushes for the user's todos, for as long as the
+// component is mounted. Changes missed while reconnecting are reported as a `Reload`.
+pub fn use_todo_events(mut on_event: impl FnMut(TodoEvent) + 'static) {
+    use_hook(move || {
+        spawn(async move {
+            let mut reconnecting = false;
+            loop {
+                let socket = live_updates_url().await.and_then(|url| WebSocket::open(&url).map_err(|e| e.to_string()));
+                match socket {
+                    Ok(mut socket) => {
+                        if reconnecting {
+                            on_event(TodoEvent::Reload);
+                        }
+                        while let Some(Ok(message)) = socket.next().await {
+                            let Message::Text(text) = message else { continue };
+                            match serde_json::from_str(&text) {
+                                Ok(event) => on_event(event),
+                                Err(e) => log::warn!("Ignoring live update {}: {}", text, e),
+                            }
+                        }
+                    }
+                    Err(e) => log::error!("Failed to connect for live updates: {}", e),
+                }
+                reconnecting = true;
+                gloo::timers::future::TimeoutFuture::new(RECONNECT_DELAY_MS).await;
+            }
+        });
+    });
+}
+
+// Applies a pushed change to a list in position order, as the server sends it. Returns false
+// for `Reload`, which only a refetch can handle.
+pub fn apply_todo_event(todos: &mut Vec<Todo>, event: TodoEvent) -> bool {
+    let place = |todos: &mut Vec<Todo>, todo: Todo| {
+        let index = todos.iter().position(|t| t.position > todo.position).unwrap_or(todos.len());
+        todos.insert(index, todo);
+    };
+    match event {
+        TodoEvent::Created { todo } => {
+            if !todos.iter().any(|t| t.id == todo.id) {
+                place(todos, todo);
+            }
+        }
+        TodoEvent::Updated { todo } => {
+            let index = todos.iter().position(|t| t.id == todo.id);
+            match index {
+                // Archived todos leave the list.
+                Some(index) if todo.archived_at.is_some() => {
+                    todos.remove(index);
+                }
+                Some(index) if todos[index].position == todo.position => todos[index] = todo,
+                Some(index) => {
+                    todos.remove(index);
+                    place(todos, todo);
+                }
+                None if todo.archived_at.is_none() => place(todos, todo),
+                None => {}
+            }
+        }
+        TodoEvent::Deleted { id } => todos.retain(|t| t.id != id),
+        TodoEvent::Reload => return false,
+    }
+    true
+}
@@ -1,9 +1,31 @@
+pub mod api_tokens;
 pub mod auth_context;
+pub mod bulk_action_bar;
+pub mod calendar_feed;
+pub mod conflict_dialog;
+pub mod csv_import;
+pub mod file_import;
 pub mod layout;
+pub mod live_updates;
+pub mod quick_add_input;
+pub mod saved_views;
+pub mod todo_dependencies;
 pub mod todo_form;
+pub mod todo_history;
 pub mod todo_item;
 
+pub use api_tokens::*;
 pub use auth_context::*;
+pub use bulk_action_bar::*;
+pub use calendar_feed::*;
+pub use conflict_dialog::*;
+pub use csv_import::*;
+pub use file_import::*;
 pub use layout::*;
+pub use live_updates::*;
+pub use quick_add_input::*;
+pub use saved_views::*;
+pub use todo_dependencies::*;
 pub use todo_form::*;
+pub use todo_history::*;
 pub use todo_item::*; 
\ No newline at end of file
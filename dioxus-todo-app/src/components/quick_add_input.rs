@@ -0,0 +1,97 @@
+use dioxus::prelude::*;
+use crate::models::{ParsedSpan, ParsedTodo};
+use crate::utils::parse_quick_add;
+
+#[derive(Props, Clone, PartialEq)]
+pub struct QuickAddInputProps {
+    pub on_apply: EventHandler<ParsedTodo>,
+}
+
+// Splits `text` into plain and recognized pieces, tagging the latter with their span kind
+fn segments(text: &str, spans: &[ParsedSpan]) -> Vec<(String, Option<String>)> {
+    let mut pieces = vec![];
+    let mut cursor = 0;
+    for span in spans {
+        let (Some(before), Some(inner)) = (text.get(cursor..span.start), text.get(span.start..span.end)) else {
+            break;
+        };
+        if !before.is_empty() {
+            pieces.push((before.to_string(), None));
+        }
+        pieces.push((inner.to_string(), Some(span.kind.clone())));
+        cursor = span.end;
+    }
+    if let Some(rest) = text.get(cursor..).filter(|rest| !rest.is_empty()) {
+        pieces.push((rest.to_string(), None));
+    }
+    pieces
+}
+
+fn highlight_class(kind: &str) -> &'static str {
+    match kind {
+        "date" | "time" => "bg-blue-100 text-blue-800 rounded",
+        "priority" => "bg-red-100 text-red-800 rounded",
+        "tag" => "bg-green-100 text-green-800 rounded",
+        "project" => "bg-purple-100 text-purple-800 rounded",
+        _ => "bg-yellow-100 text-yellow-800 rounded",
+    }
+}
+
+#[component]
+pub fn QuickAddInput(props: QuickAddInputProps) -> Element {
+    let mut text = use_signal(String::new);
+    // Re-parsed on every keystroke; the text is kept with the result so the spans line up with it.
+    let parsed = use_resource(move || async move {
+        let text = text();
+        if text.trim().is_empty() {
+            return None;
+        }
+        parse_quick_add(text.clone()).await.ok().map(|parsed| (text, parsed))
+    });
+
+    let mut apply = move || {
+        if let Some(Some((_, parsed))) = parsed.read().as_ref() {
+            props.on_apply.call(parsed.clone());
+            text.set(String::new());
+        }
+    };
+
+    rsx! {
+        div {
+            class: "pb-4 border-b border-gray-200",
+            div {
+                class: "flex space-x-2",
+                input {
+                    r#type: "text",
+                    class: "flex-1 px-3 py-2 border border-gray-300 rounded-md text-sm",
+                    placeholder: "Quick add, e.g. Pay rent tomorrow 9am !high",
+                    value: "{text}",
+                    oninput: move |evt: FormEvent| text.set(evt.value()),
+                    onkeydown: move |evt: KeyboardEvent| {
+                        if evt.key() == Key::Enter {
+                            apply();
+                        }
+                    }
+                }
+                button {
+                    r#type: "button",
+                    class: "px-3 py-2 text-sm font-medium text-blue-600 border border-blue-200 rounded-md hover:bg-blue-50",
+                    onclick: move |_| apply(),
+                    "Fill form"
+                }
+            }
+            if let Some(Some((source, result))) = parsed.read().as_ref() {
+                p {
+                    class: "mt-2 text-sm text-gray-700",
+                    for (piece, kind) in segments(source, &result.spans) {
+                        if let Some(kind) = kind {
+                            span { class: highlight_class(&kind), title: "{kind}", "{piece}" }
+                        } else {
+                            span { "{piece}" }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
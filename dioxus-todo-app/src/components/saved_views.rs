@@ -0,0 +1,143 @@
+use dioxus::prelude::*;
+use uuid::Uuid;
+use crate::models::SavedView;
+use crate::utils::{create_view, delete_view};
+
+#[derive(Props, Clone, PartialEq)]
+pub struct SavedViewsSidebarProps {
+    pub views: Vec<SavedView>,
+    pub active: Option<Uuid>,
+    pub on_select: EventHandler<Option<Uuid>>,
+    // Called after a view is created or deleted
+    pub on_change: EventHandler<()>,
+}
+
+#[component]
+pub fn SavedViewsSidebar(props: SavedViewsSidebarProps) -> Element {
+    let mut name = use_signal(String::new);
+    let mut query = use_signal(String::new);
+    let mut error = use_signal(|| None::<String>);
+    let mut adding = use_signal(|| false);
+
+    let handle_save = move |_| {
+        let (view_name, view_query) = (name(), query());
+        if view_name.trim().is_empty() || view_query.trim().is_empty() {
+            error.set(Some("Name and filter are required".to_string()));
+            return;
+        }
+        spawn(async move {
+            match create_view(&view_name, &view_query).await {
+                Ok(view) => {
+                    name.set(String::new());
+                    query.set(String::new());
+                    error.set(None);
+                    adding.set(false);
+                    props.on_change.call(());
+                    props.on_select.call(Some(view.id));
+                }
+                Err(e) => error.set(Some(e)),
+            }
+        });
+    };
+
+    let item_class = |active: bool| {
+        if active {
+            "flex justify-between items-center w-full px-3 py-2 rounded-md text-sm bg-blue-50 text-blue-700 font-medium"
+        } else {
+            "flex justify-between items-center w-full px-3 py-2 rounded-md text-sm text-gray-700 hover:bg-gray-100"
+        }
+    };
+
+    rsx! {
+        aside {
+            class: "md:w-56 shrink-0 bg-white p-4 rounded-lg shadow-md self-start",
+            h2 { class: "text-sm font-semibold text-gray-500 uppercase mb-2", "Views" }
+            ul {
+                class: "space-y-1",
+                li {
+                    button {
+                        class: item_class(props.active.is_none()),
+                        onclick: move |_| props.on_select.call(None),
+                        "All todos"
+                    }
+                }
+                for saved in props.views.clone() {
+                    li {
+                        key: "{saved.id}",
+                        class: "group flex items-center",
+                        button {
+                            class: item_class(props.active == Some(saved.id)),
+                            title: "{saved.query}",
+                            onclick: move |_| props.on_select.call(Some(saved.id)),
+                            span { class: "truncate", "{saved.name}" }
+                            span { class: "ml-2 text-xs text-gray-500", "{saved.count}" }
+                        }
+                        button {
+                            class: "ml-1 hidden group-hover:block text-xs text-gray-400 hover:text-red-600",
+                            title: "Delete view",
+                            onclick: move |_| {
+                                let was_active = props.active == Some(saved.id);
+                                spawn(async move {
+                                    match delete_view(saved.id).await {
+                                        Ok(()) => {
+                                            if was_active {
+                                                props.on_select.call(None);
+                                            }
+                                            props.on_change.call(());
+                                        }
+                                        Err(e) => log::error!("{}", e),
+                                    }
+                                });
+                            },
+                            "✕"
+                        }
+                    }
+                }
+            }
+            if *adding.read() {
+                div {
+                    class: "mt-4 space-y-2",
+                    input {
+                        r#type: "text",
+                        class: "w-full px-2 py-1 border border-gray-300 rounded-md text-sm",
+                        placeholder: "Name",
+                        value: "{name}",
+                        oninput: move |evt: FormEvent| name.set(evt.value())
+                    }
+                    input {
+                        r#type: "text",
+                        class: "w-full px-2 py-1 border border-gray-300 rounded-md text-sm font-mono",
+                        placeholder: "priority:high AND created<7d",
+                        value: "{query}",
+                        oninput: move |evt: FormEvent| query.set(evt.value())
+                    }
+                    if let Some(message) = error.read().clone() {
+                        p { class: "text-xs text-red-600", "{message}" }
+                    }
+                    div {
+                        class: "flex justify-end space-x-2",
+                        button {
+                            class: "text-xs text-gray-500 hover:text-gray-700",
+                            onclick: move |_| {
+                                error.set(None);
+                                adding.set(false);
+                            },
+                            "Cancel"
+                        }
+                        button {
+                            class: "text-xs font-medium text-blue-600 hover:text-blue-800",
+                            onclick: handle_save,
+                            "Save"
+                        }
+                    }
+                }
+            } else {
+                button {
+                    class: "mt-4 text-sm text-blue-600 hover:text-blue-800",
+                    onclick: move |_| adding.set(true),
+                    "＋ Save a view"
+                }
+            }
+        }
+    }
+}
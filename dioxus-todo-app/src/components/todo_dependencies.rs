@@ -0,0 +1,121 @@
+use dioxus::prelude::*;
+use dioxus_router::prelude::Link;
+use uuid::Uuid;
+use crate::{
+    Route,
+    models::Todo,
+    utils::{add_blocker, load_dependencies, load_todos, remove_blocker},
+};
+
+#[derive(Props, Clone, PartialEq)]
+pub struct TodoDependenciesPanelProps {
+    pub todo_id: Uuid,
+    pub on_change: EventHandler<()>,
+}
+
+#[component]
+fn LinkedTodo(todo: Todo, on_remove: Option<EventHandler<Uuid>>) -> Element {
+    rsx! {
+        li {
+            class: "flex justify-between items-center text-sm",
+            Link {
+                to: Route::TodoDetail { id: todo.id },
+                class: if todo.completed { "line-through text-gray-500" } else { "text-gray-800 hover:text-blue-600" },
+                "{todo.title}"
+            }
+            if let Some(on_remove) = on_remove {
+                button {
+                    class: "text-xs text-gray-400 hover:text-red-600",
+                    onclick: move |_| on_remove.call(todo.id),
+                    "Unlink"
+                }
+            }
+        }
+    }
+}
+
+#[component]
+pub fn TodoDependenciesPanel(props: TodoDependenciesPanelProps) -> Element {
+    let todo_id = props.todo_id;
+    let mut dependencies = use_resource(move || load_dependencies(todo_id));
+    let candidates = use_resource(load_todos);
+    let mut error = use_signal(|| None::<String>);
+
+    let mut refresh = move || {
+        dependencies.restart();
+        props.on_change.call(());
+    };
+
+    let handle_add = move |evt: FormEvent| {
+        let Ok(blocker_id) = Uuid::parse_str(&evt.value()) else { return };
+        spawn(async move {
+            match add_blocker(todo_id, blocker_id).await {
+                Ok(()) => error.set(None),
+                Err(e) => error.set(Some(e)),
+            }
+            refresh();
+        });
+    };
+
+    let handle_remove = move |blocker_id: Uuid| {
+        spawn(async move {
+            if let Err(e) = remove_blocker(todo_id, blocker_id).await {
+                error.set(Some(e));
+            }
+            refresh();
+        });
+    };
+
+    rsx! {
+        div {
+            class: "mt-6 grid sm:grid-cols-2 gap-6",
+            match &*dependencies.read() {
+                Some(Ok(deps)) => {
+                    let linked: Vec<Uuid> = deps.blockers.iter().chain(deps.dependents.iter()).map(|t| t.id).collect();
+                    rsx! {
+                        div {
+                            h2 { class: "text-lg font-semibold text-gray-900 mb-2", "Blocked by" }
+                            if deps.blockers.is_empty() {
+                                p { class: "text-sm text-gray-500", "Nothing is blocking this todo." }
+                            }
+                            ul {
+                                class: "space-y-1",
+                                for blocker in deps.blockers.iter().cloned() {
+                                    LinkedTodo { key: "{blocker.id}", todo: blocker, on_remove: handle_remove }
+                                }
+                            }
+                            if let Some(Ok(all)) = &*candidates.read() {
+                                select {
+                                    class: "mt-2 w-full px-2 py-1 text-sm border border-gray-300 rounded-md",
+                                    value: "",
+                                    onchange: handle_add,
+                                    option { value: "", "Add a blocker…" }
+                                    for candidate in all.iter().filter(|t| t.id != todo_id && !linked.contains(&t.id)) {
+                                        option { value: "{candidate.id}", "{candidate.title}" }
+                                    }
+                                }
+                            }
+                            if let Some(message) = error.read().clone() {
+                                p { class: "mt-1 text-sm text-red-600", "{message}" }
+                            }
+                        }
+                        div {
+                            h2 { class: "text-lg font-semibold text-gray-900 mb-2", "Blocking" }
+                            if deps.dependents.is_empty() {
+                                p { class: "text-sm text-gray-500", "No todos are waiting on this one." }
+                            }
+                            ul {
+                                class: "space-y-1",
+                                for dependent in deps.dependents.iter().cloned() {
+                                    LinkedTodo { key: "{dependent.id}", todo: dependent }
+                                }
+                            }
+                        }
+                    }
+                },
+                Some(Err(e)) => rsx! { p { class: "text-red-500", "Error loading dependencies: {e}" } },
+                None => rsx! { p { class: "text-gray-500", "Loading..." } },
+            }
+        }
+    }
+}
@@ -1,6 +1,9 @@
 use dioxus::prelude::*;
-use crate::models::{Priority, TodoForm};
-use crate::utils::validate_todo_title;
+use crate::components::QuickAddInput;
+use crate::models::{CustomField, CustomFieldType, ParsedTodo, Priority, TodoForm};
+use crate::utils::{load_custom_fields, load_projects, load_templates, render_template, validate_todo_title};
+use uuid::Uuid;
+use serde_json::Value;
 
 #[derive(Props, Clone, PartialEq)]
 pub struct TodoFormProps {
@@ -12,7 +15,8 @@ pub struct TodoFormProps {
 
 #[component]
 pub fn TodoForm(props: TodoFormProps) -> Element {
-    let mut form = use_signal(TodoForm::default);
+    let initial_form = props.initial_form.clone();
+    let mut form = use_signal(move || initial_form.unwrap_or_default());
     let mut title_error = use_signal(|| None::<String>);
     let mut is_submitting = use_signal(|| false);
     let api_client = reqwest::Client::new();
@@ -54,6 +58,101 @@ pub fn TodoForm(props: TodoFormProps) -> Element {
         }
     };
 
+    let handle_estimate_change = {
+        let mut form = form.clone();
+        move |evt: FormEvent| {
+            let minutes = evt.value().trim().parse::<i64>().ok().filter(|m| *m > 0);
+            form.with_mut(|f| f.estimated_minutes = minutes);
+        }
+    };
+
+    let custom_fields = use_resource(load_custom_fields);
+
+    // Values of fields limited to the old project don't carry over to the new one.
+    let handle_project_change = {
+        let mut form = form.clone();
+        move |evt: FormEvent| {
+            let project_id = Uuid::parse_str(&evt.value()).ok();
+            let fields = custom_fields.read().clone().and_then(Result::ok).unwrap_or_default();
+            form.with_mut(|f| {
+                f.project_id = project_id;
+                f.custom_fields.retain(|key, _| {
+                    fields.iter().find(|field| &field.key == key).map_or(true, |field| applies_to(field, project_id))
+                });
+            });
+        }
+    };
+
+    // Tags are typed as a comma-separated list.
+    let handle_tags_change = {
+        let mut form = form.clone();
+        move |evt: FormEvent| {
+            let tags = evt.value()
+                .split(',')
+                .map(|tag| tag.trim().trim_start_matches('#').to_string())
+                .filter(|tag| !tag.is_empty())
+                .collect();
+            form.with_mut(|f| f.tags = tags);
+        }
+    };
+
+    let projects = use_resource(load_projects);
+    let templates = use_resource(load_templates);
+    let mut template_name = use_signal(String::new);
+
+    // Prefills the form from a template, filling `{{name}}` from the name box when it is set.
+    let handle_template_change = move |evt: FormEvent| {
+        let Ok(id) = Uuid::parse_str(&evt.value()) else { return };
+        let name = Some(template_name()).filter(|n| !n.trim().is_empty());
+        spawn(async move {
+            match render_template(id, name).await {
+                Ok(todo) => form.with_mut(|f| {
+                    f.title = todo.title;
+                    f.description = todo.description.unwrap_or_default();
+                    f.priority = todo.priority;
+                    f.estimated_minutes = todo.estimated_minutes;
+                    f.custom_fields = todo.custom_fields;
+                    f.project_id = todo.project_id;
+                    f.tags = todo.tags;
+                }),
+                Err(e) => log::error!("{}", e),
+            }
+        });
+    };
+
+    // Custom field inputs report raw strings; clearing one sends `null` so the server unsets it.
+    let set_custom_field = move |field: CustomField, raw: String| {
+        let value = match field.field_type {
+            _ if raw.is_empty() => Value::Null,
+            CustomFieldType::Number => raw.parse::<f64>().map(Value::from).unwrap_or(Value::Null),
+            CustomFieldType::Checkbox => Value::Bool(raw == "true"),
+            CustomFieldType::Text | CustomFieldType::Date | CustomFieldType::Select => Value::String(raw),
+        };
+        form.with_mut(|f| {
+            f.custom_fields.insert(field.key, value);
+        });
+    };
+
+    // Quick-add only fills the fields a todo can store; the rest stays in the preview highlight.
+    // `+project` picks an existing project by name; the server creates new ones on `?parse=true`.
+    let handle_quick_add = move |parsed: ParsedTodo| {
+        let project_id = parsed.project.as_ref().and_then(|name| {
+            projects.read().clone().and_then(Result::ok).unwrap_or_default()
+                .into_iter()
+                .find(|project| project.name.eq_ignore_ascii_case(name))
+                .map(|project| project.id)
+        });
+        form.with_mut(|f| {
+            f.title = parsed.todo.title;
+            f.priority = parsed.todo.priority;
+            f.tags = parsed.todo.tags;
+            if project_id.is_some() {
+                f.project_id = project_id;
+            }
+        });
+        title_error.set(validate_todo_title(&form.read().title).err());
+    };
+
     let mut validate_form = move || -> bool {
         let mut valid = true;
         let form_data = form.read();
@@ -96,10 +195,42 @@ pub fn TodoForm(props: TodoFormProps) -> Element {
                 }
             },
             
+            // Quick add sits outside the form so Enter applies it instead of submitting
+            if props.initial_form.is_none() {
+                div {
+                    class: "mb-4",
+                    QuickAddInput { on_apply: handle_quick_add }
+                }
+            }
+            
             form { 
                 class: "space-y-4",
                 onsubmit: submit_handler,
                 
+                // New from template
+                if props.initial_form.is_none() {
+                    if let Some(Ok(list)) = templates.read().as_ref().filter(|t| t.as_ref().is_ok_and(|l| !l.is_empty())) {
+                        div {
+                            class: "flex space-x-2 pb-4 border-b border-gray-200",
+                            select {
+                                class: "flex-1 px-3 py-2 border border-gray-300 rounded-md text-sm",
+                                onchange: handle_template_change,
+                                option { value: "", "New from template…" }
+                                for template in list.clone() {
+                                    option { key: "{template.id}", value: "{template.id}", "{template.name}" }
+                                }
+                            }
+                            input {
+                                r#type: "text",
+                                class: "flex-1 px-3 py-2 border border-gray-300 rounded-md text-sm",
+                                placeholder: "Name (optional)",
+                                value: "{template_name}",
+                                oninput: move |evt: FormEvent| template_name.set(evt.value())
+                            }
+                        }
+                    }
+                }
+                
                 // Title field
                 div {
                     label { 
@@ -163,6 +294,78 @@ pub fn TodoForm(props: TodoFormProps) -> Element {
                     }
                 },
                 
+                // Project field
+                div {
+                    label { 
+                        class: "block text-sm font-medium text-gray-700 mb-1",
+                        r#for: "project",
+                        "Project"
+                    },
+                    select { 
+                        id: "project",
+                        class: "w-full px-3 py-2 border border-gray-300 rounded-md focus:outline-none focus:ring-2 focus:ring-blue-500 focus:border-transparent",
+                        value: form.read().project_id.map(|id| id.to_string()).unwrap_or_default(),
+                        onchange: handle_project_change,
+                        
+                        option { value: "", "No project" }
+                        if let Some(Ok(list)) = projects.read().as_ref() {
+                            for project in list.clone() {
+                                option { key: "{project.id}", value: "{project.id}", "{project.name}" }
+                            }
+                        }
+                    }
+                },
+                
+                // Tags field
+                div {
+                    label { 
+                        class: "block text-sm font-medium text-gray-700 mb-1",
+                        r#for: "tags",
+                        "Tags"
+                    },
+                    input { 
+                        r#type: "text",
+                        id: "tags",
+                        class: "w-full px-3 py-2 border border-gray-300 rounded-md focus:outline-none focus:ring-2 focus:ring-blue-500 focus:border-transparent",
+                        placeholder: "errand, home",
+                        value: form.read().tags.join(", "),
+                        onchange: handle_tags_change
+                    }
+                },
+                
+                // Estimate field
+                div {
+                    label { 
+                        class: "block text-sm font-medium text-gray-700 mb-1",
+                        r#for: "estimate",
+                        "Estimate (minutes)"
+                    },
+                    input { 
+                        r#type: "number",
+                        id: "estimate",
+                        min: "1",
+                        class: "w-full px-3 py-2 border border-gray-300 rounded-md focus:outline-none focus:ring-2 focus:ring-blue-500 focus:border-transparent",
+                        placeholder: "Optional",
+                        value: form.read().estimated_minutes.map(|m| m.to_string()).unwrap_or_default(),
+                        oninput: handle_estimate_change
+                    }
+                },
+                
+                // Custom fields
+                if let Some(Ok(fields)) = custom_fields.read().as_ref() {
+                    for field in fields.iter().filter(|field| applies_to(field, form.read().project_id)).cloned() {
+                        div {
+                            key: "{field.id}",
+                            label {
+                                class: "block text-sm font-medium text-gray-700 mb-1",
+                                r#for: "cf-{field.key}",
+                                "{field.name}"
+                            },
+                            {custom_field_input(field.clone(), form.read().custom_fields.get(&field.key).cloned(), set_custom_field)}
+                        }
+                    }
+                }
+                
                 // Action buttons
                 div { 
                     class: "flex items-center justify-end space-x-3 pt-4",
@@ -189,4 +392,63 @@ pub fn TodoForm(props: TodoFormProps) -> Element {
             }
         }
     }
-} 
\ No newline at end of file
+} 
+
+fn applies_to(field: &CustomField, project_id: Option<Uuid>) -> bool {
+    field.project_id.is_none() || field.project_id == project_id
+}
+
+fn custom_field_input(
+    field: CustomField,
+    value: Option<Value>,
+    mut on_change: impl FnMut(CustomField, String) + 'static,
+) -> Element {
+    let class = "w-full px-3 py-2 border border-gray-300 rounded-md focus:outline-none focus:ring-2 focus:ring-blue-500 focus:border-transparent";
+    let id = format!("cf-{}", field.key);
+    let options = field.options.clone();
+    let text = match &value {
+        Some(Value::String(s)) => s.clone(),
+        Some(Value::Number(n)) => n.to_string(),
+        _ => String::new(),
+    };
+
+    match field.field_type {
+        CustomFieldType::Checkbox => rsx! {
+            input {
+                r#type: "checkbox",
+                id: "{id}",
+                class: "h-4 w-4 text-blue-600 border-gray-300 rounded",
+                checked: value == Some(Value::Bool(true)),
+                onchange: move |evt: FormEvent| on_change(field.clone(), evt.checked().to_string())
+            }
+        },
+        CustomFieldType::Select => rsx! {
+            select {
+                id: "{id}",
+                class: class,
+                value: "{text}",
+                onchange: move |evt: FormEvent| on_change(field.clone(), evt.value()),
+                option { value: "", "—" }
+                for choice in options {
+                    option { value: "{choice}", "{choice}" }
+                }
+            }
+        },
+        CustomFieldType::Text | CustomFieldType::Number | CustomFieldType::Date => {
+            let input_type = match field.field_type {
+                CustomFieldType::Number => "number",
+                CustomFieldType::Date => "date",
+                _ => "text",
+            };
+            rsx! {
+                input {
+                    r#type: input_type,
+                    id: "{id}",
+                    class: class,
+                    value: "{text}",
+                    oninput: move |evt: FormEvent| on_change(field.clone(), evt.value())
+                }
+            }
+        }
+    }
+}
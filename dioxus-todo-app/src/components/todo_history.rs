@@ -0,0 +1,114 @@
+use dioxus::prelude::*;
+use uuid::Uuid;
+use crate::models::{FieldChange, Todo, TodoRevision};
+use crate::utils::API_URL;
+
+#[derive(Props, Clone, PartialEq)]
+pub struct TodoHistoryProps {
+    pub todo_id: Uuid,
+    pub on_revert: EventHandler<Todo>,
+}
+
+async fn fetch_history(todo_id: Uuid) -> Result<Vec<TodoRevision>, reqwest::Error> {
+    let url = format!("{}/todos/{}/history", API_URL, todo_id);
+    reqwest::get(&url).await?.json::<Vec<TodoRevision>>().await
+}
+
+fn display_value(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::Null => "—".to_string(),
+        serde_json::Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+fn describe_change(change: &FieldChange) -> String {
+    format!("{}: {} → {}", change.field, display_value(&change.old), display_value(&change.new))
+}
+
+#[component]
+pub fn TodoHistory(props: TodoHistoryProps) -> Element {
+    let todo_id = props.todo_id;
+    let mut history = use_resource(move || fetch_history(todo_id));
+
+    let handle_revert = move |rev: i64| {
+        spawn(async move {
+            let client = reqwest::Client::new();
+            let url = format!("{}/todos/{}/revert/{}", API_URL, todo_id, rev);
+            if let Ok(res) = client.post(&url).send().await {
+                if let Ok(todo) = res.json::<Todo>().await {
+                    props.on_revert.call(todo);
+                    history.restart();
+                }
+            }
+        });
+    };
+
+    // Formatted here rather than inside `rsx!`, which can't take a format string with quotes.
+    let entries = history.read().as_ref().map(|result| {
+        result
+            .as_ref()
+            .map(|revisions| {
+                revisions
+                    .iter()
+                    .cloned()
+                    .enumerate()
+                    .map(|(index, revision)| {
+                        let created_at = revision.created_at.format("%Y-%m-%d %H:%M").to_string();
+                        (index, revision, created_at)
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .map_err(|e| e.to_string())
+    });
+
+    rsx! {
+        div {
+            class: "mt-6",
+            h2 {
+                class: "text-lg font-semibold text-gray-900 mb-3",
+                "History"
+            }
+            match entries {
+                Some(Ok(entries)) => rsx! {
+                    if entries.is_empty() {
+                        p { class: "text-sm text-gray-500", "No changes recorded yet." }
+                    } else {
+                        ul {
+                            class: "space-y-3",
+                            for (index, revision, created_at) in entries {
+                                li {
+                                    key: "{revision.id}",
+                                    class: "p-3 bg-gray-50 rounded-md border border-gray-200",
+                                    div {
+                                        class: "flex justify-between items-center",
+                                        span {
+                                            class: "text-sm font-medium text-gray-700",
+                                            "Revision {revision.revision} · {created_at}"
+                                        }
+                                        // The newest revision is the current state, so there is nothing to revert to.
+                                        if index > 0 {
+                                            button {
+                                                class: "text-sm text-blue-600 hover:text-blue-800",
+                                                onclick: move |_| handle_revert(revision.revision),
+                                                "Revert to this"
+                                            }
+                                        }
+                                    }
+                                    ul {
+                                        class: "mt-1 text-sm text-gray-600",
+                                        for change in revision.changes.iter() {
+                                            li { "{describe_change(change)}" }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                },
+                Some(Err(e)) => rsx! { p { class: "text-red-500", "Error loading history: {e}" } },
+                None => rsx! { p { class: "text-gray-500", "Loading..." } },
+            }
+        }
+    }
+}
@@ -1,5 +1,10 @@
 use dioxus::prelude::*;
-use crate::models::{Todo, Priority};
+use dioxus_router::prelude::Link;
+use crate::{
+    Route,
+    models::{Todo, Priority, TimerState},
+    utils::{start_timer, stop_timer},
+};
 
 #[derive(Props, Clone, PartialEq)]
 pub struct TodoItemProps {
@@ -7,6 +12,16 @@ pub struct TodoItemProps {
     pub on_toggle: EventHandler<uuid::Uuid>,
     pub on_delete: EventHandler<uuid::Uuid>,
     pub on_edit: EventHandler<uuid::Uuid>,
+    #[props(default)]
+    pub selected: bool,
+    // Selection checkbox is only shown when the parent supports multi-select.
+    #[props(default)]
+    pub on_select: Option<EventHandler<uuid::Uuid>>,
+    // Drag-and-drop reordering is enabled when the parent handles both events.
+    #[props(default)]
+    pub on_drag_start: Option<EventHandler<uuid::Uuid>>,
+    #[props(default)]
+    pub on_drop: Option<EventHandler<uuid::Uuid>>,
 }
 
 #[component]
@@ -17,11 +32,55 @@ pub fn TodoItem(props: TodoItemProps) -> Element {
         Priority::Low => "text-green-500",
     };
 
+    let TimerState(mut timer) = use_context::<TimerState>();
+    let todo_id = props.todo.id;
+    let is_running = timer.read().as_ref().is_some_and(|entry| entry.todo_id == todo_id);
+
+    let handle_timer = move |_| {
+        spawn(async move {
+            let result = if is_running {
+                stop_timer().await.map(|_| None)
+            } else {
+                start_timer(todo_id).await.map(Some)
+            };
+            match result {
+                Ok(running) => timer.set(running),
+                Err(e) => log::error!("Failed to toggle timer for {}: {}", todo_id, e),
+            }
+        });
+    };
+
+    let done_on = props.todo.completed_at
+        .filter(|_| props.todo.completed)
+        .map(|at| at.format("%b %-d").to_string());
+
     rsx! {
         li {
             class: "flex items-center justify-between p-4 bg-white rounded-lg shadow-sm my-2",
+            draggable: props.on_drag_start.is_some(),
+            prevent_default: "ondragover ondrop",
+            ondragstart: move |_| {
+                if let Some(on_drag_start) = props.on_drag_start {
+                    on_drag_start.call(props.todo.id);
+                }
+            },
+            ondragover: move |_| {},
+            ondrop: move |_| {
+                if let Some(on_drop) = props.on_drop {
+                    on_drop.call(props.todo.id);
+                }
+            },
             div {
                 class: "flex items-center",
+                if let Some(on_select) = props.on_select {
+                    input {
+                        r#type: "checkbox",
+                        class: "mr-3 accent-blue-600",
+                        title: "Select",
+                        checked: props.selected,
+                        onchange: move |_| on_select.call(props.todo.id),
+                    }
+                }
                 input {
                     r#type: "checkbox",
                     checked: props.todo.completed,
@@ -29,10 +88,21 @@ pub fn TodoItem(props: TodoItemProps) -> Element {
                 },
                 div {
                     class: "ml-4",
-                    p {
-                        class: if props.todo.completed { "line-through text-gray-500" } else { "" },
+                    Link {
+                        to: Route::TodoDetail { id: props.todo.id },
+                        class: if props.todo.completed { "line-through text-gray-500" } else { "hover:text-blue-600" },
                         "{props.todo.title}"
                     }
+                    if props.todo.blocked && !props.todo.completed {
+                        span {
+                            class: "ml-2 px-2 py-0.5 text-xs font-medium rounded-full bg-orange-100 text-orange-700",
+                            title: "Waiting on other todos",
+                            "blocked"
+                        }
+                    }
+                    if let Some(done_on) = done_on {
+                        span { class: "ml-2 text-xs text-gray-400", "done {done_on}" }
+                    }
                     if let Some(desc) = &props.todo.description {
                         p { class: "text-sm text-gray-600", "{desc}" }
                     }
@@ -40,7 +110,26 @@ pub fn TodoItem(props: TodoItemProps) -> Element {
             }
             div {
                 class: "flex items-center",
+                for tag in props.todo.tags.iter() {
+                    span { class: "mr-2 px-2 py-0.5 text-xs rounded-full bg-blue-50 text-blue-700", "#{tag}" }
+                }
+                for (key, value) in props.todo.custom_fields.iter() {
+                    span {
+                        class: "mr-2 px-2 py-0.5 text-xs rounded bg-gray-100 text-gray-600",
+                        title: "{key}",
+                        "{custom_field_label(key, value)}"
+                    }
+                }
+                if let Some(minutes) = props.todo.estimated_minutes {
+                    span { class: "mr-3 text-xs text-gray-500", title: "Estimate", "~{minutes}m" }
+                }
                 span { class: "mr-4 {priority_color}", "{props.todo.priority}" },
+                button {
+                    onclick: handle_timer,
+                    class: if is_running { "p-2 text-red-500 hover:text-red-700" } else { "p-2 text-gray-400 hover:text-green-600" },
+                    title: if is_running { "Stop timer" } else { "Start timer" },
+                    if is_running { "⏹" } else { "▶" }
+                }
                 button {
                     onclick: move |_| props.on_edit.call(props.todo.id),
                     class: "p-2 text-gray-400 hover:text-blue-600",
@@ -54,4 +143,13 @@ pub fn TodoItem(props: TodoItemProps) -> Element {
             }
         }
     }
-} 
\ No newline at end of file
+} 
+
+fn custom_field_label(key: &str, value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::String(s) => s.clone(),
+        serde_json::Value::Bool(true) => format!("✓ {}", key),
+        serde_json::Value::Bool(false) => format!("✗ {}", key),
+        other => other.to_string(),
+    }
+}
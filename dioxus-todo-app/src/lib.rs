@@ -8,19 +8,33 @@ mod utils;
 use dioxus::prelude::*;
 use dioxus_router::prelude::*;
 use dioxus_router::components::Router;
-use models::AuthState;
+use components::Layout;
+use models::{AuthState, TimerState};
 use wasm_bindgen::prelude::*;
 
+// Every page renders inside `Layout`, which holds the navigation and the running timer.
 #[derive(Routable, Clone, PartialEq)]
+#[rustfmt::skip]
 pub enum Route {
-    #[route("/")]
-    Home {},
-    #[route("/login")]
-    LoginPage {},
-    #[route("/todos")]
-    TodoList {},
-    #[route("/:..route")]
-    PageNotFound { route: Vec<String> },
+    #[layout(Layout)]
+        #[route("/")]
+        Home {},
+        #[route("/login")]
+        LoginPage {},
+        #[route("/todos")]
+        TodoList {},
+        #[route("/todos/:id")]
+        TodoDetail { id: uuid::Uuid },
+        #[route("/board")]
+        Board {},
+        #[route("/stats")]
+        Stats {},
+        #[route("/archive")]
+        Archive {},
+        #[route("/data")]
+        ImportExport {},
+        #[route("/:..route")]
+        PageNotFound { route: Vec<String> },
 }
 
 #[wasm_bindgen(start)]
@@ -34,6 +48,7 @@ pub fn main() {
 #[component]
 fn App(cx: Scope) -> Element {
     use_context_provider(|| Signal::new(AuthState::Unknown));
+    use_context_provider(|| TimerState(Signal::new(None)));
 
     rsx! {
         Router::<Route> {}
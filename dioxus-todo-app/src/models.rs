@@ -1,7 +1,9 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
+use std::collections::BTreeMap;
 use std::fmt::{Display, Formatter, Result as FmtResult};
+use dioxus::prelude::Signal;
 
 // Todo item model
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -14,6 +16,25 @@ pub struct Todo {
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     pub priority: Priority,
+    pub version: i64,
+    #[serde(default)]
+    pub position: String,
+    #[serde(default)]
+    pub status_id: Option<Uuid>,
+    #[serde(default)]
+    pub estimated_minutes: Option<i64>,
+    #[serde(default)]
+    pub custom_fields: BTreeMap<String, serde_json::Value>,
+    #[serde(default)]
+    pub completed_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub archived_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub blocked: bool,
+    #[serde(default)]
+    pub project_id: Option<Uuid>,
+    #[serde(default)]
+    pub tags: Vec<String>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
@@ -29,6 +50,277 @@ impl Display for Priority {
     }
 }
 
+// Workflow status (board column)
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StatusCategory {
+    Todo,
+    Doing,
+    Done,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Status {
+    pub id: Uuid,
+    pub name: String,
+    pub position: i64,
+    pub category: StatusCategory,
+    #[serde(default)]
+    pub project_id: Option<Uuid>,
+}
+
+// Named group of todos; a todo is in at most one project
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Project {
+    pub id: Uuid,
+    pub name: String,
+    pub position: i64,
+}
+
+// User-defined field shown on every todo
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CustomFieldType {
+    Text,
+    Number,
+    Date,
+    Select,
+    Checkbox,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CustomField {
+    pub id: Uuid,
+    pub key: String,
+    pub name: String,
+    pub field_type: CustomFieldType,
+    #[serde(default)]
+    pub options: Vec<String>,
+    pub position: i64,
+    // Only shown on todos in this project; `None` means every todo
+    #[serde(default)]
+    pub project_id: Option<Uuid>,
+}
+
+// Reusable todo; `todo` may contain `{{date}}` and `{{name}}` placeholders
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Template {
+    pub id: Uuid,
+    pub name: String,
+    pub todo: NewTodo,
+}
+
+// Fields for creating a todo, as produced by templates and quick-add parsing
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NewTodo {
+    pub title: String,
+    pub description: Option<String>,
+    pub priority: Priority,
+    #[serde(default)]
+    pub estimated_minutes: Option<i64>,
+    #[serde(default)]
+    pub custom_fields: BTreeMap<String, serde_json::Value>,
+    #[serde(default)]
+    pub project_id: Option<Uuid>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+// Named filter expression with the number of todos it currently matches
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SavedView {
+    pub id: Uuid,
+    pub name: String,
+    pub query: String,
+    pub position: i64,
+    pub count: i64,
+}
+
+// Response of GET /api/stats
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TodoStats {
+    pub completed_per_day: Vec<PeriodCount>,
+    pub completed_per_week: Vec<PeriodCount>,
+    pub average_completion_seconds: Option<f64>,
+    pub open_by_priority: Vec<PriorityCount>,
+    pub overdue: i64,
+    pub current_streak: i64,
+    pub longest_streak: i64,
+}
+
+// Per-user preferences; `auto_archive_days` of `None` turns auto-archiving off
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct UserSettings {
+    pub auto_archive_days: Option<i64>,
+}
+
+// Outcome of an import, per kind of record
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ImportCounts {
+    pub created: u64,
+    pub updated: u64,
+    pub skipped: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ImportReport {
+    pub dry_run: bool,
+    #[serde(default)]
+    pub projects: ImportCounts,
+    pub statuses: ImportCounts,
+    pub custom_fields: ImportCounts,
+    pub todos: ImportCounts,
+    pub dependencies: ImportCounts,
+    pub time_entries: ImportCounts,
+    pub templates: ImportCounts,
+    pub saved_views: ImportCounts,
+}
+
+// Outcome of a CSV import; rows listed in `errors` were not imported
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CsvImportReport {
+    pub dry_run: bool,
+    pub header: Option<Vec<String>>,
+    pub mapping: BTreeMap<String, String>,
+    pub imported: u64,
+    pub errors: Vec<ImportRowError>,
+}
+
+// Outcome of importing a text file such as todo.txt; lines in `errors` were not imported
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TextImportReport {
+    pub dry_run: bool,
+    pub imported: u64,
+    pub errors: Vec<ImportRowError>,
+    // What the file had that todos have no place for, e.g. recurrence rules
+    #[serde(default)]
+    pub unmapped: Vec<Unmapped>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Unmapped {
+    pub what: String,
+    pub count: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ImportRowError {
+    pub line: usize,
+    pub message: String,
+}
+
+// Calendar subscription; the token is the secret part of the feed URL
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CalendarFeed {
+    pub token: String,
+    pub created_at: DateTime<Utc>,
+}
+
+// Personal API token; `token` is only sent back when it is created
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ApiToken {
+    pub id: Uuid,
+    pub name: String,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub token: Option<String>,
+}
+
+// Change to the user's todos pushed by the server over `/api/ws`
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TodoEvent {
+    Created { todo: Todo },
+    Updated { todo: Todo },
+    Deleted { id: Uuid },
+    // Too many todos changed to list them, so the list has to be loaded again
+    Reload,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PeriodCount {
+    pub period: String,
+    pub count: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PriorityCount {
+    pub priority: Priority,
+    pub count: i64,
+}
+
+// Quick-add text parsed into todo fields, with the recognized phrases as byte ranges
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ParsedTodo {
+    pub todo: NewTodo,
+    pub due: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    pub project: Option<String>,
+    pub recurrence: Option<String>,
+    pub spans: Vec<ParsedSpan>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ParsedSpan {
+    pub start: usize,
+    pub end: usize,
+    pub kind: String,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TodoDependencies {
+    pub blockers: Vec<Todo>,
+    pub dependents: Vec<Todo>,
+}
+
+// Time tracking
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TimeEntry {
+    pub id: Uuid,
+    pub todo_id: Uuid,
+    pub started_at: DateTime<Utc>,
+    pub ended_at: Option<DateTime<Utc>>,
+    pub note: Option<String>,
+}
+
+// Running timer shared between the header indicator and the todo items
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TimerState(pub Signal<Option<TimeEntry>>);
+
+// Revision history models
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FieldChange {
+    pub field: String,
+    pub old: serde_json::Value,
+    pub new: serde_json::Value,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TodoRevision {
+    pub id: Uuid,
+    pub todo_id: Uuid,
+    pub revision: i64,
+    pub actor_id: Uuid,
+    pub changes: Vec<FieldChange>,
+    pub created_at: DateTime<Utc>,
+}
+
+// Operations accepted by the bulk endpoint
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum BulkOperation {
+    Complete,
+    Uncomplete,
+    Delete,
+    SetPriority { priority: Priority },
+    MoveToProject { project_id: Option<Uuid> },
+    AddTag { tag: String },
+    RemoveTag { tag: String },
+}
+
 // User model for authentication
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct User {
@@ -67,6 +359,17 @@ pub struct TodoForm {
     pub title: String,
     pub description: String,
     pub priority: Priority,
+    pub estimated_minutes: Option<i64>,
+    pub custom_fields: BTreeMap<String, serde_json::Value>,
+    pub project_id: Option<Uuid>,
+    pub tags: Vec<String>,
+}
+
+// A write rejected because the todo changed on the server since it was loaded
+#[derive(Debug, Clone, PartialEq)]
+pub struct EditConflict {
+    pub form: TodoForm,
+    pub server: Todo,
 }
 
 impl Default for Priority {
@@ -87,6 +390,16 @@ impl Todo {
             created_at: now,
             updated_at: now,
             priority,
+            version: 1,
+            position: String::new(),
+            status_id: None,
+            estimated_minutes: None,
+            custom_fields: BTreeMap::new(),
+            completed_at: None,
+            archived_at: None,
+            blocked: false,
+            project_id: None,
+            tags: Vec::new(),
         }
     }
     
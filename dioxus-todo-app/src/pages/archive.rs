@@ -0,0 +1,146 @@
+use dioxus::prelude::*;
+use dioxus_router::prelude::Link;
+
+use crate::{
+    Route,
+    models::UserSettings,
+    utils::{load_archive, load_settings, save_settings, set_archived},
+};
+
+#[component]
+pub fn Archive() -> Element {
+    let mut search = use_signal(String::new);
+    let mut todos = use_resource(move || load_archive(search()));
+    let mut settings = use_resource(load_settings);
+    let mut settings_error = use_signal(|| None::<String>);
+
+    let handle_unarchive = move |id| {
+        spawn(async move {
+            if let Err(e) = set_archived(id, false).await {
+                log::error!("{}", e);
+            }
+            todos.restart();
+        });
+    };
+
+    let handle_days = move |evt: Event<FormData>| {
+        let value = evt.value();
+        let auto_archive_days = match value.trim() {
+            "" => None,
+            days => match days.parse::<i64>() {
+                Ok(days) if days >= 0 => Some(days),
+                _ => {
+                    settings_error.set(Some("Enter a whole number of days".to_string()));
+                    return;
+                }
+            },
+        };
+        spawn(async move {
+            match save_settings(&UserSettings { auto_archive_days }).await {
+                Ok(_) => {
+                    settings_error.set(None);
+                    settings.restart();
+                    // Shortening the window can archive todos straight away.
+                    todos.restart();
+                }
+                Err(e) => settings_error.set(Some(e)),
+            }
+        });
+    };
+
+    let card = "bg-white p-6 rounded-lg shadow-md";
+
+    rsx! {
+        div {
+            class: "min-h-screen bg-gray-50 p-4 sm:p-8",
+            div {
+                class: "max-w-3xl mx-auto space-y-6",
+
+                // Header
+                div {
+                    class: "flex justify-between items-center",
+                    h1 {
+                        class: "text-3xl font-bold text-gray-900",
+                        "Archive"
+                    },
+                    Link {
+                        to: Route::TodoList {},
+                        class: "text-blue-600 hover:text-blue-800 text-sm font-medium",
+                        "← Back to List"
+                    }
+                }
+
+                // Auto-archive setting
+                div {
+                    class: card,
+                    label {
+                        class: "flex items-center gap-2 text-sm text-gray-700",
+                        "Archive completed todos after"
+                        input {
+                            r#type: "number",
+                            min: "0",
+                            class: "w-20 border rounded px-2 py-1",
+                            placeholder: "never",
+                            value: match &*settings.read() {
+                                Some(Ok(s)) => s.auto_archive_days.map(|d| d.to_string()).unwrap_or_default(),
+                                _ => String::new(),
+                            },
+                            onchange: handle_days,
+                        }
+                        "days"
+                    }
+                    if let Some(e) = settings_error() {
+                        p { class: "text-red-500 text-sm mt-2", "{e}" }
+                    }
+                }
+
+                div {
+                    class: card,
+                    input {
+                        r#type: "search",
+                        class: "w-full border rounded px-3 py-2 mb-4",
+                        placeholder: "Search archived todos",
+                        value: "{search}",
+                        oninput: move |evt| search.set(evt.value()),
+                    }
+                    match &*todos.read() {
+                        Some(Ok(list)) if list.is_empty() => rsx! {
+                            p { class: "text-gray-500", "Nothing archived." }
+                        },
+                        Some(Ok(list)) => rsx! {
+                            ul {
+                                class: "divide-y",
+                                for (todo, archived_on) in list.iter().cloned().map(|t| {
+                                    let archived_on = t.archived_at.map(|at| at.format("%b %-d, %Y").to_string());
+                                    (t, archived_on)
+                                }) {
+                                    li {
+                                        key: "{todo.id}",
+                                        class: "flex justify-between items-center py-2",
+                                        div {
+                                            Link {
+                                                to: Route::TodoDetail { id: todo.id },
+                                                class: "text-gray-900 hover:underline",
+                                                "{todo.title}"
+                                            }
+                                            if let Some(archived_on) = archived_on {
+                                                p { class: "text-xs text-gray-400", "archived {archived_on}" }
+                                            }
+                                        }
+                                        button {
+                                            class: "text-sm text-blue-600 hover:text-blue-800",
+                                            onclick: move |_| handle_unarchive(todo.id),
+                                            "Unarchive"
+                                        }
+                                    }
+                                }
+                            }
+                        },
+                        Some(Err(e)) => rsx! { p { class: "text-red-500", "Error loading archive: {e}" } },
+                        None => rsx! { p { class: "text-gray-500", "Loading..." } },
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,155 @@
+use dioxus::prelude::*;
+use dioxus_router::prelude::Link;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::{
+    Route,
+    models::{Priority, Status, StatusCategory, Todo},
+    utils::{load_projects, load_todos, load_workflow, patch_todo, WriteOutcome},
+};
+
+// Column a todo belongs in. Todos without a status fall back on `completed`.
+fn column_for(todo: &Todo, statuses: &[Status]) -> Option<Uuid> {
+    if let Some(status_id) = todo.status_id.filter(|id| statuses.iter().any(|s| s.id == *id)) {
+        return Some(status_id);
+    }
+    let category = if todo.completed { StatusCategory::Done } else { StatusCategory::Todo };
+    statuses.iter().find(|s| s.category == category).map(|s| s.id)
+}
+
+#[component]
+pub fn Board() -> Element {
+    // The board shows one project at a time, in that project's workflow; `None` is todos without a project.
+    let mut project = use_signal(|| None::<Uuid>);
+    let projects = use_resource(load_projects);
+    let mut statuses = use_resource(move || load_workflow(project()));
+    let mut todos = use_resource(load_todos);
+    let mut dragging = use_signal(|| None::<Uuid>);
+    // Columns shown for todos whose move is still in flight; dropped once todos are refetched.
+    let mut pending_moves = use_signal(HashMap::<Uuid, Uuid>::new);
+
+    use_effect(move || {
+        let _ = todos.read();
+        pending_moves.write().clear();
+    });
+
+    let mut handle_drop = move |status_id: Uuid| {
+        let Some(todo_id) = dragging.take() else { return };
+        let Some(Ok(todos_vec)) = todos.read().as_ref().cloned() else { return };
+        let Some(todo) = todos_vec.into_iter().find(|t| t.id == todo_id) else { return };
+        if todo.status_id == Some(status_id) {
+            return;
+        }
+        pending_moves.write().insert(todo_id, status_id);
+
+        spawn(async move {
+            let patch = serde_json::json!({ "status_id": status_id });
+            match patch_todo(todo_id, todo.version, &patch).await {
+                WriteOutcome::Saved | WriteOutcome::Conflict(_) => todos.restart(),
+                WriteOutcome::Blocked(e) | WriteOutcome::Failed(e) => {
+                    log::error!("Failed to move todo {}: {}", todo_id, e);
+                    pending_moves.write().remove(&todo_id);
+                }
+            }
+        });
+    };
+
+    rsx! {
+        div {
+            class: "min-h-screen bg-gray-50 p-4 sm:p-8",
+            div {
+                class: "max-w-7xl mx-auto",
+
+                // Header
+                div {
+                    class: "flex justify-between items-center mb-6",
+                    h1 {
+                        class: "text-3xl font-bold text-gray-900",
+                        "Board"
+                    },
+                    select {
+                        class: "px-3 py-2 border border-gray-300 rounded-md text-sm bg-white",
+                        value: project().map(|id| id.to_string()).unwrap_or_default(),
+                        onchange: move |evt: FormEvent| project.set(Uuid::parse_str(&evt.value()).ok()),
+                        option { value: "", "No project" }
+                        if let Some(Ok(list)) = projects.read().as_ref() {
+                            for p in list.clone() {
+                                option { key: "{p.id}", value: "{p.id}", "{p.name}" }
+                            }
+                        }
+                    },
+                    Link {
+                        to: Route::TodoList {},
+                        class: "text-blue-600 hover:text-blue-800 text-sm font-medium",
+                        "← Back to List"
+                    }
+                }
+
+                match (&*statuses.read(), &*todos.read()) {
+                    (Some(Ok(columns)), Some(Ok(todos_vec))) => {
+                        let mut columns = columns.clone();
+                        columns.sort_by_key(|s| s.position);
+                        let pending = pending_moves.read().clone();
+                        rsx! {
+                            div {
+                                class: "flex gap-4 overflow-x-auto pb-4",
+                                for status in columns.iter().cloned() {
+                                    div {
+                                        key: "{status.id}",
+                                        class: "flex-shrink-0 w-72 bg-gray-100 rounded-lg p-3",
+                                        prevent_default: "ondragover ondrop",
+                                        ondragover: move |_| {},
+                                        ondrop: move |_| handle_drop(status.id),
+                                        h2 {
+                                            class: "text-sm font-semibold text-gray-700 uppercase tracking-wide mb-3",
+                                            "{status.name}"
+                                        }
+                                        ul {
+                                            class: "space-y-2 min-h-[4rem]",
+                                            for todo in todos_vec.iter().filter(|t| t.project_id == project()).filter(|t| {
+                                                pending.get(&t.id).copied().or_else(|| column_for(t, &columns)) == Some(status.id)
+                                            }).cloned() {
+                                                li {
+                                                    key: "{todo.id}",
+                                                    class: "p-3 bg-white rounded-md shadow-sm cursor-move",
+                                                    draggable: true,
+                                                    ondragstart: move |_| dragging.set(Some(todo.id)),
+                                                    Link {
+                                                        to: Route::TodoDetail { id: todo.id },
+                                                        class: "text-sm font-medium text-gray-900 hover:text-blue-600",
+                                                        "{todo.title}"
+                                                    }
+                                                    p {
+                                                        class: match todo.priority {
+                                                            Priority::High => "text-xs mt-1 text-red-500",
+                                                            Priority::Medium => "text-xs mt-1 text-yellow-500",
+                                                            Priority::Low => "text-xs mt-1 text-green-500",
+                                                        },
+                                                        "{todo.priority}"
+                                                    }
+                                                }
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    },
+                    (Some(Err(e)), _) | (_, Some(Err(e))) => rsx! {
+                        p { class: "text-red-500", "Error loading board: {e}" }
+                        button {
+                            class: "mt-2 text-blue-600",
+                            onclick: move |_| {
+                                statuses.restart();
+                                todos.restart();
+                            },
+                            "Retry"
+                        }
+                    },
+                    _ => rsx! { p { class: "text-gray-500", "Loading..." } },
+                }
+            }
+        }
+    }
+}
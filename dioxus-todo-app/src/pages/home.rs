@@ -1,5 +1,4 @@
 use crate::{
-    components::Layout,
     models::AuthState,
     utils::load_user,
     Route,
@@ -0,0 +1,298 @@
+use dioxus::prelude::*;
+use dioxus_router::prelude::Link;
+
+use crate::{
+    Route,
+    components::{ApiTokensPanel, CalendarFeedPanel, CsvImportPanel, FileImportPanel},
+    models::{ImportCounts, ImportReport},
+    utils::{import_json, API_URL},
+};
+
+// Columns offered for CSV export, with whether they start out selected
+const CSV_COLUMNS: &[(&str, bool)] = &[
+    ("title", true),
+    ("description", true),
+    ("priority", true),
+    ("completed", true),
+    ("status", false),
+    ("estimated_minutes", false),
+    ("created_at", true),
+    ("updated_at", false),
+    ("completed_at", true),
+];
+
+fn report_rows(report: &ImportReport) -> Vec<(&'static str, ImportCounts)> {
+    vec![
+        ("Todos", report.todos.clone()),
+        ("Projects", report.projects.clone()),
+        ("Statuses", report.statuses.clone()),
+        ("Custom fields", report.custom_fields.clone()),
+        ("Dependencies", report.dependencies.clone()),
+        ("Time entries", report.time_entries.clone()),
+        ("Templates", report.templates.clone()),
+        ("Saved views", report.saved_views.clone()),
+    ]
+}
+
+#[component]
+pub fn ImportExport() -> Element {
+    let mut document = use_signal(|| None::<String>);
+    let mut strategy = use_signal(|| "skip".to_string());
+    let mut report = use_signal(|| None::<ImportReport>);
+    let mut error = use_signal(|| None::<String>);
+    let mut csv_columns = use_signal(|| {
+        CSV_COLUMNS.iter().filter(|(_, selected)| *selected).map(|(name, _)| name.to_string()).collect::<Vec<_>>()
+    });
+    let mut markdown_group = use_signal(|| "priority".to_string());
+
+    let handle_file = move |evt: Event<FormData>| {
+        report.set(None);
+        error.set(None);
+        spawn(async move {
+            let Some(files) = evt.files() else { return };
+            let Some(name) = files.files().into_iter().next() else { return };
+            match files.read_file_to_string(&name).await {
+                Some(contents) => document.set(Some(contents)),
+                None => error.set(Some(format!("Could not read {}", name))),
+            }
+        });
+    };
+
+    let run_import = move |dry_run: bool| {
+        let Some(contents) = document() else { return };
+        spawn(async move {
+            match import_json(contents, &strategy(), dry_run).await {
+                Ok(result) => {
+                    error.set(None);
+                    report.set(Some(result));
+                }
+                Err(e) => error.set(Some(e)),
+            }
+        });
+    };
+
+    let csv_href = format!("{}/export/csv?columns={}", API_URL, csv_columns.read().join(","));
+    let markdown_href = format!("{}/export.md?group={}", API_URL, markdown_group());
+    let card = "bg-white p-6 rounded-lg shadow-md";
+    let button = "px-4 py-2 rounded text-sm font-medium disabled:opacity-50";
+
+    rsx! {
+        div {
+            class: "min-h-screen bg-gray-50 p-4 sm:p-8",
+            div {
+                class: "max-w-3xl mx-auto space-y-6",
+
+                // Header
+                div {
+                    class: "flex justify-between items-center",
+                    h1 {
+                        class: "text-3xl font-bold text-gray-900",
+                        "Import & Export"
+                    },
+                    Link {
+                        to: Route::TodoList {},
+                        class: "text-blue-600 hover:text-blue-800 text-sm font-medium",
+                        "← Back to List"
+                    }
+                }
+
+                div {
+                    class: card,
+                    h2 { class: "text-lg font-semibold text-gray-900 mb-2", "Export" }
+                    p {
+                        class: "text-sm text-gray-600 mb-4",
+                        "Download everything, including archived todos, as a JSON backup."
+                    }
+                    a {
+                        href: "{API_URL}/export",
+                        class: "{button} bg-blue-600 text-white hover:bg-blue-700",
+                        "Download JSON"
+                    }
+                }
+
+                div {
+                    class: card,
+                    h2 { class: "text-lg font-semibold text-gray-900 mb-2", "Spreadsheets (CSV)" }
+                    div {
+                        class: "flex flex-wrap gap-x-4 gap-y-1 mb-4 text-sm text-gray-700",
+                        for &(name, _) in CSV_COLUMNS.iter() {
+                            label {
+                                class: "flex items-center gap-1",
+                                input {
+                                    r#type: "checkbox",
+                                    checked: csv_columns.read().iter().any(|c| c == name),
+                                    onchange: move |_| {
+                                        let mut columns = csv_columns.write();
+                                        if columns.iter().any(|c| c == name) {
+                                            columns.retain(|c| c != name);
+                                        } else {
+                                            // Keep the columns in the order they are offered.
+                                            columns.push(name.to_string());
+                                            columns.sort_by_key(|c| CSV_COLUMNS.iter().position(|&(n, _)| n == c.as_str()));
+                                        }
+                                    },
+                                }
+                                "{name}"
+                            }
+                        }
+                    }
+                    a {
+                        href: csv_href,
+                        class: "{button} bg-blue-600 text-white hover:bg-blue-700",
+                        "Download CSV"
+                    }
+                    h3 { class: "text-sm font-semibold text-gray-900 mt-6 mb-2", "Import CSV" }
+                    CsvImportPanel {}
+                }
+
+                div {
+                    class: card,
+                    h2 { class: "text-lg font-semibold text-gray-900 mb-2", "todo.txt" }
+                    a {
+                        href: "{API_URL}/export.txt",
+                        class: "{button} bg-blue-600 text-white hover:bg-blue-700",
+                        "Download todo.txt"
+                    }
+                    h3 { class: "text-sm font-semibold text-gray-900 mt-6 mb-2", "Import todo.txt" }
+                    FileImportPanel { path: "import/todotxt", accept: ".txt,text/plain" }
+                }
+
+                div {
+                    class: card,
+                    h2 { class: "text-lg font-semibold text-gray-900 mb-2", "From other apps" }
+                    p {
+                        class: "text-sm text-gray-600 mb-4",
+                        "Bring todos over from another service's export. Subtasks and checklists become todos that block their parent."
+                    }
+                    h3 { class: "text-sm font-semibold text-gray-900 mb-2", "Todoist (CSV)" }
+                    FileImportPanel { path: "import/todoist", accept: ".csv,text/csv" }
+                    h3 { class: "text-sm font-semibold text-gray-900 mt-6 mb-2", "Trello board (JSON)" }
+                    FileImportPanel { path: "import/trello", accept: ".json,application/json", unit: "Card" }
+                    h3 { class: "text-sm font-semibold text-gray-900 mt-6 mb-2", "Google Tasks (Takeout Tasks.json)" }
+                    FileImportPanel { path: "import/google-tasks", accept: ".json,application/json", unit: "Task" }
+                    h3 { class: "text-sm font-semibold text-gray-900 mt-6 mb-2", "Microsoft To Do (JSON)" }
+                    FileImportPanel { path: "import/microsoft-todo", accept: ".json,application/json", unit: "Task" }
+                }
+
+                div {
+                    class: card,
+                    h2 { class: "text-lg font-semibold text-gray-900 mb-2", "Markdown" }
+                    p {
+                        class: "text-sm text-gray-600 mb-4",
+                        "Task lists for pasting into documents. Todos are nested under the todo they block."
+                    }
+                    label {
+                        class: "flex items-center gap-2 mb-4 text-sm text-gray-700",
+                        "Group by"
+                        select {
+                            class: "border rounded px-2 py-1",
+                            value: "{markdown_group}",
+                            onchange: move |evt| markdown_group.set(evt.value()),
+                            option { value: "priority", "Priority" }
+                            option { value: "project", "Project" }
+                            option { value: "none", "Nothing" }
+                        }
+                    }
+                    a {
+                        href: markdown_href,
+                        class: "{button} bg-blue-600 text-white hover:bg-blue-700",
+                        "Download Markdown"
+                    }
+                    h3 { class: "text-sm font-semibold text-gray-900 mt-6 mb-2", "Import Markdown" }
+                    FileImportPanel { path: "import/markdown", accept: ".md,.markdown,text/markdown" }
+                }
+
+                div {
+                    class: card,
+                    h2 { class: "text-lg font-semibold text-gray-900 mb-2", "Calendar" }
+                    p {
+                        class: "text-sm text-gray-600 mb-4",
+                        "Subscribe to your todos from a calendar app. Anyone with the link can see them."
+                    }
+                    CalendarFeedPanel {}
+                    h3 { class: "text-sm font-semibold text-gray-900 mt-6 mb-2", "Import iCalendar (.ics)" }
+                    FileImportPanel { path: "import/ics", accept: ".ics,text/calendar" }
+                }
+
+                div {
+                    class: card,
+                    h2 { class: "text-lg font-semibold text-gray-900 mb-2", "Task apps (CalDAV)" }
+                    p {
+                        class: "text-sm text-gray-600 mb-4",
+                        "Sync todos both ways with apps that support CalDAV tasks, such as Apple Reminders or Tasks.org. Each project shows up as its own list."
+                    }
+                    ApiTokensPanel {}
+                }
+
+                div {
+                    class: card,
+                    h2 { class: "text-lg font-semibold text-gray-900 mb-2", "Import" }
+                    div {
+                        class: "space-y-4",
+                        input {
+                            r#type: "file",
+                            accept: ".json,application/json",
+                            onchange: handle_file,
+                        }
+                        label {
+                            class: "flex items-center gap-2 text-sm text-gray-700",
+                            "When a record already exists"
+                            select {
+                                class: "border rounded px-2 py-1",
+                                value: "{strategy}",
+                                onchange: move |evt| strategy.set(evt.value()),
+                                option { value: "skip", "keep mine" }
+                                option { value: "overwrite", "replace it" }
+                                option { value: "duplicate", "import a copy" }
+                            }
+                        }
+                        div {
+                            class: "flex gap-2",
+                            button {
+                                class: "{button} bg-gray-100 text-gray-800 hover:bg-gray-200",
+                                disabled: document.read().is_none(),
+                                onclick: move |_| run_import(true),
+                                "Preview"
+                            }
+                            button {
+                                class: "{button} bg-blue-600 text-white hover:bg-blue-700",
+                                disabled: document.read().is_none(),
+                                onclick: move |_| run_import(false),
+                                "Import"
+                            }
+                        }
+                    }
+                    if let Some(e) = error() {
+                        p { class: "text-red-500 text-sm mt-4", "{e}" }
+                    }
+                    if let Some(result) = report() {
+                        div {
+                            class: "mt-4",
+                            p {
+                                class: "text-sm font-medium text-gray-900 mb-2",
+                                if result.dry_run { "Importing would:" } else { "Imported:" }
+                            }
+                            table {
+                                class: "text-sm text-gray-700",
+                                tr {
+                                    th { class: "text-left pr-6", "" }
+                                    th { class: "text-right pr-6", "created" }
+                                    th { class: "text-right pr-6", "updated" }
+                                    th { class: "text-right", "skipped" }
+                                }
+                                for (label, counts) in report_rows(&result) {
+                                    tr {
+                                        td { class: "pr-6", "{label}" }
+                                        td { class: "text-right pr-6", "{counts.created}" }
+                                        td { class: "text-right pr-6", "{counts.updated}" }
+                                        td { class: "text-right", "{counts.skipped}" }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
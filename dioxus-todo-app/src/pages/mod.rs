@@ -1,9 +1,19 @@
+mod archive;
+mod board;
 mod home;
+mod import_export;
 mod login;
 mod not_found;
+mod stats;
+mod todo_detail;
 mod todo_list;
 
+pub use archive::Archive;
+pub use board::Board;
 pub use home::Home;
+pub use import_export::ImportExport;
 pub use login::LoginPage;
 pub use not_found::PageNotFound;
+pub use stats::Stats;
+pub use todo_detail::TodoDetail;
 pub use todo_list::TodoList; 
\ No newline at end of file
@@ -0,0 +1,192 @@
+use dioxus::prelude::*;
+use dioxus_router::prelude::Link;
+
+use crate::{
+    Route,
+    models::{PeriodCount, Priority},
+    utils::load_stats,
+};
+
+const CHART_WIDTH: f64 = 600.0;
+const CHART_HEIGHT: f64 = 160.0;
+// Room under the bars for period labels
+const LABEL_HEIGHT: f64 = 20.0;
+
+// Rough length of a duration, e.g. "3d 4h" or "25m"
+fn humanize_seconds(seconds: f64) -> String {
+    let minutes = (seconds / 60.0).round() as i64;
+    match (minutes / 1440, (minutes % 1440) / 60, minutes % 60) {
+        (0, 0, m) => format!("{}m", m),
+        (0, h, m) => format!("{}h {}m", h, m),
+        (d, h, _) => format!("{}d {}h", d, h),
+    }
+}
+
+// Vertical bar chart drawn as inline SVG, labelling every `label_every`th bar
+fn bar_chart(data: &[PeriodCount], color: &'static str, label_every: usize) -> Element {
+    let max = data.iter().map(|p| p.count).max().unwrap_or(0).max(1) as f64;
+    let slot = CHART_WIDTH / data.len().max(1) as f64;
+    let bar_width = (slot * 0.8).max(1.0);
+    let bars: Vec<(f64, f64, f64, String, bool)> = data
+        .iter()
+        .enumerate()
+        .map(|(i, p)| {
+            let height = p.count as f64 / max * CHART_HEIGHT;
+            let x = i as f64 * slot + (slot - bar_width) / 2.0;
+            // Periods are dates; month and day are enough for a label.
+            let label = p.period.get(5..).unwrap_or(&p.period).to_string();
+            (x, CHART_HEIGHT - height, height, label, i % label_every.max(1) == 0)
+        })
+        .collect();
+
+    rsx! {
+        svg {
+            class: "w-full h-auto",
+            view_box: "0 0 {CHART_WIDTH} {CHART_HEIGHT + LABEL_HEIGHT}",
+            line {
+                x1: "0",
+                y1: "{CHART_HEIGHT}",
+                x2: "{CHART_WIDTH}",
+                y2: "{CHART_HEIGHT}",
+                stroke: "#d1d5db",
+            }
+            for (x, y, height, label, show_label) in bars {
+                g {
+                    rect {
+                        x: "{x}",
+                        y: "{y}",
+                        width: "{bar_width}",
+                        height: "{height}",
+                        rx: "2",
+                        fill: color,
+                    }
+                    if show_label {
+                        text {
+                            x: "{x + bar_width / 2.0}",
+                            y: "{CHART_HEIGHT + 14.0}",
+                            font_size: "10",
+                            text_anchor: "middle",
+                            fill: "#6b7280",
+                            "{label}"
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+// Horizontal bars, one per priority
+fn priority_chart(data: &[(Priority, i64)]) -> Element {
+    let max = data.iter().map(|(_, count)| *count).max().unwrap_or(0).max(1) as f64;
+    let row_height = 28.0;
+    let label_width = 70.0;
+    let rows: Vec<(f64, f64, String, i64, &'static str)> = data
+        .iter()
+        .enumerate()
+        .map(|(i, (priority, count))| {
+            let color = match priority {
+                Priority::High => "#ef4444",
+                Priority::Medium => "#eab308",
+                Priority::Low => "#22c55e",
+            };
+            let width = *count as f64 / max * (CHART_WIDTH - label_width - 40.0);
+            (i as f64 * row_height, width, priority.to_string(), *count, color)
+        })
+        .collect();
+    let height = row_height * rows.len().max(1) as f64;
+
+    rsx! {
+        svg {
+            class: "w-full h-auto",
+            view_box: "0 0 {CHART_WIDTH} {height}",
+            for (y, width, label, count, color) in rows {
+                g {
+                    text { x: "0", y: "{y + 18.0}", font_size: "12", fill: "#374151", "{label}" }
+                    rect { x: "{label_width}", y: "{y + 4.0}", width: "{width}", height: "20", rx: "3", fill: color }
+                    text { x: "{label_width + width + 6.0}", y: "{y + 18.0}", font_size: "12", fill: "#374151", "{count}" }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+pub fn Stats() -> Element {
+    let stats = use_resource(load_stats);
+
+    let card = "bg-white p-6 rounded-lg shadow-md";
+
+    rsx! {
+        div {
+            class: "min-h-screen bg-gray-50 p-4 sm:p-8",
+            div {
+                class: "max-w-4xl mx-auto space-y-6",
+
+                // Header
+                div {
+                    class: "flex justify-between items-center",
+                    h1 {
+                        class: "text-3xl font-bold text-gray-900",
+                        "Statistics"
+                    },
+                    Link {
+                        to: Route::TodoList {},
+                        class: "text-blue-600 hover:text-blue-800 text-sm font-medium",
+                        "← Back to List"
+                    }
+                }
+
+                match &*stats.read() {
+                    Some(Ok(stats)) => {
+                        // Every priority gets a row, even with nothing open.
+                        let by_priority: Vec<(Priority, i64)> = [Priority::High, Priority::Medium, Priority::Low]
+                            .into_iter()
+                            .map(|priority| {
+                                let count = stats.open_by_priority.iter().find(|p| p.priority == priority).map_or(0, |p| p.count);
+                                (priority, count)
+                            })
+                            .collect();
+                        let average = stats.average_completion_seconds
+                            .map(humanize_seconds)
+                            .unwrap_or_else(|| "—".to_string());
+                        rsx! {
+                            div {
+                                class: "grid grid-cols-1 sm:grid-cols-4 gap-4",
+                                for (label, value) in [
+                                    ("Overdue", stats.overdue.to_string()),
+                                    ("Current streak", format!("{} days", stats.current_streak)),
+                                    ("Longest streak", format!("{} days", stats.longest_streak)),
+                                    ("Average time to complete", average),
+                                ] {
+                                    div {
+                                        class: card,
+                                        p { class: "text-sm text-gray-500", "{label}" }
+                                        p { class: "text-2xl font-semibold text-gray-900 mt-1", "{value}" }
+                                    }
+                                }
+                            }
+                            div {
+                                class: card,
+                                h2 { class: "text-lg font-semibold text-gray-900 mb-4", "Completed per day" }
+                                {bar_chart(&stats.completed_per_day, "#3b82f6", 5)}
+                            }
+                            div {
+                                class: card,
+                                h2 { class: "text-lg font-semibold text-gray-900 mb-4", "Completed per week" }
+                                {bar_chart(&stats.completed_per_week, "#6366f1", 2)}
+                            }
+                            div {
+                                class: card,
+                                h2 { class: "text-lg font-semibold text-gray-900 mb-4", "Open by priority" }
+                                {priority_chart(&by_priority)}
+                            }
+                        }
+                    },
+                    Some(Err(e)) => rsx! { p { class: "text-red-500", "Error loading statistics: {e}" } },
+                    None => rsx! { p { class: "text-gray-500", "Loading..." } },
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,133 @@
+use dioxus::prelude::*;
+use dioxus_router::{hooks::use_navigator, prelude::Link};
+use uuid::Uuid;
+
+use crate::{
+    Route,
+    models::Todo,
+    components::{TodoDependenciesPanel, TodoHistory, TodoItem},
+    utils::{confirm_complete_blocked, delete_todo, force_patch_todo, patch_todo, save_as_template, set_archived, WriteOutcome, API_URL},
+};
+
+async fn fetch_todo(id: Uuid) -> Result<Todo, reqwest::Error> {
+    let url = format!("{}/todos/{}", API_URL, id);
+    reqwest::get(&url).await?.json::<Todo>().await
+}
+
+#[component]
+pub fn TodoDetail(id: Uuid) -> Element {
+    let mut todo = use_resource(move || fetch_todo(id));
+    let navigator = use_navigator();
+
+    let handle_toggle = move |_| {
+        if let Some(Ok(current)) = todo.read().as_ref() {
+            let completed = !current.completed;
+            let version = current.version;
+            spawn(async move {
+                let update = serde_json::json!({ "completed": completed });
+                if let WriteOutcome::Blocked(_) = patch_todo(id, version, &update).await {
+                    if confirm_complete_blocked() {
+                        force_patch_todo(id, version, &update).await;
+                    }
+                }
+                todo.restart();
+            });
+        }
+    };
+
+    let handle_delete = move |_| {
+        if let Some(Ok(current)) = todo.read().as_ref() {
+            let version = current.version;
+            spawn(async move {
+                match delete_todo(id, version).await {
+                    WriteOutcome::Saved => { navigator.push(Route::TodoList {}); }
+                    // The todo changed since it was loaded; show the fresh copy instead of deleting it.
+                    WriteOutcome::Conflict(_) => todo.restart(),
+                    WriteOutcome::Blocked(e) | WriteOutcome::Failed(e) => log::error!("Failed to delete todo {}: {}", id, e),
+                }
+            });
+        }
+    };
+
+    let handle_save_template = move |_| {
+        let default_name = todo.read().as_ref().and_then(|t| t.as_ref().ok()).map(|t| t.title.clone()).unwrap_or_default();
+        let Some(name) = gloo::dialogs::prompt("Template name", Some(&default_name)) else { return };
+        spawn(async move {
+            if let Err(e) = save_as_template(id, &name).await {
+                log::error!("{}", e);
+            }
+        });
+    };
+
+    let handle_archive = move |_| {
+        let archived = todo.read().as_ref().and_then(|t| t.as_ref().ok()).is_some_and(|t| t.archived_at.is_some());
+        spawn(async move {
+            if let Err(e) = set_archived(id, !archived).await {
+                log::error!("{}", e);
+            }
+            todo.restart();
+        });
+    };
+
+    rsx! {
+        div {
+            class: "min-h-screen bg-gray-50 p-4 sm:p-8",
+            div {
+                class: "max-w-3xl mx-auto",
+
+                // Header
+                div {
+                    class: "flex justify-between items-center mb-6",
+                    h1 {
+                        class: "text-3xl font-bold text-gray-900",
+                        "Todo Details"
+                    },
+                    Link {
+                        to: Route::TodoList {},
+                        class: "text-blue-600 hover:text-blue-800 text-sm font-medium",
+                        "← Back to Todos"
+                    }
+                }
+
+                div {
+                    class: "bg-white p-6 rounded-lg shadow-md",
+                    match &*todo.read() {
+                        Some(Ok(current)) => rsx! {
+                            ul {
+                                TodoItem {
+                                    todo: current.clone(),
+                                    on_toggle: handle_toggle,
+                                    on_edit: move |_| { navigator.push(Route::TodoList {}); },
+                                    on_delete: handle_delete,
+                                }
+                            }
+                            div {
+                                class: "flex justify-end gap-4 mt-2",
+                                button {
+                                    class: "text-sm text-blue-600 hover:text-blue-800",
+                                    onclick: handle_archive,
+                                    if current.archived_at.is_some() { "Unarchive" } else { "Archive" }
+                                }
+                                button {
+                                    class: "text-sm text-blue-600 hover:text-blue-800",
+                                    onclick: handle_save_template,
+                                    "Save as template"
+                                }
+                            }
+                            TodoDependenciesPanel {
+                                todo_id: id,
+                                on_change: move |_| todo.restart(),
+                            }
+                            TodoHistory {
+                                todo_id: id,
+                                on_revert: move |_| todo.restart(),
+                            }
+                        },
+                        Some(Err(e)) => rsx! { p { class: "text-red-500", "Error loading todo: {e}" } },
+                        None => rsx! { p { class: "text-gray-500", "Loading..." } },
+                    }
+                }
+            }
+        }
+    }
+}
@@ -1,4 +1,5 @@
 use dioxus::prelude::*;
+use std::collections::HashSet;
 use uuid::Uuid;
 use reqwest;
 use serde_json::json;
@@ -7,9 +8,9 @@ use dioxus_router::prelude::Link;
 
 use crate::{
     Route,
-    models::{Todo, TodoForm},
-    components::{TodoItem, TodoForm as TodoFormComponent, Layout},
-    utils::API_URL,
+    models::{BulkOperation, EditConflict, Todo, TodoEvent, TodoForm},
+    components::{apply_todo_event, use_todo_events, BulkActionBar, ConflictDialog, SavedViewsSidebar, TodoItem, TodoForm as TodoFormComponent},
+    utils::{bulk_update, confirm_complete_blocked, delete_todo, force_patch_todo, load_views, move_todo, patch_todo, WriteOutcome, API_URL},
 };
 
 #[derive(Debug, Clone, PartialEq, Copy)]
@@ -26,29 +27,67 @@ enum ViewState {
     EditForm(Uuid),
 }
 
-fn fetch_todos() -> impl Future<Output = Result<Vec<Todo>, reqwest::Error>> {
+fn fetch_todos(view: Option<Uuid>) -> impl Future<Output = Result<Vec<Todo>, reqwest::Error>> {
     async move {
-        let url = format!("{}/todos", API_URL);
+        let url = match view {
+            Some(id) => format!("{}/todos?view={}", API_URL, id),
+            None => format!("{}/todos", API_URL),
+        };
         reqwest::get(&url).await?.json::<Vec<Todo>>().await
     }
 }
 
 #[component]
 pub fn TodoList() -> Element {
-    let mut todos = use_resource(fetch_todos);
+    // Saved view narrowing the list on the server; the All/Active/Completed filter applies on top.
+    let mut active_view = use_signal(|| None::<Uuid>);
+    let mut todos = use_resource(move || fetch_todos(active_view()));
+    let mut views = use_resource(load_views);
     let mut filter = use_signal(|| FilterState::All);
     let mut view = use_signal(|| ViewState::List);
+    let mut dragging = use_signal(|| None::<Uuid>);
+    // Order shown while a move request is in flight; dropped once the list is refetched.
+    let mut optimistic_order = use_signal(|| None::<Vec<Uuid>>);
+    // The loaded list with changes pushed by the server applied, so edits made in other tabs and
+    // apps show up without a refetch.
+    let mut live_todos = use_signal(Vec::<Todo>::new);
+
+    use_effect(move || {
+        if let Some(Ok(loaded)) = &*todos.read() {
+            live_todos.set(loaded.clone());
+        }
+        optimistic_order.set(None);
+    });
+
+    use_todo_events(move |event| {
+        // Only the server knows whether a todo new to the list matches the active saved view.
+        let unlisted = match &event {
+            TodoEvent::Created { todo } | TodoEvent::Updated { todo } => {
+                !live_todos.peek().iter().any(|t| t.id == todo.id)
+            }
+            _ => false,
+        };
+        if (unlisted && active_view.peek().is_some()) || !apply_todo_event(&mut live_todos.write(), event) {
+            todos.restart();
+        }
+    });
+
+    // Keep the sidebar counts live by refetching them whenever the list changes.
+    use_effect(move || {
+        let _ = todos.read();
+        views.restart();
+    });
 
     let filtered_todos = use_memo(move || {
-        if let Some(Ok(todos_vec)) = todos.read().as_ref() {
-            todos_vec.iter().filter(|todo| match *filter.read() {
-                FilterState::All => true,
-                FilterState::Active => !todo.completed,
-                FilterState::Completed => todo.completed,
-            }).cloned().collect::<Vec<Todo>>()
-        } else {
-            vec![]
+        let mut visible = live_todos.read().iter().filter(|todo| match *filter.read() {
+            FilterState::All => true,
+            FilterState::Active => !todo.completed,
+            FilterState::Completed => todo.completed,
+        }).cloned().collect::<Vec<Todo>>();
+        if let Some(order) = optimistic_order.read().as_ref() {
+            visible.sort_by_key(|todo| order.iter().position(|id| *id == todo.id).unwrap_or(usize::MAX));
         }
+        visible
     });
 
     let handle_add_todo = move |form: TodoForm| {
@@ -58,7 +97,11 @@ pub fn TodoList() -> Element {
             let new_todo = json!({
                 "title": form.title,
                 "priority": form.priority,
-                "description": if form.description.is_empty() { None } else { Some(form.description) }
+                "description": if form.description.is_empty() { None } else { Some(form.description) },
+                "estimated_minutes": form.estimated_minutes,
+                "custom_fields": form.custom_fields,
+                "project_id": form.project_id,
+                "tags": form.tags
             });
 
             if client.post(&url).json(&new_todo).send().await.is_ok() {
@@ -68,176 +111,333 @@ pub fn TodoList() -> Element {
         });
     };
 
-    let handle_update_todo = move |(id, form): (Uuid, TodoForm)| {
+    let mut conflict = use_signal(|| None::<EditConflict>);
+    // Form values to start the edit form from instead of the stored todo, used when merging a conflict.
+    let mut draft = use_signal(|| None::<TodoForm>);
+
+    let version_of = move |id: Uuid| -> Option<i64> {
+        live_todos.read().iter().find(|t| t.id == id).map(|t| t.version)
+    };
+
+    let save_form = move |id: Uuid, version: i64, form: TodoForm| {
         spawn(async move {
-            let client = reqwest::Client::new();
-            let url = format!("{}/todos/{}", API_URL, id);
             let updated_todo = json!({
                 "title": form.title,
                 "priority": form.priority,
-                "description": if form.description.is_empty() { None } else { Some(form.description) }
+                "description": if form.description.is_empty() { None } else { Some(form.description.clone()) },
+                "estimated_minutes": form.estimated_minutes,
+                "custom_fields": form.custom_fields,
+                "project_id": form.project_id,
+                "tags": form.tags
             });
 
-            if client.put(&url).json(&updated_todo).send().await.is_ok() {
-                todos.restart();
+            match patch_todo(id, version, &updated_todo).await {
+                WriteOutcome::Saved => todos.restart(),
+                WriteOutcome::Conflict(server) => conflict.set(Some(EditConflict { form, server })),
+                WriteOutcome::Blocked(e) | WriteOutcome::Failed(e) => log::error!("Failed to update todo {}: {}", id, e),
             }
+            draft.set(None);
             view.set(ViewState::List);
         });
     };
 
+    let handle_update_todo = move |(id, form): (Uuid, TodoForm)| {
+        if let Some(version) = version_of(id) {
+            save_form(id, version, form);
+        }
+    };
+
     let handle_delete_todo = move |id: Uuid| {
-        spawn(async move {
-            let client = reqwest::Client::new();
-            let url = format!("{}/todos/{}", API_URL, id);
-            if let Ok(_) = client.delete(&url).send().await {
+        if let Some(version) = version_of(id) {
+            spawn(async move {
+                if let WriteOutcome::Failed(e) = delete_todo(id, version).await {
+                    log::error!("Failed to delete todo {}: {}", id, e);
+                }
                 todos.restart();
+            });
+        }
+    };
+
+    let handle_drop = move |target: Uuid| {
+        let Some(dragged) = dragging.take() else { return };
+        if dragged == target {
+            return;
+        }
+        let mut ids: Vec<Uuid> = filtered_todos.read().iter().map(|t| t.id).collect();
+        let (Some(from), Some(to)) = (
+            ids.iter().position(|id| *id == dragged),
+            ids.iter().position(|id| *id == target),
+        ) else {
+            return;
+        };
+        // Removing then inserting at the target's index puts the item below the target when
+        // dragging down and above it when dragging up.
+        ids.remove(from);
+        ids.insert(to, dragged);
+        let after = to.checked_sub(1).map(|i| ids[i]);
+        let before = ids.get(to + 1).copied();
+        optimistic_order.set(Some(ids));
+
+        spawn(async move {
+            match move_todo(dragged, before, after).await {
+                Ok(()) => todos.restart(),
+                Err(e) => {
+                    log::error!("Failed to move todo {}: {}", dragged, e);
+                    optimistic_order.set(None);
+                }
+            }
+        });
+    };
+
+    let mut selected = use_signal(HashSet::<Uuid>::new);
+
+    let handle_select_todo = move |id: Uuid| {
+        let mut set = selected.write();
+        if !set.remove(&id) {
+            set.insert(id);
+        }
+    };
+
+    let run_bulk = move |selection: serde_json::Value, operation: BulkOperation| {
+        spawn(async move {
+            if let Err(e) = bulk_update(selection, &[operation]).await {
+                log::error!("Bulk update failed: {}", e);
             }
+            selected.write().clear();
+            todos.restart();
         });
     };
 
     let handle_toggle_todo = move |id: Uuid| {
-        if let Some(Ok(todos_vec)) = todos.read().as_ref() {
-            if let Some(todo) = todos_vec.iter().find(|t| t.id == id) {
-                let new_completed_status = !todo.completed;
-                spawn(async move {
-                    let client = reqwest::Client::new();
-                    let url = format!("{}/todos/{}", API_URL, id);
-                    let updated_todo = json!({ "completed": new_completed_status });
-                    if client.put(&url).json(&updated_todo).send().await.is_ok() {
-                        todos.restart();
+        if let Some(todo) = live_todos.read().iter().find(|t| t.id == id) {
+            let new_completed_status = !todo.completed;
+            let version = todo.version;
+            spawn(async move {
+                let updated_todo = json!({ "completed": new_completed_status });
+                // A stale toggle is simply dropped; the refetch shows the current state.
+                if let WriteOutcome::Blocked(_) = patch_todo(id, version, &updated_todo).await {
+                    if confirm_complete_blocked() {
+                        force_patch_todo(id, version, &updated_todo).await;
                     }
-                });
-            }
+                }
+                todos.restart();
+            });
         }
     };
 
     rsx! {
         div {
             class: "min-h-screen bg-gray-50 p-4 sm:p-8",
+            if let Some(current) = conflict.read().clone() {
+                ConflictDialog {
+                    conflict: current,
+                    on_keep_mine: move |c: EditConflict| {
+                        conflict.set(None);
+                        save_form(c.server.id, c.server.version, c.form);
+                    },
+                    on_use_server: move |_| {
+                        conflict.set(None);
+                        todos.restart();
+                    },
+                    on_merge: move |c: EditConflict| {
+                        conflict.set(None);
+                        draft.set(Some(c.form));
+                        todos.restart();
+                        view.set(ViewState::EditForm(c.server.id));
+                    },
+                }
+            }
             div {
-                class: "max-w-3xl mx-auto",
+                class: "max-w-5xl mx-auto flex flex-col md:flex-row gap-6",
 
-                // Header
-                div {
-                    class: "flex justify-between items-center mb-6",
-                    h1 {
-                        class: "text-3xl font-bold text-gray-900",
-                        "My Todos"
+                // Saved views
+                SavedViewsSidebar {
+                    views: views.read().clone().and_then(Result::ok).unwrap_or_default(),
+                    active: active_view(),
+                    on_select: move |id| {
+                        selected.write().clear();
+                        active_view.set(id);
                     },
-                    Link {
-                        to: Route::Home {},
-                        class: "text-blue-600 hover:text-blue-800 text-sm font-medium",
-                        "← Back to Home"
-                    }
+                    on_change: move |_| views.restart(),
                 }
 
-                // Main content area
                 div {
-                    class: "bg-white p-6 rounded-lg shadow-md",
-                    match *view.read() {
-                        ViewState::List => {
-                            match &*todos.read() {
-                                Some(Ok(_)) => rsx! {
-                                    // Controls: Filter and Add button
-                                    div {
-                                        class: "flex flex-col sm:flex-row justify-between items-center mb-4 gap-4",
-                                        // Filter buttons
+                    class: "flex-1 min-w-0",
+
+                    // Header
+                    div {
+                        class: "flex justify-between items-center mb-6",
+                        h1 {
+                            class: "text-3xl font-bold text-gray-900",
+                            "My Todos"
+                        },
+                        div {
+                            class: "flex items-center space-x-4",
+                            Link {
+                                to: Route::Board {},
+                                class: "text-blue-600 hover:text-blue-800 text-sm font-medium",
+                                "Board view"
+                            }
+                            Link {
+                                to: Route::Stats {},
+                                class: "text-blue-600 hover:text-blue-800 text-sm font-medium",
+                                "Stats"
+                            }
+                            Link {
+                                to: Route::Archive {},
+                                class: "text-blue-600 hover:text-blue-800 text-sm font-medium",
+                                "Archive"
+                            }
+                            Link {
+                                to: Route::ImportExport {},
+                                class: "text-blue-600 hover:text-blue-800 text-sm font-medium",
+                                "Import & Export"
+                            }
+                            Link {
+                                to: Route::Home {},
+                                class: "text-blue-600 hover:text-blue-800 text-sm font-medium",
+                                "← Back to Home"
+                            }
+                        }
+                    }
+
+                    // Main content area
+                    div {
+                        class: "bg-white p-6 rounded-lg shadow-md",
+                        match *view.read() {
+                            ViewState::List => {
+                                match &*todos.read() {
+                                    Some(Ok(_)) => rsx! {
+                                        // Controls: Filter and Add button
                                         div {
-                                            class: "flex space-x-2 p-1 bg-gray-100 rounded-lg",
-                                            for (f, label) in [
-                                                (FilterState::All, "All"),
-                                                (FilterState::Active, "Active"),
-                                                (FilterState::Completed, "Completed")
-                                            ] {
-                                                button {
-                                                    class: if *filter.read() == f { "bg-white text-blue-600 shadow-sm" } else { "text-gray-500 hover:text-gray-700" },
-                                                    class: "px-3 py-1 rounded-md text-sm font-medium transition-colors",
-                                                    onclick: move |_| filter.set(f.clone()),
-                                                    "{label}"
+                                            class: "flex flex-col sm:flex-row justify-between items-center mb-4 gap-4",
+                                            // Filter buttons
+                                            div {
+                                                class: "flex space-x-2 p-1 bg-gray-100 rounded-lg",
+                                                for (f, label) in [
+                                                    (FilterState::All, "All"),
+                                                    (FilterState::Active, "Active"),
+                                                    (FilterState::Completed, "Completed")
+                                                ] {
+                                                    button {
+                                                        class: if *filter.read() == f { "bg-white text-blue-600 shadow-sm" } else { "text-gray-500 hover:text-gray-700" },
+                                                        class: "px-3 py-1 rounded-md text-sm font-medium transition-colors",
+                                                        onclick: move |_| filter.set(f.clone()),
+                                                        "{label}"
+                                                    }
                                                 }
                                             }
+                                            // Clear completed button
+                                            button {
+                                                class: "text-sm text-gray-500 hover:text-red-600",
+                                                onclick: move |_| run_bulk(json!({ "filter": { "completed": true } }), BulkOperation::Delete),
+                                                "Clear completed"
+                                            }
+                                            // Add Todo button
+                                            button {
+                                                class: "bg-blue-600 hover:bg-blue-700 text-white font-semibold py-2 px-4 rounded-lg transition-colors shadow",
+                                                onclick: move |_| view.set(ViewState::AddForm),
+                                                "＋ Add Todo"
+                                            }
                                         }
-                                        // Add Todo button
-                                        button {
-                                            class: "bg-blue-600 hover:bg-blue-700 text-white font-semibold py-2 px-4 rounded-lg transition-colors shadow",
-                                            onclick: move |_| view.set(ViewState::AddForm),
-                                            "＋ Add Todo"
-                                        }
-                                    }
 
-                                    // Todo List
-                                    if filtered_todos.read().is_empty() {
-                                        // Empty state message
-                                        div {
-                                            class: "text-center py-12",
-                                            p {
-                                                class: "text-gray-500",
-                                                "No todos here. Add one to get started!"
+                                        // Bulk actions for the selected todos
+                                        if !selected.read().is_empty() {
+                                            BulkActionBar {
+                                                count: selected.read().len(),
+                                                on_action: move |operation| {
+                                                    let ids: Vec<Uuid> = selected.read().iter().copied().collect();
+                                                    run_bulk(json!({ "ids": ids }), operation);
+                                                },
+                                                on_clear: move |_| selected.write().clear(),
                                             }
                                         }
-                                    } else {
-                                        // List of todos
-                                        ul {
-                                            class: "space-y-3",
-                                            for todo in filtered_todos.read().iter().cloned() {
-                                                TodoItem {
-                                                    key: "{todo.id}",
-                                                    todo: todo.clone(),
-                                                    on_toggle: move |_| handle_toggle_todo(todo.id),
-                                                    on_edit: move |_| view.set(ViewState::EditForm(todo.id)),
-                                                    on_delete: move |_| handle_delete_todo(todo.id),
+
+                                        // Todo List
+                                        if filtered_todos.read().is_empty() {
+                                            // Empty state message
+                                            div {
+                                                class: "text-center py-12",
+                                                p {
+                                                    class: "text-gray-500",
+                                                    "No todos here. Add one to get started!"
+                                                }
+                                            }
+                                        } else {
+                                            // List of todos
+                                            ul {
+                                                class: "space-y-3",
+                                                for todo in filtered_todos.read().iter().cloned() {
+                                                    TodoItem {
+                                                        key: "{todo.id}",
+                                                        todo: todo.clone(),
+                                                        selected: selected.read().contains(&todo.id),
+                                                        on_select: handle_select_todo,
+                                                        on_drag_start: move |id| dragging.set(Some(id)),
+                                                        on_drop: handle_drop,
+                                                        on_toggle: move |_| handle_toggle_todo(todo.id),
+                                                        on_edit: move |_| view.set(ViewState::EditForm(todo.id)),
+                                                        on_delete: move |_| handle_delete_todo(todo.id),
+                                                    }
                                                 }
                                             }
                                         }
-                                    }
-                                },
-                                Some(Err(e)) => rsx! { p { class: "text-red-500", "Error loading todos: {e}" } },
-                                None => rsx! { p { class: "text-gray-500", "Loading..." } },
-                            }
-                        },
+                                    },
+                                    Some(Err(e)) => rsx! { p { class: "text-red-500", "Error loading todos: {e}" } },
+                                    None => rsx! { p { class: "text-gray-500", "Loading..." } },
+                                }
+                            },
 
-                        // ViewState::AddForm: Show the form for adding a new todo
-                        ViewState::AddForm => rsx! {
-                            TodoFormComponent {
-                                on_submit: handle_add_todo,
-                                on_cancel: move |_| view.set(ViewState::List),
-                                submit_text: "Create Todo".to_string()
-                            }
-                        },
+                            // ViewState::AddForm: Show the form for adding a new todo
+                            ViewState::AddForm => rsx! {
+                                TodoFormComponent {
+                                    on_submit: handle_add_todo,
+                                    on_cancel: move |_| view.set(ViewState::List),
+                                    submit_text: "Create Todo".to_string()
+                                }
+                            },
 
-                        // ViewState::EditForm: Show the form for editing a todo
-                        ViewState::EditForm(id) => {
-                             if let Some(Ok(todos_vec)) = todos.read().as_ref() {
-                                if let Some(todo) = todos_vec.iter().find(|t| t.id == id).cloned() {
-                                    let initial_value = TodoForm {
-                                        id: Some(todo.id),
-                                        title: todo.title.clone(),
-                                        description: todo.description.clone().unwrap_or_default(),
-                                        priority: todo.priority,
-                                    };
-                                    rsx! {
-                                        TodoFormComponent {
-                                            initial_form: Some(initial_value),
-                                            on_submit: move |form| handle_update_todo((id, form)),
-                                            on_cancel: move |_| view.set(ViewState::List),
-                                            submit_text: "Update Todo".to_string()
-                                        }
-                                    }
-                                } else {
-                                     rsx! {
-                                        p {
-                                            class: "text-red-500",
-                                            "Error: Todo not found."
+                            // ViewState::EditForm: Show the form for editing a todo
+                            ViewState::EditForm(id) => {
+                                 if let Some(Ok(_)) = todos.read().as_ref() {
+                                    if let Some(todo) = live_todos.read().iter().find(|t| t.id == id).cloned() {
+                                        let initial_value = draft.read().clone().unwrap_or(TodoForm {
+                                            id: Some(todo.id),
+                                            title: todo.title.clone(),
+                                            description: todo.description.clone().unwrap_or_default(),
+                                            priority: todo.priority,
+                                            estimated_minutes: todo.estimated_minutes,
+                                            custom_fields: todo.custom_fields.clone(),
+                                            project_id: todo.project_id,
+                                            tags: todo.tags.clone(),
+                                        });
+                                        rsx! {
+                                            TodoFormComponent {
+                                                initial_form: Some(initial_value),
+                                                on_submit: move |form| handle_update_todo((id, form)),
+                                                on_cancel: move |_| {
+                                                    draft.set(None);
+                                                    view.set(ViewState::List);
+                                                },
+                                                submit_text: "Update Todo".to_string()
+                                            }
                                         }
-                                        button {
-                                            class: "mt-2 text-blue-600",
-                                            onclick: move |_| view.set(ViewState::List),
-                                            "Back to list"
+                                    } else {
+                                         rsx! {
+                                            p {
+                                                class: "text-red-500",
+                                                "Error: Todo not found."
+                                            }
+                                            button {
+                                                class: "mt-2 text-blue-600",
+                                                onclick: move |_| view.set(ViewState::List),
+                                                "Back to list"
+                                            }
                                         }
                                     }
+                                } else {
+                                    rsx! { p { class: "text-gray-500", "Loading..." } }
                                 }
-                            } else {
-                                rsx! { p { class: "text-gray-500", "Loading..." } }
                             }
                         }
                     }
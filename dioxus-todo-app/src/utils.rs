@@ -1,5 +1,6 @@
-use crate::models::{Todo, User, Credentials};
+use crate::models::{ApiToken, BulkOperation, CalendarFeed, CsvImportReport, CustomField, ImportReport, NewTodo, ParsedTodo, Project, SavedView, Status, Template, TextImportReport, TimeEntry, Todo, TodoStats, TodoDependencies, User, UserSettings, Credentials};
 use gloo_storage::{LocalStorage, Storage};
+use std::collections::BTreeMap;
 use uuid::Uuid;
 use reqwest;
 
@@ -7,6 +8,7 @@ pub const API_URL: &str = "http://localhost:3000/api";
 
 const TODOS_STORAGE_KEY: &str = "dioxus_todos";
 const USER_STORAGE_KEY: &str = "dioxus_user";
+const LIVE_TOKEN_STORAGE_KEY: &str = "dioxus_live_token";
 
 // Todo storage utilities
 pub fn save_todos(todos: &[Todo]) -> Result<(), String> {
@@ -14,6 +16,520 @@ pub fn save_todos(todos: &[Todo]) -> Result<(), String> {
         .map_err(|e| format!("Failed to save todos: {}", e))
 }
 
+// Value for the `If-Match` header guarding writes to a todo at `version`
+pub fn if_match(version: i64) -> String {
+    format!("\"{}\"", version)
+}
+
+// Result of a write guarded by `If-Match`
+pub enum WriteOutcome {
+    Saved,
+    Conflict(Todo),
+    // Completing was refused because open todos still block this one
+    Blocked(String),
+    Failed(String),
+}
+
+async fn guarded_write(request: reqwest::RequestBuilder, version: i64) -> WriteOutcome {
+    match request.header("If-Match", if_match(version)).send().await {
+        Ok(res) if res.status() == reqwest::StatusCode::PRECONDITION_FAILED => {
+            match res.json::<Todo>().await {
+                Ok(server) => WriteOutcome::Conflict(server),
+                Err(e) => WriteOutcome::Failed(e.to_string()),
+            }
+        }
+        Ok(res) if res.status() == reqwest::StatusCode::CONFLICT => {
+            WriteOutcome::Blocked(res.text().await.unwrap_or_default())
+        }
+        Ok(res) if res.status().is_success() => WriteOutcome::Saved,
+        Ok(res) => WriteOutcome::Failed(res.status().to_string()),
+        Err(e) => WriteOutcome::Failed(e.to_string()),
+    }
+}
+
+// Sends `patch` as a JSON Merge Patch, so fields set to `null` are cleared on the server
+pub async fn patch_todo(id: Uuid, version: i64, patch: &serde_json::Value) -> WriteOutcome {
+    send_patch(id, version, patch, false).await
+}
+
+// Like `patch_todo`, but completes the todo even if it still has open blockers
+pub async fn force_patch_todo(id: Uuid, version: i64, patch: &serde_json::Value) -> WriteOutcome {
+    send_patch(id, version, patch, true).await
+}
+
+async fn send_patch(id: Uuid, version: i64, patch: &serde_json::Value, force: bool) -> WriteOutcome {
+    let client = reqwest::Client::new();
+    let url = format!("{}/todos/{}?force={}", API_URL, id, force);
+    let request = client
+        .patch(&url)
+        .header("Content-Type", "application/merge-patch+json")
+        .body(patch.to_string());
+    guarded_write(request, version).await
+}
+
+pub async fn delete_todo(id: Uuid, version: i64) -> WriteOutcome {
+    let client = reqwest::Client::new();
+    let url = format!("{}/todos/{}", API_URL, id);
+    guarded_write(client.delete(&url), version).await
+}
+
+// Runs `operations` over `selection` (`{"ids": [...]}` or `{"filter": {...}}`) in one request
+pub async fn bulk_update(selection: serde_json::Value, operations: &[BulkOperation]) -> Result<(), String> {
+    let client = reqwest::Client::new();
+    let mut body = selection;
+    body["operations"] = serde_json::to_value(operations).map_err(|e| e.to_string())?;
+    let res = client.post(format!("{}/todos/bulk", API_URL))
+        .json(&body)
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+
+    if res.status().is_success() {
+        Ok(())
+    } else {
+        Err(res.text().await.map_err(|e| e.to_string())?)
+    }
+}
+
+// Moves a todo between `after` and `before`; either may be `None` at the ends of the list
+pub async fn move_todo(id: Uuid, before: Option<Uuid>, after: Option<Uuid>) -> Result<(), String> {
+    let client = reqwest::Client::new();
+    let res = client.post(format!("{}/todos/{}/move", API_URL, id))
+        .json(&serde_json::json!({ "before": before, "after": after }))
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+
+    if res.status().is_success() {
+        Ok(())
+    } else {
+        Err(res.text().await.map_err(|e| e.to_string())?)
+    }
+}
+
+// Statuses used by a project's todos: its own workflow if it has one, the shared one otherwise
+pub async fn load_workflow(project_id: Option<Uuid>) -> Result<Vec<Status>, String> {
+    let client = reqwest::Client::new();
+    let mut request = client.get(format!("{}/statuses", API_URL));
+    if let Some(project_id) = project_id {
+        request = request.query(&[("project_id", project_id.to_string())]);
+    }
+    request
+        .send()
+        .await
+        .map_err(|e| e.to_string())?
+        .json::<Vec<Status>>()
+        .await
+        .map_err(|e| e.to_string())
+}
+
+pub async fn load_projects() -> Result<Vec<Project>, String> {
+    let client = reqwest::Client::new();
+    client.get(format!("{}/projects", API_URL))
+        .send()
+        .await
+        .map_err(|e| e.to_string())?
+        .json::<Vec<Project>>()
+        .await
+        .map_err(|e| e.to_string())
+}
+
+pub async fn load_custom_fields() -> Result<Vec<CustomField>, String> {
+    let client = reqwest::Client::new();
+    client.get(format!("{}/custom-fields", API_URL))
+        .send()
+        .await
+        .map_err(|e| e.to_string())?
+        .json::<Vec<CustomField>>()
+        .await
+        .map_err(|e| e.to_string())
+}
+
+pub async fn load_templates() -> Result<Vec<Template>, String> {
+    let client = reqwest::Client::new();
+    client.get(format!("{}/templates", API_URL))
+        .send()
+        .await
+        .map_err(|e| e.to_string())?
+        .json::<Vec<Template>>()
+        .await
+        .map_err(|e| e.to_string())
+}
+
+// Fills in a template's placeholders without creating the todo
+pub async fn render_template(id: Uuid, name: Option<String>) -> Result<NewTodo, String> {
+    let client = reqwest::Client::new();
+    let variables = match name {
+        Some(name) => serde_json::json!({ "name": name }),
+        None => serde_json::json!({}),
+    };
+    let res = client.post(format!("{}/templates/{}/render", API_URL, id))
+        .json(&serde_json::json!({ "variables": variables }))
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    if !res.status().is_success() {
+        return Err(format!("Failed to render template: {}", res.status()));
+    }
+    res.json::<NewTodo>().await.map_err(|e| e.to_string())
+}
+
+pub async fn save_as_template(todo_id: Uuid, name: &str) -> Result<Template, String> {
+    let client = reqwest::Client::new();
+    let res = client.post(format!("{}/todos/{}/template", API_URL, todo_id))
+        .json(&serde_json::json!({ "name": name }))
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    if !res.status().is_success() {
+        return Err(format!("Failed to save template: {}", res.status()));
+    }
+    res.json::<Template>().await.map_err(|e| e.to_string())
+}
+
+// Parses quick-add text such as "Pay rent tomorrow 9am !high" without creating anything
+pub async fn parse_quick_add(text: String) -> Result<ParsedTodo, String> {
+    let client = reqwest::Client::new();
+    let res = client.post(format!("{}/todos/parse", API_URL))
+        .json(&serde_json::json!({ "text": text }))
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    if !res.status().is_success() {
+        return Err(format!("Failed to parse: {}", res.status()));
+    }
+    res.json::<ParsedTodo>().await.map_err(|e| e.to_string())
+}
+
+pub async fn load_views() -> Result<Vec<SavedView>, String> {
+    let client = reqwest::Client::new();
+    client.get(format!("{}/views", API_URL))
+        .send()
+        .await
+        .map_err(|e| e.to_string())?
+        .json::<Vec<SavedView>>()
+        .await
+        .map_err(|e| e.to_string())
+}
+
+// Saves a filter expression; the server's parse error is returned for an invalid one
+pub async fn create_view(name: &str, query: &str) -> Result<SavedView, String> {
+    let client = reqwest::Client::new();
+    let res = client.post(format!("{}/views", API_URL))
+        .json(&serde_json::json!({ "name": name, "query": query }))
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    if !res.status().is_success() {
+        return Err(res.text().await.unwrap_or_else(|e| e.to_string()));
+    }
+    res.json::<SavedView>().await.map_err(|e| e.to_string())
+}
+
+pub async fn delete_view(id: Uuid) -> Result<(), String> {
+    let client = reqwest::Client::new();
+    let res = client.delete(format!("{}/views/{}", API_URL, id))
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    if !res.status().is_success() {
+        return Err(format!("Failed to delete view: {}", res.status()));
+    }
+    Ok(())
+}
+
+pub async fn load_stats() -> Result<TodoStats, String> {
+    let client = reqwest::Client::new();
+    client.get(format!("{}/stats", API_URL))
+        .send()
+        .await
+        .map_err(|e| e.to_string())?
+        .json::<TodoStats>()
+        .await
+        .map_err(|e| e.to_string())
+}
+
+// Archived todos, most recently archived first, optionally filtered by a search term
+pub async fn load_archive(q: String) -> Result<Vec<Todo>, String> {
+    let client = reqwest::Client::new();
+    client.get(format!("{}/archive", API_URL))
+        .query(&[("q", q)])
+        .send()
+        .await
+        .map_err(|e| e.to_string())?
+        .json::<Vec<Todo>>()
+        .await
+        .map_err(|e| e.to_string())
+}
+
+// Moves a todo into the archive, or back out of it
+pub async fn set_archived(id: Uuid, archived: bool) -> Result<Todo, String> {
+    let action = if archived { "archive" } else { "unarchive" };
+    let client = reqwest::Client::new();
+    let res = client.post(format!("{}/todos/{}/{}", API_URL, id, action))
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    if !res.status().is_success() {
+        return Err(format!("Failed to {} todo: {}", action, res.status()));
+    }
+    res.json::<Todo>().await.map_err(|e| e.to_string())
+}
+
+pub async fn load_settings() -> Result<UserSettings, String> {
+    let client = reqwest::Client::new();
+    client.get(format!("{}/settings", API_URL))
+        .send()
+        .await
+        .map_err(|e| e.to_string())?
+        .json::<UserSettings>()
+        .await
+        .map_err(|e| e.to_string())
+}
+
+pub async fn save_settings(settings: &UserSettings) -> Result<UserSettings, String> {
+    let client = reqwest::Client::new();
+    let res = client.put(format!("{}/settings", API_URL))
+        .json(settings)
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    if !res.status().is_success() {
+        return Err(res.text().await.unwrap_or_else(|e| e.to_string()));
+    }
+    res.json::<UserSettings>().await.map_err(|e| e.to_string())
+}
+
+// Imports a JSON export; `strategy` is `skip`, `overwrite` or `duplicate`
+pub async fn import_json(document: String, strategy: &str, dry_run: bool) -> Result<ImportReport, String> {
+    let client = reqwest::Client::new();
+    let res = client.post(format!("{}/import", API_URL))
+        .query(&[("strategy", strategy), ("dry_run", if dry_run { "true" } else { "false" })])
+        .header("Content-Type", "application/json")
+        .body(document)
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    if !res.status().is_success() {
+        return Err(res.text().await.unwrap_or_else(|e| e.to_string()));
+    }
+    res.json::<ImportReport>().await.map_err(|e| e.to_string())
+}
+
+// Imports todos from CSV; an empty `mapping` uses the header names as they are
+pub async fn import_csv(
+    csv: String,
+    mapping: &BTreeMap<String, String>,
+    dry_run: bool,
+) -> Result<CsvImportReport, String> {
+    let client = reqwest::Client::new();
+    let res = client.post(format!("{}/import/csv", API_URL))
+        .json(&serde_json::json!({ "csv": csv, "mapping": mapping, "dry_run": dry_run }))
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    if !res.status().is_success() {
+        return Err(res.text().await.unwrap_or_else(|e| e.to_string()));
+    }
+    res.json::<CsvImportReport>().await.map_err(|e| e.to_string())
+}
+
+// Sends a file's contents to an importer such as `import/todotxt`
+pub async fn import_file(path: &str, contents: String, dry_run: bool) -> Result<TextImportReport, String> {
+    let client = reqwest::Client::new();
+    let res = client.post(format!("{}/{}", API_URL, path))
+        .query(&[("dry_run", if dry_run { "true" } else { "false" })])
+        .header("Content-Type", "text/plain")
+        .body(contents)
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    if !res.status().is_success() {
+        return Err(res.text().await.unwrap_or_else(|e| e.to_string()));
+    }
+    res.json::<TextImportReport>().await.map_err(|e| e.to_string())
+}
+
+// The calendar subscription, or `None` while the feed is turned off
+pub async fn load_calendar_feed() -> Result<Option<CalendarFeed>, String> {
+    let client = reqwest::Client::new();
+    let res = client.get(format!("{}/calendar/feed", API_URL))
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    if res.status() == reqwest::StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+    res.json::<CalendarFeed>().await.map(Some).map_err(|e| e.to_string())
+}
+
+// Turns the feed on with a new secret URL; the old URL stops working
+pub async fn reset_calendar_feed() -> Result<CalendarFeed, String> {
+    let client = reqwest::Client::new();
+    client.post(format!("{}/calendar/feed", API_URL))
+        .send()
+        .await
+        .map_err(|e| e.to_string())?
+        .json::<CalendarFeed>()
+        .await
+        .map_err(|e| e.to_string())
+}
+
+pub async fn delete_calendar_feed() -> Result<(), String> {
+    let client = reqwest::Client::new();
+    client.delete(format!("{}/calendar/feed", API_URL))
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+// The URL calendar apps subscribe to
+pub fn calendar_feed_url(feed: &CalendarFeed) -> String {
+    format!("{}/calendar.ics?token={}", API_URL, feed.token)
+}
+
+pub async fn load_api_tokens() -> Result<Vec<ApiToken>, String> {
+    let client = reqwest::Client::new();
+    client.get(format!("{}/tokens", API_URL))
+        .send()
+        .await
+        .map_err(|e| e.to_string())?
+        .json::<Vec<ApiToken>>()
+        .await
+        .map_err(|e| e.to_string())
+}
+
+pub async fn create_api_token(name: &str) -> Result<ApiToken, String> {
+    let client = reqwest::Client::new();
+    let res = client.post(format!("{}/tokens", API_URL))
+        .json(&serde_json::json!({ "name": name }))
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    if !res.status().is_success() {
+        return Err(res.text().await.unwrap_or_else(|e| e.to_string()));
+    }
+    res.json::<ApiToken>().await.map_err(|e| e.to_string())
+}
+
+pub async fn delete_api_token(id: Uuid) -> Result<(), String> {
+    let client = reqwest::Client::new();
+    client.delete(format!("{}/tokens/{}", API_URL, id))
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+// The address CalDAV apps are given; it lives next to the API rather than under it
+pub fn caldav_url() -> String {
+    format!("{}/dav/", API_URL.trim_end_matches("/api"))
+}
+
+// The WebSocket streaming changes to the user's todos. Browsers cannot set headers on a
+// WebSocket, so an API token goes in the query string. The first connection creates one and
+// later ones reuse it.
+pub async fn live_updates_url() -> Result<String, String> {
+    let token = match LocalStorage::get::<String>(LIVE_TOKEN_STORAGE_KEY) {
+        Ok(token) => token,
+        Err(_) => {
+            let token = create_api_token("Live updates")
+                .await?
+                .token
+                .ok_or_else(|| "The server did not return the new token".to_string())?;
+            LocalStorage::set(LIVE_TOKEN_STORAGE_KEY, &token)
+                .map_err(|e| format!("Failed to save the live updates token: {}", e))?;
+            token
+        }
+    };
+    Ok(format!("{}/ws?token={}", API_URL.replacen("http", "ws", 1), token))
+}
+
+// Asks whether to complete a todo despite its open blockers
+pub fn confirm_complete_blocked() -> bool {
+    gloo::dialogs::confirm("This todo is blocked by open todos. Complete it anyway?")
+}
+
+pub async fn load_dependencies(id: Uuid) -> Result<TodoDependencies, String> {
+    let client = reqwest::Client::new();
+    client.get(format!("{}/todos/{}/dependencies", API_URL, id))
+        .send()
+        .await
+        .map_err(|e| e.to_string())?
+        .json::<TodoDependencies>()
+        .await
+        .map_err(|e| e.to_string())
+}
+
+pub async fn add_blocker(id: Uuid, blocker_id: Uuid) -> Result<(), String> {
+    let client = reqwest::Client::new();
+    let res = client.post(format!("{}/todos/{}/blockers", API_URL, id))
+        .json(&serde_json::json!({ "blocker_id": blocker_id }))
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+
+    if res.status().is_success() {
+        Ok(())
+    } else {
+        Err(res.text().await.map_err(|e| e.to_string())?)
+    }
+}
+
+pub async fn remove_blocker(id: Uuid, blocker_id: Uuid) -> Result<(), String> {
+    let client = reqwest::Client::new();
+    let res = client.delete(format!("{}/todos/{}/blockers/{}", API_URL, id, blocker_id))
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+
+    if res.status().is_success() {
+        Ok(())
+    } else {
+        Err(res.text().await.map_err(|e| e.to_string())?)
+    }
+}
+
+pub async fn load_running_timer() -> Result<Option<TimeEntry>, String> {
+    let client = reqwest::Client::new();
+    client.get(format!("{}/timer", API_URL))
+        .send()
+        .await
+        .map_err(|e| e.to_string())?
+        .json::<Option<TimeEntry>>()
+        .await
+        .map_err(|e| e.to_string())
+}
+
+pub async fn start_timer(todo_id: Uuid) -> Result<TimeEntry, String> {
+    let client = reqwest::Client::new();
+    client.post(format!("{}/todos/{}/timer/start", API_URL, todo_id))
+        .send()
+        .await
+        .map_err(|e| e.to_string())?
+        .json::<TimeEntry>()
+        .await
+        .map_err(|e| e.to_string())
+}
+
+pub async fn stop_timer() -> Result<TimeEntry, String> {
+    let client = reqwest::Client::new();
+    client.post(format!("{}/timer/stop", API_URL))
+        .send()
+        .await
+        .map_err(|e| e.to_string())?
+        .json::<TimeEntry>()
+        .await
+        .map_err(|e| e.to_string())
+}
+
+// Formats a number of seconds as `H:MM:SS`
+pub fn format_duration(seconds: i64) -> String {
+    let seconds = seconds.max(0);
+    format!("{}:{:02}:{:02}", seconds / 3600, (seconds % 3600) / 60, seconds % 60)
+}
+
 pub async fn load_todos() -> Result<Vec<Todo>, String> {
     let client = reqwest::Client::new();
     client.get(format!("{}/todos", API_URL))
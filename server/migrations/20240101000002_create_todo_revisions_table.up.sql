@@ -0,0 +1,12 @@
+-- Add migration script here
+CREATE TABLE IF NOT EXISTS todo_revisions (
+    id TEXT PRIMARY KEY NOT NULL,
+    todo_id TEXT NOT NULL,
+    revision INTEGER NOT NULL,
+    actor_id TEXT NOT NULL,
+    changes TEXT NOT NULL,
+    snapshot TEXT NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (todo_id, revision),
+    FOREIGN KEY (todo_id) REFERENCES todos(id) ON DELETE CASCADE
+);
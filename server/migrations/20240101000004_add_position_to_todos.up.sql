@@ -0,0 +1,15 @@
+-- Add migration script here
+ALTER TABLE todos ADD COLUMN position TEXT NOT NULL DEFAULT '';
+
+-- Seed rank keys in creation order. The trailing `V` keeps keys free of trailing zeros,
+-- which the rank key generator relies on.
+UPDATE todos SET position = (
+    SELECT printf('%08dV', ranked.rn)
+    FROM (
+        SELECT id, ROW_NUMBER() OVER (PARTITION BY user_id ORDER BY created_at, id) AS rn
+        FROM todos
+    ) AS ranked
+    WHERE ranked.id = todos.id
+);
+
+CREATE INDEX IF NOT EXISTS idx_todos_user_position ON todos (user_id, position);
@@ -0,0 +1,35 @@
+-- Add migration script here
+CREATE TABLE IF NOT EXISTS statuses (
+    id TEXT PRIMARY KEY NOT NULL,
+    user_id TEXT NOT NULL,
+    name TEXT NOT NULL,
+    position INTEGER NOT NULL,
+    category TEXT NOT NULL CHECK (category IN ('todo', 'doing', 'done')),
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_statuses_user ON statuses (user_id, position);
+
+ALTER TABLE todos ADD COLUMN status_id TEXT REFERENCES statuses(id);
+
+-- Give every user who already has todos the default workflow, then map `completed`
+-- onto its done/todo columns.
+INSERT INTO statuses (id, user_id, name, position, category)
+SELECT
+    lower(hex(randomblob(4)) || '-' || hex(randomblob(2)) || '-4' || substr(hex(randomblob(2)), 2) || '-a' || substr(hex(randomblob(2)), 2) || '-' || hex(randomblob(6))),
+    owners.user_id,
+    defaults.name,
+    defaults.position,
+    defaults.category
+FROM (SELECT DISTINCT user_id FROM todos) AS owners
+CROSS JOIN (
+    SELECT 'To Do' AS name, 0 AS position, 'todo' AS category
+    UNION ALL SELECT 'In Progress', 1, 'doing'
+    UNION ALL SELECT 'Done', 2, 'done'
+) AS defaults;
+
+UPDATE todos SET status_id = (
+    SELECT statuses.id FROM statuses
+    WHERE statuses.user_id = todos.user_id
+      AND statuses.category = CASE WHEN todos.completed THEN 'done' ELSE 'todo' END
+);
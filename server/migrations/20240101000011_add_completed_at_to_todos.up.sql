@@ -0,0 +1,7 @@
+-- Add migration script here
+ALTER TABLE todos ADD COLUMN completed_at TIMESTAMP;
+
+-- The best guess for todos completed before this column existed is their last update.
+UPDATE todos SET completed_at = updated_at WHERE completed = TRUE;
+
+CREATE INDEX IF NOT EXISTS idx_todos_completed_at ON todos (user_id, completed_at);
@@ -0,0 +1,10 @@
+-- Add migration script here
+ALTER TABLE todos ADD COLUMN archived_at TIMESTAMP;
+
+CREATE INDEX IF NOT EXISTS idx_todos_archived_at ON todos (user_id, archived_at);
+
+CREATE TABLE IF NOT EXISTS user_settings (
+    user_id TEXT PRIMARY KEY NOT NULL,
+    -- Archive completed todos this many days after completion; NULL turns it off.
+    auto_archive_days INTEGER CHECK (auto_archive_days IS NULL OR auto_archive_days >= 0)
+);
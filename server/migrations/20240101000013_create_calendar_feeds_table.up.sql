@@ -0,0 +1,7 @@
+-- Add migration script here
+-- The secret token in a user's calendar subscription URL. Replacing it revokes the old URL.
+CREATE TABLE IF NOT EXISTS calendar_feeds (
+    user_id TEXT PRIMARY KEY NOT NULL,
+    token TEXT NOT NULL UNIQUE,
+    created_at TIMESTAMP NOT NULL
+);
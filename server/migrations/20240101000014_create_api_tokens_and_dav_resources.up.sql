@@ -0,0 +1,20 @@
+-- Add migration script here
+-- Personal API tokens, used as the password for HTTP Basic auth by CalDAV clients.
+CREATE TABLE IF NOT EXISTS api_tokens (
+    id TEXT PRIMARY KEY NOT NULL,
+    user_id TEXT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    token TEXT NOT NULL UNIQUE,
+    created_at TIMESTAMP NOT NULL,
+    last_used_at TIMESTAMP
+);
+
+-- The resource name and UID a CalDAV client chose for a todo it created. Todos without a row
+-- are served as `<id>.ics` with their id as the UID.
+CREATE TABLE IF NOT EXISTS dav_resources (
+    todo_id TEXT PRIMARY KEY NOT NULL REFERENCES todos(id) ON DELETE CASCADE,
+    user_id TEXT NOT NULL,
+    name TEXT NOT NULL,
+    uid TEXT NOT NULL,
+    UNIQUE (user_id, name)
+);
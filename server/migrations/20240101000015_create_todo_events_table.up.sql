@@ -0,0 +1,11 @@
+-- Add migration script here
+-- Log of todo change events, replayed to `/api/events` clients that reconnect with a
+-- `Last-Event-ID`. `event` is the JSON sent to clients; rows are pruned after a week.
+CREATE TABLE IF NOT EXISTS todo_events (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    user_id TEXT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    event TEXT NOT NULL,
+    created_at TIMESTAMP NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_todo_events_user ON todo_events (user_id, id);
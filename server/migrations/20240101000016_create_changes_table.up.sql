@@ -0,0 +1,32 @@
+-- Add migration script here
+-- Sync log for offline clients: the latest change to each todo, numbered per user. A deleted
+-- todo keeps a tombstone row. Triggers keep it up to date, so every write path is covered,
+-- including imports and cascading deletes. There is no foreign key on `user_id`, since deleting
+-- a user deletes their todos and that writes tombstones.
+CREATE TABLE IF NOT EXISTS changes (
+    user_id TEXT NOT NULL,
+    seq INTEGER NOT NULL,
+    todo_id TEXT NOT NULL UNIQUE,
+    deleted BOOLEAN NOT NULL DEFAULT FALSE,
+    PRIMARY KEY (user_id, seq)
+);
+
+-- Todos that already exist are the first changes.
+INSERT INTO changes (user_id, seq, todo_id, deleted)
+SELECT user_id, ROW_NUMBER() OVER (PARTITION BY user_id ORDER BY updated_at, id), id, FALSE FROM todos;
+
+-- `OR REPLACE` drops the todo's previous change, so only its latest is kept.
+CREATE TRIGGER IF NOT EXISTS todos_insert_change AFTER INSERT ON todos BEGIN
+    INSERT OR REPLACE INTO changes (user_id, seq, todo_id, deleted)
+    VALUES (NEW.user_id, (SELECT COALESCE(MAX(seq), 0) + 1 FROM changes WHERE user_id = NEW.user_id), NEW.id, FALSE);
+END;
+
+CREATE TRIGGER IF NOT EXISTS todos_update_change AFTER UPDATE ON todos BEGIN
+    INSERT OR REPLACE INTO changes (user_id, seq, todo_id, deleted)
+    VALUES (NEW.user_id, (SELECT COALESCE(MAX(seq), 0) + 1 FROM changes WHERE user_id = NEW.user_id), NEW.id, FALSE);
+END;
+
+CREATE TRIGGER IF NOT EXISTS todos_delete_change AFTER DELETE ON todos BEGIN
+    INSERT OR REPLACE INTO changes (user_id, seq, todo_id, deleted)
+    VALUES (OLD.user_id, (SELECT COALESCE(MAX(seq), 0) + 1 FROM changes WHERE user_id = OLD.user_id), OLD.id, TRUE);
+END;
@@ -0,0 +1,5 @@
+-- Add migration script here
+-- API tokens are kept as SHA-256 hashes from now on. Plaintext tokens can't be hashed in SQL,
+-- so existing ones are revoked and have to be created again.
+DELETE FROM api_tokens;
+ALTER TABLE api_tokens RENAME COLUMN token TO token_hash;
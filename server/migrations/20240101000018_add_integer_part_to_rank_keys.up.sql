@@ -0,0 +1,4 @@
+-- Add migration script here
+-- Rank keys now start with an integer part whose first character gives its length. Existing keys
+-- are bare fractions, so they all become fractions of the integer zero, which keeps their order.
+UPDATE todos SET position = 'a0' || position;
@@ -0,0 +1,25 @@
+-- Add migration script here
+CREATE TABLE IF NOT EXISTS projects (
+    id TEXT PRIMARY KEY NOT NULL,
+    user_id TEXT NOT NULL,
+    name TEXT NOT NULL COLLATE NOCASE,
+    position INTEGER NOT NULL DEFAULT 0,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (user_id, name)
+);
+
+CREATE INDEX IF NOT EXISTS idx_projects_user ON projects (user_id, position);
+
+ALTER TABLE todos ADD COLUMN project_id TEXT REFERENCES projects(id);
+
+CREATE INDEX IF NOT EXISTS idx_todos_project ON todos (project_id);
+
+-- Tags are free-form labels, stored as written.
+CREATE TABLE IF NOT EXISTS todo_tags (
+    todo_id TEXT NOT NULL,
+    tag TEXT NOT NULL,
+    PRIMARY KEY (todo_id, tag),
+    FOREIGN KEY (todo_id) REFERENCES todos(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_todo_tags_tag ON todo_tags (tag);
@@ -0,0 +1,7 @@
+-- Add migration script here
+-- Statuses without a project make up the shared workflow. A project with statuses of its own
+-- uses those instead.
+ALTER TABLE statuses ADD COLUMN project_id TEXT REFERENCES projects(id) ON DELETE CASCADE;
+
+DROP INDEX IF EXISTS idx_statuses_user;
+CREATE INDEX IF NOT EXISTS idx_statuses_user ON statuses (user_id, project_id, position);
@@ -0,0 +1,3 @@
+-- Add migration script here
+-- Fields without a project apply to every todo; the others only to todos in their project.
+ALTER TABLE custom_fields ADD COLUMN project_id TEXT REFERENCES projects(id) ON DELETE CASCADE;
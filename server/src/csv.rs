@@ -0,0 +1,83 @@
+//! Minimal CSV reading and writing (RFC 4180): comma separated, fields optionally quoted with
+//! `"`, quotes inside quoted fields doubled, and line breaks allowed inside quotes.
+
+/// A parsed record with the 1-based line it starts on, for error reports.
+#[derive(Debug, Clone)]
+pub struct Record {
+    pub line: usize,
+    pub fields: Vec<String>,
+}
+
+/// Splits `text` into records. Blank lines are skipped and a leading byte order mark is ignored.
+pub fn parse(text: &str) -> Result<Vec<Record>, String> {
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+    let mut records = vec![];
+    let mut fields = vec![];
+    let mut field = String::new();
+    let mut in_quotes = false;
+    // Whether the current field was quoted, so `""` on its own is kept as an empty field.
+    let mut quoted = false;
+    let (mut line, mut record_line) = (1, 1);
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match (c, in_quotes) {
+            ('"', true) if chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            ('"', true) => in_quotes = false,
+            ('"', false) if field.is_empty() && !quoted => {
+                in_quotes = true;
+                quoted = true;
+            }
+            (',', false) => {
+                fields.push(std::mem::take(&mut field));
+                quoted = false;
+            }
+            ('\r', false) if chars.peek() == Some(&'\n') => {}
+            ('\n', false) | ('\r', false) => {
+                fields.push(std::mem::take(&mut field));
+                quoted = false;
+                if fields.len() > 1 || !fields[0].is_empty() {
+                    records.push(Record { line: record_line, fields: std::mem::take(&mut fields) });
+                } else {
+                    fields.clear();
+                }
+                line += 1;
+                record_line = line;
+            }
+            (c, _) => {
+                if c == '\n' {
+                    line += 1;
+                }
+                field.push(c);
+            }
+        }
+    }
+
+    if in_quotes {
+        return Err(format!("unterminated quoted field starting on line {}", record_line));
+    }
+    if !fields.is_empty() || !field.is_empty() || quoted {
+        fields.push(field);
+        records.push(Record { line: record_line, fields });
+    }
+    Ok(records)
+}
+
+/// Quotes a field when it contains a separator, quote or line break.
+pub fn escape(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Appends one record, terminated with CRLF as RFC 4180 asks.
+pub fn write_record<S: AsRef<str>>(out: &mut String, fields: &[S]) {
+    let fields: Vec<String> = fields.iter().map(|field| escape(field.as_ref())).collect();
+    out.push_str(&fields.join(","));
+    out.push_str("\r\n");
+}
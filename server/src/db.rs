@@ -20,4 +20,23 @@ pub async fn init_db() -> Result<DB, sqlx::Error> {
     info!("Migrations complete");
 
     Ok(pool)
-} 
\ No newline at end of file
+} 
+/// A fresh in-memory database with the migrations applied and the dummy user in place.
+#[cfg(test)]
+pub async fn test_db() -> DB {
+    // Each connection to `:memory:` is its own database, so the pool keeps exactly one open.
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .idle_timeout(None)
+        .max_lifetime(None)
+        .connect("sqlite::memory:")
+        .await
+        .expect("failed to open the test database");
+    sqlx::migrate!("./migrations").run(&pool).await.expect("failed to migrate the test database");
+    sqlx::query("INSERT INTO users (id, username, password_hash) VALUES ($1, 'test', '')")
+        .bind(crate::handlers::todos::DUMMY_USER_ID)
+        .execute(&pool)
+        .await
+        .expect("failed to add the test user");
+    pool
+}
@@ -1,8 +1,10 @@
 use axum::{
     response::{IntoResponse, Response},
-    http::StatusCode,
+    http::{header, StatusCode},
+    Json,
 };
 use thiserror::Error;
+use crate::{handlers::todos::etag, models::Todo};
 
 #[derive(Error, Debug)]
 pub enum AppError {
@@ -20,6 +22,24 @@ pub enum AppError {
 
     #[error("Item not found")]
     NotFound,
+
+    #[error("Missing or malformed If-Match header")]
+    PreconditionRequired,
+
+    #[error("Bad request: {0}")]
+    BadRequest(String),
+
+    #[error("Invalid patch: {0}")]
+    InvalidPatch(String),
+
+    #[error("Dependency would create a cycle")]
+    DependencyCycle,
+
+    #[error("Todo is blocked by open todos")]
+    Blocked(Vec<uuid::Uuid>),
+
+    #[error("Item was modified by another request")]
+    PreconditionFailed(Box<Todo>),
     
     #[error("Internal Server Error")]
     InternalServerError,
@@ -27,12 +47,31 @@ pub enum AppError {
 
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
+        // A stale write gets the current server copy back so the client can resolve the conflict.
+        if let AppError::PreconditionFailed(current) = self {
+            return (
+                StatusCode::PRECONDITION_FAILED,
+                [(header::ETAG, etag(current.version))],
+                Json(current),
+            )
+                .into_response();
+        }
+
         let (status, error_message) = match self {
             AppError::DatabaseError(_) => (StatusCode::INTERNAL_SERVER_ERROR, "Database Error".to_string()),
             AppError::AuthenticationError => (StatusCode::UNAUTHORIZED, "Authentication Failed".to_string()),
             AppError::UserNotFound => (StatusCode::NOT_FOUND, "User Not Found".to_string()),
             AppError::InvalidCredentials => (StatusCode::UNAUTHORIZED, "Invalid Credentials".to_string()),
             AppError::NotFound => (StatusCode::NOT_FOUND, "Not Found".to_string()),
+            AppError::PreconditionRequired => (StatusCode::PRECONDITION_REQUIRED, "If-Match Header Required".to_string()),
+            AppError::BadRequest(reason) => (StatusCode::BAD_REQUEST, format!("Bad Request: {}", reason)),
+            AppError::InvalidPatch(reason) => (StatusCode::UNPROCESSABLE_ENTITY, format!("Invalid Patch: {}", reason)),
+            AppError::DependencyCycle => (StatusCode::CONFLICT, "Dependency Would Create A Cycle".to_string()),
+            AppError::Blocked(blockers) => {
+                let ids: Vec<String> = blockers.iter().map(|id| id.to_string()).collect();
+                (StatusCode::CONFLICT, format!("Blocked By Open Todos: {}", ids.join(", ")))
+            }
+            AppError::PreconditionFailed(_) => unreachable!("handled above"),
             AppError::InternalServerError => (StatusCode::INTERNAL_SERVER_ERROR, "Internal Server Error".to_string()),
         };
 
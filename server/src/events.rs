@@ -0,0 +1,98 @@
+//! Change notifications for todos. Handlers record them in the `todo_events` log in the same
+//! transaction as the change and announce them once it commits, to live connections such as
+//! `/api/ws` and `/api/events`.
+
+use crate::{db::DB, errors::AppError, models::Todo};
+use chrono::{Duration, Utc};
+use serde::Serialize;
+use sqlx::{Sqlite, SqliteConnection, Transaction};
+use tokio::sync::broadcast;
+use tracing::error;
+use uuid::Uuid;
+
+// How many events a slow connection may fall behind by before it starts missing them.
+const CHANNEL_CAPACITY: usize = 256;
+
+// How long events stay in the log for clients catching up after a disconnect.
+const LOG_RETENTION_DAYS: i64 = 7;
+
+// How often old events are pruned from the log.
+const PRUNE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60 * 60);
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TodoEvent {
+    Created { todo: Todo },
+    Updated { todo: Todo },
+    Deleted { id: Uuid },
+    /// Too many todos changed at once to send one by one, e.g. by an import; clients should
+    /// load the list again.
+    Reload,
+}
+
+#[derive(Debug, Clone)]
+pub struct UserEvent {
+    pub user_id: String,
+    pub event: TodoEvent,
+}
+
+#[derive(Clone)]
+pub struct Events(broadcast::Sender<UserEvent>);
+
+impl Default for Events {
+    fn default() -> Self {
+        Events(broadcast::channel(CHANNEL_CAPACITY).0)
+    }
+}
+
+/// Adds `event` to the change log. Call `Events::publish` once the transaction has committed.
+pub async fn record(conn: &mut SqliteConnection, user_id: &str, event: &TodoEvent) -> Result<(), AppError> {
+    let event = serde_json::to_string(event).map_err(|_| AppError::InternalServerError)?;
+    sqlx::query("INSERT INTO todo_events (user_id, event, created_at) VALUES ($1, $2, $3)")
+        .bind(user_id)
+        .bind(event)
+        .bind(Utc::now())
+        .execute(&mut *conn)
+        .await?;
+    Ok(())
+}
+
+impl Events {
+    pub fn publish(&self, user_id: &str, event: TodoEvent) {
+        // Sending only fails when nobody is listening, which is fine.
+        let _ = self.0.send(UserEvent { user_id: user_id.to_string(), event });
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<UserEvent> {
+        self.0.subscribe()
+    }
+
+    /// Records `events`, commits `tx` and then publishes them.
+    pub async fn commit(
+        &self,
+        mut tx: Transaction<'_, Sqlite>,
+        user_id: &str,
+        events: Vec<TodoEvent>,
+    ) -> Result<(), AppError> {
+        for event in &events {
+            record(&mut tx, user_id, event).await?;
+        }
+        tx.commit().await?;
+        for event in events {
+            self.publish(user_id, event);
+        }
+        Ok(())
+    }
+}
+
+/// Drops events older than the retention period from the log, for the lifetime of the server.
+pub async fn prune_log_job(db: DB) {
+    let mut interval = tokio::time::interval(PRUNE_INTERVAL);
+    loop {
+        interval.tick().await;
+        let cutoff = Utc::now() - Duration::days(LOG_RETENTION_DAYS);
+        if let Err(e) = sqlx::query("DELETE FROM todo_events WHERE created_at < $1").bind(cutoff).execute(&db).await {
+            error!("pruning the event log failed: {}", e);
+        }
+    }
+}
@@ -0,0 +1,149 @@
+use crate::{
+    db::DB,
+    errors::AppError,
+    events::{self, Events, TodoEvent},
+    handlers::todos::{etag, fetch_todo, DUMMY_USER_ID, TODO_COLUMNS},
+    models::{ArchiveQuery, Todo, UserSettings},
+};
+use axum::{
+    extract::{Path, Query, State},
+    http::{header, HeaderName},
+    Json,
+};
+use chrono::Utc;
+use std::{collections::BTreeSet, time::Duration};
+use tracing::{error, info};
+use uuid::Uuid;
+
+// How often the auto-archive job looks for todos that are due to be archived.
+const AUTO_ARCHIVE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Archives every completed todo whose owner's `auto_archive_days` have passed since completion.
+pub async fn auto_archive(db: &DB, events: &Events) -> Result<u64, AppError> {
+    let now = Utc::now();
+    let mut tx = db.begin().await?;
+    let owners: Vec<String> = sqlx::query_scalar(
+        "UPDATE todos SET archived_at = $1, version = version + 1 \
+        WHERE archived_at IS NULL AND completed = TRUE AND completed_at IS NOT NULL \
+        AND EXISTS (SELECT 1 FROM user_settings s WHERE s.user_id = todos.user_id \
+            AND s.auto_archive_days IS NOT NULL \
+            AND julianday(todos.completed_at) <= julianday($1) - s.auto_archive_days) \
+        RETURNING user_id",
+    )
+    .bind(now)
+    .fetch_all(&mut *tx)
+    .await?;
+    let users = owners.iter().collect::<BTreeSet<_>>();
+    for user_id in &users {
+        events::record(&mut tx, user_id, &TodoEvent::Reload).await?;
+    }
+    tx.commit().await?;
+    for user_id in users {
+        events.publish(user_id, TodoEvent::Reload);
+    }
+    Ok(owners.len() as u64)
+}
+
+/// Runs `auto_archive` on a fixed interval for the lifetime of the server.
+pub async fn auto_archive_job(db: DB, events: Events) {
+    let mut interval = tokio::time::interval(AUTO_ARCHIVE_INTERVAL);
+    loop {
+        interval.tick().await;
+        match auto_archive(&db, &events).await {
+            Ok(0) => {}
+            Ok(archived) => info!("auto-archived {} todos", archived),
+            Err(e) => error!("auto-archive failed: {}", e),
+        }
+    }
+}
+
+/// Lists archived todos, most recently archived first.
+pub async fn list_archive(
+    State(db): State<DB>,
+    Query(query): Query<ArchiveQuery>,
+) -> Result<Json<Vec<Todo>>, AppError> {
+    let pattern = query.q.as_deref().map(|q| {
+        format!("%{}%", q.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_"))
+    });
+    let todos = sqlx::query_as::<_, Todo>(&format!(
+        "SELECT {TODO_COLUMNS} FROM todos WHERE user_id = $1 AND archived_at IS NOT NULL \
+        AND ($2 IS NULL OR title LIKE $2 ESCAPE '\\' OR description LIKE $2 ESCAPE '\\') \
+        ORDER BY archived_at DESC, id LIMIT $3 OFFSET $4"
+    ))
+    .bind(DUMMY_USER_ID)
+    .bind(pattern)
+    .bind(query.limit.clamp(1, 200))
+    .bind(query.offset.max(0))
+    .fetch_all(&db)
+    .await?;
+    Ok(Json(todos))
+}
+
+async fn set_archived(db: &DB, events: &Events, id: Uuid, archived: bool) -> Result<([(HeaderName, String); 1], Json<Todo>), AppError> {
+    let mut tx = db.begin().await?;
+    let id_str = id.to_string();
+    fetch_todo(&mut tx, &id_str).await?;
+
+    sqlx::query(
+        "UPDATE todos SET archived_at = CASE WHEN $1 THEN COALESCE(archived_at, $2) ELSE NULL END, \
+        version = version + 1 WHERE id = $3",
+    )
+    .bind(archived)
+    .bind(Utc::now())
+    .bind(&id_str)
+    .execute(&mut *tx)
+    .await?;
+
+    let todo = fetch_todo(&mut tx, &id_str).await?;
+    events.commit(tx, DUMMY_USER_ID, vec![TodoEvent::Updated { todo: todo.clone() }]).await?;
+    Ok(([(header::ETAG, etag(todo.version))], Json(todo)))
+}
+
+pub async fn archive_todo(
+    State(db): State<DB>,
+    State(events): State<Events>,
+    Path(id): Path<Uuid>,
+) -> Result<([(HeaderName, String); 1], Json<Todo>), AppError> {
+    set_archived(&db, &events, id, true).await
+}
+
+pub async fn unarchive_todo(
+    State(db): State<DB>,
+    State(events): State<Events>,
+    Path(id): Path<Uuid>,
+) -> Result<([(HeaderName, String); 1], Json<Todo>), AppError> {
+    set_archived(&db, &events, id, false).await
+}
+
+pub async fn get_settings(State(db): State<DB>) -> Result<Json<UserSettings>, AppError> {
+    let settings = sqlx::query_as::<_, UserSettings>(
+        "SELECT auto_archive_days FROM user_settings WHERE user_id = $1",
+    )
+    .bind(DUMMY_USER_ID)
+    .fetch_optional(&db)
+    .await?
+    .unwrap_or_default();
+    Ok(Json(settings))
+}
+
+pub async fn update_settings(
+    State(db): State<DB>,
+    State(events): State<Events>,
+    Json(payload): Json<UserSettings>,
+) -> Result<Json<UserSettings>, AppError> {
+    if payload.auto_archive_days.is_some_and(|days| days < 0) {
+        return Err(AppError::BadRequest("auto_archive_days cannot be negative".to_string()));
+    }
+    sqlx::query(
+        "INSERT INTO user_settings (user_id, auto_archive_days) VALUES ($1, $2) \
+        ON CONFLICT (user_id) DO UPDATE SET auto_archive_days = excluded.auto_archive_days",
+    )
+    .bind(DUMMY_USER_ID)
+    .bind(payload.auto_archive_days)
+    .execute(&db)
+    .await?;
+
+    // Apply a shortened window right away rather than on the next run of the job.
+    auto_archive(&db, &events).await?;
+    Ok(Json(payload))
+}
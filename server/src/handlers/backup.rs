@@ -0,0 +1,755 @@
+use crate::{
+    db::DB,
+    errors::AppError,
+    events::{Events, TodoEvent},
+    handlers::{
+        custom_fields::{all_fields, save_field_values},
+        projects::all_projects,
+        revisions::record_revision,
+        statuses::{resolve_status, STATUS_COLUMNS},
+        tags::save_tags,
+        templates::all_templates,
+        time_entries::TIME_ENTRY_COLUMNS,
+        todos::{fetch_todo, DUMMY_USER_ID, TODO_COLUMNS},
+        views::all_views,
+    },
+    models::{
+        ConflictStrategy, CustomField, Dependency, ExportDocument, ImportCounts, ImportOptions,
+        ImportReport, Project, SavedView, Status, Template, TimeEntry, Todo, UserSettings,
+        EXPORT_FORMAT_VERSION,
+    },
+    rank::key_between,
+    view_query,
+};
+use axum::{
+    body::Body,
+    extract::{Query, State},
+    http::header,
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::Utc;
+use futures::{future, stream, StreamExt};
+use sqlx::{Sqlite, SqliteConnection, Transaction};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+// Todos are read this many at a time while the export is written out.
+const EXPORT_PAGE_SIZE: i64 = 500;
+
+/// Writes the user's full data set, archived todos included, as a versioned JSON document.
+/// Todos are read and sent a page at a time after the rest of the document. Everything is read
+/// in one transaction, so the export is a consistent snapshot.
+pub async fn export_data(State(db): State<DB>) -> Result<Response, AppError> {
+    let mut tx = db.begin().await?;
+
+    let settings = sqlx::query_as::<_, UserSettings>(
+        "SELECT auto_archive_days FROM user_settings WHERE user_id = $1",
+    )
+    .bind(DUMMY_USER_ID)
+    .fetch_optional(&mut *tx)
+    .await?
+    .unwrap_or_default();
+    let statuses = sqlx::query_as::<_, Status>(&format!(
+        "SELECT {STATUS_COLUMNS} FROM statuses WHERE user_id = $1 ORDER BY position, created_at"
+    ))
+    .bind(DUMMY_USER_ID)
+    .fetch_all(&mut *tx)
+    .await?;
+    let dependencies = sqlx::query_as::<_, (String, String)>(
+        "SELECT d.blocker_id, d.blocked_id FROM todo_dependencies d JOIN todos t ON t.id = d.blocked_id \
+        WHERE t.user_id = $1 ORDER BY d.created_at",
+    )
+    .bind(DUMMY_USER_ID)
+    .fetch_all(&mut *tx)
+    .await?
+    .into_iter()
+    .map(|(blocker_id, blocked_id)| {
+        let parse_id = |s: &str| Uuid::parse_str(s).map_err(|_| AppError::InternalServerError);
+        Ok(Dependency { blocker_id: parse_id(&blocker_id)?, blocked_id: parse_id(&blocked_id)? })
+    })
+    .collect::<Result<Vec<_>, AppError>>()?;
+    let time_entries = sqlx::query_as::<_, TimeEntry>(&format!(
+        "SELECT {TIME_ENTRY_COLUMNS} FROM time_entries WHERE user_id = $1 ORDER BY started_at"
+    ))
+    .bind(DUMMY_USER_ID)
+    .fetch_all(&mut *tx)
+    .await?;
+    let now = Utc::now();
+    let document = ExportDocument {
+        version: EXPORT_FORMAT_VERSION,
+        exported_at: now,
+        settings,
+        projects: all_projects(&mut tx).await?,
+        statuses,
+        custom_fields: all_fields(&mut tx).await?,
+        templates: all_templates(&mut tx).await?,
+        saved_views: all_views(&mut tx).await?,
+        dependencies,
+        time_entries,
+        todos: vec![],
+    };
+
+    // With no todos the document serializes without its `todos` key, so the closing brace can be
+    // swapped for the start of the todo array.
+    let mut head = serde_json::to_string(&document).map_err(|_| AppError::InternalServerError)?;
+    head.pop();
+    head.push_str(",\"todos\":[");
+    let todos = stream::try_unfold((tx, None, true), |(tx, after, first)| todo_page(tx, after, first));
+    let chunks = stream::once(future::ready(Ok(head)))
+        .chain(todos)
+        .chain(stream::once(future::ready(Ok("]}".to_string()))));
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/json".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"todos-{}.json\"", now.format("%Y-%m-%d")),
+            ),
+        ],
+        Body::from_stream(chunks),
+    )
+        .into_response())
+}
+
+type ExportPage = (String, (Transaction<'static, Sqlite>, Option<(String, String)>, bool));
+
+/// The next page of exported todos after the `(position, id)` in `after`, as a chunk of the todo
+/// array, or `None` once they have all been sent.
+async fn todo_page(
+    mut tx: Transaction<'static, Sqlite>,
+    after: Option<(String, String)>,
+    first: bool,
+) -> Result<Option<ExportPage>, AppError> {
+    let (position, id) = after.unzip();
+    let todos = sqlx::query_as::<_, Todo>(&format!(
+        "SELECT {TODO_COLUMNS} FROM todos WHERE user_id = $1 AND ($2 IS NULL OR (position, id) > ($2, $3)) \
+        ORDER BY position, id LIMIT $4"
+    ))
+    .bind(DUMMY_USER_ID)
+    .bind(position)
+    .bind(id)
+    .bind(EXPORT_PAGE_SIZE)
+    .fetch_all(&mut *tx)
+    .await?;
+    let Some(last) = todos.last() else {
+        return Ok(None);
+    };
+    let after = Some((last.position.clone(), last.id.to_string()));
+
+    let todos = todos
+        .iter()
+        .map(serde_json::to_string)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| AppError::InternalServerError)?;
+    let chunk = if first { todos.join(",") } else { format!(",{}", todos.join(",")) };
+    Ok(Some((chunk, (tx, after, false))))
+}
+
+enum Action {
+    Insert(Uuid),
+    Update(Uuid),
+    Skip(Uuid),
+}
+
+impl Action {
+    fn id(&self) -> Uuid {
+        match self {
+            Action::Insert(id) | Action::Update(id) | Action::Skip(id) => *id,
+        }
+    }
+}
+
+impl ImportCounts {
+    fn tally(&mut self, action: &Action) {
+        match action {
+            Action::Insert(_) => self.created += 1,
+            Action::Update(_) => self.updated += 1,
+            Action::Skip(_) => self.skipped += 1,
+        }
+    }
+}
+
+struct Importer<'c> {
+    conn: &'c mut SqliteConnection,
+    strategy: ConflictStrategy,
+    /// Imported id to the id it is stored under.
+    ids: HashMap<Uuid, Uuid>,
+    report: ImportReport,
+}
+
+impl Importer<'_> {
+    /// Decides what to do with an imported record. It conflicts with a record of the user's that
+    /// has the same id, or with `same_as`, an existing record it matched some other way.
+    async fn resolve(
+        &mut self,
+        table: &str,
+        id: Uuid,
+        same_as: Option<Uuid>,
+        strategy: ConflictStrategy,
+    ) -> Result<Action, AppError> {
+        let owner: Option<String> = sqlx::query_scalar(&format!("SELECT user_id FROM {table} WHERE id = $1"))
+            .bind(id.to_string())
+            .fetch_optional(&mut *self.conn)
+            .await?;
+        let existing = match owner.as_deref() {
+            Some(DUMMY_USER_ID) => Some(id),
+            _ => same_as,
+        };
+
+        let action = match (existing, strategy) {
+            (Some(existing), ConflictStrategy::Skip) => Action::Skip(existing),
+            (Some(existing), ConflictStrategy::Overwrite) => Action::Update(existing),
+            // New records keep their id when it is free, so importing the same file again finds
+            // them as conflicts instead of copying them.
+            (None, _) if owner.is_none() => Action::Insert(id),
+            _ => Action::Insert(Uuid::new_v4()),
+        };
+        self.ids.insert(id, action.id());
+        if action.id() != id {
+            self.report.remapped_ids.insert(id, action.id());
+        }
+        Ok(action)
+    }
+
+    fn mapped(&self, id: Uuid) -> Option<Uuid> {
+        self.ids.get(&id).copied()
+    }
+
+    async fn import_settings(&mut self, settings: &UserSettings) -> Result<(), AppError> {
+        let on_conflict = match self.strategy {
+            ConflictStrategy::Overwrite => "DO UPDATE SET auto_archive_days = excluded.auto_archive_days",
+            ConflictStrategy::Skip | ConflictStrategy::Duplicate => "DO NOTHING",
+        };
+        sqlx::query(&format!(
+            "INSERT INTO user_settings (user_id, auto_archive_days) VALUES ($1, $2) ON CONFLICT (user_id) {on_conflict}"
+        ))
+        .bind(DUMMY_USER_ID)
+        .bind(settings.auto_archive_days)
+        .execute(&mut *self.conn)
+        .await?;
+        Ok(())
+    }
+
+    async fn import_projects(&mut self, projects: &[Project]) -> Result<(), AppError> {
+        for project in projects {
+            let same_as: Option<String> =
+                sqlx::query_scalar("SELECT id FROM projects WHERE user_id = $1 AND name = $2")
+                    .bind(DUMMY_USER_ID)
+                    .bind(&project.name)
+                    .fetch_optional(&mut *self.conn)
+                    .await?;
+            let same_as = same_as.and_then(|id| Uuid::parse_str(&id).ok());
+
+            // Names are unique, so like custom fields a project whose name is taken is merged
+            // into the existing one instead of duplicated.
+            let strategy = match self.strategy {
+                ConflictStrategy::Duplicate if same_as.is_some() => ConflictStrategy::Skip,
+                strategy => strategy,
+            };
+            let action = self.resolve("projects", project.id, same_as, strategy).await?;
+            match action {
+                Action::Insert(id) => {
+                    sqlx::query("INSERT INTO projects (id, user_id, name, position, created_at) VALUES ($1, $2, $3, $4, $5)")
+                        .bind(id.to_string())
+                        .bind(DUMMY_USER_ID)
+                        .bind(&project.name)
+                        .bind(project.position)
+                        .bind(project.created_at)
+                        .execute(&mut *self.conn)
+                        .await?;
+                }
+                Action::Update(id) => {
+                    sqlx::query("UPDATE projects SET name = $1, position = $2 WHERE id = $3")
+                        .bind(&project.name)
+                        .bind(project.position)
+                        .bind(id.to_string())
+                        .execute(&mut *self.conn)
+                        .await?;
+                }
+                Action::Skip(_) => {}
+            }
+            self.report.projects.tally(&action);
+        }
+        Ok(())
+    }
+
+    /// The stored project for an imported project id. `Err` when the document names a project it
+    /// doesn't include, so whatever belongs to it can be skipped.
+    fn mapped_project(&self, id: Option<Uuid>) -> Result<Option<Uuid>, ()> {
+        match id {
+            Some(id) => self.mapped(id).map(Some).ok_or(()),
+            None => Ok(None),
+        }
+    }
+
+    async fn import_statuses(&mut self, statuses: &[Status]) -> Result<(), AppError> {
+        for status in statuses {
+            let Ok(project_id) = self.mapped_project(status.project_id) else {
+                self.report.statuses.skipped += 1;
+                continue;
+            };
+            // Every user starts with the default workflow, so match statuses by name as well.
+            let same_as: Option<String> = sqlx::query_scalar(
+                "SELECT id FROM statuses WHERE user_id = $1 AND name = $2 AND category = $3 AND project_id IS $4 \
+                ORDER BY position LIMIT 1",
+            )
+            .bind(DUMMY_USER_ID)
+            .bind(&status.name)
+            .bind(status.category)
+            .bind(project_id.map(|id| id.to_string()))
+            .fetch_optional(&mut *self.conn)
+            .await?;
+            let same_as = same_as.and_then(|id| Uuid::parse_str(&id).ok());
+
+            let action = self.resolve("statuses", status.id, same_as, self.strategy).await?;
+            match action {
+                Action::Insert(id) => {
+                    sqlx::query(
+                        "INSERT INTO statuses (id, user_id, name, position, category, created_at, project_id) VALUES ($1, $2, $3, $4, $5, $6, $7)",
+                    )
+                    .bind(id.to_string())
+                    .bind(DUMMY_USER_ID)
+                    .bind(&status.name)
+                    .bind(status.position)
+                    .bind(status.category)
+                    .bind(status.created_at)
+                    .bind(project_id.map(|id| id.to_string()))
+                    .execute(&mut *self.conn)
+                    .await?;
+                }
+                Action::Update(id) => {
+                    sqlx::query("UPDATE statuses SET name = $1, position = $2, category = $3 WHERE id = $4")
+                        .bind(&status.name)
+                        .bind(status.position)
+                        .bind(status.category)
+                        .bind(id.to_string())
+                        .execute(&mut *self.conn)
+                        .await?;
+                }
+                Action::Skip(_) => {}
+            }
+            self.report.statuses.tally(&action);
+        }
+        Ok(())
+    }
+
+    async fn import_custom_fields(&mut self, fields: &[CustomField]) -> Result<(), AppError> {
+        for field in fields {
+            let Ok(project_id) = self.mapped_project(field.project_id) else {
+                self.report.custom_fields.skipped += 1;
+                continue;
+            };
+            let same_as: Option<String> =
+                sqlx::query_scalar("SELECT id FROM custom_fields WHERE user_id = $1 AND key = $2")
+                    .bind(DUMMY_USER_ID)
+                    .bind(&field.key)
+                    .fetch_optional(&mut *self.conn)
+                    .await?;
+            let same_as = same_as.and_then(|id| Uuid::parse_str(&id).ok());
+
+            // Keys are unique, so a field whose key is taken cannot be duplicated; its values go
+            // to the existing field instead.
+            let strategy = match self.strategy {
+                ConflictStrategy::Duplicate if same_as.is_some() => ConflictStrategy::Skip,
+                strategy => strategy,
+            };
+            let action = self.resolve("custom_fields", field.id, same_as, strategy).await?;
+            // Values are keyed by `key`, so a field's type is left alone when overwriting; the
+            // stored values may not fit another type.
+            match action {
+                Action::Insert(id) => {
+                    sqlx::query(
+                        "INSERT INTO custom_fields (id, user_id, key, name, field_type, options, position, created_at, project_id) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)",
+                    )
+                    .bind(id.to_string())
+                    .bind(DUMMY_USER_ID)
+                    .bind(&field.key)
+                    .bind(&field.name)
+                    .bind(field.field_type)
+                    .bind(serde_json::to_string(&field.options).map_err(|_| AppError::InternalServerError)?)
+                    .bind(field.position)
+                    .bind(field.created_at)
+                    .bind(project_id.map(|id| id.to_string()))
+                    .execute(&mut *self.conn)
+                    .await?;
+                }
+                Action::Update(id) => {
+                    sqlx::query("UPDATE custom_fields SET name = $1, options = $2, position = $3, project_id = $4 WHERE id = $5")
+                        .bind(&field.name)
+                        .bind(serde_json::to_string(&field.options).map_err(|_| AppError::InternalServerError)?)
+                        .bind(field.position)
+                        .bind(project_id.map(|id| id.to_string()))
+                        .bind(id.to_string())
+                        .execute(&mut *self.conn)
+                        .await?;
+                }
+                Action::Skip(_) => {}
+            }
+            self.report.custom_fields.tally(&action);
+        }
+        Ok(())
+    }
+
+    async fn import_todos(&mut self, todos: &[Todo]) -> Result<(), AppError> {
+        // Imported todos keep their relative order and go after the user's existing ones.
+        let mut todos: Vec<&Todo> = todos.iter().collect();
+        todos.sort_by(|a, b| a.position.cmp(&b.position));
+        let mut last_position: Option<String> =
+            sqlx::query_scalar("SELECT MAX(position) FROM todos WHERE user_id = $1")
+                .bind(DUMMY_USER_ID)
+                .fetch_one(&mut *self.conn)
+                .await?;
+
+        for todo in todos {
+            let action = self.resolve("todos", todo.id, None, self.strategy).await?;
+            let requested = todo.status_id.and_then(|id| self.mapped(id));
+            // A project missing from the document leaves the todo out of any project.
+            let project_id = self.mapped_project(todo.project_id).unwrap_or(None);
+            let before = match &action {
+                Action::Skip(_) => {
+                    self.report.todos.tally(&action);
+                    continue;
+                }
+                Action::Insert(_) => None,
+                Action::Update(id) => Some(fetch_todo(self.conn, &id.to_string()).await?),
+            };
+            let (status_id, completed) = resolve_status(self.conn, before.as_ref(), requested, todo.completed, project_id).await?;
+            let completed_at = completed.then(|| todo.completed_at.unwrap_or(todo.updated_at));
+            let id_str = action.id().to_string();
+
+            if before.is_none() {
+                let position = key_between(last_position.as_deref(), None);
+                sqlx::query(
+                    "INSERT INTO todos (id, user_id, title, description, completed, priority, created_at, updated_at, position, status_id, estimated_minutes, completed_at, archived_at, project_id) \
+                    VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14)",
+                )
+                .bind(&id_str)
+                .bind(DUMMY_USER_ID)
+                .bind(&todo.title)
+                .bind(&todo.description)
+                .bind(completed)
+                .bind(&todo.priority)
+                .bind(todo.created_at)
+                .bind(todo.updated_at)
+                .bind(&position)
+                .bind(status_id.to_string())
+                .bind(todo.estimated_minutes)
+                .bind(completed_at)
+                .bind(todo.archived_at)
+                .bind(project_id.map(|id| id.to_string()))
+                .execute(&mut *self.conn)
+                .await?;
+                last_position = Some(position);
+            } else {
+                sqlx::query(
+                    "UPDATE todos SET title = $1, description = $2, completed = $3, priority = $4, status_id = $5, estimated_minutes = $6, \
+                    completed_at = $7, archived_at = $8, updated_at = $9, project_id = $10, version = version + 1 WHERE id = $11",
+                )
+                .bind(&todo.title)
+                .bind(&todo.description)
+                .bind(completed)
+                .bind(&todo.priority)
+                .bind(status_id.to_string())
+                .bind(todo.estimated_minutes)
+                .bind(completed_at)
+                .bind(todo.archived_at)
+                .bind(Utc::now())
+                .bind(project_id.map(|id| id.to_string()))
+                .bind(&id_str)
+                .execute(&mut *self.conn)
+                .await?;
+            }
+            save_field_values(self.conn, &id_str, &todo.custom_fields, project_id).await?;
+            save_tags(self.conn, &id_str, &todo.tags).await?;
+
+            let after = fetch_todo(self.conn, &id_str).await?;
+            record_revision(self.conn, before.as_ref(), &after, DUMMY_USER_ID).await?;
+            self.report.todos.tally(&action);
+        }
+        Ok(())
+    }
+
+    async fn import_dependencies(&mut self, dependencies: &[Dependency]) -> Result<(), AppError> {
+        for dependency in dependencies {
+            let (Some(blocker_id), Some(blocked_id)) =
+                (self.mapped(dependency.blocker_id), self.mapped(dependency.blocked_id))
+            else {
+                self.report.dependencies.skipped += 1;
+                continue;
+            };
+            let inserted = sqlx::query(
+                "INSERT OR IGNORE INTO todo_dependencies (blocker_id, blocked_id, created_at) VALUES ($1, $2, $3)",
+            )
+            .bind(blocker_id.to_string())
+            .bind(blocked_id.to_string())
+            .bind(Utc::now())
+            .execute(&mut *self.conn)
+            .await?
+            .rows_affected();
+            if inserted > 0 {
+                self.report.dependencies.created += 1;
+            } else {
+                self.report.dependencies.skipped += 1;
+            }
+        }
+        Ok(())
+    }
+
+    async fn import_time_entries(&mut self, entries: &[TimeEntry]) -> Result<(), AppError> {
+        for entry in entries {
+            let Some(todo_id) = self.mapped(entry.todo_id) else {
+                self.report.time_entries.skipped += 1;
+                continue;
+            };
+            // Only one timer may run at a time; an imported running timer loses to a local one.
+            if entry.ended_at.is_none() {
+                let running: i64 = sqlx::query_scalar(
+                    "SELECT COUNT(*) FROM time_entries WHERE user_id = $1 AND ended_at IS NULL AND id != $2",
+                )
+                .bind(DUMMY_USER_ID)
+                .bind(entry.id.to_string())
+                .fetch_one(&mut *self.conn)
+                .await?;
+                if running > 0 {
+                    self.report.time_entries.skipped += 1;
+                    continue;
+                }
+            }
+
+            let action = self.resolve("time_entries", entry.id, None, self.strategy).await?;
+            match action {
+                Action::Insert(id) => {
+                    sqlx::query(
+                        "INSERT INTO time_entries (id, user_id, todo_id, started_at, ended_at, note, created_at) VALUES ($1, $2, $3, $4, $5, $6, $7)",
+                    )
+                    .bind(id.to_string())
+                    .bind(DUMMY_USER_ID)
+                    .bind(todo_id.to_string())
+                    .bind(entry.started_at)
+                    .bind(entry.ended_at)
+                    .bind(&entry.note)
+                    .bind(entry.created_at)
+                    .execute(&mut *self.conn)
+                    .await?;
+                }
+                Action::Update(id) => {
+                    sqlx::query("UPDATE time_entries SET todo_id = $1, started_at = $2, ended_at = $3, note = $4 WHERE id = $5")
+                        .bind(todo_id.to_string())
+                        .bind(entry.started_at)
+                        .bind(entry.ended_at)
+                        .bind(&entry.note)
+                        .bind(id.to_string())
+                        .execute(&mut *self.conn)
+                        .await?;
+                }
+                Action::Skip(_) => {}
+            }
+            self.report.time_entries.tally(&action);
+        }
+        Ok(())
+    }
+
+    async fn import_templates(&mut self, templates: &[Template]) -> Result<(), AppError> {
+        for template in templates {
+            let mut body = template.todo.clone();
+            body.status_id = body.status_id.and_then(|id| self.mapped(id));
+            body.project_id = self.mapped_project(body.project_id).unwrap_or(None);
+            let body = serde_json::to_string(&body).map_err(|_| AppError::InternalServerError)?;
+
+            let action = self.resolve("templates", template.id, None, self.strategy).await?;
+            match action {
+                Action::Insert(id) => {
+                    sqlx::query(
+                        "INSERT INTO templates (id, user_id, name, body, created_at) VALUES ($1, $2, $3, $4, $5)",
+                    )
+                    .bind(id.to_string())
+                    .bind(DUMMY_USER_ID)
+                    .bind(&template.name)
+                    .bind(&body)
+                    .bind(template.created_at)
+                    .execute(&mut *self.conn)
+                    .await?;
+                }
+                Action::Update(id) => {
+                    sqlx::query("UPDATE templates SET name = $1, body = $2 WHERE id = $3")
+                        .bind(&template.name)
+                        .bind(&body)
+                        .bind(id.to_string())
+                        .execute(&mut *self.conn)
+                        .await?;
+                }
+                Action::Skip(_) => {}
+            }
+            self.report.templates.tally(&action);
+        }
+        Ok(())
+    }
+
+    async fn import_saved_views(&mut self, views: &[SavedView]) -> Result<(), AppError> {
+        for view in views {
+            let action = self.resolve("saved_views", view.id, None, self.strategy).await?;
+            match action {
+                Action::Insert(id) => {
+                    sqlx::query(
+                        "INSERT INTO saved_views (id, user_id, name, query, position, created_at) VALUES ($1, $2, $3, $4, $5, $6)",
+                    )
+                    .bind(id.to_string())
+                    .bind(DUMMY_USER_ID)
+                    .bind(&view.name)
+                    .bind(&view.query)
+                    .bind(view.position)
+                    .bind(view.created_at)
+                    .execute(&mut *self.conn)
+                    .await?;
+                }
+                Action::Update(id) => {
+                    sqlx::query("UPDATE saved_views SET name = $1, query = $2, position = $3 WHERE id = $4")
+                        .bind(&view.name)
+                        .bind(&view.query)
+                        .bind(view.position)
+                        .bind(id.to_string())
+                        .execute(&mut *self.conn)
+                        .await?;
+                }
+                Action::Skip(_) => {}
+            }
+            self.report.saved_views.tally(&action);
+        }
+        Ok(())
+    }
+}
+
+/// Imports a document written by `export_data` in one transaction. Ids that are taken by
+/// another user or duplicated are replaced, and references to them follow. With `?dry_run=true`
+/// the import runs in full and is then rolled back, so the report shows exactly what it would do.
+pub async fn import_data(
+    State(db): State<DB>,
+    State(events): State<Events>,
+    Query(options): Query<ImportOptions>,
+    Json(document): Json<ExportDocument>,
+) -> Result<Json<ImportReport>, AppError> {
+    if document.version == 0 || document.version > EXPORT_FORMAT_VERSION {
+        return Err(AppError::BadRequest(format!(
+            "unsupported export version {}; this server reads up to version {}",
+            document.version, EXPORT_FORMAT_VERSION
+        )));
+    }
+
+    // Checked up front, so a bad view is named instead of failing the import part way through.
+    for view in &document.saved_views {
+        view_query::parse(&view.query, Utc::now()).map_err(|e| {
+            AppError::BadRequest(format!("saved view `{}` ({}) has an invalid query: {}", view.name, view.id, e))
+        })?;
+    }
+
+    let mut tx = db.begin().await?;
+    let mut importer = Importer {
+        conn: &mut tx,
+        strategy: options.strategy,
+        ids: HashMap::new(),
+        report: ImportReport { dry_run: options.dry_run, strategy: options.strategy, ..Default::default() },
+    };
+    importer.import_settings(&document.settings).await?;
+    importer.import_projects(&document.projects).await?;
+    importer.import_statuses(&document.statuses).await?;
+    importer.import_custom_fields(&document.custom_fields).await?;
+    importer.import_todos(&document.todos).await?;
+    importer.import_dependencies(&document.dependencies).await?;
+    importer.import_time_entries(&document.time_entries).await?;
+    importer.import_templates(&document.templates).await?;
+    importer.import_saved_views(&document.saved_views).await?;
+    let report = importer.report;
+
+    if options.dry_run {
+        tx.rollback().await?;
+    } else {
+        events.commit(tx, DUMMY_USER_ID, vec![TodoEvent::Reload]).await?;
+    }
+    Ok(Json(report))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        db::test_db,
+        handlers::{projects::create_project, statuses::create_status, todos::insert_todo},
+        models::{CreateProject, CreateStatus, CreateTodo, Priority, StatusCategory},
+    };
+
+    #[tokio::test]
+    async fn projects_and_tags_survive_an_export_and_import() {
+        let source = test_db().await;
+        let (_, Json(project)) = create_project(
+            State(source.clone()),
+            Json(CreateProject { name: "Launch".to_string(), position: None }),
+        )
+        .await
+        .unwrap();
+        let mut conn = source.acquire().await.unwrap();
+        let todo = CreateTodo {
+            title: "Write the post".to_string(),
+            description: None,
+            priority: Priority::Medium,
+            status_id: None,
+            estimated_minutes: None,
+            custom_fields: Default::default(),
+            project_id: Some(project.id),
+            tags: vec!["blog".to_string(), "launch".to_string()],
+        };
+        let todo = insert_todo(&mut conn, &todo).await.unwrap();
+        drop(conn);
+        let request = CreateStatus {
+            name: "Review".to_string(),
+            category: StatusCategory::Doing,
+            position: None,
+            project_id: Some(project.id),
+        };
+        let (_, Json(review)) = create_status(State(source.clone()), State(Events::default()), Json(request)).await.unwrap();
+
+        let mut conn = source.acquire().await.unwrap();
+        let statuses = sqlx::query_as::<_, Status>(&format!("SELECT {STATUS_COLUMNS} FROM statuses"))
+            .fetch_all(&mut *conn)
+            .await
+            .unwrap();
+        assert!(statuses.iter().any(|status| status.id == review.id));
+        let document = ExportDocument {
+            version: EXPORT_FORMAT_VERSION,
+            exported_at: Utc::now(),
+            settings: UserSettings::default(),
+            projects: all_projects(&mut conn).await.unwrap(),
+            statuses: statuses.clone(),
+            custom_fields: vec![],
+            templates: vec![],
+            saved_views: vec![],
+            dependencies: vec![],
+            time_entries: vec![],
+            todos: vec![fetch_todo(&mut conn, &todo.id.to_string()).await.unwrap()],
+        };
+        let exported_status = statuses.iter().find(|status| Some(status.id) == document.todos[0].status_id).unwrap();
+        assert_eq!(exported_status.project_id, Some(project.id));
+
+        let target = test_db().await;
+        let Json(report) = import_data(
+            State(target.clone()),
+            State(Events::default()),
+            Query(ImportOptions::default()),
+            Json(document),
+        )
+        .await
+        .unwrap();
+        assert_eq!(report.projects.created, 1);
+
+        let mut conn = target.acquire().await.unwrap();
+        let imported = fetch_todo(&mut conn, &todo.id.to_string()).await.unwrap();
+        assert_eq!((imported.project_id, imported.tags), (Some(project.id), todo.tags));
+        let status = sqlx::query_as::<_, Status>(&format!("SELECT {STATUS_COLUMNS} FROM statuses WHERE id = $1"))
+            .bind(imported.status_id.unwrap().to_string())
+            .fetch_one(&mut *conn)
+            .await
+            .unwrap();
+        assert_eq!((status.name, status.project_id), (exported_status.name.clone(), Some(project.id)));
+    }
+}
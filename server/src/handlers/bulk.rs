@@ -0,0 +1,120 @@
+use crate::{
+    db::DB,
+    errors::AppError,
+    events::{Events, TodoEvent},
+    handlers::todos::{apply_replacement, fetch_todo, DUMMY_USER_ID},
+    models::{
+        BulkItemResult, BulkItemStatus, BulkOperation, BulkRequest, BulkResponse, BulkSelection,
+        ReplaceTodo, TodoFilter,
+    },
+};
+use axum::{extract::State, http::StatusCode, Json};
+use sqlx::SqliteConnection;
+use uuid::Uuid;
+
+async fn select_ids(conn: &mut SqliteConnection, filter: &TodoFilter) -> Result<Vec<Uuid>, AppError> {
+    let ids: Vec<String> = sqlx::query_scalar(
+        "SELECT id FROM todos WHERE user_id = $1 AND archived_at IS NULL AND ($2 IS NULL OR completed = $2) AND ($3 IS NULL OR priority = $3)",
+    )
+    .bind(DUMMY_USER_ID)
+    .bind(filter.completed)
+    .bind(&filter.priority)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    ids.iter()
+        .map(|id| Uuid::parse_str(id).map_err(|_| AppError::InternalServerError))
+        .collect()
+}
+
+async fn apply_operations(
+    conn: &mut SqliteConnection,
+    id: Uuid,
+    operations: &[BulkOperation],
+) -> Result<BulkItemResult, AppError> {
+    let id_str = id.to_string();
+    let before = match fetch_todo(conn, &id_str).await {
+        Ok(todo) => todo,
+        Err(AppError::NotFound) => {
+            return Ok(BulkItemResult { id, status: BulkItemStatus::NotFound, todo: None });
+        }
+        Err(e) => return Err(e),
+    };
+
+    let mut replacement = ReplaceTodo::from(&before);
+    let mut delete = false;
+    for operation in operations {
+        match operation {
+            BulkOperation::Complete => replacement.completed = true,
+            BulkOperation::Uncomplete => replacement.completed = false,
+            BulkOperation::SetPriority { priority } => replacement.priority = priority.clone(),
+            BulkOperation::MoveToProject { project_id } => replacement.project_id = *project_id,
+            BulkOperation::AddTag { tag } => replacement.tags.push(tag.clone()),
+            BulkOperation::RemoveTag { tag } => replacement.tags.retain(|t| t != tag.trim().trim_start_matches('#')),
+            BulkOperation::Delete => delete = true,
+        }
+    }
+
+    if delete {
+        sqlx::query("DELETE FROM todos WHERE id = $1 AND user_id = $2")
+            .bind(&id_str)
+            .bind(DUMMY_USER_ID)
+            .execute(&mut *conn)
+            .await?;
+        return Ok(BulkItemResult { id, status: BulkItemStatus::Deleted, todo: None });
+    }
+
+    match apply_replacement(conn, &before, &replacement, false).await {
+        Ok(todo) => Ok(BulkItemResult { id, status: BulkItemStatus::Updated, todo: Some(todo) }),
+        Err(AppError::PreconditionFailed(current)) => {
+            Ok(BulkItemResult { id, status: BulkItemStatus::Conflict, todo: Some(*current) })
+        }
+        Err(AppError::Blocked(_)) => {
+            Ok(BulkItemResult { id, status: BulkItemStatus::Blocked, todo: Some(before) })
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Applies `operations` to every selected todo in a single transaction. If any item fails the
+/// transaction is rolled back and the response reports which items caused it.
+pub async fn bulk_todos(
+    State(db): State<DB>,
+    State(events): State<Events>,
+    Json(payload): Json<BulkRequest>,
+) -> Result<(StatusCode, Json<BulkResponse>), AppError> {
+    let mut tx = db.begin().await?;
+    let ids = match payload.selection {
+        BulkSelection::Ids(ids) => ids,
+        BulkSelection::Filter(filter) => select_ids(&mut tx, &filter).await?,
+    };
+
+    let mut results = Vec::with_capacity(ids.len());
+    for id in ids {
+        results.push(apply_operations(&mut tx, id, &payload.operations).await?);
+    }
+
+    let applied = results
+        .iter()
+        .all(|r| matches!(r.status, BulkItemStatus::Updated | BulkItemStatus::Deleted));
+    if !applied {
+        tx.rollback().await?;
+        for result in &mut results {
+            if matches!(result.status, BulkItemStatus::Updated | BulkItemStatus::Deleted) {
+                result.status = BulkItemStatus::RolledBack;
+                result.todo = None;
+            }
+        }
+        return Ok((StatusCode::CONFLICT, Json(BulkResponse { applied, results })));
+    }
+
+    let changes = results
+        .iter()
+        .map(|result| match (&result.status, &result.todo) {
+            (BulkItemStatus::Updated, Some(todo)) => TodoEvent::Updated { todo: todo.clone() },
+            _ => TodoEvent::Deleted { id: result.id },
+        })
+        .collect();
+    events.commit(tx, DUMMY_USER_ID, changes).await?;
+    Ok((StatusCode::OK, Json(BulkResponse { applied, results })))
+}
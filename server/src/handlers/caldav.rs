@@ -0,0 +1,647 @@
+//! A minimal CalDAV (RFC 4791) server under `/dav/`, so task apps can sync todos as VTODOs.
+//!
+//! There is one principal, and a calendar collection for todos outside any project plus one
+//! per project:
+//!
+//! - `/dav/principal/` points clients at the calendar home, `/dav/calendars/`
+//! - `/dav/calendars/todos/` holds one resource per unarchived todo without a project
+//! - `/dav/calendars/<project id>/` holds the project's unarchived todos, and todos created in
+//!   it join the project
+//!
+//! Clients sign in with HTTP Basic, using a personal API token as the password.
+
+use crate::{
+    db::DB,
+    errors::AppError,
+    events::{Events, TodoEvent},
+    handlers::{
+        calendar::{calendar_document, category_of, due_field, ical_status, imported_from_vtodo, status_categories, vtodos},
+        custom_fields::all_fields,
+        projects::all_projects,
+        statuses::first_status_in,
+        tokens::basic_auth_user,
+        todos::{apply_replacement, etag, expected_version, insert_imported_todo, DUMMY_USER_ID, TODO_COLUMNS},
+    },
+    ical,
+    models::{Project, ReplaceTodo, StatusCategory, Todo},
+    webdav::{self, Element, CALDAV, CALENDAR_SERVER, DAV},
+};
+use axum::{
+    extract::State,
+    http::{header, HeaderMap, Method, StatusCode, Uri},
+    response::{IntoResponse, Redirect, Response},
+};
+use sqlx::SqliteConnection;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+const PRINCIPAL: &str = "/dav/principal/";
+const HOME: &str = "/dav/calendars/";
+/// Segment of the collection for todos without a project.
+const INBOX: &str = "todos";
+
+const ALLOW: &str = "OPTIONS, GET, HEAD, PUT, DELETE, PROPFIND, REPORT";
+
+/// Properties returned for `allprop`, when a client does not name the ones it wants.
+const ALL_PROPERTIES: &[(&str, &str)] = &[
+    (DAV, "resourcetype"),
+    (DAV, "displayname"),
+    (DAV, "current-user-principal"),
+    (DAV, "principal-URL"),
+    (CALDAV, "calendar-home-set"),
+    (CALDAV, "supported-calendar-component-set"),
+    (DAV, "supported-report-set"),
+    (CALENDAR_SERVER, "getctag"),
+    (DAV, "getetag"),
+    (DAV, "getcontenttype"),
+];
+
+/// A todo as a calendar object resource.
+pub(crate) struct Resource {
+    pub todo: Todo,
+    /// Last path segment of the resource's URL.
+    pub name: String,
+    pub uid: String,
+}
+
+/// The user's unarchived todos in list order, with the names and UIDs clients gave those they
+/// created. Other todos are named `<id>.ics` and use their id as the UID.
+pub(crate) async fn resources(conn: &mut SqliteConnection, user_id: &str) -> Result<Vec<Resource>, AppError> {
+    let todos = sqlx::query_as::<_, Todo>(&format!(
+        "SELECT {TODO_COLUMNS} FROM todos WHERE user_id = $1 AND archived_at IS NULL ORDER BY position"
+    ))
+    .bind(user_id)
+    .fetch_all(&mut *conn)
+    .await?;
+    let mut names: HashMap<String, (String, String)> =
+        sqlx::query_as::<_, (String, String, String)>("SELECT todo_id, name, uid FROM dav_resources WHERE user_id = $1")
+            .bind(user_id)
+            .fetch_all(&mut *conn)
+            .await?
+            .into_iter()
+            .map(|(todo_id, name, uid)| (todo_id, (name, uid)))
+            .collect();
+
+    Ok(todos
+        .into_iter()
+        .map(|todo| {
+            let id = todo.id.to_string();
+            let (name, uid) = names.remove(&id).unwrap_or_else(|| (format!("{}.ics", id), id));
+            Resource { todo, name, uid }
+        })
+        .collect())
+}
+
+struct Listing {
+    resources: Vec<Resource>,
+    projects: Vec<Project>,
+    categories: HashMap<String, StatusCategory>,
+}
+
+impl Listing {
+    async fn load(conn: &mut SqliteConnection, user_id: &str) -> Result<Self, AppError> {
+        Ok(Listing {
+            resources: resources(conn, user_id).await?,
+            projects: all_projects(conn).await?,
+            categories: status_categories(conn, user_id).await?,
+        })
+    }
+
+    /// Checks that a collection exists; a project's goes away with the project.
+    fn collection(&self, project_id: Option<Uuid>) -> Result<Option<&Project>, AppError> {
+        match project_id {
+            Some(id) => self.projects.iter().find(|project| project.id == id).map(Some).ok_or(AppError::NotFound),
+            None => Ok(None),
+        }
+    }
+
+    fn in_collection(&self, project_id: Option<Uuid>) -> impl Iterator<Item = &Resource> {
+        self.resources.iter().filter(move |resource| resource.todo.project_id == project_id)
+    }
+
+    fn find(&self, project_id: Option<Uuid>, name: &str) -> Option<&Resource> {
+        self.in_collection(project_id).find(|resource| resource.name == name)
+    }
+
+    /// Changes whenever a todo in the collection is added, edited, archived, deleted or moved
+    /// in or out, so clients know to resync.
+    fn ctag(&self, project_id: Option<Uuid>) -> String {
+        let resources: Vec<&Resource> = self.in_collection(project_id).collect();
+        let versions: i64 = resources.iter().map(|resource| resource.todo.version).sum();
+        let updated = resources.iter().map(|resource| resource.todo.updated_at.timestamp_millis()).max();
+        format!("{}-{}-{}", resources.len(), versions, updated.unwrap_or(0))
+    }
+
+    fn calendar_data(&self, resource: &Resource) -> String {
+        let category = category_of(&resource.todo, &self.categories);
+        calendar_document(&[(&resource.todo, &resource.uid, category)])
+    }
+}
+
+/// Collections are named by their project, `None` being the one for todos without a project.
+enum Target {
+    Root,
+    Principal,
+    Home,
+    Collection(Option<Uuid>),
+    Resource(Option<Uuid>, String),
+}
+
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes.get(i + 1..i + 3).and_then(|hex| std::str::from_utf8(hex).ok());
+        match hex.and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+            Some(byte) if bytes[i] == b'%' => {
+                out.push(byte);
+                i += 3;
+            }
+            _ => {
+                out.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+fn percent_encode(text: &str) -> String {
+    text.bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'@' => (byte as char).to_string(),
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+/// What a path or href under `/dav` refers to. Hrefs may be full URLs.
+fn parse_target(path: &str) -> Option<Target> {
+    let path = match path.find("://") {
+        Some(scheme) => path[scheme + 3..].find('/').map_or("/", |host| &path[scheme + 3 + host..]),
+        None => path,
+    };
+    let path = path.strip_prefix("/dav")?;
+    let collection = |segment: &str| match segment {
+        INBOX => Some(None),
+        id => Uuid::parse_str(id).ok().map(Some),
+    };
+    match path.trim_matches('/') {
+        "" => Some(Target::Root),
+        "principal" => Some(Target::Principal),
+        "calendars" => Some(Target::Home),
+        rest => {
+            let rest = rest.strip_prefix("calendars/")?;
+            match rest.split_once('/') {
+                None => collection(rest).map(Target::Collection),
+                Some((segment, name)) if !name.contains('/') => {
+                    collection(segment).map(|project_id| Target::Resource(project_id, percent_decode(name)))
+                }
+                Some(_) => None,
+            }
+        }
+    }
+}
+
+fn collection_href(project_id: Option<Uuid>) -> String {
+    match project_id {
+        Some(id) => format!("{}{}/", HOME, id),
+        None => format!("{}{}/", HOME, INBOX),
+    }
+}
+
+fn href(resource: &Resource) -> String {
+    format!("{}{}", collection_href(resource.todo.project_id), percent_encode(&resource.name))
+}
+
+/// Something a PROPFIND or REPORT can describe.
+enum Node<'a> {
+    Root,
+    Principal,
+    Home,
+    Collection(Option<&'a Project>),
+    Resource(&'a Resource),
+}
+
+fn property(node: &Node, listing: &Listing, namespace: &str, name: &str) -> Option<String> {
+    let href = |path: &str| format!("<D:href>{}</D:href>", webdav::escape(path));
+    let value = match (namespace, name, node) {
+        (DAV, "resourcetype", Node::Resource(_)) => String::new(),
+        (DAV, "resourcetype", Node::Principal) => "<D:principal/>".to_string(),
+        (DAV, "resourcetype", Node::Collection(_)) => "<D:collection/><C:calendar/>".to_string(),
+        (DAV, "resourcetype", _) => "<D:collection/>".to_string(),
+        (DAV, "displayname", Node::Collection(None)) => "Todos".to_string(),
+        (DAV, "displayname", Node::Collection(Some(project))) => webdav::escape(&project.name),
+        (DAV, "current-user-principal", _) => href(PRINCIPAL),
+        (DAV, "principal-URL", Node::Principal) => href(PRINCIPAL),
+        (CALDAV, "calendar-home-set", Node::Principal) => href(HOME),
+        (DAV, "current-user-privilege-set", _) => {
+            "<D:privilege><D:read/></D:privilege><D:privilege><D:write/></D:privilege>".to_string()
+        }
+        (CALDAV, "supported-calendar-component-set", Node::Collection(_)) => "<C:comp name=\"VTODO\"/>".to_string(),
+        (DAV, "supported-report-set", Node::Collection(_)) => ["calendar-query", "calendar-multiget"]
+            .iter()
+            .map(|report| format!("<D:supported-report><D:report><C:{}/></D:report></D:supported-report>", report))
+            .collect(),
+        (CALENDAR_SERVER, "getctag", Node::Collection(project)) => {
+            webdav::escape(&listing.ctag(project.map(|project| project.id)))
+        }
+        (DAV, "getetag", Node::Resource(resource)) => webdav::escape(&etag(resource.todo.version)),
+        (DAV, "getcontenttype", Node::Resource(_)) => "text/calendar; charset=utf-8; component=VTODO".to_string(),
+        (CALDAV, "calendar-data", Node::Resource(resource)) => webdav::escape(&listing.calendar_data(resource)),
+        _ => return None,
+    };
+    Some(webdav::property(namespace, name, &value))
+}
+
+/// The `response` describing a node. `requested` is `None` for `allprop`.
+fn node_response(href: &str, node: &Node, listing: &Listing, requested: Option<&[(String, String)]>) -> String {
+    let mut found = vec![];
+    let mut missing = vec![];
+    match requested {
+        Some(names) => {
+            for (namespace, name) in names {
+                match property(node, listing, namespace, name) {
+                    Some(value) => found.push(value),
+                    None => missing.push((namespace.clone(), name.clone())),
+                }
+            }
+        }
+        None => found.extend(ALL_PROPERTIES.iter().filter_map(|(namespace, name)| property(node, listing, namespace, name))),
+    }
+    webdav::response(href, &found, &missing)
+}
+
+/// The properties named in the `prop` element of a PROPFIND or REPORT body.
+fn requested_properties(body: &Element) -> Option<Vec<(String, String)>> {
+    body.child(DAV, "prop")
+        .map(|prop| prop.children.iter().map(|child| (child.namespace.clone(), child.name.clone())).collect())
+}
+
+fn multistatus(responses: &[String]) -> Response {
+    (
+        StatusCode::MULTI_STATUS,
+        [(header::CONTENT_TYPE, "application/xml; charset=utf-8")],
+        webdav::multistatus(responses),
+    )
+        .into_response()
+}
+
+/// A response naming the precondition a request failed, e.g. `<D:supported-report/>`.
+fn condition(status: StatusCode, condition: &str) -> Response {
+    let body = format!(
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<D:error xmlns:D=\"{}\" xmlns:C=\"{}\">{}</D:error>",
+        DAV, CALDAV, condition
+    );
+    (status, [(header::CONTENT_TYPE, "application/xml; charset=utf-8")], body).into_response()
+}
+
+fn parse_body(body: &str) -> Result<Option<Element>, AppError> {
+    if body.trim().is_empty() {
+        return Ok(None);
+    }
+    webdav::parse(body).map(Some).map_err(AppError::BadRequest)
+}
+
+fn propfind(target: Target, listing: &Listing, headers: &HeaderMap, body: &str) -> Result<Response, AppError> {
+    let requested = match parse_body(body)? {
+        Some(body) if body.is(DAV, "propfind") => requested_properties(&body),
+        Some(_) => return Err(AppError::BadRequest("expected a propfind element".to_string())),
+        None => None,
+    };
+    let requested = requested.as_deref();
+    // Depth defaults to infinity, which is served as 1.
+    let children = headers.get("depth").is_none_or(|depth| depth != "0");
+
+    let mut responses = vec![];
+    let mut describe = |href: &str, node: Node| responses.push(node_response(href, &node, listing, requested));
+    match target {
+        Target::Root => {
+            describe("/dav/", Node::Root);
+            if children {
+                describe(PRINCIPAL, Node::Principal);
+                describe(HOME, Node::Home);
+            }
+        }
+        Target::Principal => describe(PRINCIPAL, Node::Principal),
+        Target::Home => {
+            describe(HOME, Node::Home);
+            if children {
+                describe(&collection_href(None), Node::Collection(None));
+                for project in &listing.projects {
+                    describe(&collection_href(Some(project.id)), Node::Collection(Some(project)));
+                }
+            }
+        }
+        Target::Collection(project_id) => {
+            describe(&collection_href(project_id), Node::Collection(listing.collection(project_id)?));
+            if children {
+                for resource in listing.in_collection(project_id) {
+                    describe(&href(resource), Node::Resource(resource));
+                }
+            }
+        }
+        Target::Resource(project_id, name) => {
+            let resource = listing.find(project_id, &name).ok_or(AppError::NotFound)?;
+            describe(&href(resource), Node::Resource(resource));
+        }
+    }
+    Ok(multistatus(&responses))
+}
+
+/// Whether a todo passes a `prop-filter`. Only COMPLETED and STATUS are looked at; filters on
+/// other properties match every todo.
+fn matches_property(filter: &Element, todo: &Todo, category: Option<StatusCategory>) -> bool {
+    let value = match filter.attribute("name").map(str::to_uppercase).as_deref() {
+        Some("COMPLETED") => todo.completed.then(String::new),
+        Some("STATUS") => Some(ical_status(todo, category).to_string()),
+        _ => return true,
+    };
+    if filter.child(CALDAV, "is-not-defined").is_some() {
+        return value.is_none();
+    }
+    match (value, filter.child(CALDAV, "text-match")) {
+        (None, _) => false,
+        (Some(value), Some(text_match)) => {
+            let contains = value.to_lowercase().contains(&text_match.text.trim().to_lowercase());
+            contains != (text_match.attribute("negate-condition") == Some("yes"))
+        }
+        (Some(_), None) => true,
+    }
+}
+
+/// Whether a todo passes a calendar-query `filter`. Time ranges are not checked, since todos
+/// have no dates of their own to compare them with.
+fn matches_filter(filter: &Element, todo: &Todo, category: Option<StatusCategory>) -> bool {
+    let Some(calendar) = filter.child(CALDAV, "comp-filter") else {
+        return true;
+    };
+    calendar.attribute("name") == Some("VCALENDAR")
+        && calendar.children.iter().filter(|child| child.is(CALDAV, "comp-filter")).all(|component| {
+            component.attribute("name") == Some("VTODO")
+                && component
+                    .children
+                    .iter()
+                    .filter(|child| child.is(CALDAV, "prop-filter"))
+                    .all(|property| matches_property(property, todo, category))
+        })
+}
+
+fn report(target: Target, listing: &Listing, body: &str) -> Result<Response, AppError> {
+    let body = parse_body(body)?.ok_or_else(|| AppError::BadRequest("a REPORT needs a body".to_string()))?;
+    let project_id = match target {
+        Target::Collection(project_id) | Target::Resource(project_id, _) => project_id,
+        _ => return Ok(condition(StatusCode::FORBIDDEN, "<D:supported-report/>")),
+    };
+    listing.collection(project_id)?;
+    let requested = requested_properties(&body);
+    let requested = requested.as_deref();
+
+    let mut responses = vec![];
+    if body.is(CALDAV, "calendar-multiget") {
+        for element in body.children.iter().filter(|child| child.is(DAV, "href")) {
+            let href = element.text.trim();
+            let resource = match parse_target(href) {
+                Some(Target::Resource(project_id, name)) => listing.find(project_id, &name),
+                _ => None,
+            };
+            responses.push(match resource {
+                Some(resource) => node_response(href, &Node::Resource(resource), listing, requested),
+                None => webdav::status_response(href, "404 Not Found"),
+            });
+        }
+    } else if body.is(CALDAV, "calendar-query") {
+        for resource in listing.in_collection(project_id) {
+            let category = category_of(&resource.todo, &listing.categories);
+            let matches = match body.child(CALDAV, "filter") {
+                Some(filter) => matches_filter(filter, &resource.todo, category),
+                None => true,
+            };
+            let in_target = match &target {
+                Target::Resource(_, name) => &resource.name == name,
+                _ => true,
+            };
+            if matches && in_target {
+                responses.push(node_response(&href(resource), &Node::Resource(resource), listing, requested));
+            }
+        }
+    } else {
+        return Ok(condition(StatusCode::FORBIDDEN, "<D:supported-report/>"));
+    }
+    Ok(multistatus(&responses))
+}
+
+fn get(target: Target, listing: &Listing) -> Result<Response, AppError> {
+    let Target::Resource(project_id, name) = target else {
+        return Ok((StatusCode::METHOD_NOT_ALLOWED, [(header::ALLOW, ALLOW)]).into_response());
+    };
+    let resource = listing.find(project_id, &name).ok_or(AppError::NotFound)?;
+    Ok((
+        [
+            (header::CONTENT_TYPE, "text/calendar; charset=utf-8".to_string()),
+            (header::ETAG, etag(resource.todo.version)),
+        ],
+        listing.calendar_data(resource),
+    )
+        .into_response())
+}
+
+/// Creates or replaces the todo at `name` from the VTODO in the body. A new todo joins the
+/// collection's project. A replaced todo keeps whatever the VTODO has no place for, such as its
+/// estimate and other custom fields.
+async fn put(db: &DB, events: &Events, target: Target, headers: &HeaderMap, body: &str) -> Result<Response, AppError> {
+    let Target::Resource(project_id, name) = target else {
+        return Ok((StatusCode::METHOD_NOT_ALLOWED, [(header::ALLOW, ALLOW)]).into_response());
+    };
+    let components = ical::parse(body).map_err(AppError::BadRequest)?;
+    let vtodo = *vtodos(&components)
+        .first()
+        .ok_or_else(|| AppError::BadRequest("the body has no VTODO".to_string()))?;
+    let uid = vtodo
+        .property("UID")
+        .map(|property| ical::unescape_text(&property.value).trim().to_string())
+        .filter(|uid| !uid.is_empty())
+        .ok_or_else(|| AppError::BadRequest("the VTODO has no UID".to_string()))?;
+
+    let mut tx = db.begin().await?;
+    let listing = Listing::load(&mut tx, DUMMY_USER_ID).await?;
+    listing.collection(project_id)?;
+    let existing = listing.find(project_id, &name);
+    let create_only = headers.get(header::IF_NONE_MATCH).is_some_and(|value| value == "*");
+    let expected = match headers.contains_key(header::IF_MATCH) {
+        true => Some(expected_version(headers)?),
+        false => None,
+    };
+    match (existing, expected) {
+        (Some(_), _) if create_only => return Ok(StatusCode::PRECONDITION_FAILED.into_response()),
+        (None, Some(_)) => return Ok(StatusCode::PRECONDITION_FAILED.into_response()),
+        (Some(resource), Some(Some(version))) if version != resource.todo.version => {
+            return Err(AppError::PreconditionFailed(Box::new(resource.todo.clone())));
+        }
+        _ => {}
+    }
+
+    let fields = all_fields(&mut tx).await?;
+    let in_progress = first_status_in(&mut tx, None, StatusCategory::Doing).await?;
+    let mut imported = imported_from_vtodo(vtodo, &fields, in_progress).map_err(AppError::BadRequest)?;
+    imported.todo.project_id = project_id;
+
+    let (status, todo) = match existing {
+        None => {
+            if let Some(other) = listing.resources.iter().find(|resource| resource.uid == uid) {
+                let conflict = format!("<C:no-uid-conflict><D:href>{}</D:href></C:no-uid-conflict>", webdav::escape(&href(other)));
+                return Ok(condition(StatusCode::FORBIDDEN, &conflict));
+            }
+            // Names are unique across collections, so one taken in another project can't be reused.
+            if listing.resources.iter().any(|resource| resource.name == name) {
+                return Ok(StatusCode::CONFLICT.into_response());
+            }
+            let todo = insert_imported_todo(&mut tx, &imported).await?;
+            // A name left behind by an archived todo is handed to the new one.
+            sqlx::query(
+                "INSERT INTO dav_resources (todo_id, user_id, name, uid) VALUES ($1, $2, $3, $4) \
+                ON CONFLICT (user_id, name) DO UPDATE SET todo_id = excluded.todo_id, uid = excluded.uid",
+            )
+            .bind(todo.id.to_string())
+            .bind(DUMMY_USER_ID)
+            .bind(&name)
+            .bind(&uid)
+            .execute(&mut *tx)
+            .await?;
+            (StatusCode::CREATED, todo)
+        }
+        Some(resource) => {
+            let before = &resource.todo;
+            let mut replacement = ReplaceTodo {
+                title: imported.todo.title,
+                description: imported.todo.description,
+                completed: imported.completed,
+                priority: imported.todo.priority,
+                tags: imported.todo.tags,
+                ..ReplaceTodo::from(before)
+            };
+            if let Some(field) = due_field(&fields) {
+                match imported.todo.custom_fields.0.get(&field.key) {
+                    Some(due) => replacement.custom_fields.0.insert(field.key.clone(), due.clone()),
+                    None => replacement.custom_fields.0.remove(&field.key),
+                };
+            }
+            // IN-PROCESS moves the todo to the first in-progress status, and NEEDS-ACTION moves
+            // it back out of one; otherwise the status follows `completed`.
+            let category = category_of(before, &listing.categories);
+            if imported.todo.status_id.is_some() {
+                replacement.status_id = imported.todo.status_id;
+            } else if !imported.completed && category == Some(StatusCategory::Doing) {
+                replacement.status_id = first_status_in(&mut tx, None, StatusCategory::Todo).await?;
+            }
+            (StatusCode::NO_CONTENT, apply_replacement(&mut tx, before, &replacement, false).await?)
+        }
+    };
+    let etag = etag(todo.version);
+    let event = match status {
+        StatusCode::CREATED => TodoEvent::Created { todo },
+        _ => TodoEvent::Updated { todo },
+    };
+    events.commit(tx, DUMMY_USER_ID, vec![event]).await?;
+    Ok((status, [(header::ETAG, etag)]).into_response())
+}
+
+async fn delete(db: &DB, events: &Events, target: Target, headers: &HeaderMap) -> Result<Response, AppError> {
+    let Target::Resource(project_id, name) = target else {
+        return Ok((StatusCode::METHOD_NOT_ALLOWED, [(header::ALLOW, ALLOW)]).into_response());
+    };
+    let expected = match headers.contains_key(header::IF_MATCH) {
+        true => expected_version(headers)?,
+        false => None,
+    };
+    let mut tx = db.begin().await?;
+    let listing = Listing::load(&mut tx, DUMMY_USER_ID).await?;
+    let todo = &listing.find(project_id, &name).ok_or(AppError::NotFound)?.todo;
+
+    let rows_affected = sqlx::query("DELETE FROM todos WHERE id = $1 AND user_id = $2 AND version = $3")
+        .bind(todo.id.to_string())
+        .bind(DUMMY_USER_ID)
+        .bind(expected.unwrap_or(todo.version))
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+    if rows_affected == 0 {
+        return Err(AppError::PreconditionFailed(Box::new(todo.clone())));
+    }
+    events.commit(tx, DUMMY_USER_ID, vec![TodoEvent::Deleted { id: todo.id }]).await?;
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
+
+/// Every request under `/dav`. Requests other than OPTIONS need an API token.
+pub async fn caldav(
+    State(db): State<DB>,
+    State(events): State<Events>,
+    method: Method,
+    uri: Uri,
+    headers: HeaderMap,
+    body: String,
+) -> Result<Response, AppError> {
+    if method == Method::OPTIONS {
+        return Ok((StatusCode::OK, [("dav", "1, 3, calendar-access"), ("allow", ALLOW)]).into_response());
+    }
+    // The token only has to be valid. Like every other handler, CalDAV acts for `DUMMY_USER_ID`,
+    // the only user tokens are issued to.
+    match basic_auth_user(&db, &headers).await {
+        Ok(_) => {}
+        Err(AppError::AuthenticationError) => {
+            return Ok((
+                StatusCode::UNAUTHORIZED,
+                [(header::WWW_AUTHENTICATE, "Basic realm=\"todos\"")],
+                "Authentication Failed",
+            )
+                .into_response());
+        }
+        Err(e) => return Err(e),
+    }
+    let target = parse_target(uri.path()).ok_or(AppError::NotFound)?;
+
+    match method.as_str() {
+        "PUT" => put(&db, &events, target, &headers, &body).await,
+        "DELETE" => delete(&db, &events, target, &headers).await,
+        "GET" | "HEAD" | "PROPFIND" | "REPORT" => {
+            let mut conn = db.acquire().await?;
+            let listing = Listing::load(&mut conn, DUMMY_USER_ID).await?;
+            match method.as_str() {
+                "PROPFIND" => propfind(target, &listing, &headers, &body),
+                "REPORT" => report(target, &listing, &body),
+                _ => get(target, &listing),
+            }
+        }
+        _ => Ok((StatusCode::METHOD_NOT_ALLOWED, [(header::ALLOW, ALLOW)]).into_response()),
+    }
+}
+
+/// Service discovery (RFC 6764) for clients given only the host name.
+pub async fn well_known_caldav() -> Redirect {
+    Redirect::permanent("/dav/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn targets() {
+        let project = Uuid::new_v4();
+        let collection = |path: &str| match parse_target(path) {
+            Some(Target::Collection(project_id)) => Some(project_id),
+            _ => None,
+        };
+        assert_eq!(collection("/dav/calendars/todos/"), Some(None));
+        assert_eq!(collection(&format!("https://example.com/dav/calendars/{}", project)), Some(Some(project)));
+        assert_eq!(collection("/dav/calendars/work/"), None);
+
+        match parse_target(&format!("/dav/calendars/{}/pay%20rent.ics", project)) {
+            Some(Target::Resource(project_id, name)) => assert_eq!((project_id, name.as_str()), (Some(project), "pay rent.ics")),
+            _ => panic!("expected a resource"),
+        }
+        assert!(parse_target("/dav/calendars/todos/a/b.ics").is_none());
+        assert_eq!(collection_href(None), "/dav/calendars/todos/");
+    }
+}
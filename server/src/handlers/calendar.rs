@@ -0,0 +1,331 @@
+use crate::{
+    db::DB,
+    errors::AppError,
+    events::{Events, TodoEvent},
+    handlers::{
+        caldav::resources,
+        custom_fields::all_fields,
+        statuses::first_status_in,
+        todos::{insert_imported_todo, ImportedTodo, DUMMY_USER_ID},
+    },
+    ical::{self, Component},
+    models::{
+        CalendarFeed, CalendarFeedQuery, CreateTodo, CustomField, DryRunQuery, FieldType,
+        FieldValues, ImportRowError, Priority, StatusCategory, TextImportReport, Todo,
+    },
+};
+use axum::{
+    extract::{Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{DateTime, NaiveDate, Utc};
+use serde_json::Value;
+use sqlx::SqliteConnection;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+const PRODID: &str = "PRODID:-//dioxus-todo//Todos//EN";
+
+/// Todos have no due date of their own; a date custom field with this key stands in for one
+/// and is written as DUE.
+pub(crate) const DUE_FIELD: &str = "due";
+
+/// RFC 5545 ranks 1 highest and 9 lowest; 1, 5 and 9 are what clients show as high, medium and low.
+fn ical_priority(priority: &Priority) -> u8 {
+    match priority {
+        Priority::High => 1,
+        Priority::Medium => 5,
+        Priority::Low => 9,
+    }
+}
+
+/// 0 means undefined and reads as medium, the default for new todos.
+fn priority_from_ical(priority: u8) -> Priority {
+    match priority {
+        1..=4 => Priority::High,
+        6..=9 => Priority::Low,
+        _ => Priority::Medium,
+    }
+}
+
+pub(crate) fn ical_status(todo: &Todo, category: Option<StatusCategory>) -> &'static str {
+    if todo.completed {
+        "COMPLETED"
+    } else if category == Some(StatusCategory::Doing) {
+        "IN-PROCESS"
+    } else {
+        "NEEDS-ACTION"
+    }
+}
+
+/// The custom field that holds due dates, if the user has set one up.
+pub(crate) fn due_field(fields: &[CustomField]) -> Option<&CustomField> {
+    fields.iter().find(|field| field.key == DUE_FIELD && field.field_type == FieldType::Date)
+}
+
+pub(crate) fn due_date(todo: &Todo) -> Option<NaiveDate> {
+    let value = todo.custom_fields.0.get(DUE_FIELD)?.as_str()?;
+    NaiveDate::parse_from_str(value, "%Y-%m-%d").ok()
+}
+
+fn write_vtodo(out: &mut String, todo: &Todo, uid: &str, category: Option<StatusCategory>, now: DateTime<Utc>) {
+    let mut line = |name: &str, value: String| ical::write_line(out, &format!("{}:{}", name, value));
+    line("BEGIN", "VTODO".to_string());
+    line("UID", ical::escape_text(uid));
+    line("DTSTAMP", ical::format_date_time(now));
+    line("CREATED", ical::format_date_time(todo.created_at));
+    line("LAST-MODIFIED", ical::format_date_time(todo.updated_at));
+    line("SEQUENCE", todo.version.to_string());
+    line("SUMMARY", ical::escape_text(&todo.title));
+    if let Some(description) = todo.description.as_deref().filter(|d| !d.is_empty()) {
+        line("DESCRIPTION", ical::escape_text(description));
+    }
+    line("PRIORITY", ical_priority(&todo.priority).to_string());
+    line("STATUS", ical_status(todo, category).to_string());
+    if !todo.tags.is_empty() {
+        let tags: Vec<String> = todo.tags.iter().map(|tag| ical::escape_text(tag)).collect();
+        line("CATEGORIES", tags.join(","));
+    }
+    if let Some(due) = due_date(todo) {
+        line("DUE;VALUE=DATE", ical::format_date(due));
+    }
+    if let Some(completed_at) = todo.completed_at.filter(|_| todo.completed) {
+        line("COMPLETED", ical::format_date_time(completed_at));
+        line("PERCENT-COMPLETE", "100".to_string());
+    }
+    line("END", "VTODO".to_string());
+}
+
+/// An iCalendar file holding one VTODO per todo, each with its UID and status category.
+pub(crate) fn calendar_document(todos: &[(&Todo, &str, Option<StatusCategory>)]) -> String {
+    let mut out = String::new();
+    for line in ["BEGIN:VCALENDAR", "VERSION:2.0", PRODID, "CALSCALE:GREGORIAN", "X-WR-CALNAME:Todos"] {
+        ical::write_line(&mut out, line);
+    }
+    let now = Utc::now();
+    for (todo, uid, category) in todos {
+        write_vtodo(&mut out, todo, uid, *category, now);
+    }
+    ical::write_line(&mut out, "END:VCALENDAR");
+    out
+}
+
+/// The category of each of the user's statuses, by status id.
+pub(crate) async fn status_categories(
+    conn: &mut SqliteConnection,
+    user_id: &str,
+) -> Result<HashMap<String, StatusCategory>, AppError> {
+    Ok(sqlx::query_as::<_, (String, StatusCategory)>("SELECT id, category FROM statuses WHERE user_id = $1")
+        .bind(user_id)
+        .fetch_all(&mut *conn)
+        .await?
+        .into_iter()
+        .collect())
+}
+
+pub(crate) fn category_of(todo: &Todo, categories: &HashMap<String, StatusCategory>) -> Option<StatusCategory> {
+    todo.status_id.and_then(|id| categories.get(&id.to_string()).copied())
+}
+
+/// The todos of the feed token's owner as an iCalendar file of VTODOs. Archived todos are left
+/// out. The token in the query string is the only credential, so calendar apps can subscribe
+/// to the URL as is.
+pub async fn calendar_feed(
+    State(db): State<DB>,
+    Query(query): Query<CalendarFeedQuery>,
+) -> Result<Response, AppError> {
+    let mut conn = db.acquire().await?;
+    let user_id: String = sqlx::query_scalar("SELECT user_id FROM calendar_feeds WHERE token = $1")
+        .bind(&query.token)
+        .fetch_optional(&mut *conn)
+        .await?
+        .ok_or(AppError::AuthenticationError)?;
+
+    // Todos created over CalDAV keep the UID their client gave them.
+    let resources = resources(&mut conn, &user_id).await?;
+    let categories = status_categories(&mut conn, &user_id).await?;
+
+    let entries: Vec<(&Todo, &str, Option<StatusCategory>)> = resources
+        .iter()
+        .map(|resource| (&resource.todo, resource.uid.as_str(), category_of(&resource.todo, &categories)))
+        .collect();
+    let out = calendar_document(&entries);
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "text/calendar; charset=utf-8"),
+            (header::CONTENT_DISPOSITION, "inline; filename=\"todos.ics\""),
+        ],
+        out,
+    )
+        .into_response())
+}
+
+/// The current subscription, if the user has turned the feed on.
+pub async fn get_calendar_feed(State(db): State<DB>) -> Result<Json<CalendarFeed>, AppError> {
+    let feed = sqlx::query_as::<_, CalendarFeed>(
+        "SELECT token, created_at FROM calendar_feeds WHERE user_id = $1",
+    )
+    .bind(DUMMY_USER_ID)
+    .fetch_optional(&db)
+    .await?
+    .ok_or(AppError::NotFound)?;
+    Ok(Json(feed))
+}
+
+/// Turns the feed on with a new token, replacing any earlier one so its URL stops working.
+pub async fn reset_calendar_feed(State(db): State<DB>) -> Result<Json<CalendarFeed>, AppError> {
+    let feed = CalendarFeed {
+        token: format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple()),
+        created_at: Utc::now(),
+    };
+    sqlx::query(
+        "INSERT INTO calendar_feeds (user_id, token, created_at) VALUES ($1, $2, $3) \
+        ON CONFLICT (user_id) DO UPDATE SET token = excluded.token, created_at = excluded.created_at",
+    )
+    .bind(DUMMY_USER_ID)
+    .bind(&feed.token)
+    .bind(feed.created_at)
+    .execute(&db)
+    .await?;
+    Ok(Json(feed))
+}
+
+pub async fn delete_calendar_feed(State(db): State<DB>) -> Result<StatusCode, AppError> {
+    sqlx::query("DELETE FROM calendar_feeds WHERE user_id = $1")
+        .bind(DUMMY_USER_ID)
+        .execute(&db)
+        .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+fn text(component: &Component, name: &str) -> Option<String> {
+    component
+        .property(name)
+        .map(|property| ical::unescape_text(&property.value).trim().to_string())
+        .filter(|text| !text.is_empty())
+}
+
+fn date_time(component: &Component, name: &str) -> Result<Option<DateTime<Utc>>, String> {
+    component
+        .property(name)
+        .map(|property| {
+            ical::parse_date_time(&property.value).ok_or_else(|| format!("cannot read {} `{}`", name, property.value))
+        })
+        .transpose()
+}
+
+/// The VTODOs of a parsed file, whether at the top level or inside a VCALENDAR.
+pub(crate) fn vtodos(components: &[Component]) -> Vec<&Component> {
+    components
+        .iter()
+        .flat_map(|component| match component.name.as_str() {
+            "VTODO" => vec![component],
+            _ => component.components.iter().filter(|child| child.name == "VTODO").collect(),
+        })
+        .collect()
+}
+
+/// Builds the todo for one VTODO. DUE fills the `due` date field when there is one and
+/// CATEGORIES become tags; RRULE and anything else without a place on a todo is left out.
+pub(crate) fn imported_from_vtodo(
+    vtodo: &Component,
+    fields: &[CustomField],
+    in_progress: Option<Uuid>,
+) -> Result<ImportedTodo, String> {
+    let title = text(vtodo, "SUMMARY").ok_or_else(|| "the VTODO has no SUMMARY".to_string())?;
+    let priority = match vtodo.property("PRIORITY") {
+        Some(property) => priority_from_ical(
+            property
+                .value
+                .trim()
+                .parse()
+                .map_err(|_| format!("`{}` is not a PRIORITY", property.value))?,
+        ),
+        None => Priority::Medium,
+    };
+    let status = vtodo.property("STATUS").map(|property| property.value.trim().to_uppercase());
+    let completed_at = date_time(vtodo, "COMPLETED")?;
+    // A cancelled task is no longer outstanding, and there is no other way to say so.
+    let completed = completed_at.is_some() || matches!(status.as_deref(), Some("COMPLETED" | "CANCELLED"));
+
+    let mut custom_fields = FieldValues::default();
+    if let (Some(field), Some(due)) = (due_field(fields), date_time(vtodo, "DUE")?) {
+        custom_fields.0.insert(field.key.clone(), Value::String(due.format("%Y-%m-%d").to_string()));
+    }
+
+    // CATEGORIES may be repeated, each with a comma-separated list.
+    let tags = vtodo
+        .properties
+        .iter()
+        .filter(|property| property.name == "CATEGORIES")
+        .flat_map(|property| ical::split_text_list(&property.value))
+        .collect();
+
+    Ok(ImportedTodo {
+        todo: CreateTodo {
+            title,
+            description: text(vtodo, "DESCRIPTION"),
+            priority,
+            status_id: in_progress.filter(|_| !completed && status.as_deref() == Some("IN-PROCESS")),
+            estimated_minutes: None,
+            custom_fields,
+            project_id: None,
+            tags,
+        },
+        completed,
+        created_at: date_time(vtodo, "CREATED")?,
+        completed_at,
+    })
+}
+
+/// Imports the VTODOs of an iCalendar file sent as the request body. Other components such as
+/// events are skipped. VTODOs that cannot be imported are reported by the line they begin on.
+pub async fn import_ics(
+    State(db): State<DB>,
+    State(events): State<Events>,
+    Query(options): Query<DryRunQuery>,
+    body: String,
+) -> Result<Json<TextImportReport>, AppError> {
+    let components = ical::parse(&body).map_err(AppError::BadRequest)?;
+
+    let mut tx = db.begin().await?;
+    let fields = all_fields(&mut tx).await?;
+    let in_progress = first_status_in(&mut tx, None, StatusCategory::Doing).await?;
+
+    let mut imported = 0;
+    let mut errors = vec![];
+    for vtodo in vtodos(&components) {
+        let result = match imported_from_vtodo(vtodo, &fields, in_progress) {
+            Ok(todo) => insert_imported_todo(&mut tx, &todo).await.map(|_| ()),
+            Err(message) => Err(AppError::BadRequest(message)),
+        };
+        match result {
+            Ok(()) => imported += 1,
+            Err(AppError::BadRequest(message)) => errors.push(ImportRowError { line: vtodo.line, message }),
+            Err(e) => return Err(e),
+        }
+    }
+
+    if options.dry_run {
+        tx.rollback().await?;
+    } else {
+        events.commit(tx, DUMMY_USER_ID, vec![TodoEvent::Reload]).await?;
+    }
+    Ok(Json(TextImportReport { dry_run: options.dry_run, imported, errors, unmapped: vec![] }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn categories_are_tags() {
+        let text = "BEGIN:VTODO\r\nSUMMARY:Pay rent\r\nCATEGORIES:finance,home\\, garden\r\nCATEGORIES:monthly\r\nEND:VTODO\r\n";
+        let components = ical::parse(text).unwrap();
+        let imported = imported_from_vtodo(vtodos(&components)[0], &[], None).unwrap();
+        assert_eq!(imported.todo.tags, ["finance", "home, garden", "monthly"]);
+    }
+}
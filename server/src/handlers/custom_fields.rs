@@ -0,0 +1,456 @@
+use crate::{
+    db::DB,
+    errors::AppError,
+    events::{Events, TodoEvent},
+    handlers::{projects::check_project, todos::DUMMY_USER_ID},
+    models::{CreateCustomField, CustomField, FieldType, FieldValues, UpdateCustomField},
+};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::{DateTime, NaiveDate, Utc};
+use serde_json::Value;
+use sqlx::{QueryBuilder, Sqlite, SqliteConnection};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+const CUSTOM_FIELD_COLUMNS: &str = "id, user_id, key, name, field_type, options, position, created_at, project_id";
+
+// Prefix that marks a custom field in list filters and sort keys, e.g. `cf.effort=gte:3`.
+const FILTER_PREFIX: &str = "cf.";
+
+#[derive(sqlx::FromRow)]
+struct CustomFieldRow {
+    id: String,
+    user_id: String,
+    key: String,
+    name: String,
+    field_type: FieldType,
+    options: String,
+    position: i64,
+    created_at: DateTime<Utc>,
+    project_id: Option<String>,
+}
+
+impl TryFrom<CustomFieldRow> for CustomField {
+    type Error = AppError;
+
+    fn try_from(row: CustomFieldRow) -> Result<Self, Self::Error> {
+        let parse_id = |s: &str| Uuid::parse_str(s).map_err(|_| AppError::InternalServerError);
+        Ok(CustomField {
+            id: parse_id(&row.id)?,
+            user_id: parse_id(&row.user_id)?,
+            key: row.key,
+            name: row.name,
+            field_type: row.field_type,
+            options: serde_json::from_str(&row.options).map_err(|_| AppError::InternalServerError)?,
+            position: row.position,
+            created_at: row.created_at,
+            project_id: row.project_id.as_deref().map(parse_id).transpose()?,
+        })
+    }
+}
+
+async fn fetch_field(conn: &mut SqliteConnection, id: Uuid) -> Result<CustomField, AppError> {
+    sqlx::query_as::<_, CustomFieldRow>(&format!(
+        "SELECT {CUSTOM_FIELD_COLUMNS} FROM custom_fields WHERE id = $1 AND user_id = $2"
+    ))
+    .bind(id.to_string())
+    .bind(DUMMY_USER_ID)
+    .fetch_optional(&mut *conn)
+    .await?
+    .ok_or(AppError::NotFound)?
+    .try_into()
+}
+
+/// The user's field definitions, keyed by `key`.
+async fn fields_by_key(conn: &mut SqliteConnection) -> Result<HashMap<String, CustomField>, AppError> {
+    let rows = sqlx::query_as::<_, CustomFieldRow>(&format!(
+        "SELECT {CUSTOM_FIELD_COLUMNS} FROM custom_fields WHERE user_id = $1"
+    ))
+    .bind(DUMMY_USER_ID)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    rows.into_iter()
+        .map(|row| CustomField::try_from(row).map(|field| (field.key.clone(), field)))
+        .collect()
+}
+
+/// Checks a value against its field's type.
+fn validate_value(field: &CustomField, value: &Value) -> Result<(), AppError> {
+    let valid = match (field.field_type, value) {
+        (FieldType::Text, Value::String(_)) => true,
+        (FieldType::Number, Value::Number(_)) => true,
+        (FieldType::Checkbox, Value::Bool(_)) => true,
+        (FieldType::Date, Value::String(s)) => NaiveDate::parse_from_str(s, "%Y-%m-%d").is_ok(),
+        (FieldType::Select, Value::String(s)) => field.options.contains(s),
+        _ => false,
+    };
+    if !valid {
+        return Err(AppError::BadRequest(format!(
+            "invalid value {} for {:?} field `{}`",
+            value, field.field_type, field.key
+        )));
+    }
+    Ok(())
+}
+
+/// Reads a value written as plain text, as in a CSV cell or a todo.txt extension.
+pub(crate) fn parse_field_text(field: &CustomField, text: &str) -> Result<Value, String> {
+    match field.field_type {
+        FieldType::Number => text
+            .parse::<f64>()
+            .ok()
+            .and_then(serde_json::Number::from_f64)
+            .map(Value::Number)
+            .ok_or_else(|| format!("`{}` is not a number", text)),
+        FieldType::Checkbox => match text.to_lowercase().as_str() {
+            "true" | "yes" | "y" | "1" | "x" => Ok(Value::Bool(true)),
+            "false" | "no" | "n" | "0" => Ok(Value::Bool(false)),
+            _ => Err(format!("cannot read `{}` as a checkbox; use true or false", text)),
+        },
+        FieldType::Text | FieldType::Date | FieldType::Select => Ok(Value::String(text.to_string())),
+    }
+}
+
+/// Replaces every custom field value stored for a todo in `project_id`. `null` values are treated
+/// as unset.
+pub(crate) async fn save_field_values(
+    conn: &mut SqliteConnection,
+    todo_id: &str,
+    values: &FieldValues,
+    project_id: Option<Uuid>,
+) -> Result<(), AppError> {
+    let fields = fields_by_key(conn).await?;
+    for (key, value) in values.0.iter().filter(|(_, value)| !value.is_null()) {
+        let field = fields
+            .get(key)
+            .ok_or_else(|| AppError::BadRequest(format!("unknown custom field `{}`", key)))?;
+        if field.project_id.is_some_and(|id| Some(id) != project_id) {
+            return Err(AppError::BadRequest(format!("custom field `{}` belongs to another project", key)));
+        }
+        validate_value(field, value)?;
+    }
+
+    sqlx::query("DELETE FROM todo_field_values WHERE todo_id = $1")
+        .bind(todo_id)
+        .execute(&mut *conn)
+        .await?;
+    for (key, value) in values.0.iter().filter(|(_, value)| !value.is_null()) {
+        sqlx::query("INSERT INTO todo_field_values (todo_id, field_id, value) VALUES ($1, $2, $3)")
+            .bind(todo_id)
+            .bind(fields[key].id.to_string())
+            .bind(value.to_string())
+            .execute(&mut *conn)
+            .await?;
+    }
+    Ok(())
+}
+
+/// Drops the values of fields limited to a project other than `project_id`, for a todo moving
+/// between projects. Unknown keys are kept so saving still reports them.
+pub(crate) async fn retain_project_fields(
+    conn: &mut SqliteConnection,
+    values: &mut FieldValues,
+    project_id: Option<Uuid>,
+) -> Result<(), AppError> {
+    let fields = fields_by_key(conn).await?;
+    values.0.retain(|key, _| {
+        fields
+            .get(key)
+            .and_then(|field| field.project_id)
+            .is_none_or(|id| Some(id) == project_id)
+    });
+    Ok(())
+}
+
+fn field_for<'a>(fields: &'a HashMap<String, CustomField>, name: &str) -> Result<&'a CustomField, AppError> {
+    let key = name.strip_prefix(FILTER_PREFIX).unwrap_or(name);
+    fields
+        .get(key)
+        .ok_or_else(|| AppError::BadRequest(format!("unknown custom field `{}`", key)))
+}
+
+/// Appends ` AND ...` conditions for every `cf.<key>` entry in `params` to a query over `todos`.
+/// Values are compared with `json_extract`, so numbers compare numerically and dates as ISO strings.
+pub(crate) async fn push_field_filters(
+    conn: &mut SqliteConnection,
+    builder: &mut QueryBuilder<'_, Sqlite>,
+    params: &HashMap<String, String>,
+) -> Result<(), AppError> {
+    let filters: Vec<_> = params
+        .iter()
+        .filter(|(name, _)| name.starts_with(FILTER_PREFIX))
+        .collect();
+    if filters.is_empty() {
+        return Ok(());
+    }
+
+    let fields = fields_by_key(conn).await?;
+    for (name, raw) in filters {
+        let field = field_for(&fields, name)?;
+        let (op, operand) = match raw.split_once(':') {
+            Some(("gt", rest)) => (">", rest),
+            Some(("gte", rest)) => (">=", rest),
+            Some(("lt", rest)) => ("<", rest),
+            Some(("lte", rest)) => ("<=", rest),
+            _ => ("=", raw.as_str()),
+        };
+
+        builder
+            .push(" AND EXISTS (SELECT 1 FROM todo_field_values v WHERE v.todo_id = todos.id AND v.field_id = ")
+            .push_bind(field.id.to_string())
+            .push(format!(" AND json_extract(v.value, '$') {op} "));
+        match field.field_type {
+            FieldType::Number => {
+                let number: f64 = operand.parse().map_err(|_| {
+                    AppError::BadRequest(format!("`{}` expects a number", field.key))
+                })?;
+                builder.push_bind(number);
+            }
+            FieldType::Checkbox => {
+                let checked: bool = operand.parse().map_err(|_| {
+                    AppError::BadRequest(format!("`{}` expects true or false", field.key))
+                })?;
+                builder.push_bind(checked as i64);
+            }
+            FieldType::Text | FieldType::Date | FieldType::Select => {
+                builder.push_bind(operand.to_string());
+            }
+        }
+        builder.push(")");
+    }
+    Ok(())
+}
+
+/// Appends an `ORDER BY` for `sort`, which is `cf.<key>` or `-cf.<key>`. Todos without a value
+/// sort last either way, and manual order breaks ties.
+pub(crate) async fn push_field_sort(
+    conn: &mut SqliteConnection,
+    builder: &mut QueryBuilder<'_, Sqlite>,
+    sort: Option<&str>,
+) -> Result<(), AppError> {
+    let Some(sort) = sort else {
+        builder.push(" ORDER BY position, id");
+        return Ok(());
+    };
+    let (name, direction) = match sort.strip_prefix('-') {
+        Some(name) => (name, "DESC"),
+        None => (sort, "ASC"),
+    };
+    if !name.starts_with(FILTER_PREFIX) {
+        return Err(AppError::BadRequest(format!("cannot sort by `{}`", sort)));
+    }
+    let fields = fields_by_key(conn).await?;
+    let field = field_for(&fields, name)?;
+
+    builder
+        .push(" ORDER BY (SELECT json_extract(v.value, '$') FROM todo_field_values v WHERE v.todo_id = todos.id AND v.field_id = ")
+        .push_bind(field.id.to_string())
+        .push(format!(") {direction} NULLS LAST, position, id"));
+    Ok(())
+}
+
+fn check_definition(field_type: FieldType, options: &[String]) -> Result<(), AppError> {
+    match field_type {
+        FieldType::Select if options.is_empty() => Err(AppError::BadRequest(
+            "select fields need at least one option".to_string(),
+        )),
+        FieldType::Select => Ok(()),
+        _ if !options.is_empty() => Err(AppError::BadRequest(
+            "only select fields take options".to_string(),
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// The user's field definitions in display order.
+pub(crate) async fn all_fields(conn: &mut SqliteConnection) -> Result<Vec<CustomField>, AppError> {
+    let rows = sqlx::query_as::<_, CustomFieldRow>(&format!(
+        "SELECT {CUSTOM_FIELD_COLUMNS} FROM custom_fields WHERE user_id = $1 ORDER BY position, created_at"
+    ))
+    .bind(DUMMY_USER_ID)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    rows.into_iter().map(CustomField::try_from).collect()
+}
+
+pub async fn list_custom_fields(State(db): State<DB>) -> Result<Json<Vec<CustomField>>, AppError> {
+    let mut conn = db.acquire().await?;
+    Ok(Json(all_fields(&mut conn).await?))
+}
+
+pub async fn create_custom_field(
+    State(db): State<DB>,
+    Json(payload): Json<CreateCustomField>,
+) -> Result<(StatusCode, Json<CustomField>), AppError> {
+    let key_is_valid = !payload.key.is_empty()
+        && payload.key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    if !key_is_valid {
+        return Err(AppError::BadRequest(
+            "field keys may only contain letters, digits, `_` and `-`".to_string(),
+        ));
+    }
+    check_definition(payload.field_type, &payload.options)?;
+
+    let mut tx = db.begin().await?;
+    check_project(&mut tx, payload.project_id).await?;
+    if fields_by_key(&mut tx).await?.contains_key(&payload.key) {
+        return Err(AppError::BadRequest(format!("field `{}` already exists", payload.key)));
+    }
+
+    let position = match payload.position {
+        Some(position) => position,
+        None => sqlx::query_scalar(
+            "SELECT COALESCE(MAX(position), -1) + 1 FROM custom_fields WHERE user_id = $1",
+        )
+        .bind(DUMMY_USER_ID)
+        .fetch_one(&mut *tx)
+        .await?,
+    };
+
+    let id = Uuid::new_v4();
+    sqlx::query(
+        "INSERT INTO custom_fields (id, user_id, key, name, field_type, options, position, created_at, project_id) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)",
+    )
+    .bind(id.to_string())
+    .bind(DUMMY_USER_ID)
+    .bind(&payload.key)
+    .bind(&payload.name)
+    .bind(payload.field_type)
+    .bind(serde_json::to_string(&payload.options).map_err(|_| AppError::InternalServerError)?)
+    .bind(position)
+    .bind(Utc::now())
+    .bind(payload.project_id.map(|id| id.to_string()))
+    .execute(&mut *tx)
+    .await?;
+
+    let field = fetch_field(&mut tx, id).await?;
+    tx.commit().await?;
+    Ok((StatusCode::CREATED, Json(field)))
+}
+
+/// Renames or reorders a field. The key and type are fixed once created, since stored values
+/// were validated against them; changing a select's options drops values no longer allowed.
+pub async fn update_custom_field(
+    State(db): State<DB>,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<UpdateCustomField>,
+) -> Result<Json<CustomField>, AppError> {
+    let mut tx = db.begin().await?;
+    let field = fetch_field(&mut tx, id).await?;
+
+    let name = payload.name.unwrap_or(field.name);
+    let position = payload.position.unwrap_or(field.position);
+    let options = payload.options.unwrap_or(field.options);
+    check_definition(field.field_type, &options)?;
+    let options_json = serde_json::to_string(&options).map_err(|_| AppError::InternalServerError)?;
+
+    sqlx::query("UPDATE custom_fields SET name = $1, position = $2, options = $3 WHERE id = $4")
+        .bind(&name)
+        .bind(position)
+        .bind(&options_json)
+        .bind(id.to_string())
+        .execute(&mut *tx)
+        .await?;
+
+    if field.field_type == FieldType::Select {
+        sqlx::query(
+            "DELETE FROM todo_field_values WHERE field_id = $1 AND json_extract(value, '$') NOT IN (SELECT value FROM json_each($2))",
+        )
+        .bind(id.to_string())
+        .bind(&options_json)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    let field = fetch_field(&mut tx, id).await?;
+    tx.commit().await?;
+    Ok(Json(field))
+}
+
+/// Deletes a field along with every value stored for it.
+pub async fn delete_custom_field(
+    State(db): State<DB>,
+    State(events): State<Events>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, AppError> {
+    let mut tx = db.begin().await?;
+    fetch_field(&mut tx, id).await?;
+
+    sqlx::query("DELETE FROM todo_field_values WHERE field_id = $1")
+        .bind(id.to_string())
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query("DELETE FROM custom_fields WHERE id = $1")
+        .bind(id.to_string())
+        .execute(&mut *tx)
+        .await?;
+    events.commit(tx, DUMMY_USER_ID, vec![TodoEvent::Reload]).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        db::test_db,
+        handlers::{
+            projects::create_project,
+            todos::{apply_replacement, insert_todo},
+        },
+        models::{CreateProject, CreateTodo, Priority, ReplaceTodo},
+    };
+
+    fn todo(title: &str, project_id: Option<Uuid>, fields: Value) -> CreateTodo {
+        CreateTodo {
+            title: title.to_string(),
+            description: None,
+            priority: Priority::Medium,
+            status_id: None,
+            estimated_minutes: None,
+            custom_fields: serde_json::from_value(fields).unwrap(),
+            project_id,
+            tags: vec![],
+        }
+    }
+
+    #[tokio::test]
+    async fn project_fields_only_apply_to_their_project() {
+        let db = test_db().await;
+        let (_, Json(project)) = create_project(
+            State(db.clone()),
+            Json(CreateProject { name: "Sprint".to_string(), position: None }),
+        )
+        .await
+        .unwrap();
+        for (key, project_id) in [("points", Some(project.id)), ("owner", None)] {
+            let field = CreateCustomField {
+                key: key.to_string(),
+                name: key.to_string(),
+                field_type: if key == "points" { FieldType::Number } else { FieldType::Text },
+                options: vec![],
+                position: None,
+                project_id,
+            };
+            let (_, Json(field)) = create_custom_field(State(db.clone()), Json(field)).await.unwrap();
+            assert_eq!(field.project_id, project_id);
+        }
+
+        let mut conn = db.acquire().await.unwrap();
+        let values = serde_json::json!({"points": 3, "owner": "sam"});
+        let story = insert_todo(&mut conn, &todo("Story", Some(project.id), values.clone())).await.unwrap();
+        assert_eq!(story.custom_fields.0.len(), 2);
+        let outside = insert_todo(&mut conn, &todo("Chore", None, values)).await;
+        assert!(matches!(outside, Err(AppError::BadRequest(message)) if message.contains("another project")));
+
+        // Leaving the project drops the value of its field and keeps the rest.
+        let moved = ReplaceTodo { project_id: None, ..ReplaceTodo::from(&story) };
+        let story = apply_replacement(&mut conn, &story, &moved, false).await.unwrap();
+        assert_eq!(serde_json::to_value(&story.custom_fields).unwrap(), serde_json::json!({"owner": "sam"}));
+    }
+}
@@ -0,0 +1,146 @@
+use crate::{
+    db::DB,
+    errors::AppError,
+    events::{Events, TodoEvent},
+    handlers::todos::{fetch_todo, DUMMY_USER_ID, TODO_COLUMNS},
+    models::{LinkBlocker, Todo, TodoDependencies},
+};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::Utc;
+use sqlx::SqliteConnection;
+use uuid::Uuid;
+
+/// Ids of the incomplete todos blocking `id`.
+pub(crate) async fn open_blockers(conn: &mut SqliteConnection, id: &str) -> Result<Vec<Uuid>, AppError> {
+    let ids: Vec<String> = sqlx::query_scalar(
+        "SELECT d.blocker_id FROM todo_dependencies d JOIN todos b ON b.id = d.blocker_id WHERE d.blocked_id = $1 AND b.completed = FALSE",
+    )
+    .bind(id)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    ids.iter()
+        .map(|id| Uuid::parse_str(id).map_err(|_| AppError::InternalServerError))
+        .collect()
+}
+
+// Whether `blocker` is already reachable from `blocked` by following blocker -> blocked edges,
+// in which case the new edge would close a cycle.
+async fn would_create_cycle(
+    conn: &mut SqliteConnection,
+    blocker: &str,
+    blocked: &str,
+) -> Result<bool, AppError> {
+    Ok(sqlx::query_scalar(
+        r#"WITH RECURSIVE downstream(id) AS (
+            SELECT $1
+            UNION
+            SELECT d.blocked_id FROM todo_dependencies d JOIN downstream ON d.blocker_id = downstream.id
+        )
+        SELECT EXISTS (SELECT 1 FROM downstream WHERE id = $2)"#,
+    )
+    .bind(blocked)
+    .bind(blocker)
+    .fetch_one(&mut *conn)
+    .await?)
+}
+
+/// Records that `blocker` blocks `blocked`. Callers check that the link does not close a cycle.
+pub(crate) async fn link_blocker(conn: &mut SqliteConnection, blocker: &str, blocked: &str) -> Result<(), AppError> {
+    sqlx::query(
+        "INSERT OR IGNORE INTO todo_dependencies (blocker_id, blocked_id, created_at) VALUES ($1, $2, $3)",
+    )
+    .bind(blocker)
+    .bind(blocked)
+    .bind(Utc::now())
+    .execute(&mut *conn)
+    .await?;
+    Ok(())
+}
+
+async fn linked_todos(conn: &mut SqliteConnection, query: &str, id: &str) -> Result<Vec<Todo>, AppError> {
+    Ok(sqlx::query_as::<_, Todo>(query)
+        .bind(id)
+        .bind(DUMMY_USER_ID)
+        .fetch_all(&mut *conn)
+        .await?)
+}
+
+pub async fn todo_dependencies(
+    State(db): State<DB>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<TodoDependencies>, AppError> {
+    let id_str = id.to_string();
+    let mut conn = db.acquire().await?;
+    fetch_todo(&mut conn, &id_str).await?;
+
+    let blockers = linked_todos(
+        &mut conn,
+        &format!("SELECT {TODO_COLUMNS} FROM todos WHERE id IN (SELECT blocker_id FROM todo_dependencies WHERE blocked_id = $1) AND user_id = $2 ORDER BY position"),
+        &id_str,
+    )
+    .await?;
+    let dependents = linked_todos(
+        &mut conn,
+        &format!("SELECT {TODO_COLUMNS} FROM todos WHERE id IN (SELECT blocked_id FROM todo_dependencies WHERE blocker_id = $1) AND user_id = $2 ORDER BY position"),
+        &id_str,
+    )
+    .await?;
+
+    Ok(Json(TodoDependencies { blockers, dependents }))
+}
+
+/// Marks `id` as blocked by `blocker_id`. Links that would make a todo (transitively) block
+/// itself are rejected.
+pub async fn add_blocker(
+    State(db): State<DB>,
+    State(events): State<Events>,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<LinkBlocker>,
+) -> Result<StatusCode, AppError> {
+    let blocked = id.to_string();
+    let blocker = payload.blocker_id.to_string();
+    let mut tx = db.begin().await?;
+    fetch_todo(&mut tx, &blocked).await?;
+    fetch_todo(&mut tx, &blocker).await?;
+
+    if would_create_cycle(&mut tx, &blocker, &blocked).await? {
+        return Err(AppError::DependencyCycle);
+    }
+
+    link_blocker(&mut tx, &blocker, &blocked).await?;
+    let todo = fetch_todo(&mut tx, &blocked).await?;
+    events.commit(tx, DUMMY_USER_ID, vec![TodoEvent::Updated { todo }]).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn remove_blocker(
+    State(db): State<DB>,
+    State(events): State<Events>,
+    Path((id, blocker_id)): Path<(Uuid, Uuid)>,
+) -> Result<StatusCode, AppError> {
+    let blocked = id.to_string();
+    let mut tx = db.begin().await?;
+    fetch_todo(&mut tx, &blocked).await?;
+
+    let rows_affected = sqlx::query(
+        "DELETE FROM todo_dependencies WHERE blocker_id = $1 AND blocked_id = $2",
+    )
+    .bind(blocker_id.to_string())
+    .bind(&blocked)
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+
+    if rows_affected == 0 {
+        return Err(AppError::NotFound);
+    }
+    let todo = fetch_todo(&mut tx, &blocked).await?;
+    events.commit(tx, DUMMY_USER_ID, vec![TodoEvent::Updated { todo }]).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,167 @@
+use crate::{
+    db::DB,
+    errors::AppError,
+    events::{Events, TodoEvent, UserEvent},
+    handlers::tokens::request_user,
+    models::LiveQuery,
+};
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        Query, State,
+    },
+    http::HeaderMap,
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        Response,
+    },
+};
+use futures::{stream, Stream};
+use std::{collections::VecDeque, convert::Infallible};
+use tokio::sync::broadcast::{error::RecvError, Receiver};
+use tracing::error;
+
+/// Streams the user's todo changes over a WebSocket as JSON `TodoEvent`s. Browsers cannot set
+/// headers on a WebSocket, so the token may also be given as `?token=`.
+pub async fn todo_socket(
+    State(db): State<DB>,
+    State(events): State<Events>,
+    Query(query): Query<LiveQuery>,
+    headers: HeaderMap,
+    upgrade: WebSocketUpgrade,
+) -> Result<Response, AppError> {
+    let user_id = request_user(&db, &headers, query.token.as_deref()).await?;
+    // Subscribe before upgrading so nothing published during the handshake is missed.
+    let receiver = events.subscribe();
+    Ok(upgrade.on_upgrade(move |socket| forward_events(socket, receiver, user_id)))
+}
+
+async fn forward_events(mut socket: WebSocket, mut receiver: Receiver<UserEvent>, user_id: String) {
+    loop {
+        tokio::select! {
+            received = receiver.recv() => {
+                let event = match received {
+                    Ok(UserEvent { user_id: owner, event }) if owner == user_id => event,
+                    Ok(_) => continue,
+                    // The missed events are gone, so the client has to load the list again.
+                    Err(RecvError::Lagged(_)) => TodoEvent::Reload,
+                    Err(RecvError::Closed) => break,
+                };
+                let Ok(text) = serde_json::to_string(&event) else { continue };
+                if socket.send(Message::Text(text)).await.is_err() {
+                    break;
+                }
+            }
+            // Nothing is read from the client; axum answers pings by itself.
+            message = socket.recv() => match message {
+                Some(Ok(Message::Close(_)) | Err(_)) | None => break,
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct LoggedEvent {
+    id: i64,
+    event: String,
+}
+
+impl From<LoggedEvent> for Event {
+    fn from(logged: LoggedEvent) -> Self {
+        Event::default().id(logged.id.to_string()).data(logged.event)
+    }
+}
+
+// State of one `/api/events` stream: events read from the log but not sent yet, and the id of
+// the last one read.
+struct Feed {
+    db: DB,
+    receiver: Receiver<UserEvent>,
+    user_id: String,
+    after: i64,
+    pending: VecDeque<Event>,
+}
+
+impl Feed {
+    async fn next(&mut self) -> Option<Event> {
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                return Some(event);
+            }
+            let logged = sqlx::query_as::<_, LoggedEvent>(
+                "SELECT id, event FROM todo_events WHERE user_id = $1 AND id > $2 ORDER BY id",
+            )
+            .bind(&self.user_id)
+            .bind(self.after)
+            .fetch_all(&self.db)
+            .await;
+            match logged {
+                Ok(logged) if !logged.is_empty() => {
+                    self.after = logged.last().map_or(self.after, |last| last.id);
+                    self.pending.extend(logged.into_iter().map(Event::from));
+                    continue;
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    error!("reading the event log failed: {}", e);
+                    return None;
+                }
+            }
+            // The broadcast only says there is something new in the log; a lagging receiver
+            // catches up from the log all the same.
+            loop {
+                match self.receiver.recv().await {
+                    Ok(UserEvent { user_id, .. }) if user_id == self.user_id => break,
+                    Ok(_) => {}
+                    Err(RecvError::Lagged(_)) => break,
+                    Err(RecvError::Closed) => return None,
+                }
+            }
+        }
+    }
+}
+
+/// Streams the user's todo changes as server-sent events, each with the id of its entry in the
+/// change log. A client reconnecting with `Last-Event-ID` is first sent the events it missed, or
+/// a `reload` if some of them have been pruned from the log.
+pub async fn todo_event_stream(
+    State(db): State<DB>,
+    State(events): State<Events>,
+    Query(query): Query<LiveQuery>,
+    headers: HeaderMap,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, AppError> {
+    let user_id = request_user(&db, &headers, query.token.as_deref()).await?;
+    // Subscribe before reading the log so nothing recorded in between is missed.
+    let receiver = events.subscribe();
+
+    // The sequence survives pruning, unlike the largest id still in the table.
+    let (oldest, latest): (Option<i64>, i64) = sqlx::query_as(
+        "SELECT (SELECT MIN(id) FROM todo_events), \
+        COALESCE((SELECT seq FROM sqlite_sequence WHERE name = 'todo_events'), 0)",
+    )
+    .fetch_one(&db)
+    .await?;
+    let last_seen = headers
+        .get("last-event-id")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse::<i64>().ok());
+
+    let mut pending = VecDeque::new();
+    let after = match last_seen {
+        Some(seen) if seen <= latest && oldest.map_or(seen == latest, |oldest| seen + 1 >= oldest) => seen,
+        Some(_) => {
+            let reload = serde_json::to_string(&TodoEvent::Reload).map_err(|_| AppError::InternalServerError)?;
+            pending.push_back(Event::default().id(latest.to_string()).data(reload));
+            latest
+        }
+        None => latest,
+    };
+
+    let feed = Feed { db, receiver, user_id, after, pending };
+    let stream = stream::unfold(feed, |mut feed| async move {
+        let event = feed.next().await?;
+        Some((Ok(event), feed))
+    });
+    Ok(Sse::new(stream).keep_alive(KeepAlive::new().text("keep-alive")))
+}
@@ -1,2 +1,23 @@
+pub mod archive;
 pub mod auth;
-pub mod todos; 
\ No newline at end of file
+pub mod backup;
+pub mod bulk;
+pub mod caldav;
+pub mod calendar;
+pub mod custom_fields;
+pub mod dependencies;
+pub mod live;
+pub mod projects;
+pub mod revisions;
+pub mod service_imports;
+pub mod spreadsheets;
+pub mod stats;
+pub mod statuses;
+pub mod sync;
+pub mod tags;
+pub mod templates;
+pub mod text_formats;
+pub mod time_entries;
+pub mod todos;
+pub mod tokens;
+pub mod views;
\ No newline at end of file
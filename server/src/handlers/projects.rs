@@ -0,0 +1,237 @@
+use crate::{
+    db::DB,
+    errors::AppError,
+    events::{Events, TodoEvent},
+    handlers::todos::{apply_replacement, DUMMY_USER_ID, TODO_COLUMNS},
+    models::{CreateProject, Project, ReplaceTodo, Todo, UpdateProject},
+};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::Utc;
+use sqlx::SqliteConnection;
+use uuid::Uuid;
+
+pub(crate) const PROJECT_COLUMNS: &str = "id, user_id, name, position, created_at";
+
+pub(crate) async fn fetch_project(conn: &mut SqliteConnection, id: Uuid) -> Result<Project, AppError> {
+    sqlx::query_as::<_, Project>(&format!(
+        "SELECT {PROJECT_COLUMNS} FROM projects WHERE id = $1 AND user_id = $2"
+    ))
+    .bind(id.to_string())
+    .bind(DUMMY_USER_ID)
+    .fetch_optional(&mut *conn)
+    .await?
+    .ok_or(AppError::NotFound)
+}
+
+/// Checks that a todo's project is one of the user's.
+pub(crate) async fn check_project(conn: &mut SqliteConnection, id: Option<Uuid>) -> Result<(), AppError> {
+    match id {
+        Some(id) => fetch_project(conn, id)
+            .await
+            .map(|_| ())
+            .map_err(|_| AppError::BadRequest(format!("unknown project {}", id))),
+        None => Ok(()),
+    }
+}
+
+pub(crate) async fn all_projects(conn: &mut SqliteConnection) -> Result<Vec<Project>, AppError> {
+    Ok(sqlx::query_as::<_, Project>(&format!(
+        "SELECT {PROJECT_COLUMNS} FROM projects WHERE user_id = $1 ORDER BY position, created_at"
+    ))
+    .bind(DUMMY_USER_ID)
+    .fetch_all(&mut *conn)
+    .await?)
+}
+
+fn project_name(name: &str) -> Result<&str, AppError> {
+    Some(name.trim())
+        .filter(|name| !name.is_empty())
+        .ok_or_else(|| AppError::BadRequest("project name cannot be empty".to_string()))
+}
+
+async fn insert_project(conn: &mut SqliteConnection, name: &str, position: Option<i64>) -> Result<Uuid, AppError> {
+    let taken: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM projects WHERE user_id = $1 AND name = $2")
+        .bind(DUMMY_USER_ID)
+        .bind(name)
+        .fetch_one(&mut *conn)
+        .await?;
+    if taken > 0 {
+        return Err(AppError::BadRequest(format!("a project named `{}` already exists", name)));
+    }
+
+    let id = Uuid::new_v4();
+    sqlx::query(
+        "INSERT INTO projects (id, user_id, name, position, created_at) \
+        VALUES ($1, $2, $3, COALESCE($4, (SELECT COALESCE(MAX(position) + 1, 0) FROM projects WHERE user_id = $2)), $5)",
+    )
+    .bind(id.to_string())
+    .bind(DUMMY_USER_ID)
+    .bind(name)
+    .bind(position)
+    .bind(Utc::now())
+    .execute(&mut *conn)
+    .await?;
+    Ok(id)
+}
+
+/// The user's project with this name, ignoring case, or a new one at the end of the list.
+/// Quick add and the importers use it to file todos under a project named in the text.
+pub(crate) async fn project_named(conn: &mut SqliteConnection, name: &str) -> Result<Uuid, AppError> {
+    let name = project_name(name)?;
+    let existing: Option<String> = sqlx::query_scalar("SELECT id FROM projects WHERE user_id = $1 AND name = $2")
+        .bind(DUMMY_USER_ID)
+        .bind(name)
+        .fetch_optional(&mut *conn)
+        .await?;
+    match existing {
+        Some(id) => Uuid::parse_str(&id).map_err(|_| AppError::InternalServerError),
+        None => insert_project(conn, name, None).await,
+    }
+}
+
+pub async fn list_projects(State(db): State<DB>) -> Result<Json<Vec<Project>>, AppError> {
+    let mut conn = db.acquire().await?;
+    Ok(Json(all_projects(&mut conn).await?))
+}
+
+pub async fn create_project(
+    State(db): State<DB>,
+    Json(payload): Json<CreateProject>,
+) -> Result<(StatusCode, Json<Project>), AppError> {
+    let mut tx = db.begin().await?;
+    let id = insert_project(&mut tx, project_name(&payload.name)?, payload.position).await?;
+    let project = fetch_project(&mut tx, id).await?;
+    tx.commit().await?;
+    Ok((StatusCode::CREATED, Json(project)))
+}
+
+pub async fn update_project(
+    State(db): State<DB>,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<UpdateProject>,
+) -> Result<Json<Project>, AppError> {
+    let mut tx = db.begin().await?;
+    let project = fetch_project(&mut tx, id).await?;
+    let name = match &payload.name {
+        Some(name) => project_name(name)?.to_string(),
+        None => project.name,
+    };
+
+    let taken: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM projects WHERE user_id = $1 AND name = $2 AND id != $3")
+        .bind(DUMMY_USER_ID)
+        .bind(&name)
+        .bind(id.to_string())
+        .fetch_one(&mut *tx)
+        .await?;
+    if taken > 0 {
+        return Err(AppError::BadRequest(format!("a project named `{}` already exists", name)));
+    }
+
+    sqlx::query("UPDATE projects SET name = $1, position = $2 WHERE id = $3")
+        .bind(&name)
+        .bind(payload.position.unwrap_or(project.position))
+        .bind(id.to_string())
+        .execute(&mut *tx)
+        .await?;
+
+    let project = fetch_project(&mut tx, id).await?;
+    tx.commit().await?;
+    Ok(Json(project))
+}
+
+/// Deletes a project. Its todos are kept and taken out of it, each through the normal write path
+/// so it gets a revision and clients hear about it.
+pub async fn delete_project(
+    State(db): State<DB>,
+    State(events): State<Events>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, AppError> {
+    let mut tx = db.begin().await?;
+    fetch_project(&mut tx, id).await?;
+
+    let todos = sqlx::query_as::<_, Todo>(&format!(
+        "SELECT {TODO_COLUMNS} FROM todos WHERE project_id = $1 AND user_id = $2"
+    ))
+    .bind(id.to_string())
+    .bind(DUMMY_USER_ID)
+    .fetch_all(&mut *tx)
+    .await?;
+    let mut changes = Vec::with_capacity(todos.len());
+    for todo in todos {
+        let replacement = ReplaceTodo { project_id: None, ..ReplaceTodo::from(&todo) };
+        let todo = apply_replacement(&mut tx, &todo, &replacement, true).await?;
+        changes.push(TodoEvent::Updated { todo });
+    }
+
+    sqlx::query("DELETE FROM projects WHERE id = $1")
+        .bind(id.to_string())
+        .execute(&mut *tx)
+        .await?;
+    events.commit(tx, DUMMY_USER_ID, changes).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        db::test_db,
+        handlers::todos::{fetch_todo, insert_todo},
+        models::{CreateTodo, Priority},
+        view_query,
+    };
+    use sqlx::QueryBuilder;
+
+    fn todo(title: &str, project_id: Option<Uuid>, tags: &[&str]) -> CreateTodo {
+        CreateTodo {
+            title: title.to_string(),
+            description: None,
+            priority: Priority::Medium,
+            status_id: None,
+            estimated_minutes: None,
+            custom_fields: Default::default(),
+            project_id,
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+        }
+    }
+
+    #[tokio::test]
+    async fn todos_keep_their_project_and_tags() {
+        let db = test_db().await;
+        let mut conn = db.acquire().await.unwrap();
+        let home = insert_project(&mut conn, "Home", None).await.unwrap();
+        assert!(matches!(insert_project(&mut conn, "home", None).await, Err(AppError::BadRequest(_))));
+
+        let errand = insert_todo(&mut conn, &todo("Buy milk", Some(home), &["#errand", " shop ", "errand"])).await.unwrap();
+        assert_eq!((errand.project_id, errand.tags.clone()), (Some(home), vec!["errand".to_string(), "shop".to_string()]));
+        insert_todo(&mut conn, &todo("Call mom", None, &["family"])).await.unwrap();
+        let unknown = insert_todo(&mut conn, &todo("Lost", Some(Uuid::new_v4()), &[])).await;
+        assert!(matches!(unknown, Err(AppError::BadRequest(_))));
+
+        let cases = [
+            ("project:home", vec!["Buy milk"]),
+            ("project!=Home", vec!["Call mom"]),
+            ("tag:Errand", vec!["Buy milk"]),
+            ("NOT tag:errand", vec!["Call mom"]),
+        ];
+        for (input, expected) in cases {
+            let mut builder = QueryBuilder::new("SELECT title FROM todos WHERE ");
+            view_query::push_sql(&view_query::parse(input, Utc::now()).unwrap(), &mut builder);
+            builder.push(" ORDER BY position");
+            let titles: Vec<String> = builder.build_query_scalar().fetch_all(&mut *conn).await.unwrap();
+            assert_eq!(titles, expected, "{}", input);
+        }
+        drop(conn);
+
+        let status = delete_project(State(db.clone()), State(Events::default()), Path(home)).await.unwrap();
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        let mut conn = db.acquire().await.unwrap();
+        let after = fetch_todo(&mut conn, &errand.id.to_string()).await.unwrap();
+        assert_eq!((after.project_id, after.version), (None, errand.version + 1));
+        assert!(all_projects(&mut conn).await.unwrap().is_empty());
+    }
+}
@@ -0,0 +1,168 @@
+use crate::{
+    db::DB,
+    errors::AppError,
+    events::{Events, TodoEvent},
+    handlers::todos::{apply_replacement, etag, fetch_todo, DUMMY_USER_ID},
+    models::{FieldChange, ReplaceTodo, Todo, TodoRevision},
+};
+use axum::{
+    extract::{Path, State},
+    http::{header, HeaderName},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use sqlx::SqliteConnection;
+use uuid::Uuid;
+
+// Bookkeeping fields that change on every write and are not worth diffing.
+const UNTRACKED_FIELDS: &[&str] = &["id", "user_id", "created_at", "updated_at", "version", "position", "completed_at", "archived_at", "blocked"];
+
+#[derive(sqlx::FromRow)]
+struct RevisionRow {
+    id: String,
+    todo_id: String,
+    revision: i64,
+    actor_id: String,
+    changes: String,
+    snapshot: String,
+    created_at: DateTime<Utc>,
+}
+
+impl TryFrom<RevisionRow> for TodoRevision {
+    type Error = AppError;
+
+    fn try_from(row: RevisionRow) -> Result<Self, Self::Error> {
+        let parse_id = |s: &str| Uuid::parse_str(s).map_err(|_| AppError::InternalServerError);
+        Ok(TodoRevision {
+            id: parse_id(&row.id)?,
+            todo_id: parse_id(&row.todo_id)?,
+            revision: row.revision,
+            actor_id: parse_id(&row.actor_id)?,
+            changes: serde_json::from_str(&row.changes).map_err(|_| AppError::InternalServerError)?,
+            snapshot: serde_json::from_str(&row.snapshot).map_err(|_| AppError::InternalServerError)?,
+            created_at: row.created_at,
+        })
+    }
+}
+
+/// Field-level diff between two states of a todo. `before` is `None` for a newly created
+/// todo, in which case every tracked field is reported as changed from `null`.
+pub(crate) fn diff_todos(before: Option<&Todo>, after: &Todo) -> Vec<FieldChange> {
+    let before = before
+        .and_then(|todo| serde_json::to_value(todo).ok())
+        .unwrap_or(Value::Null);
+    let Ok(Value::Object(after)) = serde_json::to_value(after) else {
+        return vec![];
+    };
+
+    after
+        .into_iter()
+        .filter(|(field, _)| !UNTRACKED_FIELDS.contains(&field.as_str()))
+        .filter_map(|(field, new)| {
+            let old = before.get(&field).cloned().unwrap_or(Value::Null);
+            (old != new).then_some(FieldChange { field, old, new })
+        })
+        .collect()
+}
+
+/// Stores a new revision for `after`. Writes that did not change any tracked field are not recorded.
+pub(crate) async fn record_revision(
+    conn: &mut SqliteConnection,
+    before: Option<&Todo>,
+    after: &Todo,
+    actor_id: &str,
+) -> Result<(), AppError> {
+    let changes = diff_todos(before, after);
+    if before.is_some() && changes.is_empty() {
+        return Ok(());
+    }
+
+    let todo_id = after.id.to_string();
+    let next_revision: i64 = sqlx::query_scalar(
+        "SELECT COALESCE(MAX(revision), 0) + 1 FROM todo_revisions WHERE todo_id = $1",
+    )
+    .bind(&todo_id)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    let changes = serde_json::to_string(&changes).map_err(|_| AppError::InternalServerError)?;
+    let snapshot = serde_json::to_string(after).map_err(|_| AppError::InternalServerError)?;
+
+    sqlx::query(
+        "INSERT INTO todo_revisions (id, todo_id, revision, actor_id, changes, snapshot, created_at) VALUES ($1, $2, $3, $4, $5, $6, $7)",
+    )
+    .bind(Uuid::new_v4().to_string())
+    .bind(&todo_id)
+    .bind(next_revision)
+    .bind(actor_id)
+    .bind(changes)
+    .bind(snapshot)
+    .bind(Utc::now())
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn todo_history(
+    State(db): State<DB>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Vec<TodoRevision>>, AppError> {
+    let id_str = id.to_string();
+    let mut conn = db.acquire().await?;
+    fetch_todo(&mut conn, &id_str).await?;
+
+    let rows = sqlx::query_as::<_, RevisionRow>(
+        "SELECT id, todo_id, revision, actor_id, changes, snapshot, created_at FROM todo_revisions WHERE todo_id = $1 ORDER BY revision DESC",
+    )
+    .bind(&id_str)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let revisions = rows
+        .into_iter()
+        .map(TodoRevision::try_from)
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(Json(revisions))
+}
+
+pub async fn revert_todo(
+    State(db): State<DB>,
+    State(events): State<Events>,
+    Path((id, rev)): Path<(Uuid, i64)>,
+) -> Result<([(HeaderName, String); 1], Json<Todo>), AppError> {
+    let id_str = id.to_string();
+    let mut tx = db.begin().await?;
+    let current = fetch_todo(&mut tx, &id_str).await?;
+
+    let snapshot: String = sqlx::query_scalar(
+        "SELECT snapshot FROM todo_revisions WHERE todo_id = $1 AND revision = $2",
+    )
+    .bind(&id_str)
+    .bind(rev)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or(AppError::NotFound)?;
+    let snapshot: Value = serde_json::from_str(&snapshot).map_err(|_| AppError::InternalServerError)?;
+
+    // A snapshot only holds what existed when it was taken, so fields added since then, such as
+    // a newer column or a custom field set later, keep their current values.
+    let mut target = serde_json::to_value(&current).map_err(|_| AppError::InternalServerError)?;
+    if let (Value::Object(target), Value::Object(snapshot)) = (&mut target, snapshot) {
+        for (key, value) in snapshot {
+            match (key.as_str(), target.get_mut("custom_fields"), value) {
+                ("custom_fields", Some(Value::Object(fields)), Value::Object(old)) => fields.extend(old),
+                (_, _, value) => {
+                    target.insert(key, value);
+                }
+            }
+        }
+    }
+    let target: Todo = serde_json::from_value(target).map_err(|_| AppError::InternalServerError)?;
+
+    let reverted = apply_replacement(&mut tx, &current, &ReplaceTodo::from(&target), false).await?;
+    events.commit(tx, DUMMY_USER_ID, vec![TodoEvent::Updated { todo: reverted.clone() }]).await?;
+
+    Ok(([(header::ETAG, etag(reverted.version))], Json(reverted)))
+}
@@ -0,0 +1,689 @@
+//! Importers for the export files of other todo services: Todoist CSV, Trello board JSON, Google
+//! Tasks JSON (from Google Takeout) and Microsoft To Do JSON (lists with their tasks from the
+//! Graph API, since To Do has no file export of its own).
+//!
+//! They run through the same pipeline as the other importers: one transaction, rolled back for a
+//! dry run, with a report of the items that failed and of what the file had that todos have no
+//! place for, such as attachments and recurrence. Lists and boards become projects, and labels
+//! and categories become tags.
+
+use crate::{
+    csv,
+    db::DB,
+    errors::AppError,
+    events::{Events, TodoEvent},
+    handlers::{
+        calendar::due_field,
+        custom_fields::all_fields,
+        projects::project_named,
+        statuses::{first_status_in, status_named},
+        todos::{insert_nested_imports, ImportedTodo, NestedImport, DUMMY_USER_ID},
+    },
+    models::{
+        CreateTodo, CustomField, DryRunQuery, FieldValues, ImportRowError, Priority, StatusCategory,
+        TextImportReport, Unmapped,
+    },
+};
+use axum::{
+    extract::{Query, State},
+    Json,
+};
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use serde::Deserialize;
+use serde_json::Value;
+use sqlx::{Sqlite, Transaction};
+use std::collections::{BTreeMap, HashMap};
+use uuid::Uuid;
+
+/// Counts of what an import left out, by description.
+#[derive(Default)]
+struct Leftovers(BTreeMap<&'static str, u64>);
+
+impl Leftovers {
+    fn add(&mut self, what: &'static str, count: usize) {
+        if count > 0 {
+            *self.0.entry(what).or_default() += count as u64;
+        }
+    }
+
+    fn into_report(self) -> Vec<Unmapped> {
+        self.0.into_iter().map(|(what, count)| Unmapped { what: what.to_string(), count }).collect()
+    }
+}
+
+fn new_todo(title: &str, description: Option<&str>, priority: Priority) -> Result<ImportedTodo, String> {
+    let title = title.trim();
+    if title.is_empty() {
+        return Err("the task has no title".to_string());
+    }
+    Ok(ImportedTodo {
+        todo: CreateTodo {
+            title: title.to_string(),
+            description: description.map(str::trim).filter(|d| !d.is_empty()).map(str::to_string),
+            priority,
+            status_id: None,
+            estimated_minutes: None,
+            custom_fields: FieldValues::default(),
+            project_id: None,
+            tags: vec![],
+        },
+        completed: false,
+        created_at: None,
+        completed_at: None,
+    })
+}
+
+/// The date at the start of a `YYYY-MM-DD...` value, as all four services write due dates.
+fn date_prefix(value: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(value.trim().get(..10)?, "%Y-%m-%d").ok()
+}
+
+/// An RFC 3339 time, or one without an offset, read as UTC.
+fn timestamp(value: &str) -> Option<DateTime<Utc>> {
+    let value = value.trim();
+    DateTime::parse_from_rfc3339(value)
+        .map(|at| at.with_timezone(&Utc))
+        .or_else(|_| NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S%.f").map(|at| at.and_utc()))
+        .ok()
+}
+
+/// Stores a due date in the `due` date field, or counts it as left out when there is none.
+fn set_due(todo: &mut ImportedTodo, date: NaiveDate, due: Option<&CustomField>, leftovers: &mut Leftovers) {
+    match due {
+        Some(field) => {
+            todo.todo.custom_fields.0.insert(field.key.clone(), Value::String(date.to_string()));
+        }
+        None => leftovers.add("due dates (there is no `due` date field)", 1),
+    }
+}
+
+async fn finish(
+    tx: Transaction<'_, Sqlite>,
+    events: &Events,
+    options: DryRunQuery,
+    (imported, errors): (u64, Vec<ImportRowError>),
+    leftovers: Leftovers,
+) -> Result<Json<TextImportReport>, AppError> {
+    if options.dry_run {
+        tx.rollback().await?;
+    } else {
+        events.commit(tx, DUMMY_USER_ID, vec![TodoEvent::Reload]).await?;
+    }
+    Ok(Json(TextImportReport { dry_run: options.dry_run, imported, errors, unmapped: leftovers.into_report() }))
+}
+
+fn parse_json<'a, T: Deserialize<'a>>(body: &'a str, what: &str) -> Result<T, AppError> {
+    serde_json::from_str(body).map_err(|e| AppError::BadRequest(format!("not {}: {}", what, e)))
+}
+
+/// Todoist numbers priorities from p1, its most urgent, to p4, its default.
+fn todoist_priority(value: &str) -> Result<Priority, String> {
+    match value {
+        "1" | "2" => Ok(Priority::High),
+        "3" | "4" | "" => Ok(Priority::Medium),
+        other => Err(format!("`{}` is not a PRIORITY", other)),
+    }
+}
+
+/// Imports a Todoist project exported as CSV. Tasks nest by their INDENT and notes are added to the
+/// description of the task above them. Sections and dates written in words are left out.
+pub async fn import_todoist(
+    State(db): State<DB>,
+    State(events): State<Events>,
+    Query(options): Query<DryRunQuery>,
+    body: String,
+) -> Result<Json<TextImportReport>, AppError> {
+    let records = csv::parse(&body).map_err(AppError::BadRequest)?;
+    let (header, rows) = records.split_first().ok_or_else(|| AppError::BadRequest("the file is empty".to_string()))?;
+    let column = |name: &str| header.fields.iter().position(|field| field.trim().eq_ignore_ascii_case(name));
+    let (Some(kind), Some(content)) = (column("TYPE"), column("CONTENT")) else {
+        return Err(AppError::BadRequest("expected a Todoist CSV with TYPE and CONTENT columns".to_string()));
+    };
+    let [description, priority, indent, date, responsible, duration, duration_unit] =
+        ["DESCRIPTION", "PRIORITY", "INDENT", "DATE", "RESPONSIBLE", "DURATION", "DURATION_UNIT"].map(column);
+
+    let mut tx = db.begin().await?;
+    let fields = all_fields(&mut tx).await?;
+    let due = due_field(&fields);
+
+    let mut leftovers = Leftovers::default();
+    let mut items: Vec<NestedImport> = vec![];
+    // Index of the last task at each indent level.
+    let mut levels: Vec<usize> = vec![];
+    for record in rows {
+        let cell = |index: Option<usize>| index.and_then(|i| record.fields.get(i)).map_or("", |value| value.trim());
+        match cell(Some(kind)).to_lowercase().as_str() {
+            "task" => {}
+            "note" => {
+                match items.last_mut().map(|item| &mut item.todo) {
+                    Some(Ok(todo)) => {
+                        let text = todo.todo.description.get_or_insert_with(String::new);
+                        if !text.is_empty() {
+                            text.push_str("\n\n");
+                        }
+                        text.push_str(cell(Some(content)));
+                    }
+                    _ => leftovers.add("notes without a task", 1),
+                }
+                continue;
+            }
+            "section" => {
+                leftovers.add("sections", 1);
+                levels.clear();
+                continue;
+            }
+            "" => continue,
+            _ => {
+                leftovers.add("rows of other types", 1);
+                continue;
+            }
+        }
+
+        let depth = cell(indent).parse::<usize>().unwrap_or(1).max(1);
+        levels.truncate(depth - 1);
+        let parent = levels.last().copied();
+        levels.push(items.len());
+
+        let todo = todoist_priority(cell(priority))
+            .and_then(|priority| new_todo(cell(Some(content)), Some(cell(description)), priority))
+            .map(|mut todo| {
+                let date = cell(date);
+                match date_prefix(date) {
+                    Some(date) => set_due(&mut todo, date, due, &mut leftovers),
+                    None if date.to_lowercase().starts_with("every") => leftovers.add("recurring dates", 1),
+                    None if !date.is_empty() => leftovers.add("dates written in words", 1),
+                    None => {}
+                }
+                if !cell(responsible).is_empty() {
+                    leftovers.add("assignees", 1);
+                }
+                if let Ok(minutes) = cell(duration).parse::<i64>() {
+                    match cell(duration_unit) {
+                        "minute" | "" => todo.todo.estimated_minutes = Some(minutes),
+                        _ => leftovers.add("durations in days", 1),
+                    }
+                }
+                todo
+            });
+        items.push(NestedImport { line: record.line, parent, todo });
+    }
+
+    let result = insert_nested_imports(&mut tx, items).await?;
+    finish(tx, &events, options, result, leftovers).await
+}
+
+#[derive(Deserialize)]
+struct TrelloBoard {
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    lists: Vec<TrelloList>,
+    #[serde(default)]
+    cards: Vec<TrelloCard>,
+    #[serde(default)]
+    checklists: Vec<TrelloChecklist>,
+    #[serde(default)]
+    actions: Vec<TrelloAction>,
+}
+
+#[derive(Deserialize)]
+struct TrelloList {
+    id: String,
+    name: String,
+    #[serde(default)]
+    closed: bool,
+    #[serde(default)]
+    pos: f64,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TrelloCard {
+    id: String,
+    name: String,
+    #[serde(default)]
+    desc: String,
+    #[serde(default)]
+    closed: bool,
+    id_list: String,
+    #[serde(default)]
+    pos: f64,
+    due: Option<String>,
+    #[serde(default)]
+    due_complete: bool,
+    #[serde(default)]
+    labels: Vec<TrelloLabel>,
+    #[serde(default)]
+    id_members: Vec<String>,
+    #[serde(default)]
+    attachments: Vec<Value>,
+}
+
+#[derive(Deserialize)]
+struct TrelloLabel {
+    #[serde(default)]
+    name: String,
+    color: Option<String>,
+}
+
+impl TrelloLabel {
+    /// Labels may be left unnamed and told apart by color alone.
+    fn tag(&self) -> Option<&str> {
+        Some(self.name.trim()).filter(|name| !name.is_empty()).or(self.color.as_deref())
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TrelloChecklist {
+    id_card: String,
+    #[serde(default)]
+    check_items: Vec<TrelloCheckItem>,
+}
+
+#[derive(Deserialize)]
+struct TrelloCheckItem {
+    name: String,
+    #[serde(default)]
+    state: String,
+    #[serde(default)]
+    pos: f64,
+}
+
+#[derive(Deserialize)]
+struct TrelloAction {
+    #[serde(rename = "type")]
+    kind: String,
+}
+
+/// The category for a status made from a Trello list, going by its name.
+fn list_category(name: &str) -> StatusCategory {
+    let name = name.to_lowercase();
+    if ["done", "complete", "finished"].iter().any(|word| name.contains(word)) {
+        StatusCategory::Done
+    } else if ["doing", "progress", "review"].iter().any(|word| name.contains(word)) {
+        StatusCategory::Doing
+    } else {
+        StatusCategory::Todo
+    }
+}
+
+/// Imports a Trello board exported as JSON into the project named after the board. Each open list
+/// becomes a status, or reuses the status with its name, and its cards become todos in that status
+/// with their labels as tags. Checklist items become todos that block their card, since todos have
+/// no subtasks. Archived cards and lists are skipped.
+pub async fn import_trello(
+    State(db): State<DB>,
+    State(events): State<Events>,
+    Query(options): Query<DryRunQuery>,
+    body: String,
+) -> Result<Json<TextImportReport>, AppError> {
+    let mut board: TrelloBoard = parse_json(&body, "a Trello board")?;
+    board.lists.sort_by(|a, b| a.pos.total_cmp(&b.pos));
+
+    let mut tx = db.begin().await?;
+    let fields = all_fields(&mut tx).await?;
+    let due = due_field(&fields);
+    let project_id = match board.name.trim() {
+        "" => None,
+        name => Some(project_named(&mut tx, name).await?),
+    };
+
+    // Open lists by id, with their status and place on the board.
+    let mut lists = HashMap::new();
+    for (order, list) in board.lists.iter().filter(|list| !list.closed).enumerate() {
+        let status = status_named(&mut tx, list.name.trim(), list_category(&list.name)).await?;
+        lists.insert(list.id.as_str(), (status, order));
+    }
+
+    let mut leftovers = Leftovers::default();
+    leftovers.add("comments", board.actions.iter().filter(|action| action.kind == "commentCard").count());
+
+    // Cards in board order, each with its 1-based position in the file for error reports.
+    let mut cards: Vec<(usize, &TrelloCard, usize)> = board
+        .cards
+        .iter()
+        .enumerate()
+        .filter_map(|(index, card)| match lists.get(card.id_list.as_str()) {
+            Some(&(_, order)) if !card.closed => Some((order, card, index + 1)),
+            _ => {
+                leftovers.add("archived cards", 1);
+                None
+            }
+        })
+        .collect();
+    cards.sort_by(|(a_order, a, _), (b_order, b, _)| a_order.cmp(b_order).then(a.pos.total_cmp(&b.pos)));
+
+    let mut items = vec![];
+    for (_, card, position) in cards {
+        let todo = new_todo(&card.name, Some(&card.desc), Priority::Medium).map(|mut todo| {
+            todo.todo.status_id = lists.get(card.id_list.as_str()).map(|&(status, _)| status);
+            todo.todo.project_id = project_id;
+            todo.todo.tags = card.labels.iter().filter_map(TrelloLabel::tag).map(str::to_string).collect();
+            todo.completed = card.due_complete;
+            if let Some(date) = card.due.as_deref().and_then(date_prefix) {
+                set_due(&mut todo, date, due, &mut leftovers);
+            }
+            todo
+        });
+        leftovers.add("labels without a name or color", card.labels.iter().filter(|label| label.tag().is_none()).count());
+        leftovers.add("card members (todos have no assignees)", card.id_members.len());
+        leftovers.add("attachments", card.attachments.len());
+
+        let parent = items.len();
+        items.push(NestedImport { line: position, parent: None, todo });
+        for checklist in board.checklists.iter().filter(|checklist| checklist.id_card == card.id) {
+            leftovers.add("checklist names", 1);
+            let mut check_items: Vec<&TrelloCheckItem> = checklist.check_items.iter().collect();
+            check_items.sort_by(|a, b| a.pos.total_cmp(&b.pos));
+            for check_item in check_items {
+                let todo = new_todo(&check_item.name, None, Priority::Medium).map(|mut todo| {
+                    todo.todo.project_id = project_id;
+                    todo.completed = check_item.state == "complete";
+                    todo
+                });
+                items.push(NestedImport { line: position, parent: Some(parent), todo });
+            }
+        }
+    }
+
+    let result = insert_nested_imports(&mut tx, items).await?;
+    finish(tx, &events, options, result, leftovers).await
+}
+
+#[derive(Deserialize)]
+struct GoogleTaskLists {
+    #[serde(default)]
+    items: Vec<GoogleTaskList>,
+}
+
+#[derive(Deserialize)]
+struct GoogleTaskList {
+    #[serde(default)]
+    title: String,
+    #[serde(default)]
+    items: Vec<GoogleTask>,
+}
+
+#[derive(Deserialize)]
+struct GoogleTask {
+    #[serde(default)]
+    id: String,
+    #[serde(default)]
+    title: String,
+    notes: Option<String>,
+    #[serde(default)]
+    status: String,
+    due: Option<String>,
+    completed: Option<String>,
+    parent: Option<String>,
+    #[serde(default)]
+    position: String,
+    #[serde(default)]
+    deleted: bool,
+    #[serde(default)]
+    links: Vec<Value>,
+}
+
+/// Imports the `Tasks.json` file of a Google Takeout export. Each task list becomes a project, or
+/// reuses the project with its name. Subtasks become todos that block their parent. Deleted tasks
+/// are skipped.
+pub async fn import_google_tasks(
+    State(db): State<DB>,
+    State(events): State<Events>,
+    Query(options): Query<DryRunQuery>,
+    body: String,
+) -> Result<Json<TextImportReport>, AppError> {
+    let lists: GoogleTaskLists = parse_json(&body, "a Google Tasks export")?;
+
+    let mut tx = db.begin().await?;
+    let fields = all_fields(&mut tx).await?;
+    let due = due_field(&fields);
+
+    let mut leftovers = Leftovers::default();
+    let mut items = vec![];
+    let mut position = 0;
+    for list in &lists.items {
+        let project_id = list_project(&mut tx, &list.title).await?;
+        // Each task with its 1-based position in the file, parents before their subtasks.
+        let mut tasks: Vec<(usize, &GoogleTask)> = vec![];
+        for task in &list.items {
+            position += 1;
+            if task.deleted {
+                leftovers.add("deleted tasks", 1);
+            } else {
+                tasks.push((position, task));
+            }
+        }
+        tasks.sort_by(|(_, a), (_, b)| a.position.cmp(&b.position));
+        let is_top = |task: &GoogleTask| {
+            task.parent.as_ref().is_none_or(|parent| !tasks.iter().any(|(_, other)| &other.id == parent))
+        };
+
+        for &(position, task) in tasks.iter().filter(|(_, task)| is_top(task)) {
+            let parent = items.len();
+            let todo = google_todo(task, project_id, due, &mut leftovers);
+            items.push(NestedImport { line: position, parent: None, todo });
+            for &(position, subtask) in tasks.iter().filter(|(_, other)| other.parent.as_ref() == Some(&task.id)) {
+                let todo = google_todo(subtask, project_id, due, &mut leftovers);
+                items.push(NestedImport { line: position, parent: Some(parent), todo });
+            }
+        }
+    }
+
+    let result = insert_nested_imports(&mut tx, items).await?;
+    finish(tx, &events, options, result, leftovers).await
+}
+
+/// The project for a task list, by the list's name. Unnamed lists leave their tasks out of any project.
+async fn list_project(tx: &mut Transaction<'_, Sqlite>, name: &str) -> Result<Option<Uuid>, AppError> {
+    match name.trim() {
+        "" => Ok(None),
+        name => Ok(Some(project_named(tx, name).await?)),
+    }
+}
+
+fn google_todo(
+    task: &GoogleTask,
+    project_id: Option<Uuid>,
+    due: Option<&CustomField>,
+    leftovers: &mut Leftovers,
+) -> Result<ImportedTodo, String> {
+    let mut todo = new_todo(&task.title, task.notes.as_deref(), Priority::Medium)?;
+    todo.todo.project_id = project_id;
+    todo.completed = task.status == "completed";
+    todo.completed_at = task.completed.as_deref().and_then(timestamp);
+    if let Some(date) = task.due.as_deref().and_then(date_prefix) {
+        set_due(&mut todo, date, due, leftovers);
+    }
+    leftovers.add("links", task.links.len());
+    Ok(todo)
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum MicrosoftLists {
+    /// A Graph API collection response.
+    Wrapped { value: Vec<MicrosoftList> },
+    Bare(Vec<MicrosoftList>),
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MicrosoftList {
+    #[serde(default)]
+    display_name: String,
+    #[serde(default)]
+    tasks: Vec<MicrosoftTask>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MicrosoftTask {
+    #[serde(default)]
+    title: String,
+    body: Option<MicrosoftBody>,
+    #[serde(default)]
+    importance: String,
+    #[serde(default)]
+    status: String,
+    created_date_time: Option<String>,
+    completed_date_time: Option<MicrosoftDateTime>,
+    due_date_time: Option<MicrosoftDateTime>,
+    #[serde(default)]
+    checklist_items: Vec<MicrosoftChecklistItem>,
+    #[serde(default)]
+    categories: Vec<String>,
+    recurrence: Option<Value>,
+    reminder_date_time: Option<Value>,
+    #[serde(default)]
+    linked_resources: Vec<Value>,
+}
+
+#[derive(Deserialize)]
+struct MicrosoftBody {
+    #[serde(default)]
+    content: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MicrosoftDateTime {
+    date_time: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MicrosoftChecklistItem {
+    display_name: String,
+    #[serde(default)]
+    is_checked: bool,
+}
+
+/// Imports Microsoft To Do lists given as Graph API JSON, each list with its `tasks` expanded.
+/// Lists become projects and categories become tags. Checklist steps become todos that block their
+/// task, and tasks in progress move to the first in-progress status. Reminders and recurrence are
+/// left out.
+pub async fn import_microsoft_todo(
+    State(db): State<DB>,
+    State(events): State<Events>,
+    Query(options): Query<DryRunQuery>,
+    body: String,
+) -> Result<Json<TextImportReport>, AppError> {
+    let lists = match parse_json(&body, "Microsoft To Do lists")? {
+        MicrosoftLists::Wrapped { value } => value,
+        MicrosoftLists::Bare(lists) => lists,
+    };
+
+    let mut tx = db.begin().await?;
+    let fields = all_fields(&mut tx).await?;
+    let due = due_field(&fields);
+    let in_progress = first_status_in(&mut tx, None, StatusCategory::Doing).await?;
+
+    let mut projects = Vec::with_capacity(lists.len());
+    for list in &lists {
+        projects.push(list_project(&mut tx, &list.display_name).await?);
+    }
+
+    let mut leftovers = Leftovers::default();
+    let mut items = vec![];
+    let tasks = lists.iter().zip(projects).flat_map(|(list, project_id)| list.tasks.iter().map(move |task| (task, project_id)));
+    for (position, (task, project_id)) in tasks.enumerate() {
+        let priority = match task.importance.as_str() {
+            "high" => Priority::High,
+            "low" => Priority::Low,
+            _ => Priority::Medium,
+        };
+        let todo = new_todo(&task.title, task.body.as_ref().map(|body| body.content.as_str()), priority).map(|mut todo| {
+            todo.todo.project_id = project_id;
+            todo.todo.tags = task.categories.clone();
+            match task.status.as_str() {
+                "completed" => todo.completed = true,
+                "inProgress" => todo.todo.status_id = in_progress,
+                "waitingOnOthers" | "deferred" => leftovers.add("waiting and deferred states", 1),
+                _ => {}
+            }
+            todo.created_at = task.created_date_time.as_deref().and_then(timestamp);
+            todo.completed_at = task.completed_date_time.as_ref().and_then(|at| timestamp(&at.date_time));
+            if let Some(date) = task.due_date_time.as_ref().and_then(|at| date_prefix(&at.date_time)) {
+                set_due(&mut todo, date, due, &mut leftovers);
+            }
+            todo
+        });
+        leftovers.add("recurrence rules", usize::from(task.recurrence.is_some()));
+        leftovers.add("reminders", usize::from(task.reminder_date_time.is_some()));
+        leftovers.add("linked resources", task.linked_resources.len());
+
+        let parent = items.len();
+        items.push(NestedImport { line: position + 1, parent: None, todo });
+        for step in &task.checklist_items {
+            let todo = new_todo(&step.display_name, None, Priority::Medium).map(|mut todo| {
+                todo.todo.project_id = project_id;
+                todo.completed = step.is_checked;
+                todo
+            });
+            items.push(NestedImport { line: position + 1, parent: Some(parent), todo });
+        }
+    }
+
+    let result = insert_nested_imports(&mut tx, items).await?;
+    finish(tx, &events, options, result, leftovers).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        db::test_db,
+        handlers::{projects::all_projects, todos::TODO_COLUMNS},
+        models::Todo,
+    };
+
+    async fn todos(db: &DB) -> Vec<Todo> {
+        sqlx::query_as::<_, Todo>(&format!("SELECT {TODO_COLUMNS} FROM todos ORDER BY position"))
+            .fetch_all(db)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn lists_become_projects_and_labels_become_tags() {
+        let db = test_db().await;
+        let board = r#"{
+            "name": "Website",
+            "lists": [{"id": "l1", "name": "To do"}],
+            "cards": [{"id": "c1", "name": "Fix footer", "idList": "l1", "labels": [{"name": "bug", "color": "red"}, {"name": "", "color": "blue"}]}]
+        }"#;
+        let Json(report) = import_trello(State(db.clone()), State(Events::default()), Query(DryRunQuery::default()), board.to_string())
+            .await
+            .unwrap();
+        assert_eq!((report.imported, report.unmapped.len()), (1, 0));
+
+        let lists = r#"{"value": [
+            {"displayName": "website", "tasks": [{"title": "Renew domain", "categories": ["Admin"]}]},
+            {"displayName": "Groceries", "tasks": [{"title": "Eggs"}]}
+        ]}"#;
+        let Json(report) =
+            import_microsoft_todo(State(db.clone()), State(Events::default()), Query(DryRunQuery::default()), lists.to_string())
+                .await
+                .unwrap();
+        assert_eq!(report.imported, 2);
+
+        let mut conn = db.acquire().await.unwrap();
+        let projects = all_projects(&mut conn).await.unwrap();
+        drop(conn);
+        assert_eq!(projects.iter().map(|project| project.name.as_str()).collect::<Vec<_>>(), ["Website", "Groceries"]);
+        let project_of = |name: &str| projects.iter().find(|project| project.name == name).map(|project| project.id);
+
+        let todos = todos(&db).await;
+        let summary: Vec<_> = todos.iter().map(|todo| (todo.title.as_str(), todo.project_id, todo.tags.clone())).collect();
+        assert_eq!(
+            summary,
+            [
+                ("Fix footer", project_of("Website"), vec!["blue".to_string(), "bug".to_string()]),
+                ("Renew domain", project_of("Website"), vec!["Admin".to_string()]),
+                ("Eggs", project_of("Groceries"), vec![]),
+            ]
+        );
+    }
+}
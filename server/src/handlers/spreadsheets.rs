@@ -0,0 +1,329 @@
+use crate::{
+    csv,
+    db::DB,
+    errors::AppError,
+    events::{Events, TodoEvent},
+    handlers::{
+        custom_fields::{all_fields, parse_field_text},
+        todos::{insert_imported_todo, ImportedTodo, DUMMY_USER_ID, TODO_COLUMNS},
+        views::parse_query,
+    },
+    models::{
+        CreateTodo, CsvExportQuery, CsvImport, CsvImportReport, CustomField, FieldValues,
+        ImportRowError, Priority, Todo,
+    },
+    view_query,
+};
+use axum::{
+    extract::{Query, State},
+    http::header,
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use sqlx::QueryBuilder;
+use std::collections::{BTreeMap, HashMap};
+
+/// Built-in columns of the CSV export, besides `cf.<key>`.
+pub(crate) const EXPORT_COLUMNS: &[&str] = &[
+    "id", "title", "description", "priority", "completed", "status", "estimated_minutes",
+    "created_at", "updated_at", "completed_at", "archived_at",
+];
+
+const DEFAULT_EXPORT_COLUMNS: &[&str] = &["title", "description", "priority", "completed", "created_at", "completed_at"];
+
+const FIELD_PREFIX: &str = "cf.";
+
+fn format_time(at: Option<DateTime<Utc>>) -> String {
+    at.map(|at| at.to_rfc3339()).unwrap_or_default()
+}
+
+fn cell(todo: &Todo, column: &str, statuses: &HashMap<String, String>) -> String {
+    match column {
+        "id" => todo.id.to_string(),
+        "title" => todo.title.clone(),
+        "description" => todo.description.clone().unwrap_or_default(),
+        "priority" => format!("{:?}", todo.priority),
+        "completed" => todo.completed.to_string(),
+        "status" => todo
+            .status_id
+            .and_then(|id| statuses.get(&id.to_string()).cloned())
+            .unwrap_or_default(),
+        "estimated_minutes" => todo.estimated_minutes.map(|m| m.to_string()).unwrap_or_default(),
+        "created_at" => format_time(Some(todo.created_at)),
+        "updated_at" => format_time(Some(todo.updated_at)),
+        "completed_at" => format_time(todo.completed_at),
+        "archived_at" => format_time(todo.archived_at),
+        _ => match column.strip_prefix(FIELD_PREFIX).and_then(|key| todo.custom_fields.0.get(key)) {
+            Some(Value::String(text)) => text.clone(),
+            Some(value) => value.to_string(),
+            None => String::new(),
+        },
+    }
+}
+
+/// Todos as CSV with the chosen `columns`, filtered by an optional `q` expression. Archived todos
+/// are left out, as in the list.
+pub async fn export_csv(
+    State(db): State<DB>,
+    Query(query): Query<CsvExportQuery>,
+) -> Result<Response, AppError> {
+    let mut conn = db.acquire().await?;
+    let fields = all_fields(&mut conn).await?;
+    let columns: Vec<String> = match query.columns.as_deref() {
+        Some(columns) => columns.split(',').map(|c| c.trim().to_string()).filter(|c| !c.is_empty()).collect(),
+        None => DEFAULT_EXPORT_COLUMNS.iter().map(|c| c.to_string()).collect(),
+    };
+    for column in &columns {
+        let known = match column.strip_prefix(FIELD_PREFIX) {
+            Some(key) => fields.iter().any(|field| field.key == key),
+            None => EXPORT_COLUMNS.contains(&column.as_str()),
+        };
+        if !known {
+            return Err(AppError::BadRequest(format!("unknown column `{}`", column)));
+        }
+    }
+
+    let mut builder = QueryBuilder::new(format!("SELECT {TODO_COLUMNS} FROM todos WHERE user_id = "));
+    builder.push_bind(DUMMY_USER_ID).push(" AND archived_at IS NULL");
+    if let Some(q) = &query.q {
+        builder.push(" AND ");
+        view_query::push_sql(&parse_query(q)?, &mut builder);
+    }
+    builder.push(" ORDER BY position");
+    let todos = builder.build_query_as::<Todo>().fetch_all(&mut *conn).await?;
+
+    let statuses: HashMap<String, String> =
+        sqlx::query_as::<_, (String, String)>("SELECT id, name FROM statuses WHERE user_id = $1")
+            .bind(DUMMY_USER_ID)
+            .fetch_all(&mut *conn)
+            .await?
+            .into_iter()
+            .collect();
+
+    let mut out = String::new();
+    csv::write_record(&mut out, &columns);
+    for todo in &todos {
+        let row: Vec<String> = columns.iter().map(|column| cell(todo, column, &statuses)).collect();
+        csv::write_record(&mut out, &row);
+    }
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "text/csv; charset=utf-8"),
+            (header::CONTENT_DISPOSITION, "attachment; filename=\"todos.csv\""),
+        ],
+        out,
+    )
+        .into_response())
+}
+
+#[derive(Debug, Clone)]
+enum Target {
+    Title,
+    Description,
+    Priority,
+    Completed,
+    Field(CustomField),
+}
+
+impl Target {
+    fn name(&self) -> String {
+        match self {
+            Target::Title => "title".to_string(),
+            Target::Description => "description".to_string(),
+            Target::Priority => "priority".to_string(),
+            Target::Completed => "completed".to_string(),
+            Target::Field(field) => format!("{}{}", FIELD_PREFIX, field.key),
+        }
+    }
+}
+
+fn parse_target(name: &str, fields: &[CustomField]) -> Result<Target, AppError> {
+    let name = name.trim();
+    if let Some(key) = name.strip_prefix(FIELD_PREFIX) {
+        return fields
+            .iter()
+            .find(|field| field.key == key)
+            .map(|field| Target::Field(field.clone()))
+            .ok_or_else(|| AppError::BadRequest(format!("unknown custom field `{}`", key)));
+    }
+    match name.to_lowercase().as_str() {
+        "title" => Ok(Target::Title),
+        "description" => Ok(Target::Description),
+        "priority" => Ok(Target::Priority),
+        "completed" => Ok(Target::Completed),
+        "due" | "due_date" | "due date" => Err(AppError::BadRequest(
+            "todos have no due date; map the column to a date custom field (`cf.<key>`) instead".to_string(),
+        )),
+        _ => Err(AppError::BadRequest(format!("unknown field `{}`", name))),
+    }
+}
+
+/// The field a header names by itself, for files imported without a mapping.
+fn default_target(header: &str, fields: &[CustomField]) -> Option<Target> {
+    if header.trim().starts_with(FIELD_PREFIX) {
+        return parse_target(header, fields).ok();
+    }
+    let header = header.trim().to_lowercase();
+    match header.as_str() {
+        "title" | "name" | "task" | "todo" => Some(Target::Title),
+        "description" | "notes" | "details" => Some(Target::Description),
+        "priority" => Some(Target::Priority),
+        "completed" | "done" => Some(Target::Completed),
+        _ => fields
+            .iter()
+            .find(|field| header == field.key.to_lowercase() || header == field.name.to_lowercase())
+            .map(|field| Target::Field(field.clone())),
+    }
+}
+
+fn looks_like_header(row: &[String], mapping: &HashMap<String, String>, fields: &[CustomField]) -> bool {
+    row.iter().any(|cell| {
+        mapping.keys().any(|column| column.trim().eq_ignore_ascii_case(cell.trim()))
+            || default_target(cell, fields).is_some()
+    })
+}
+
+/// Resolves the mapping to column indexes. Every column may feed one field and every field
+/// may be fed by one column.
+fn resolve_mapping(
+    request: &CsvImport,
+    header: Option<&[String]>,
+    fields: &[CustomField],
+) -> Result<Vec<(usize, Target)>, AppError> {
+    let mut columns: Vec<(usize, Target)> = vec![];
+    if request.mapping.is_empty() {
+        if let Some(header) = header {
+            columns = header
+                .iter()
+                .enumerate()
+                .filter_map(|(i, name)| default_target(name, fields).map(|target| (i, target)))
+                .collect();
+        }
+    } else {
+        for (column, target) in &request.mapping {
+            let index = match header {
+                Some(header) => header.iter().position(|name| name.trim().eq_ignore_ascii_case(column.trim())),
+                None => column.trim().parse::<usize>().ok(),
+            }
+            .ok_or_else(|| AppError::BadRequest(format!("no column `{}` in the file", column)))?;
+            columns.push((index, parse_target(target, fields)?));
+        }
+    }
+
+    for (i, (_, target)) in columns.iter().enumerate() {
+        if columns[..i].iter().any(|(_, other)| other.name() == target.name()) {
+            return Err(AppError::BadRequest(format!("`{}` is mapped more than once", target.name())));
+        }
+    }
+    if !columns.iter().any(|(_, target)| matches!(target, Target::Title)) {
+        return Err(AppError::BadRequest("no column is mapped to `title`".to_string()));
+    }
+    columns.sort_by_key(|(index, _)| *index);
+    Ok(columns)
+}
+
+fn parse_priority(value: &str) -> Result<Priority, String> {
+    match value.to_lowercase().as_str() {
+        "low" => Ok(Priority::Low),
+        "medium" => Ok(Priority::Medium),
+        "high" => Ok(Priority::High),
+        _ => Err(format!("unknown priority `{}`; expected Low, Medium or High", value)),
+    }
+}
+
+fn parse_completed(value: &str) -> Result<bool, String> {
+    match value.to_lowercase().as_str() {
+        "true" | "yes" | "y" | "1" | "x" | "done" => Ok(true),
+        "false" | "no" | "n" | "0" => Ok(false),
+        _ => Err(format!("cannot read `{}` as completed; use true or false", value)),
+    }
+}
+
+/// Builds the todo for one row. Empty cells leave the field at its default.
+fn row_todo(row: &[String], columns: &[(usize, Target)]) -> Result<ImportedTodo, String> {
+    let mut todo = CreateTodo {
+        title: String::new(),
+        description: None,
+        priority: Priority::Medium,
+        status_id: None,
+        estimated_minutes: None,
+        custom_fields: FieldValues::default(),
+        project_id: None,
+        tags: vec![],
+    };
+    let mut completed = false;
+
+    for (index, target) in columns {
+        let value = row.get(*index).map(|value| value.trim()).unwrap_or_default();
+        if value.is_empty() {
+            continue;
+        }
+        match target {
+            Target::Title => todo.title = value.to_string(),
+            Target::Description => todo.description = Some(value.to_string()),
+            Target::Priority => todo.priority = parse_priority(value)?,
+            Target::Completed => completed = parse_completed(value)?,
+            Target::Field(field) => {
+                let value = parse_field_text(field, value).map_err(|e| format!("{}: {}", field.key, e))?;
+                todo.custom_fields.0.insert(field.key.clone(), value);
+            }
+        }
+    }
+
+    if todo.title.is_empty() {
+        return Err("title is empty".to_string());
+    }
+    Ok(ImportedTodo { todo, completed, created_at: None, completed_at: None })
+}
+
+/// Imports todos from CSV. Problems with the file or the mapping fail the request; problems
+/// with a row are reported with its line number and the remaining rows are still imported.
+pub async fn import_csv(
+    State(db): State<DB>,
+    State(events): State<Events>,
+    Json(request): Json<CsvImport>,
+) -> Result<Json<CsvImportReport>, AppError> {
+    let mut records = csv::parse(&request.csv).map_err(AppError::BadRequest)?;
+    let mut tx = db.begin().await?;
+    let fields = all_fields(&mut tx).await?;
+
+    let has_header = match (request.has_header, records.first()) {
+        (Some(has_header), _) => has_header,
+        (None, Some(first)) => looks_like_header(&first.fields, &request.mapping, &fields),
+        (None, None) => false,
+    };
+    let header = (has_header && !records.is_empty()).then(|| records.remove(0).fields);
+    let columns = resolve_mapping(&request, header.as_deref(), &fields)?;
+
+    let mut imported = 0;
+    let mut errors = vec![];
+    for record in &records {
+        let result = match row_todo(&record.fields, &columns) {
+            Ok(imported) => insert_imported_todo(&mut tx, &imported).await.map(|_| ()),
+            Err(message) => Err(AppError::BadRequest(message)),
+        };
+        match result {
+            Ok(()) => imported += 1,
+            Err(AppError::BadRequest(message)) => errors.push(ImportRowError { line: record.line, message }),
+            Err(e) => return Err(e),
+        }
+    }
+
+    let mapping: BTreeMap<String, String> = columns
+        .iter()
+        .map(|(index, target)| {
+            let column = header.as_ref().map_or_else(|| index.to_string(), |header| header[*index].clone());
+            (column, target.name())
+        })
+        .collect();
+
+    if request.dry_run {
+        tx.rollback().await?;
+    } else {
+        events.commit(tx, DUMMY_USER_ID, vec![TodoEvent::Reload]).await?;
+    }
+    Ok(Json(CsvImportReport { dry_run: request.dry_run, header, mapping, imported, errors }))
+}
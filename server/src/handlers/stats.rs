@@ -0,0 +1,173 @@
+use crate::{
+    db::DB,
+    errors::AppError,
+    handlers::{calendar::DUE_FIELD, todos::DUMMY_USER_ID},
+    models::{PeriodCount, PriorityCount, Stats, StatsQuery},
+};
+use axum::{
+    extract::{Query, State},
+    Json,
+};
+
+// Longest ranges a client may ask for, to keep the generated series small.
+const MAX_DAYS: i64 = 366;
+const MAX_WEEKS: i64 = 104;
+
+pub async fn stats(
+    State(db): State<DB>,
+    Query(query): Query<StatsQuery>,
+) -> Result<Json<Stats>, AppError> {
+    if !(1..=MAX_DAYS).contains(&query.days) || !(1..=MAX_WEEKS).contains(&query.weeks) {
+        return Err(AppError::BadRequest(format!(
+            "days must be 1-{} and weeks 1-{}",
+            MAX_DAYS, MAX_WEEKS
+        )));
+    }
+    let mut conn = db.acquire().await?;
+
+    let completed_per_day = sqlx::query_as::<_, PeriodCount>(
+        "WITH RECURSIVE days(day) AS ( \
+            SELECT date('now', '-' || ($2 - 1) || ' days') \
+            UNION ALL SELECT date(day, '+1 day') FROM days WHERE day < date('now') \
+        ) \
+        SELECT day AS period, \
+            (SELECT COUNT(*) FROM todos WHERE user_id = $1 AND date(completed_at) = day) AS count \
+        FROM days ORDER BY day",
+    )
+    .bind(DUMMY_USER_ID)
+    .bind(query.days)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    // Weeks start on Monday: `weekday 0` moves to the coming Sunday, six days after it.
+    let completed_per_week = sqlx::query_as::<_, PeriodCount>(
+        "WITH RECURSIVE weeks(week) AS ( \
+            SELECT date('now', 'weekday 0', '-6 days', '-' || (($2 - 1) * 7) || ' days') \
+            UNION ALL SELECT date(week, '+7 days') FROM weeks WHERE week < date('now', 'weekday 0', '-6 days') \
+        ) \
+        SELECT week AS period, \
+            (SELECT COUNT(*) FROM todos WHERE user_id = $1 AND completed_at IS NOT NULL \
+                AND date(completed_at, 'weekday 0', '-6 days') = week) AS count \
+        FROM weeks ORDER BY week",
+    )
+    .bind(DUMMY_USER_ID)
+    .bind(query.weeks)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let average_completion_seconds: Option<f64> = sqlx::query_scalar(
+        "SELECT AVG((julianday(completed_at) - julianday(created_at)) * 86400.0) FROM todos \
+        WHERE user_id = $1 AND completed_at IS NOT NULL",
+    )
+    .bind(DUMMY_USER_ID)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    let open_by_priority = sqlx::query_as::<_, PriorityCount>(
+        "SELECT priority, COUNT(*) AS count FROM todos WHERE user_id = $1 AND completed = FALSE AND archived_at IS NULL \
+        GROUP BY priority ORDER BY CASE priority WHEN 'high' THEN 0 WHEN 'medium' THEN 1 ELSE 2 END",
+    )
+    .bind(DUMMY_USER_ID)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    // Due dates are `YYYY-MM-DD` strings, so they compare with `date('now')` as text.
+    let overdue: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM todos t \
+        JOIN todo_field_values v ON v.todo_id = t.id \
+        JOIN custom_fields f ON f.id = v.field_id AND f.key = $2 AND f.field_type = 'date' \
+        WHERE t.user_id = $1 AND t.completed = FALSE AND t.archived_at IS NULL \
+            AND json_extract(v.value, '$') < date('now')",
+    )
+    .bind(DUMMY_USER_ID)
+    .bind(DUE_FIELD)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    // Gaps and islands: consecutive days share the same `julianday - row_number`.
+    let (longest_streak, current_streak): (i64, i64) = sqlx::query_as(
+        "WITH days AS ( \
+            SELECT DISTINCT date(completed_at) AS day FROM todos WHERE user_id = $1 AND completed_at IS NOT NULL \
+        ), runs AS ( \
+            SELECT day, julianday(day) - ROW_NUMBER() OVER (ORDER BY day) AS grp FROM days \
+        ), streaks AS ( \
+            SELECT MAX(day) AS last_day, COUNT(*) AS length FROM runs GROUP BY grp \
+        ) \
+        SELECT COALESCE(MAX(length), 0), \
+            COALESCE(MAX(CASE WHEN last_day >= date('now', '-1 day') THEN length END), 0) \
+        FROM streaks",
+    )
+    .bind(DUMMY_USER_ID)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    Ok(Json(Stats {
+        completed_per_day,
+        completed_per_week,
+        average_completion_seconds,
+        open_by_priority,
+        overdue,
+        current_streak,
+        longest_streak,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        db::test_db,
+        handlers::todos::insert_todo,
+        models::{CreateTodo, FieldValues, Priority},
+    };
+    use chrono::{Days, Utc};
+
+    #[tokio::test]
+    async fn counts_open_todos_past_their_due_date() {
+        let db = test_db().await;
+        let mut conn = db.acquire().await.unwrap();
+        sqlx::query("INSERT INTO custom_fields (id, user_id, key, name, field_type) VALUES ($1, $2, $3, 'Due', 'date')")
+            .bind(uuid::Uuid::new_v4().to_string())
+            .bind(DUMMY_USER_ID)
+            .bind(DUE_FIELD)
+            .execute(&mut *conn)
+            .await
+            .unwrap();
+
+        let today = Utc::now().date_naive();
+        let yesterday = today - Days::new(1);
+        let todos = [
+            ("overdue", Some(today - Days::new(3)), ""),
+            ("due yesterday", Some(yesterday), ""),
+            ("due today", Some(today), ""),
+            ("due tomorrow", Some(today + Days::new(1)), ""),
+            ("no due date", None, ""),
+            ("done late", Some(yesterday), "UPDATE todos SET completed = TRUE, completed_at = CURRENT_TIMESTAMP WHERE id = $1"),
+            ("archived", Some(yesterday), "UPDATE todos SET archived_at = CURRENT_TIMESTAMP WHERE id = $1"),
+        ];
+        for (title, due, update) in todos {
+            let mut custom_fields = FieldValues::default();
+            if let Some(due) = due {
+                custom_fields.0.insert(DUE_FIELD.to_string(), serde_json::Value::String(due.to_string()));
+            }
+            let todo = CreateTodo {
+                title: title.to_string(),
+                description: None,
+                priority: Priority::Medium,
+                status_id: None,
+                estimated_minutes: None,
+                custom_fields,
+                project_id: None,
+                tags: vec![],
+            };
+            let todo = insert_todo(&mut conn, &todo).await.unwrap();
+            if !update.is_empty() {
+                sqlx::query(update).bind(todo.id.to_string()).execute(&mut *conn).await.unwrap();
+            }
+        }
+        drop(conn);
+
+        let Json(stats) = stats(State(db), Query(StatsQuery { days: 7, weeks: 2 })).await.unwrap();
+        assert_eq!(stats.overdue, 2);
+    }
+}
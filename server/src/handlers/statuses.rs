@@ -0,0 +1,470 @@
+use crate::{
+    db::DB,
+    errors::AppError,
+    events::{Events, TodoEvent},
+    handlers::{
+        projects::check_project,
+        todos::{apply_replacement, DUMMY_USER_ID, TODO_COLUMNS},
+    },
+    models::{CreateStatus, ReplaceTodo, Status, StatusCategory, StatusQuery, Todo, UpdateStatus},
+};
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::Utc;
+use sqlx::SqliteConnection;
+use uuid::Uuid;
+
+pub(crate) const STATUS_COLUMNS: &str = "id, user_id, name, position, category, created_at, project_id";
+
+const DEFAULT_STATUSES: &[(&str, StatusCategory)] = &[
+    ("To Do", StatusCategory::Todo),
+    ("In Progress", StatusCategory::Doing),
+    ("Done", StatusCategory::Done),
+];
+
+/// Gives a user the default shared workflow the first time their statuses are needed.
+async fn ensure_default_statuses(conn: &mut SqliteConnection, user_id: &str) -> Result<(), AppError> {
+    let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM statuses WHERE user_id = $1 AND project_id IS NULL")
+        .bind(user_id)
+        .fetch_one(&mut *conn)
+        .await?;
+    if count > 0 {
+        return Ok(());
+    }
+
+    for (position, (name, category)) in DEFAULT_STATUSES.iter().enumerate() {
+        sqlx::query(
+            "INSERT INTO statuses (id, user_id, name, position, category, created_at) VALUES ($1, $2, $3, $4, $5, $6)",
+        )
+        .bind(Uuid::new_v4().to_string())
+        .bind(user_id)
+        .bind(name)
+        .bind(position as i64)
+        .bind(category)
+        .bind(Utc::now())
+        .execute(&mut *conn)
+        .await?;
+    }
+    Ok(())
+}
+
+async fn fetch_status(conn: &mut SqliteConnection, id: Uuid) -> Result<Status, AppError> {
+    sqlx::query_as::<_, Status>(&format!(
+        "SELECT {STATUS_COLUMNS} FROM statuses WHERE id = $1 AND user_id = $2"
+    ))
+    .bind(id.to_string())
+    .bind(DUMMY_USER_ID)
+    .fetch_optional(&mut *conn)
+    .await?
+    .ok_or(AppError::NotFound)
+}
+
+/// The workflow used by todos in `project_id`: the project's own once it has statuses, and the
+/// shared one (`None`) otherwise.
+pub(crate) async fn workflow_of(
+    conn: &mut SqliteConnection,
+    project_id: Option<Uuid>,
+) -> Result<Option<Uuid>, AppError> {
+    let Some(project_id) = project_id else { return Ok(None) };
+    let own: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM statuses WHERE user_id = $1 AND project_id = $2")
+        .bind(DUMMY_USER_ID)
+        .bind(project_id.to_string())
+        .fetch_one(&mut *conn)
+        .await?;
+    Ok((own > 0).then_some(project_id))
+}
+
+/// `status` when it is part of `workflow`, otherwise the first status of the same category there.
+/// An in-progress status falls back to the todo category in a workflow without one.
+async fn in_workflow(
+    conn: &mut SqliteConnection,
+    status: Status,
+    workflow: Option<Uuid>,
+) -> Result<(Uuid, StatusCategory), AppError> {
+    if status.project_id == workflow {
+        return Ok((status.id, status.category));
+    }
+    let mut category = status.category;
+    let mut id = first_status_in(conn, workflow, category).await?;
+    if id.is_none() && category == StatusCategory::Doing {
+        category = StatusCategory::Todo;
+        id = first_status_in(conn, workflow, category).await?;
+    }
+    let id = id.ok_or_else(|| AppError::BadRequest(format!("no status in the {:?} category", category)))?;
+    Ok((id, category))
+}
+
+/// Works out the status and `completed` flag for a write to a todo in `project_id`. An explicitly
+/// changed status wins and sets `completed` from its category; otherwise the current status is
+/// kept unless `completed` crossed the done boundary, in which case the todo moves to the first
+/// status of that category. A status from another workflow, as after a move between projects, is
+/// swapped for the first of its category in the todo's workflow.
+pub(crate) async fn resolve_status(
+    conn: &mut SqliteConnection,
+    before: Option<&Todo>,
+    requested: Option<Uuid>,
+    completed: bool,
+    project_id: Option<Uuid>,
+) -> Result<(Uuid, bool), AppError> {
+    ensure_default_statuses(conn, DUMMY_USER_ID).await?;
+    let workflow = workflow_of(conn, project_id).await?;
+    let previous = before.and_then(|todo| todo.status_id);
+
+    if let Some(id) = requested.filter(|id| Some(*id) != previous) {
+        let status = fetch_status(conn, id)
+            .await
+            .map_err(|_| AppError::BadRequest(format!("unknown status {}", id)))?;
+        let (id, category) = in_workflow(conn, status, workflow).await?;
+        return Ok((id, category == StatusCategory::Done));
+    }
+
+    if let Some(id) = previous {
+        if let Ok(status) = fetch_status(conn, id).await {
+            if (status.category == StatusCategory::Done) == completed {
+                let (id, _) = in_workflow(conn, status, workflow).await?;
+                return Ok((id, completed));
+            }
+        }
+    }
+
+    let category = if completed { StatusCategory::Done } else { StatusCategory::Todo };
+    let id = first_status_in(conn, workflow, category)
+        .await?
+        .ok_or_else(|| AppError::BadRequest(format!("no status in the {:?} category", category)))?;
+    Ok((id, completed))
+}
+
+/// The first status of `category` in a workflow, if it has one. `workflow` is a project with
+/// statuses of its own, or `None` for the shared workflow.
+pub(crate) async fn first_status_in(
+    conn: &mut SqliteConnection,
+    workflow: Option<Uuid>,
+    category: StatusCategory,
+) -> Result<Option<Uuid>, AppError> {
+    ensure_default_statuses(conn, DUMMY_USER_ID).await?;
+    let id: Option<String> = sqlx::query_scalar(
+        "SELECT id FROM statuses WHERE user_id = $1 AND project_id IS $2 AND category = $3 ORDER BY position LIMIT 1",
+    )
+    .bind(DUMMY_USER_ID)
+    .bind(workflow.map(|id| id.to_string()))
+    .bind(category)
+    .fetch_optional(&mut *conn)
+    .await?;
+    id.map(|id| Uuid::parse_str(&id).map_err(|_| AppError::InternalServerError)).transpose()
+}
+
+/// The user's shared status with this name, ignoring case, or a new one of `category` at the end
+/// of the shared workflow. Importers use it to bring over columns from other tools.
+pub(crate) async fn status_named(
+    conn: &mut SqliteConnection,
+    name: &str,
+    category: StatusCategory,
+) -> Result<Uuid, AppError> {
+    ensure_default_statuses(conn, DUMMY_USER_ID).await?;
+    let existing: Option<String> =
+        sqlx::query_scalar("SELECT id FROM statuses WHERE user_id = $1 AND project_id IS NULL AND name = $2 COLLATE NOCASE ORDER BY position LIMIT 1")
+            .bind(DUMMY_USER_ID)
+            .bind(name)
+            .fetch_optional(&mut *conn)
+            .await?;
+    if let Some(id) = existing {
+        return Uuid::parse_str(&id).map_err(|_| AppError::InternalServerError);
+    }
+
+    let id = Uuid::new_v4();
+    sqlx::query(
+        "INSERT INTO statuses (id, user_id, name, position, category, created_at) \
+        VALUES ($1, $2, $3, (SELECT COALESCE(MAX(position) + 1, 0) FROM statuses WHERE user_id = $2 AND project_id IS NULL), $4, $5)",
+    )
+    .bind(id.to_string())
+    .bind(DUMMY_USER_ID)
+    .bind(name)
+    .bind(category)
+    .bind(Utc::now())
+    .execute(&mut *conn)
+    .await?;
+    Ok(id)
+}
+
+// `completed` needs a todo and a done status to land on, so neither category may be emptied
+// within a workflow.
+async fn ensure_not_last_of_category(conn: &mut SqliteConnection, status: &Status) -> Result<(), AppError> {
+    if status.category == StatusCategory::Doing {
+        return Ok(());
+    }
+    let siblings: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM statuses WHERE user_id = $1 AND category = $2 AND id != $3 AND project_id IS $4",
+    )
+    .bind(DUMMY_USER_ID)
+    .bind(status.category)
+    .bind(status.id.to_string())
+    .bind(status.project_id.map(|id| id.to_string()))
+    .fetch_one(&mut *conn)
+    .await?;
+    if siblings == 0 {
+        return Err(AppError::BadRequest(format!(
+            "cannot remove the last {:?} status",
+            status.category
+        )));
+    }
+    Ok(())
+}
+
+async fn workflow_statuses(conn: &mut SqliteConnection, workflow: Option<Uuid>) -> Result<Vec<Status>, AppError> {
+    Ok(sqlx::query_as::<_, Status>(&format!(
+        "SELECT {STATUS_COLUMNS} FROM statuses WHERE user_id = $1 AND project_id IS $2 ORDER BY position, created_at"
+    ))
+    .bind(DUMMY_USER_ID)
+    .bind(workflow.map(|id| id.to_string()))
+    .fetch_all(&mut *conn)
+    .await?)
+}
+
+/// Gives a project its own workflow, starting as a copy of the shared one. The project's todos
+/// move to the copies of their statuses.
+async fn copy_shared_workflow(
+    conn: &mut SqliteConnection,
+    project_id: Uuid,
+) -> Result<Vec<TodoEvent>, AppError> {
+    for status in workflow_statuses(conn, None).await? {
+        sqlx::query(
+            "INSERT INTO statuses (id, user_id, name, position, category, created_at, project_id) VALUES ($1, $2, $3, $4, $5, $6, $7)",
+        )
+        .bind(Uuid::new_v4().to_string())
+        .bind(DUMMY_USER_ID)
+        .bind(&status.name)
+        .bind(status.position)
+        .bind(status.category)
+        .bind(Utc::now())
+        .bind(project_id.to_string())
+        .execute(&mut *conn)
+        .await?;
+    }
+    let copies = workflow_statuses(conn, Some(project_id)).await?;
+
+    let todos = sqlx::query_as::<_, Todo>(&format!(
+        "SELECT {TODO_COLUMNS} FROM todos WHERE project_id = $1 AND user_id = $2"
+    ))
+    .bind(project_id.to_string())
+    .bind(DUMMY_USER_ID)
+    .fetch_all(&mut *conn)
+    .await?;
+    let mut changes = Vec::with_capacity(todos.len());
+    for todo in todos {
+        let Some(current) = todo.status_id else { continue };
+        let shared = fetch_status(conn, current).await?;
+        let copy = copies.iter().find(|copy| copy.name == shared.name && copy.category == shared.category);
+        let replacement = ReplaceTodo { status_id: copy.map(|copy| copy.id), ..ReplaceTodo::from(&todo) };
+        let todo = apply_replacement(conn, &todo, &replacement, true).await?;
+        changes.push(TodoEvent::Updated { todo });
+    }
+    Ok(changes)
+}
+
+pub async fn list_statuses(
+    State(db): State<DB>,
+    Query(query): Query<StatusQuery>,
+) -> Result<Json<Vec<Status>>, AppError> {
+    let mut conn = db.acquire().await?;
+    ensure_default_statuses(&mut conn, DUMMY_USER_ID).await?;
+    check_project(&mut conn, query.project_id).await?;
+    let workflow = workflow_of(&mut conn, query.project_id).await?;
+    Ok(Json(workflow_statuses(&mut conn, workflow).await?))
+}
+
+pub async fn create_status(
+    State(db): State<DB>,
+    State(events): State<Events>,
+    Json(payload): Json<CreateStatus>,
+) -> Result<(StatusCode, Json<Status>), AppError> {
+    let mut tx = db.begin().await?;
+    ensure_default_statuses(&mut tx, DUMMY_USER_ID).await?;
+    check_project(&mut tx, payload.project_id).await?;
+
+    let mut changes = vec![];
+    if let Some(project_id) = payload.project_id {
+        if workflow_of(&mut tx, Some(project_id)).await?.is_none() {
+            changes = copy_shared_workflow(&mut tx, project_id).await?;
+        }
+    }
+    let project_id = payload.project_id.map(|id| id.to_string());
+
+    let position = match payload.position {
+        Some(position) => position,
+        None => sqlx::query_scalar(
+            "SELECT COALESCE(MAX(position), -1) + 1 FROM statuses WHERE user_id = $1 AND project_id IS $2",
+        )
+        .bind(DUMMY_USER_ID)
+        .bind(&project_id)
+        .fetch_one(&mut *tx)
+        .await?,
+    };
+
+    let id = Uuid::new_v4();
+    sqlx::query(
+        "INSERT INTO statuses (id, user_id, name, position, category, created_at, project_id) VALUES ($1, $2, $3, $4, $5, $6, $7)",
+    )
+    .bind(id.to_string())
+    .bind(DUMMY_USER_ID)
+    .bind(&payload.name)
+    .bind(position)
+    .bind(payload.category)
+    .bind(Utc::now())
+    .bind(&project_id)
+    .execute(&mut *tx)
+    .await?;
+
+    let status = fetch_status(&mut tx, id).await?;
+    events.commit(tx, DUMMY_USER_ID, changes).await?;
+    Ok((StatusCode::CREATED, Json(status)))
+}
+
+pub async fn update_status(
+    State(db): State<DB>,
+    State(events): State<Events>,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<UpdateStatus>,
+) -> Result<Json<Status>, AppError> {
+    let mut tx = db.begin().await?;
+    let status = fetch_status(&mut tx, id).await?;
+    if payload.category.is_some_and(|category| category != status.category) {
+        ensure_not_last_of_category(&mut tx, &status).await?;
+    }
+
+    let name = payload.name.unwrap_or(status.name);
+    let position = payload.position.unwrap_or(status.position);
+    let category = payload.category.unwrap_or(status.category);
+
+    sqlx::query("UPDATE statuses SET name = $1, position = $2, category = $3 WHERE id = $4")
+        .bind(&name)
+        .bind(position)
+        .bind(category)
+        .bind(id.to_string())
+        .execute(&mut *tx)
+        .await?;
+
+    // Keep the compatibility `completed` flag in step with a re-categorised status. Each todo
+    // goes through the normal write path so it gets a revision and clients hear about it.
+    let mut changes = vec![];
+    if category != status.category {
+        let done = category == StatusCategory::Done;
+        let todos = sqlx::query_as::<_, Todo>(&format!(
+            "SELECT {TODO_COLUMNS} FROM todos WHERE status_id = $1 AND user_id = $2 AND completed != $3"
+        ))
+        .bind(id.to_string())
+        .bind(DUMMY_USER_ID)
+        .bind(done)
+        .fetch_all(&mut *tx)
+        .await?;
+        for todo in todos {
+            // Moving a whole column to done is not held up by blockers.
+            let replacement = ReplaceTodo { completed: done, ..ReplaceTodo::from(&todo) };
+            let todo = apply_replacement(&mut tx, &todo, &replacement, true).await?;
+            changes.push(TodoEvent::Updated { todo });
+        }
+    }
+
+    let status = fetch_status(&mut tx, id).await?;
+    events.commit(tx, DUMMY_USER_ID, changes).await?;
+    Ok(Json(status))
+}
+
+pub async fn delete_status(
+    State(db): State<DB>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, AppError> {
+    let mut tx = db.begin().await?;
+    let status = fetch_status(&mut tx, id).await?;
+
+    let in_use: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM todos WHERE status_id = $1")
+        .bind(id.to_string())
+        .fetch_one(&mut *tx)
+        .await?;
+    if in_use > 0 {
+        return Err(AppError::BadRequest(format!("status is used by {} todos", in_use)));
+    }
+
+    ensure_not_last_of_category(&mut tx, &status).await?;
+
+    sqlx::query("DELETE FROM statuses WHERE id = $1")
+        .bind(id.to_string())
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        db::test_db,
+        handlers::todos::{fetch_todo, insert_todo},
+        models::{CreateProject, CreateTodo, Priority},
+    };
+
+    fn names(statuses: &[Status]) -> Vec<&str> {
+        statuses.iter().map(|status| status.name.as_str()).collect()
+    }
+
+    #[tokio::test]
+    async fn projects_can_have_their_own_workflow() {
+        let db = test_db().await;
+        let (_, Json(project)) = crate::handlers::projects::create_project(
+            State(db.clone()),
+            Json(CreateProject { name: "Launch".to_string(), position: None }),
+        )
+        .await
+        .unwrap();
+
+        let mut conn = db.acquire().await.unwrap();
+        let doing = first_status_in(&mut conn, None, StatusCategory::Doing).await.unwrap();
+        let todo = CreateTodo {
+            title: "Write the post".to_string(),
+            description: None,
+            priority: Priority::Medium,
+            status_id: doing,
+            estimated_minutes: None,
+            custom_fields: Default::default(),
+            project_id: Some(project.id),
+            tags: vec![],
+        };
+        let todo = insert_todo(&mut conn, &todo).await.unwrap();
+        assert_eq!(todo.status_id, doing);
+        drop(conn);
+
+        let request = CreateStatus {
+            name: "Review".to_string(),
+            category: StatusCategory::Doing,
+            position: None,
+            project_id: Some(project.id),
+        };
+        let (_, Json(review)) = create_status(State(db.clone()), State(Events::default()), Json(request)).await.unwrap();
+        assert_eq!(review.project_id, Some(project.id));
+
+        let shared = list_statuses(State(db.clone()), Query(StatusQuery::default())).await.unwrap().0;
+        assert_eq!(names(&shared), ["To Do", "In Progress", "Done"]);
+        let query = StatusQuery { project_id: Some(project.id) };
+        let own = list_statuses(State(db.clone()), Query(query)).await.unwrap().0;
+        assert_eq!(names(&own), ["To Do", "In Progress", "Done", "Review"]);
+        assert!(own.iter().all(|status| status.project_id == Some(project.id)));
+
+        // The todo moved to the project's copy of its status.
+        let mut conn = db.acquire().await.unwrap();
+        let todo = fetch_todo(&mut conn, &todo.id.to_string()).await.unwrap();
+        assert_eq!(todo.status_id, Some(own[1].id));
+
+        // Completing it lands on the project's done status.
+        let done = ReplaceTodo { completed: true, ..ReplaceTodo::from(&todo) };
+        let todo = apply_replacement(&mut conn, &todo, &done, false).await.unwrap();
+        assert_eq!(todo.status_id, Some(own[2].id));
+
+        // Leaving the project swaps it for the shared status of the same category.
+        let moved = ReplaceTodo { project_id: None, ..ReplaceTodo::from(&todo) };
+        let todo = apply_replacement(&mut conn, &todo, &moved, false).await.unwrap();
+        assert_eq!((todo.status_id, todo.completed), (Some(shared[2].id), true));
+    }
+}
@@ -0,0 +1,176 @@
+use crate::{
+    db::DB,
+    errors::AppError,
+    events::{Events, TodoEvent},
+    handlers::todos::{apply_replacement, fetch_todo, insert_todo_with_id, DUMMY_USER_ID, TODO_COLUMNS},
+    models::{
+        SyncChange, SyncChanges, SyncMutation, SyncQuery, SyncRequest, SyncResponse, SyncResult, SyncStatus,
+        Todo,
+    },
+};
+use axum::{
+    extract::{Query, State},
+    Json,
+};
+use sqlx::{Connection, SqliteConnection};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+#[derive(sqlx::FromRow)]
+struct ChangeRow {
+    seq: i64,
+    todo_id: String,
+    deleted: bool,
+}
+
+async fn latest_seq(conn: &mut SqliteConnection) -> Result<i64, AppError> {
+    Ok(sqlx::query_scalar("SELECT COALESCE(MAX(seq), 0) FROM changes WHERE user_id = $1")
+        .bind(DUMMY_USER_ID)
+        .fetch_one(&mut *conn)
+        .await?)
+}
+
+/// Lists the todos changed after `since` in the order of their latest change, with tombstones
+/// for deleted ones. A todo changed several times is listed once.
+pub async fn pull_changes(
+    State(db): State<DB>,
+    Query(query): Query<SyncQuery>,
+) -> Result<Json<SyncChanges>, AppError> {
+    let limit = query.limit.clamp(1, 1000);
+    // Both reads have to see the same state of the database.
+    let mut tx = db.begin().await?;
+    let mut rows = sqlx::query_as::<_, ChangeRow>(
+        "SELECT seq, todo_id, deleted FROM changes WHERE user_id = $1 AND seq > $2 ORDER BY seq LIMIT $3",
+    )
+    .bind(DUMMY_USER_ID)
+    .bind(query.since)
+    .bind(limit + 1)
+    .fetch_all(&mut *tx)
+    .await?;
+    let has_more = rows.len() as i64 > limit;
+    rows.truncate(limit as usize);
+    let cursor = rows.last().map_or(query.since, |row| row.seq);
+
+    let mut todos: HashMap<Uuid, Todo> = sqlx::query_as::<_, Todo>(&format!(
+        "SELECT {TODO_COLUMNS} FROM todos WHERE id IN \
+        (SELECT todo_id FROM changes WHERE user_id = $1 AND seq > $2 AND seq <= $3 AND NOT deleted)"
+    ))
+    .bind(DUMMY_USER_ID)
+    .bind(query.since)
+    .bind(cursor)
+    .fetch_all(&mut *tx)
+    .await?
+    .into_iter()
+    .map(|todo| (todo.id, todo))
+    .collect();
+
+    let changes = rows
+        .into_iter()
+        .map(|row| {
+            let id = Uuid::parse_str(&row.todo_id).map_err(|_| AppError::InternalServerError)?;
+            let todo = if row.deleted { None } else { todos.remove(&id) };
+            Ok(SyncChange { seq: row.seq, id, deleted: row.deleted, todo })
+        })
+        .collect::<Result<_, AppError>>()?;
+    Ok(Json(SyncChanges { changes, cursor, has_more }))
+}
+
+async fn apply(conn: &mut SqliteConnection, mutation: &SyncMutation) -> Result<TodoEvent, AppError> {
+    match mutation {
+        SyncMutation::Create { id, todo } => {
+            let owner: Option<String> = sqlx::query_scalar("SELECT user_id FROM todos WHERE id = $1")
+                .bind(id.to_string())
+                .fetch_optional(&mut *conn)
+                .await?;
+            match owner {
+                // Most likely a retry of a create that went through.
+                Some(owner) if owner == DUMMY_USER_ID => {
+                    Err(AppError::PreconditionFailed(Box::new(fetch_todo(conn, &id.to_string()).await?)))
+                }
+                Some(_) => Err(AppError::BadRequest("id is already in use".to_string())),
+                None => Ok(TodoEvent::Created { todo: insert_todo_with_id(conn, *id, todo).await? }),
+            }
+        }
+        SyncMutation::Update { id, version, todo } => {
+            let before = fetch_todo(conn, &id.to_string()).await?;
+            if before.version != *version {
+                return Err(AppError::PreconditionFailed(Box::new(before)));
+            }
+            Ok(TodoEvent::Updated { todo: apply_replacement(conn, &before, todo, false).await? })
+        }
+        SyncMutation::Delete { id, version } => {
+            let before = fetch_todo(conn, &id.to_string()).await?;
+            if before.version != *version {
+                return Err(AppError::PreconditionFailed(Box::new(before)));
+            }
+            sqlx::query("DELETE FROM todos WHERE id = $1 AND user_id = $2")
+                .bind(id.to_string())
+                .bind(DUMMY_USER_ID)
+                .execute(&mut *conn)
+                .await?;
+            Ok(TodoEvent::Deleted { id: *id })
+        }
+    }
+}
+
+// Applies a mutation inside a savepoint, so a rejected one is rolled back without the rest.
+async fn apply_mutation(
+    conn: &mut SqliteConnection,
+    mutation: &SyncMutation,
+) -> Result<(SyncResult, Option<TodoEvent>), AppError> {
+    let id = mutation.id();
+    let mut savepoint = conn.begin().await?;
+    let error = match apply(&mut savepoint, mutation).await {
+        Ok(event) => {
+            savepoint.commit().await?;
+            let todo = match &event {
+                TodoEvent::Created { todo } | TodoEvent::Updated { todo } => Some(todo.clone()),
+                _ => None,
+            };
+            return Ok((SyncResult { id, status: SyncStatus::Applied, todo, error: None }, Some(event)));
+        }
+        Err(e) => {
+            savepoint.rollback().await?;
+            e
+        }
+    };
+
+    let (status, error) = match error {
+        AppError::PreconditionFailed(_) => (SyncStatus::Conflict, None),
+        AppError::NotFound => (SyncStatus::NotFound, None),
+        AppError::Blocked(_) => (SyncStatus::Blocked, None),
+        AppError::BadRequest(message) => (SyncStatus::Invalid, Some(message)),
+        e => return Err(e),
+    };
+    let todo = match fetch_todo(conn, &id.to_string()).await {
+        Ok(todo) => Some(todo),
+        Err(AppError::NotFound) => None,
+        Err(e) => return Err(e),
+    };
+    Ok((SyncResult { id, status, todo, error }, None))
+}
+
+/// Applies writes a client made offline, in order. Each one succeeds or fails on its own, and a
+/// stale one is reported as a conflict along with the server's copy of the todo.
+pub async fn push_changes(
+    State(db): State<DB>,
+    State(events): State<Events>,
+    Json(request): Json<SyncRequest>,
+) -> Result<Json<SyncResponse>, AppError> {
+    let mut tx = db.begin().await?;
+    let start = latest_seq(&mut tx).await?;
+
+    let mut results = Vec::with_capacity(request.mutations.len());
+    let mut changes = vec![];
+    for mutation in &request.mutations {
+        let (result, event) = apply_mutation(&mut tx, mutation).await?;
+        results.push(result);
+        changes.extend(event);
+    }
+
+    // Moving the cursor past this batch would skip changes from other clients the caller has
+    // not pulled yet.
+    let cursor = if request.cursor >= start { latest_seq(&mut tx).await? } else { request.cursor };
+    events.commit(tx, DUMMY_USER_ID, changes).await?;
+    Ok(Json(SyncResponse { results, cursor }))
+}
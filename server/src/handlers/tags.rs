@@ -0,0 +1,49 @@
+use crate::{
+    db::DB,
+    errors::AppError,
+    handlers::todos::DUMMY_USER_ID,
+    models::TagCount,
+};
+use axum::{extract::State, Json};
+use sqlx::SqliteConnection;
+
+/// Tidies tags as written by a client or read from a file: surrounding whitespace and a leading
+/// `#` are dropped, empty tags are skipped, and the rest are sorted without duplicates.
+pub(crate) fn normalize_tags<'a>(tags: impl IntoIterator<Item = &'a String>) -> Vec<String> {
+    let mut tags: Vec<String> = tags
+        .into_iter()
+        .map(|tag| tag.trim().trim_start_matches('#').trim().to_string())
+        .filter(|tag| !tag.is_empty())
+        .collect();
+    tags.sort();
+    tags.dedup();
+    tags
+}
+
+/// Replaces every tag stored for a todo.
+pub(crate) async fn save_tags(conn: &mut SqliteConnection, todo_id: &str, tags: &[String]) -> Result<(), AppError> {
+    sqlx::query("DELETE FROM todo_tags WHERE todo_id = $1")
+        .bind(todo_id)
+        .execute(&mut *conn)
+        .await?;
+    for tag in normalize_tags(tags) {
+        sqlx::query("INSERT INTO todo_tags (todo_id, tag) VALUES ($1, $2)")
+            .bind(todo_id)
+            .bind(tag)
+            .execute(&mut *conn)
+            .await?;
+    }
+    Ok(())
+}
+
+/// Every tag in use, most used first.
+pub async fn list_tags(State(db): State<DB>) -> Result<Json<Vec<TagCount>>, AppError> {
+    let tags = sqlx::query_as::<_, TagCount>(
+        "SELECT t.tag, COUNT(*) AS count FROM todo_tags t JOIN todos ON todos.id = t.todo_id \
+        WHERE todos.user_id = $1 AND todos.archived_at IS NULL GROUP BY t.tag ORDER BY count DESC, t.tag",
+    )
+    .bind(DUMMY_USER_ID)
+    .fetch_all(&db)
+    .await?;
+    Ok(Json(tags))
+}
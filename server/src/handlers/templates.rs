@@ -0,0 +1,252 @@
+use crate::{
+    db::DB,
+    errors::AppError,
+    events::{Events, TodoEvent},
+    handlers::{
+        projects::fetch_project,
+        todos::{etag, fetch_todo, insert_todo, DUMMY_USER_ID},
+    },
+    models::{CreateTemplate, CreateTodo, InstantiateTemplate, Template, Todo},
+};
+use axum::{
+    extract::{Path, State},
+    http::{header, HeaderName, StatusCode},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use sqlx::SqliteConnection;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+const TEMPLATE_COLUMNS: &str = "id, user_id, name, body, created_at";
+
+#[derive(sqlx::FromRow)]
+struct TemplateRow {
+    id: String,
+    user_id: String,
+    name: String,
+    body: String,
+    created_at: DateTime<Utc>,
+}
+
+impl TryFrom<TemplateRow> for Template {
+    type Error = AppError;
+
+    fn try_from(row: TemplateRow) -> Result<Self, Self::Error> {
+        let parse_id = |s: &str| Uuid::parse_str(s).map_err(|_| AppError::InternalServerError);
+        Ok(Template {
+            id: parse_id(&row.id)?,
+            user_id: parse_id(&row.user_id)?,
+            name: row.name,
+            todo: serde_json::from_str(&row.body).map_err(|_| AppError::InternalServerError)?,
+            created_at: row.created_at,
+        })
+    }
+}
+
+async fn fetch_template(conn: &mut SqliteConnection, id: Uuid) -> Result<Template, AppError> {
+    sqlx::query_as::<_, TemplateRow>(&format!(
+        "SELECT {TEMPLATE_COLUMNS} FROM templates WHERE id = $1 AND user_id = $2"
+    ))
+    .bind(id.to_string())
+    .bind(DUMMY_USER_ID)
+    .fetch_optional(&mut *conn)
+    .await?
+    .ok_or(AppError::NotFound)?
+    .try_into()
+}
+
+fn substitute(text: &str, variables: &HashMap<String, String>) -> String {
+    variables.iter().fold(text.to_string(), |text, (key, value)| {
+        text.replace(&format!("{{{{{}}}}}", key), value)
+    })
+}
+
+/// The todo a template would create, with its placeholders filled in. Unknown placeholders are
+/// left as they are so they stay visible to whoever reviews the result.
+fn render(template: &Template, request: InstantiateTemplate) -> CreateTodo {
+    let mut variables = request.variables;
+    variables
+        .entry("date".to_string())
+        .or_insert_with(|| Utc::now().date_naive().to_string());
+    variables
+        .entry("name".to_string())
+        .or_insert_with(|| template.name.clone());
+
+    let mut todo = template.todo.clone();
+    todo.title = substitute(&todo.title, &variables);
+    todo.description = todo.description.map(|text| substitute(&text, &variables));
+    for value in todo.custom_fields.0.values_mut() {
+        if let Value::String(text) = value {
+            *text = substitute(text, &variables);
+        }
+    }
+    for tag in &mut todo.tags {
+        *tag = substitute(tag, &variables);
+    }
+    todo
+}
+
+/// Renders a template for the user's current projects: a todo whose project has since been
+/// deleted is created without one.
+async fn render_for(
+    conn: &mut SqliteConnection,
+    template: &Template,
+    request: InstantiateTemplate,
+) -> Result<CreateTodo, AppError> {
+    let mut todo = render(template, request);
+    if let Some(project_id) = todo.project_id {
+        match fetch_project(conn, project_id).await {
+            Ok(_) => {}
+            Err(AppError::NotFound) => todo.project_id = None,
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(todo)
+}
+
+pub(crate) async fn all_templates(conn: &mut SqliteConnection) -> Result<Vec<Template>, AppError> {
+    let rows = sqlx::query_as::<_, TemplateRow>(&format!(
+        "SELECT {TEMPLATE_COLUMNS} FROM templates WHERE user_id = $1 ORDER BY name"
+    ))
+    .bind(DUMMY_USER_ID)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    rows.into_iter().map(Template::try_from).collect()
+}
+
+pub async fn list_templates(State(db): State<DB>) -> Result<Json<Vec<Template>>, AppError> {
+    let mut conn = db.acquire().await?;
+    Ok(Json(all_templates(&mut conn).await?))
+}
+
+/// Saves an existing todo's editable fields as a template, including its project and tags. Its
+/// status is not kept, since the status may be gone by the time the template is used.
+pub async fn create_template_from_todo(
+    State(db): State<DB>,
+    Path(todo_id): Path<Uuid>,
+    Json(payload): Json<CreateTemplate>,
+) -> Result<(StatusCode, Json<Template>), AppError> {
+    let mut tx = db.begin().await?;
+    let source: Todo = fetch_todo(&mut tx, &todo_id.to_string()).await?;
+    let body = CreateTodo {
+        title: source.title,
+        description: source.description,
+        priority: source.priority,
+        status_id: None,
+        estimated_minutes: source.estimated_minutes,
+        custom_fields: source.custom_fields,
+        project_id: source.project_id,
+        tags: source.tags,
+    };
+
+    let id = Uuid::new_v4();
+    sqlx::query(
+        "INSERT INTO templates (id, user_id, name, body, created_at) VALUES ($1, $2, $3, $4, $5)",
+    )
+    .bind(id.to_string())
+    .bind(DUMMY_USER_ID)
+    .bind(&payload.name)
+    .bind(serde_json::to_string(&body).map_err(|_| AppError::InternalServerError)?)
+    .bind(Utc::now())
+    .execute(&mut *tx)
+    .await?;
+
+    let template = fetch_template(&mut tx, id).await?;
+    tx.commit().await?;
+    Ok((StatusCode::CREATED, Json(template)))
+}
+
+/// Returns the todo a template would create without saving it, so a form can be prefilled.
+pub async fn render_template(
+    State(db): State<DB>,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<InstantiateTemplate>,
+) -> Result<Json<CreateTodo>, AppError> {
+    let mut conn = db.acquire().await?;
+    let template = fetch_template(&mut conn, id).await?;
+    Ok(Json(render_for(&mut conn, &template, payload).await?))
+}
+
+pub async fn instantiate_template(
+    State(db): State<DB>,
+    State(events): State<Events>,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<InstantiateTemplate>,
+) -> Result<(StatusCode, [(HeaderName, String); 1], Json<Todo>), AppError> {
+    let mut tx = db.begin().await?;
+    let template = fetch_template(&mut tx, id).await?;
+    let todo = render_for(&mut tx, &template, payload).await?;
+    let todo = insert_todo(&mut tx, &todo).await?;
+    events.commit(tx, DUMMY_USER_ID, vec![TodoEvent::Created { todo: todo.clone() }]).await?;
+
+    Ok((StatusCode::CREATED, [(header::ETAG, etag(todo.version))], Json(todo)))
+}
+
+pub async fn delete_template(
+    State(db): State<DB>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, AppError> {
+    let rows_affected = sqlx::query("DELETE FROM templates WHERE id = $1 AND user_id = $2")
+        .bind(id.to_string())
+        .bind(DUMMY_USER_ID)
+        .execute(&db)
+        .await?
+        .rows_affected();
+    if rows_affected == 0 {
+        return Err(AppError::NotFound);
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        db::test_db,
+        handlers::projects::{create_project, delete_project},
+        models::{CreateProject, Priority},
+    };
+
+    #[tokio::test]
+    async fn templates_keep_project_and_tags() {
+        let db = test_db().await;
+        let (_, Json(project)) = create_project(
+            State(db.clone()),
+            Json(CreateProject { name: "Onboarding".to_string(), position: None }),
+        )
+        .await
+        .unwrap();
+        let source = CreateTodo {
+            title: "Welcome {{name}}".to_string(),
+            description: None,
+            priority: Priority::High,
+            status_id: None,
+            estimated_minutes: None,
+            custom_fields: Default::default(),
+            project_id: Some(project.id),
+            tags: vec!["hire-{{name}}".to_string(), "people".to_string()],
+        };
+        let mut conn = db.acquire().await.unwrap();
+        let source = insert_todo(&mut conn, &source).await.unwrap();
+        drop(conn);
+
+        let request = CreateTemplate { name: "Onboard".to_string() };
+        let (_, Json(template)) = create_template_from_todo(State(db.clone()), Path(source.id), Json(request)).await.unwrap();
+        assert_eq!((template.todo.project_id, template.todo.tags.len()), (Some(project.id), 2));
+
+        let variables = || InstantiateTemplate { variables: HashMap::from([("name".to_string(), "ada".to_string())]) };
+        let (_, _, Json(todo)) = instantiate_template(State(db.clone()), State(Events::default()), Path(template.id), Json(variables()))
+            .await
+            .unwrap();
+        assert_eq!(todo.title, "Welcome ada");
+        assert_eq!(todo.project_id, Some(project.id));
+        assert_eq!(todo.tags, ["hire-ada", "people"]);
+
+        delete_project(State(db.clone()), State(Events::default()), Path(project.id)).await.unwrap();
+        let Json(rendered) = render_template(State(db.clone()), Path(template.id), Json(variables())).await.unwrap();
+        assert_eq!(rendered.project_id, None);
+    }
+}
@@ -0,0 +1,560 @@
+use crate::{
+    db::DB,
+    errors::AppError,
+    events::{Events, TodoEvent},
+    handlers::{
+        calendar::{due_date, due_field},
+        custom_fields::{all_fields, parse_field_text},
+        projects::{all_projects, project_named},
+        todos::{insert_imported_todo, insert_nested_imports, ImportedTodo, NestedImport, DUMMY_USER_ID, TODO_COLUMNS},
+    },
+    markdown,
+    models::{
+        CreateTodo, CustomField, DryRunQuery, FieldValues, ImportRowError, MarkdownExportQuery,
+        MarkdownGroup, Priority, TextImportReport, Todo,
+    },
+    quick_add, todo_txt,
+};
+use axum::{
+    extract::{Query, State},
+    http::header,
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
+use serde_json::Value;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+fn todo_txt_priority(priority: &Priority) -> char {
+    match priority {
+        Priority::High => 'A',
+        Priority::Medium => 'B',
+        Priority::Low => 'C',
+    }
+}
+
+/// `(A)` is high and `(B)` medium; `(C)` and anything lower is low. No priority reads as medium,
+/// the default for new todos.
+fn priority_from_todo_txt(priority: Option<char>) -> Priority {
+    match priority {
+        Some('A') => Priority::High,
+        Some('B') | None => Priority::Medium,
+        Some(_) => Priority::Low,
+    }
+}
+
+/// A todo.txt line for a todo in `project`. The project and tags are written as `+project` and
+/// `@context` words, and like custom field values only when they fit in one word.
+fn todo_txt_line(todo: &Todo, project: Option<&str>) -> String {
+    let mut text = todo.title.clone();
+    let one_word = |word: &&str| !word.is_empty() && !word.contains(char::is_whitespace);
+    if let Some(project) = project.filter(one_word) {
+        text.push_str(&format!(" +{}", project));
+    }
+    for tag in todo.tags.iter().map(String::as_str).filter(one_word) {
+        text.push_str(&format!(" @{}", tag));
+    }
+    // Custom fields become `key:value` extensions, when the value fits in one word.
+    for (key, value) in &todo.custom_fields.0 {
+        let value = match value {
+            Value::String(s) => s.clone(),
+            Value::Null => continue,
+            other => other.to_string(),
+        };
+        if !value.is_empty() && !value.contains(char::is_whitespace) {
+            text.push_str(&format!(" {}:{}", key, value));
+        }
+    }
+
+    todo_txt::format(&todo_txt::Line {
+        completed: todo.completed,
+        priority: Some(todo_txt_priority(&todo.priority)),
+        completion_date: todo.completed_at.map(|at| at.date_naive()),
+        creation_date: Some(todo.created_at.date_naive()),
+        text,
+    })
+}
+
+/// The user's todos in todo.txt format, one per line in list order. Archived todos are left out.
+pub async fn export_todo_txt(State(db): State<DB>) -> Result<Response, AppError> {
+    let mut conn = db.acquire().await?;
+    let todos = sqlx::query_as::<_, Todo>(&format!(
+        "SELECT {TODO_COLUMNS} FROM todos WHERE user_id = $1 AND archived_at IS NULL ORDER BY position"
+    ))
+    .bind(DUMMY_USER_ID)
+    .fetch_all(&mut *conn)
+    .await?;
+    let projects: HashMap<_, _> =
+        all_projects(&mut conn).await?.into_iter().map(|project| (project.id, project.name)).collect();
+
+    let mut out = String::new();
+    for todo in &todos {
+        let project = todo.project_id.and_then(|id| projects.get(&id)).map(String::as_str);
+        out.push_str(&todo_txt_line(todo, project));
+        out.push('\n');
+    }
+    Ok(([(header::CONTENT_TYPE, "text/plain; charset=utf-8")], out).into_response())
+}
+
+fn midnight_utc(date: NaiveDate) -> DateTime<Utc> {
+    date.and_time(NaiveTime::MIN).and_utc()
+}
+
+/// Extensions whose key names a custom field fill that field and `pri:` is read by the parser.
+/// Contexts become tags, and the first project is returned for the caller to look up, since a
+/// todo is in at most one; everything else, later projects included, stays in the title.
+fn imported_from_line(line: todo_txt::Line, fields: &[CustomField]) -> Result<(ImportedTodo, Option<String>), String> {
+    let mut custom_fields = FieldValues::default();
+    let mut project = None;
+    let mut tags = vec![];
+    let mut words = vec![];
+    for word in line.text.split_whitespace() {
+        if let Some(name) = todo_txt::project(word).filter(|_| project.is_none()) {
+            project = Some(name.to_string());
+            continue;
+        }
+        if let Some(context) = todo_txt::context(word) {
+            tags.push(context.to_string());
+            continue;
+        }
+        if let Some((key, value)) = todo_txt::extension(word) {
+            if let Some(field) = fields.iter().find(|field| field.key == key) {
+                let value = parse_field_text(field, value).map_err(|e| format!("{}: {}", field.key, e))?;
+                custom_fields.0.insert(field.key.clone(), value);
+                continue;
+            }
+            if key == "pri" {
+                continue;
+            }
+        }
+        words.push(word);
+    }
+
+    let title = words.join(" ");
+    if title.is_empty() {
+        return Err("the task has no text".to_string());
+    }
+    let todo = ImportedTodo {
+        todo: CreateTodo {
+            title,
+            description: None,
+            priority: priority_from_todo_txt(line.priority),
+            status_id: None,
+            estimated_minutes: None,
+            custom_fields,
+            project_id: None,
+            tags,
+        },
+        completed: line.completed,
+        created_at: line.creation_date.map(midnight_utc),
+        completed_at: line.completion_date.map(midnight_utc),
+    };
+    Ok((todo, project))
+}
+
+/// Imports a todo.txt file sent as the request body. Lines that cannot be imported are reported
+/// by line number and the rest are still imported.
+pub async fn import_todo_txt(
+    State(db): State<DB>,
+    State(events): State<Events>,
+    Query(options): Query<DryRunQuery>,
+    body: String,
+) -> Result<Json<TextImportReport>, AppError> {
+    let mut tx = db.begin().await?;
+    let fields = all_fields(&mut tx).await?;
+
+    let mut imported = 0;
+    let mut errors = vec![];
+    for (index, text) in body.lines().enumerate() {
+        let Some(line) = todo_txt::parse(text) else { continue };
+        let result = match imported_from_line(line, &fields) {
+            Ok((mut todo, project)) => {
+                if let Some(project) = project {
+                    todo.todo.project_id = Some(project_named(&mut tx, &project).await?);
+                }
+                insert_imported_todo(&mut tx, &todo).await.map(|_| ())
+            }
+            Err(message) => Err(AppError::BadRequest(message)),
+        };
+        match result {
+            Ok(()) => imported += 1,
+            Err(AppError::BadRequest(message)) => errors.push(ImportRowError { line: index + 1, message }),
+            Err(e) => return Err(e),
+        }
+    }
+
+    if options.dry_run {
+        tx.rollback().await?;
+    } else {
+        events.commit(tx, DUMMY_USER_ID, vec![TodoEvent::Reload]).await?;
+    }
+    Ok(Json(TextImportReport { dry_run: options.dry_run, imported, errors, unmapped: vec![] }))
+}
+
+fn priority_heading(priority: &Priority) -> &'static str {
+    match priority {
+        Priority::High => "High",
+        Priority::Medium => "Medium",
+        Priority::Low => "Low",
+    }
+}
+
+/// Heading of the todos without a project when grouping by project.
+const NO_PROJECT: &str = "No project";
+
+fn priority_marker(priority: &Priority) -> &'static str {
+    match priority {
+        Priority::High => "!high",
+        Priority::Medium => "!medium",
+        Priority::Low => "!low",
+    }
+}
+
+/// Writes a todo and the todos nested under it. A todo whose priority differs from the group
+/// `heading` gets a quick-add priority marker, as does one whose title already ends in something
+/// that reads as a marker.
+fn write_markdown_tree(
+    out: &mut String,
+    todos: &[Todo],
+    children: &[Vec<usize>],
+    index: usize,
+    depth: usize,
+    heading: Option<&Priority>,
+) {
+    let todo = &todos[index];
+    let mut text = todo.title.clone();
+    let ends_in_marker = todo.title.rsplit(' ').next().and_then(quick_add::priority_marker).is_some();
+    if heading != Some(&todo.priority) || ends_in_marker {
+        text.push(' ');
+        text.push_str(priority_marker(&todo.priority));
+    }
+    if let Some(due) = due_date(todo) {
+        text.push_str(&format!(" (due {})", due));
+    }
+    markdown::write_item(out, depth, todo.completed, &text, todo.description.as_deref());
+    for &child in &children[index] {
+        write_markdown_tree(out, todos, children, child, depth + 1, heading);
+    }
+}
+
+/// The user's todos as GitHub task lists, under a heading per priority, or per project with
+/// `group=project`, unless `group=none`. Archived todos are left out. Todos have no subtasks, so a
+/// todo is nested under the first todo it blocks instead, and due dates come from the `due` date
+/// field.
+pub async fn export_markdown(
+    State(db): State<DB>,
+    Query(query): Query<MarkdownExportQuery>,
+) -> Result<Response, AppError> {
+    let mut conn = db.acquire().await?;
+    let todos = sqlx::query_as::<_, Todo>(&format!(
+        "SELECT {TODO_COLUMNS} FROM todos WHERE user_id = $1 AND archived_at IS NULL ORDER BY position"
+    ))
+    .bind(DUMMY_USER_ID)
+    .fetch_all(&mut *conn)
+    .await?;
+    let dependencies = sqlx::query_as::<_, (String, String)>(
+        "SELECT d.blocker_id, d.blocked_id FROM todo_dependencies d JOIN todos t ON t.id = d.blocked_id WHERE t.user_id = $1",
+    )
+    .bind(DUMMY_USER_ID)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let index: HashMap<String, usize> = todos.iter().enumerate().map(|(i, todo)| (todo.id.to_string(), i)).collect();
+    let mut parents: Vec<Option<usize>> = vec![None; todos.len()];
+    for (blocker, blocked) in &dependencies {
+        if let (Some(&child), Some(&parent)) = (index.get(blocker), index.get(blocked)) {
+            let current = &mut parents[child];
+            *current = Some(current.map_or(parent, |current| current.min(parent)));
+        }
+    }
+    let mut children = vec![vec![]; todos.len()];
+    for (child, parent) in parents.iter().enumerate() {
+        if let Some(parent) = parent {
+            children[*parent].push(child);
+        }
+    }
+    let roots: Vec<usize> = (0..todos.len()).filter(|&i| parents[i].is_none()).collect();
+
+    let mut out = String::new();
+    markdown::write_heading(&mut out, 1, "Todos");
+    match query.group {
+        MarkdownGroup::Priority => {
+            for priority in [Priority::High, Priority::Medium, Priority::Low] {
+                let heading = priority_heading(&priority);
+                let group: Vec<usize> =
+                    roots.iter().copied().filter(|&i| priority_heading(&todos[i].priority) == heading).collect();
+                if group.is_empty() {
+                    continue;
+                }
+                markdown::write_heading(&mut out, 2, heading);
+                for root in group {
+                    write_markdown_tree(&mut out, &todos, &children, root, 0, Some(&priority));
+                }
+            }
+        }
+        MarkdownGroup::Project => {
+            let projects = all_projects(&mut conn).await?;
+            let groups = projects
+                .iter()
+                .map(|project| (Some(project.id), project.name.as_str()))
+                .chain([(None, NO_PROJECT)]);
+            for (project_id, heading) in groups {
+                let group: Vec<usize> = roots.iter().copied().filter(|&i| todos[i].project_id == project_id).collect();
+                if group.is_empty() {
+                    continue;
+                }
+                markdown::write_heading(&mut out, 2, heading);
+                for root in group {
+                    write_markdown_tree(&mut out, &todos, &children, root, 0, None);
+                }
+            }
+        }
+        MarkdownGroup::None => {
+            for root in roots {
+                write_markdown_tree(&mut out, &todos, &children, root, 0, None);
+            }
+        }
+    }
+    Ok(([(header::CONTENT_TYPE, "text/markdown; charset=utf-8")], out).into_response())
+}
+
+/// Reads a task. A trailing `(due YYYY-MM-DD)` fills the `due` date field when there is one, and
+/// a priority marker such as `!high` before it sets the priority. Without a marker, a `High`,
+/// `Medium` or `Low` heading above the task does.
+fn imported_from_item(item: &markdown::Item, due: Option<&CustomField>) -> Result<ImportedTodo, String> {
+    let mut title = item.text.as_str();
+    let mut custom_fields = FieldValues::default();
+    if let Some(field) = due {
+        let date = title
+            .strip_suffix(')')
+            .and_then(|rest| rest.rsplit_once(" (due "))
+            .and_then(|(text, date)| Some((text, NaiveDate::parse_from_str(date, "%Y-%m-%d").ok()?)));
+        if let Some((text, date)) = date {
+            title = text;
+            custom_fields.0.insert(field.key.clone(), Value::String(date.to_string()));
+        }
+    }
+    let marked = title.trim_end().rsplit_once(' ').and_then(|(text, word)| Some((text, quick_add::priority_marker(word)?)));
+    let priority = match marked {
+        Some((text, priority)) => {
+            title = text;
+            priority
+        }
+        None => match item.heading.as_ref().map(|heading| heading.text.to_lowercase()).as_deref() {
+            Some("high") => Priority::High,
+            Some("low") => Priority::Low,
+            _ => Priority::Medium,
+        },
+    };
+    let title = title.trim();
+    if title.is_empty() {
+        return Err("the task has no text".to_string());
+    }
+
+    Ok(ImportedTodo {
+        todo: CreateTodo {
+            title: title.to_string(),
+            description: item.description.clone(),
+            priority,
+            status_id: None,
+            project_id: None,
+            tags: vec![],
+            estimated_minutes: None,
+            custom_fields,
+        },
+        completed: item.completed,
+        created_at: None,
+        completed_at: None,
+    })
+}
+
+/// The project a task's heading names. Top-level headings title the document, and priority
+/// headings and the one for todos without a project name none.
+fn heading_project(item: &markdown::Item) -> Option<&str> {
+    let heading = item.heading.as_ref().filter(|heading| heading.level > 1)?;
+    let is_priority = [Priority::High, Priority::Medium, Priority::Low]
+        .iter()
+        .any(|priority| heading.text.eq_ignore_ascii_case(priority_heading(priority)));
+    (!is_priority && heading.text != NO_PROJECT).then_some(heading.text.as_str())
+}
+
+/// Imports the task lists of a Markdown file sent as the request body. A nested task becomes a
+/// todo that blocks the one it is nested under, the closest thing to a subtask, and a task under a
+/// second-level heading other than a priority joins the project of that name. Tasks that cannot be
+/// imported are reported by line number.
+pub async fn import_markdown(
+    State(db): State<DB>,
+    State(events): State<Events>,
+    Query(options): Query<DryRunQuery>,
+    body: String,
+) -> Result<Json<TextImportReport>, AppError> {
+    let mut tx = db.begin().await?;
+    let fields = all_fields(&mut tx).await?;
+    let due = due_field(&fields);
+
+    // Project ids by heading, so each project is looked up once.
+    let mut projects: HashMap<String, Uuid> = HashMap::new();
+    let mut items = vec![];
+    for item in markdown::parse(&body) {
+        let project_id = match heading_project(&item) {
+            Some(name) => match projects.get(name) {
+                Some(&id) => Some(id),
+                None => {
+                    let id = project_named(&mut tx, name).await?;
+                    projects.insert(name.to_string(), id);
+                    Some(id)
+                }
+            },
+            None => None,
+        };
+        let todo = imported_from_item(&item, due).map(|mut todo| {
+            todo.todo.project_id = project_id;
+            todo
+        });
+        items.push(NestedImport { line: item.line, parent: item.parent, todo });
+    }
+    let (imported, errors) = insert_nested_imports(&mut tx, items).await?;
+
+    if options.dry_run {
+        tx.rollback().await?;
+    } else {
+        events.commit(tx, DUMMY_USER_ID, vec![TodoEvent::Reload]).await?;
+    }
+    Ok(Json(TextImportReport { dry_run: options.dry_run, imported, errors, unmapped: vec![] }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        db::test_db,
+        handlers::{calendar::DUE_FIELD, dependencies::link_blocker, todos::insert_todo},
+        models::MarkdownGroup,
+    };
+    use sqlx::SqliteConnection;
+
+    async fn add_due_field(conn: &mut SqliteConnection) {
+        sqlx::query("INSERT INTO custom_fields (id, user_id, key, name, field_type) VALUES ($1, $2, $3, 'Due', 'date')")
+            .bind(uuid::Uuid::new_v4().to_string())
+            .bind(DUMMY_USER_ID)
+            .bind(DUE_FIELD)
+            .execute(&mut *conn)
+            .await
+            .unwrap();
+    }
+
+    async fn add_todo(
+        conn: &mut SqliteConnection,
+        title: &str,
+        priority: Priority,
+        description: Option<&str>,
+        due: Option<&str>,
+    ) -> String {
+        let mut custom_fields = FieldValues::default();
+        if let Some(due) = due {
+            custom_fields.0.insert(DUE_FIELD.to_string(), Value::String(due.to_string()));
+        }
+        let todo = CreateTodo {
+            title: title.to_string(),
+            description: description.map(str::to_string),
+            project_id: None,
+            tags: vec![],
+            priority,
+            status_id: None,
+            estimated_minutes: None,
+            custom_fields,
+        };
+        insert_todo(conn, &todo).await.unwrap().id.to_string()
+    }
+
+    async fn export(db: &DB, group: MarkdownGroup) -> String {
+        let response = export_markdown(State(db.clone()), Query(MarkdownExportQuery { group })).await.unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        String::from_utf8(body.to_vec()).unwrap()
+    }
+
+    async fn todos(db: &DB) -> Vec<(String, Priority, bool, Option<String>, Option<NaiveDate>)> {
+        let todos = sqlx::query_as::<_, Todo>(&format!("SELECT {TODO_COLUMNS} FROM todos ORDER BY title"))
+            .fetch_all(db)
+            .await
+            .unwrap();
+        todos
+            .iter()
+            .map(|todo| (todo.title.clone(), todo.priority.clone(), todo.completed, todo.description.clone(), due_date(todo)))
+            .collect()
+    }
+
+    async fn project_names(db: &DB) -> Vec<(String, Option<String>)> {
+        sqlx::query_as("SELECT t.title, p.name FROM todos t LEFT JOIN projects p ON p.id = t.project_id ORDER BY t.title")
+            .fetch_all(db)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn markdown_export_reads_back() {
+        let original = test_db().await;
+        let mut conn = original.acquire().await.unwrap();
+        add_due_field(&mut conn).await;
+        let report = add_todo(&mut conn, "Write report", Priority::High, Some("Two pages\nwith charts"), Some("2024-05-03")).await;
+        let numbers = add_todo(&mut conn, "Collect numbers", Priority::Low, None, None).await;
+        let finance = add_todo(&mut conn, "Ask finance", Priority::High, None, Some("2024-05-01")).await;
+        let flights = add_todo(&mut conn, "Book flights", Priority::Medium, None, None).await;
+        let passport = add_todo(&mut conn, "Check passport !", Priority::Medium, None, None).await;
+        add_todo(&mut conn, "Water plants", Priority::Low, Some("Balcony too"), None).await;
+        link_blocker(&mut conn, &numbers, &report).await.unwrap();
+        link_blocker(&mut conn, &finance, &numbers).await.unwrap();
+        link_blocker(&mut conn, &passport, &flights).await.unwrap();
+        sqlx::query("UPDATE todos SET completed = TRUE WHERE id = $1").bind(&flights).execute(&mut *conn).await.unwrap();
+        let trip = project_named(&mut conn, "Trip").await.unwrap();
+        sqlx::query("UPDATE todos SET project_id = $1 WHERE id IN ($2, $3)")
+            .bind(trip.to_string())
+            .bind(&flights)
+            .bind(&passport)
+            .execute(&mut *conn)
+            .await
+            .unwrap();
+        drop(conn);
+
+        for group in [MarkdownGroup::Priority, MarkdownGroup::Project, MarkdownGroup::None] {
+            let markdown = export(&original, group).await;
+            let copy = test_db().await;
+            add_due_field(&mut copy.acquire().await.unwrap()).await;
+            let Json(report) = import_markdown(State(copy.clone()), State(Events::default()), Query(DryRunQuery::default()), markdown.clone())
+                .await
+                .unwrap();
+            assert_eq!((report.imported, report.errors.len()), (6, 0), "{:?}", group);
+
+            assert_eq!(todos(&copy).await, todos(&original).await, "{:?}", group);
+            assert_eq!(export(&copy, group).await, markdown, "{:?}", group);
+            if group == MarkdownGroup::Project {
+                assert_eq!(project_names(&copy).await, project_names(&original).await);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn todo_txt_keeps_projects_and_contexts() {
+        let db = test_db().await;
+        let body = "(A) Call mom +Family @phone @evening\nFix +sink +plumbing\n";
+        let Json(report) = import_todo_txt(State(db.clone()), State(Events::default()), Query(DryRunQuery::default()), body.to_string())
+            .await
+            .unwrap();
+        assert_eq!((report.imported, report.errors.len()), (2, 0));
+
+        let mut conn = db.acquire().await.unwrap();
+        let projects = all_projects(&mut conn).await.unwrap();
+        drop(conn);
+        assert_eq!(projects.iter().map(|project| project.name.as_str()).collect::<Vec<_>>(), ["Family", "sink"]);
+
+        let response = export_todo_txt(State(db.clone())).await.unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let lines: Vec<String> = String::from_utf8(body.to_vec())
+            .unwrap()
+            .lines()
+            .map(|line| line.split_once(' ').unwrap().1.split_once(' ').unwrap().1.to_string())
+            .collect();
+        assert_eq!(lines, ["Call mom +Family @evening @phone", "Fix +plumbing +sink"]);
+    }
+}
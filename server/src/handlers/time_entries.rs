@@ -0,0 +1,395 @@
+use crate::{
+    csv,
+    db::DB,
+    errors::AppError,
+    handlers::todos::{fetch_todo, DUMMY_USER_ID},
+    models::{
+        CreateTimeEntry, DayTotal, ProjectTotal, TagTotal, TimeEntry, TimeReport, TimeReportQuery,
+        TimeReportRow, TodoTotal, UpdateTimeEntry,
+    },
+};
+use axum::{
+    extract::{Path, Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use sqlx::SqliteConnection;
+use std::collections::BTreeMap;
+use uuid::Uuid;
+
+pub(crate) const TIME_ENTRY_COLUMNS: &str = "id, user_id, todo_id, started_at, ended_at, note, created_at";
+
+async fn fetch_entry(conn: &mut SqliteConnection, id: Uuid) -> Result<TimeEntry, AppError> {
+    sqlx::query_as::<_, TimeEntry>(&format!(
+        "SELECT {TIME_ENTRY_COLUMNS} FROM time_entries WHERE id = $1 AND user_id = $2"
+    ))
+    .bind(id.to_string())
+    .bind(DUMMY_USER_ID)
+    .fetch_optional(&mut *conn)
+    .await?
+    .ok_or(AppError::NotFound)
+}
+
+async fn running_entry(conn: &mut SqliteConnection) -> Result<Option<TimeEntry>, AppError> {
+    Ok(sqlx::query_as::<_, TimeEntry>(&format!(
+        "SELECT {TIME_ENTRY_COLUMNS} FROM time_entries WHERE user_id = $1 AND ended_at IS NULL"
+    ))
+    .bind(DUMMY_USER_ID)
+    .fetch_optional(&mut *conn)
+    .await?)
+}
+
+async fn stop_running(conn: &mut SqliteConnection, now: DateTime<Utc>) -> Result<Option<TimeEntry>, AppError> {
+    let Some(running) = running_entry(conn).await? else {
+        return Ok(None);
+    };
+    sqlx::query("UPDATE time_entries SET ended_at = $1 WHERE id = $2")
+        .bind(now)
+        .bind(running.id.to_string())
+        .execute(&mut *conn)
+        .await?;
+    fetch_entry(conn, running.id).await.map(Some)
+}
+
+fn check_range(started_at: DateTime<Utc>, ended_at: Option<DateTime<Utc>>) -> Result<(), AppError> {
+    match ended_at {
+        Some(ended_at) if ended_at < started_at => {
+            Err(AppError::BadRequest("`ended_at` must not be before `started_at`".to_string()))
+        }
+        _ => Ok(()),
+    }
+}
+
+pub async fn current_timer(State(db): State<DB>) -> Result<Json<Option<TimeEntry>>, AppError> {
+    let mut conn = db.acquire().await?;
+    Ok(Json(running_entry(&mut conn).await?))
+}
+
+/// Starts a timer on a todo. A timer already running for the user is stopped first, since only
+/// one may run at a time.
+pub async fn start_timer(
+    State(db): State<DB>,
+    Path(todo_id): Path<Uuid>,
+) -> Result<(StatusCode, Json<TimeEntry>), AppError> {
+    let now = Utc::now();
+    let mut tx = db.begin().await?;
+    fetch_todo(&mut tx, &todo_id.to_string()).await?;
+    stop_running(&mut tx, now).await?;
+
+    let id = Uuid::new_v4();
+    sqlx::query(
+        "INSERT INTO time_entries (id, user_id, todo_id, started_at, created_at) VALUES ($1, $2, $3, $4, $5)",
+    )
+    .bind(id.to_string())
+    .bind(DUMMY_USER_ID)
+    .bind(todo_id.to_string())
+    .bind(now)
+    .bind(now)
+    .execute(&mut *tx)
+    .await?;
+
+    let entry = fetch_entry(&mut tx, id).await?;
+    tx.commit().await?;
+    Ok((StatusCode::CREATED, Json(entry)))
+}
+
+pub async fn stop_timer(State(db): State<DB>) -> Result<Json<TimeEntry>, AppError> {
+    let mut tx = db.begin().await?;
+    let entry = stop_running(&mut tx, Utc::now()).await?.ok_or(AppError::NotFound)?;
+    tx.commit().await?;
+    Ok(Json(entry))
+}
+
+pub async fn todo_time_entries(
+    State(db): State<DB>,
+    Path(todo_id): Path<Uuid>,
+) -> Result<Json<Vec<TimeEntry>>, AppError> {
+    let mut conn = db.acquire().await?;
+    fetch_todo(&mut conn, &todo_id.to_string()).await?;
+
+    let entries = sqlx::query_as::<_, TimeEntry>(&format!(
+        "SELECT {TIME_ENTRY_COLUMNS} FROM time_entries WHERE todo_id = $1 AND user_id = $2 ORDER BY started_at DESC"
+    ))
+    .bind(todo_id.to_string())
+    .bind(DUMMY_USER_ID)
+    .fetch_all(&mut *conn)
+    .await?;
+    Ok(Json(entries))
+}
+
+pub async fn create_time_entry(
+    State(db): State<DB>,
+    Path(todo_id): Path<Uuid>,
+    Json(payload): Json<CreateTimeEntry>,
+) -> Result<(StatusCode, Json<TimeEntry>), AppError> {
+    check_range(payload.started_at, Some(payload.ended_at))?;
+    let mut tx = db.begin().await?;
+    fetch_todo(&mut tx, &todo_id.to_string()).await?;
+
+    let id = Uuid::new_v4();
+    sqlx::query(
+        "INSERT INTO time_entries (id, user_id, todo_id, started_at, ended_at, note, created_at) VALUES ($1, $2, $3, $4, $5, $6, $7)",
+    )
+    .bind(id.to_string())
+    .bind(DUMMY_USER_ID)
+    .bind(todo_id.to_string())
+    .bind(payload.started_at)
+    .bind(payload.ended_at)
+    .bind(&payload.note)
+    .bind(Utc::now())
+    .execute(&mut *tx)
+    .await?;
+
+    let entry = fetch_entry(&mut tx, id).await?;
+    tx.commit().await?;
+    Ok((StatusCode::CREATED, Json(entry)))
+}
+
+pub async fn update_time_entry(
+    State(db): State<DB>,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<UpdateTimeEntry>,
+) -> Result<Json<TimeEntry>, AppError> {
+    let mut tx = db.begin().await?;
+    let entry = fetch_entry(&mut tx, id).await?;
+
+    let started_at = payload.started_at.unwrap_or(entry.started_at);
+    let ended_at = payload.ended_at.or(entry.ended_at);
+    let note = payload.note.or(entry.note);
+    check_range(started_at, ended_at)?;
+
+    sqlx::query("UPDATE time_entries SET started_at = $1, ended_at = $2, note = $3 WHERE id = $4")
+        .bind(started_at)
+        .bind(ended_at)
+        .bind(&note)
+        .bind(id.to_string())
+        .execute(&mut *tx)
+        .await?;
+
+    let entry = fetch_entry(&mut tx, id).await?;
+    tx.commit().await?;
+    Ok(Json(entry))
+}
+
+pub async fn delete_time_entry(
+    State(db): State<DB>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, AppError> {
+    let rows_affected = sqlx::query("DELETE FROM time_entries WHERE id = $1 AND user_id = $2")
+        .bind(id.to_string())
+        .bind(DUMMY_USER_ID)
+        .execute(&db)
+        .await?
+        .rows_affected();
+
+    if rows_affected == 0 {
+        Err(AppError::NotFound)
+    } else {
+        Ok(StatusCode::NO_CONTENT)
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct ReportEntryRow {
+    todo_id: String,
+    title: String,
+    project_id: Option<String>,
+    project: Option<String>,
+    /// JSON array.
+    tags: String,
+    started_at: DateTime<Utc>,
+    ended_at: Option<DateTime<Utc>>,
+}
+
+// Splits `[start, end)` at UTC midnights so time is credited to the day it was spent.
+fn split_by_day(mut start: DateTime<Utc>, end: DateTime<Utc>) -> Vec<(NaiveDate, i64)> {
+    let mut parts = Vec::new();
+    while start < end {
+        let next_midnight = (start.date_naive() + Duration::days(1))
+            .and_hms_opt(0, 0, 0)
+            .expect("midnight is a valid time")
+            .and_utc();
+        let part_end = end.min(next_midnight);
+        parts.push((start.date_naive(), (part_end - start).num_seconds()));
+        start = part_end;
+    }
+    parts
+}
+
+fn report_csv(report: &TimeReport) -> String {
+    let mut out = String::new();
+    csv::write_record(&mut out, &["day", "todo_id", "title", "project", "tags", "seconds", "hours"]);
+    for row in &report.rows {
+        csv::write_record(
+            &mut out,
+            &[
+                row.day.to_string(),
+                row.todo_id.to_string(),
+                row.title.clone(),
+                row.project.clone().unwrap_or_default(),
+                row.tags.join(", "),
+                row.seconds.to_string(),
+                format!("{:.2}", row.seconds as f64 / 3600.0),
+            ],
+        );
+    }
+    out
+}
+
+/// Tracked time between `from` and `to`, aggregated by day, todo, project and tag. Running timers
+/// count up to now. `?format=csv` returns the per-day, per-todo rows as CSV.
+pub async fn time_report(
+    State(db): State<DB>,
+    Query(query): Query<TimeReportQuery>,
+) -> Result<Response, AppError> {
+    let now = Utc::now();
+    let to = query.to.unwrap_or(now);
+    let from = query.from.unwrap_or(to - Duration::days(30));
+    if from > to {
+        return Err(AppError::BadRequest("`from` must not be after `to`".to_string()));
+    }
+
+    let entries = sqlx::query_as::<_, ReportEntryRow>(
+        "SELECT e.todo_id, t.title, t.project_id, p.name AS project, \
+         (SELECT json_group_array(tag) FROM (SELECT tag FROM todo_tags WHERE todo_id = t.id ORDER BY tag)) AS tags, \
+         e.started_at, e.ended_at FROM time_entries e JOIN todos t ON t.id = e.todo_id LEFT JOIN projects p ON p.id = t.project_id \
+         WHERE e.user_id = $1 AND e.started_at < $2 AND (e.ended_at IS NULL OR e.ended_at > $3)",
+    )
+    .bind(DUMMY_USER_ID)
+    .bind(to)
+    .bind(from)
+    .fetch_all(&db)
+    .await?;
+
+    let mut cells: BTreeMap<(NaiveDate, Uuid), TimeReportRow> = BTreeMap::new();
+    for entry in entries {
+        let parse_id = |id: &str| Uuid::parse_str(id).map_err(|_| AppError::InternalServerError);
+        let todo_id = parse_id(&entry.todo_id)?;
+        let project_id = entry.project_id.as_deref().map(parse_id).transpose()?;
+        let tags: Vec<String> = serde_json::from_str(&entry.tags).map_err(|_| AppError::InternalServerError)?;
+        let start = entry.started_at.max(from);
+        let end = entry.ended_at.unwrap_or(now).min(to);
+        for (day, seconds) in split_by_day(start, end) {
+            cells
+                .entry((day, todo_id))
+                .or_insert_with(|| TimeReportRow {
+                    day,
+                    todo_id,
+                    title: entry.title.clone(),
+                    project_id,
+                    project: entry.project.clone(),
+                    tags: tags.clone(),
+                    seconds: 0,
+                })
+                .seconds += seconds;
+        }
+    }
+    let rows: Vec<TimeReportRow> = cells.into_values().collect();
+
+    let mut by_day: BTreeMap<NaiveDate, i64> = BTreeMap::new();
+    let mut by_todo: BTreeMap<Uuid, TodoTotal> = BTreeMap::new();
+    let mut by_project: BTreeMap<Option<Uuid>, ProjectTotal> = BTreeMap::new();
+    let mut by_tag: BTreeMap<&str, i64> = BTreeMap::new();
+    for row in &rows {
+        *by_day.entry(row.day).or_default() += row.seconds;
+        by_todo
+            .entry(row.todo_id)
+            .or_insert_with(|| TodoTotal { todo_id: row.todo_id, title: row.title.clone(), seconds: 0 })
+            .seconds += row.seconds;
+        by_project
+            .entry(row.project_id)
+            .or_insert_with(|| ProjectTotal { project_id: row.project_id, name: row.project.clone(), seconds: 0 })
+            .seconds += row.seconds;
+        for tag in &row.tags {
+            *by_tag.entry(tag).or_default() += row.seconds;
+        }
+    }
+
+    let mut by_todo: Vec<TodoTotal> = by_todo.into_values().collect();
+    by_todo.sort_by_key(|entry| std::cmp::Reverse(entry.seconds));
+    let mut by_project: Vec<ProjectTotal> = by_project.into_values().collect();
+    by_project.sort_by_key(|entry| std::cmp::Reverse(entry.seconds));
+    let mut by_tag: Vec<TagTotal> =
+        by_tag.into_iter().map(|(tag, seconds)| TagTotal { tag: tag.to_string(), seconds }).collect();
+    by_tag.sort_by_key(|entry| std::cmp::Reverse(entry.seconds));
+
+    let report = TimeReport {
+        total_seconds: rows.iter().map(|r| r.seconds).sum(),
+        by_day: by_day.into_iter().map(|(day, seconds)| DayTotal { day, seconds }).collect(),
+        by_todo,
+        by_project,
+        by_tag,
+        rows,
+    };
+
+    if query.format.as_deref() == Some("csv") {
+        Ok((
+            [
+                (header::CONTENT_TYPE, "text/csv; charset=utf-8"),
+                (header::CONTENT_DISPOSITION, "attachment; filename=\"time-report.csv\""),
+            ],
+            report_csv(&report),
+        )
+            .into_response())
+    } else {
+        Ok(Json(report).into_response())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        db::test_db,
+        handlers::{projects::project_named, todos::insert_todo},
+        models::{CreateTodo, Priority},
+    };
+
+    async fn track(conn: &mut SqliteConnection, title: &str, project_id: Option<Uuid>, tags: &[&str], minutes: i64) {
+        let todo = CreateTodo {
+            title: title.to_string(),
+            description: None,
+            priority: Priority::Medium,
+            status_id: None,
+            estimated_minutes: None,
+            custom_fields: Default::default(),
+            project_id,
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+        };
+        let todo = insert_todo(conn, &todo).await.unwrap();
+        let ended_at = Utc::now() - Duration::hours(1);
+        sqlx::query(
+            "INSERT INTO time_entries (id, user_id, todo_id, started_at, ended_at, created_at) VALUES ($1, $2, $3, $4, $5, $5)",
+        )
+        .bind(Uuid::new_v4().to_string())
+        .bind(DUMMY_USER_ID)
+        .bind(todo.id.to_string())
+        .bind(ended_at - Duration::minutes(minutes))
+        .bind(ended_at)
+        .execute(&mut *conn)
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn report_totals_by_project_and_tag() {
+        let db = test_db().await;
+        let mut conn = db.acquire().await.unwrap();
+        let client = project_named(&mut conn, "Client").await.unwrap();
+        track(&mut conn, "Design", Some(client), &["billable"], 30).await;
+        track(&mut conn, "Build", Some(client), &["billable", "dev"], 20).await;
+        track(&mut conn, "Inbox zero", None, &[], 10).await;
+        drop(conn);
+
+        let response = time_report(State(db), Query(TimeReportQuery::default())).await.unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let report: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let totals = |key: &str, name: &str| -> Vec<(serde_json::Value, i64)> {
+            report[key].as_array().unwrap().iter().map(|total| (total[name].clone(), total["seconds"].as_i64().unwrap())).collect()
+        };
+        assert_eq!(totals("by_project", "name"), [("Client".into(), 3000), (serde_json::Value::Null, 600)]);
+        assert_eq!(totals("by_tag", "tag"), [("billable".into(), 3000), ("dev".into(), 1200)]);
+    }
+}
@@ -1,151 +1,632 @@
 use crate::{
     db::DB,
     errors::AppError,
-    models::{CreateTodo, Todo, UpdateTodo, Priority},
+    events::{Events, TodoEvent},
+    handlers::{
+        calendar::due_field,
+        custom_fields::{all_fields, push_field_filters, push_field_sort, retain_project_fields, save_field_values},
+        dependencies::{link_blocker, open_blockers},
+        projects::{check_project, project_named},
+        revisions::record_revision,
+        statuses::resolve_status,
+        tags::{normalize_tags, save_tags},
+        views::{parse_query, view_expression},
+    },
+    models::{CreateOptions, CreateTodo, ImportRowError, ListTodosQuery, MoveTodo, ParseTodo, ParsedTodo, Priority, ReplaceTodo, Todo, WriteOptions},
+    patch::{apply_json_patch, merge_patch, PatchOperation, JSON_PATCH_CONTENT_TYPE},
+    quick_add,
+    rank::key_between,
+    view_query,
 };
 use axum::{
-    extract::{Path, State},
-    http::StatusCode,
+    body::Bytes,
+    extract::{Path, Query, State},
+    http::{header, HeaderMap, HeaderName, StatusCode},
     Json,
 };
+use sqlx::{Connection, QueryBuilder, SqliteConnection};
+use sha2::{Digest, Sha256};
 use uuid::Uuid;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Local, NaiveDate, NaiveTime, Utc};
 
 // A dummy user ID for now. In a real app, this would come from an authenticated session.
-const DUMMY_USER_ID: &str = "a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11";
+pub(crate) const DUMMY_USER_ID: &str = "a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11";
 
-pub async fn all_todos(State(db): State<DB>) -> Result<Json<Vec<Todo>>, AppError> {
-    let todos = sqlx::query_as!(
-        Todo,
-        r#"SELECT id as "id: Uuid", user_id as "user_id: Uuid", title, description, completed, priority as "priority: Priority", created_at as "created_at: DateTime<Utc>", updated_at as "updated_at: DateTime<Utc>" FROM todos WHERE user_id = $1"#,
-        DUMMY_USER_ID
-    )
-    .fetch_all(&db)
-    .await?;
-    Ok(Json(todos))
+// Column list matching the `Todo` struct, for queries built at runtime. Must be selected
+// `FROM todos` without an alias, since `custom_fields`, `tags` and `blocked` are computed from
+// correlated subqueries.
+pub(crate) const TODO_COLUMNS: &str = "id, user_id, title, description, completed, priority, created_at, updated_at, version, position, status_id, estimated_minutes, completed_at, archived_at, project_id, \
+    (SELECT json_group_object(f.key, json(v.value)) FROM todo_field_values v JOIN custom_fields f ON f.id = v.field_id WHERE v.todo_id = todos.id) AS custom_fields, \
+    (SELECT json_group_array(tag) FROM (SELECT tag FROM todo_tags WHERE todo_id = todos.id ORDER BY tag)) AS tags, \
+    EXISTS (SELECT 1 FROM todo_dependencies d JOIN todos b ON b.id = d.blocker_id WHERE d.blocked_id = todos.id AND b.completed = FALSE) AS blocked";
+
+pub(crate) fn etag(version: i64) -> String {
+    format!("\"{}\"", version)
+}
+
+/// Reads the version a client expects from `If-Match`. `Ok(None)` means `*`, which matches any version.
+pub(crate) fn expected_version(headers: &HeaderMap) -> Result<Option<i64>, AppError> {
+    let value = headers
+        .get(header::IF_MATCH)
+        .and_then(|v| v.to_str().ok())
+        .ok_or(AppError::PreconditionRequired)?
+        .trim();
+    if value == "*" {
+        return Ok(None);
+    }
+    value
+        .trim_start_matches("W/")
+        .trim_matches('"')
+        .parse::<i64>()
+        .map(Some)
+        .map_err(|_| AppError::PreconditionRequired)
+}
+
+pub(crate) async fn fetch_todo(conn: &mut SqliteConnection, id: &str) -> Result<Todo, AppError> {
+    sqlx::query_as::<_, Todo>(&format!(
+        "SELECT {TODO_COLUMNS} FROM todos WHERE id = $1 AND user_id = $2"
+    ))
+    .bind(id)
+    .bind(DUMMY_USER_ID)
+    .fetch_optional(&mut *conn)
+    .await?
+    .ok_or(AppError::NotFound)
+}
+
+/// Reads a time bound given as RFC 3339 or as a date, which means midnight UTC.
+fn parse_bound(value: &str) -> Result<DateTime<Utc>, AppError> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|at| at.with_timezone(&Utc))
+        .or_else(|_| NaiveDate::parse_from_str(value, "%Y-%m-%d").map(|day| day.and_time(NaiveTime::MIN).and_utc()))
+        .map_err(|_| AppError::BadRequest(format!("invalid date `{}`", value)))
+}
+
+pub async fn all_todos(
+    State(db): State<DB>,
+    Query(query): Query<ListTodosQuery>,
+) -> Result<([(HeaderName, String); 1], Json<Vec<Todo>>), AppError> {
+    let mut conn = db.acquire().await?;
+    let mut builder = QueryBuilder::new(format!("SELECT {TODO_COLUMNS} FROM todos WHERE user_id = "));
+    builder.push_bind(DUMMY_USER_ID).push(" AND archived_at IS NULL");
+    push_field_filters(&mut conn, &mut builder, &query.fields).await?;
+    let expr = match (&query.q, query.view) {
+        (Some(q), _) => Some(parse_query(q)?),
+        (None, Some(view)) => Some(view_expression(&mut conn, view).await?),
+        (None, None) => None,
+    };
+    if let Some(expr) = expr {
+        builder.push(" AND ");
+        view_query::push_sql(&expr, &mut builder);
+    }
+    if let Some(after) = query.completed_after.as_deref() {
+        builder.push(" AND julianday(completed_at) >= julianday(").push_bind(parse_bound(after)?).push(")");
+    }
+    if let Some(before) = query.completed_before.as_deref() {
+        builder.push(" AND julianday(completed_at) < julianday(").push_bind(parse_bound(before)?).push(")");
+    }
+    push_field_sort(&mut conn, &mut builder, query.sort.as_deref()).await?;
+    let todos = builder.build_query_as::<Todo>().fetch_all(&mut *conn).await?;
+
+    // The list has no single version, so its tag is derived from every (id, version) pair. The
+    // hash has to be stable across builds, since clients keep the tag between server releases.
+    let mut hasher = Sha256::new();
+    for todo in &todos {
+        hasher.update(todo.id.as_bytes());
+        hasher.update(todo.version.to_le_bytes());
+    }
+    let list_etag = format!("W/\"{:x}\"", hasher.finalize());
+
+    Ok(([(header::ETAG, list_etag)], Json(todos)))
 }
 
 pub async fn get_todo(
     State(db): State<DB>,
     Path(id): Path<Uuid>,
-) -> Result<Json<Todo>, AppError> {
-    let id_str = id.to_string();
-    let todo = sqlx::query_as!(
-        Todo,
-        r#"SELECT id as "id: Uuid", user_id as "user_id: Uuid", title, description, completed, priority as "priority: Priority", created_at as "created_at: DateTime<Utc>", updated_at as "updated_at: DateTime<Utc>" FROM todos WHERE id = $1 AND user_id = $2"#,
-        id_str,
-        DUMMY_USER_ID
-    )
-    .fetch_one(&db)
-    .await
-    .map_err(|_| AppError::NotFound)?;
-    Ok(Json(todo))
+) -> Result<([(HeaderName, String); 1], Json<Todo>), AppError> {
+    let mut conn = db.acquire().await?;
+    let todo = fetch_todo(&mut conn, &id.to_string()).await?;
+    Ok(([(header::ETAG, etag(todo.version))], Json(todo)))
 }
 
-pub async fn create_todo(
-    State(db): State<DB>,
-    Json(payload): Json<CreateTodo>,
-) -> Result<(StatusCode, Json<Todo>), AppError> {
-    let id = Uuid::new_v4();
+/// Inserts a new todo at the end of the list and records its first revision.
+pub(crate) async fn insert_todo(conn: &mut SqliteConnection, payload: &CreateTodo) -> Result<Todo, AppError> {
+    insert_todo_with_id(conn, Uuid::new_v4(), payload).await
+}
+
+/// Like `insert_todo`, for clients that choose the id themselves.
+pub(crate) async fn insert_todo_with_id(
+    conn: &mut SqliteConnection,
+    id: Uuid,
+    payload: &CreateTodo,
+) -> Result<Todo, AppError> {
     let id_str = id.to_string();
     let now = Utc::now();
-    let priority_str = match payload.priority {
-        Priority::Low => "low",
-        Priority::Medium => "medium",
-        Priority::High => "high",
-    };
 
+    // New todos go to the end of the list.
+    let last_position: Option<String> = sqlx::query_scalar!(
+        "SELECT MAX(position) FROM todos WHERE user_id = $1",
+        DUMMY_USER_ID,
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+    let position = key_between(last_position.as_deref(), None);
+    check_project(conn, payload.project_id).await?;
+    let (status_id, completed) = resolve_status(conn, None, payload.status_id, false, payload.project_id).await?;
+
+    let status_id = status_id.to_string();
+    let completed_at = completed.then_some(now);
+    let project_id = payload.project_id.map(|id| id.to_string());
     sqlx::query!(
-        "INSERT INTO todos (id, user_id, title, description, priority, created_at, updated_at) VALUES ($1, $2, $3, $4, $5, $6, $7)",
+        "INSERT INTO todos (id, user_id, title, description, priority, created_at, updated_at, position, status_id, completed, estimated_minutes, completed_at, project_id) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)",
         id_str,
         DUMMY_USER_ID,
         payload.title,
         payload.description,
-        priority_str,
+        payload.priority,
         now,
-        now
+        now,
+        position,
+        status_id,
+        completed,
+        payload.estimated_minutes,
+        completed_at,
+        project_id,
     )
-    .execute(&db)
+    .execute(&mut *conn)
     .await?;
+    save_field_values(conn, &id_str, &payload.custom_fields, payload.project_id).await?;
+    save_tags(conn, &id_str, &payload.tags).await?;
 
-    let todo = sqlx::query_as!(
-        Todo,
-        r#"SELECT id as "id: Uuid", user_id as "user_id: Uuid", title, description, completed, priority as "priority: Priority", created_at as "created_at: DateTime<Utc>", updated_at as "updated_at: DateTime<Utc>" FROM todos WHERE id = $1"#,
-        id_str
+    let todo = fetch_todo(conn, &id_str).await?;
+    record_revision(conn, None, &todo, DUMMY_USER_ID).await?;
+    Ok(todo)
+}
+
+/// A todo read from an import file, with the state it had there.
+pub(crate) struct ImportedTodo {
+    pub todo: CreateTodo,
+    pub completed: bool,
+    /// Kept from the file when it has them; otherwise the import time is used.
+    pub created_at: Option<DateTime<Utc>>,
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+async fn insert_imported(conn: &mut SqliteConnection, imported: &ImportedTodo) -> Result<Todo, AppError> {
+    let mut todo = insert_todo(conn, &imported.todo).await?;
+    if imported.completed {
+        // Blockers are ignored, since an imported todo has none yet.
+        let replacement = ReplaceTodo { completed: true, ..ReplaceTodo::from(&todo) };
+        todo = apply_replacement(conn, &todo, &replacement, true).await?;
+    }
+    if imported.created_at.is_none() && imported.completed_at.is_none() {
+        return Ok(todo);
+    }
+
+    let id_str = todo.id.to_string();
+    sqlx::query!(
+        "UPDATE todos SET created_at = COALESCE($1, created_at), \
+        completed_at = CASE WHEN completed THEN COALESCE($2, completed_at) ELSE NULL END WHERE id = $3",
+        imported.created_at,
+        imported.completed_at,
+        id_str,
     )
-    .fetch_one(&db)
+    .execute(&mut *conn)
     .await?;
+    fetch_todo(conn, &id_str).await
+}
 
-    Ok((StatusCode::CREATED, Json(todo)))
+/// Inserts an imported todo inside a savepoint, so one the database rejects is rolled back
+/// without losing the rest of the import.
+pub(crate) async fn insert_imported_todo(
+    conn: &mut SqliteConnection,
+    imported: &ImportedTodo,
+) -> Result<Todo, AppError> {
+    let mut savepoint = conn.begin().await?;
+    match insert_imported(&mut savepoint, imported).await {
+        Ok(todo) => {
+            savepoint.commit().await?;
+            Ok(todo)
+        }
+        Err(e) => {
+            savepoint.rollback().await?;
+            Err(e)
+        }
+    }
+}
+
+/// An imported todo, or why it could not be read, with where it was in the file and the earlier
+/// item it was nested under.
+pub(crate) struct NestedImport {
+    /// 1-based line, or position for JSON files.
+    pub line: usize,
+    /// Index of the parent item.
+    pub parent: Option<usize>,
+    pub todo: Result<ImportedTodo, String>,
+}
+
+/// Inserts imported todos in order. Todos have no subtasks, so a nested todo blocks its parent
+/// instead. Items that cannot be imported are returned as errors, and those nested under them are
+/// imported unlinked.
+pub(crate) async fn insert_nested_imports(
+    conn: &mut SqliteConnection,
+    items: Vec<NestedImport>,
+) -> Result<(u64, Vec<ImportRowError>), AppError> {
+    let mut imported = 0;
+    let mut errors = vec![];
+    // The id each item was imported as, by index.
+    let mut ids: Vec<Option<Uuid>> = Vec::with_capacity(items.len());
+    for item in items {
+        let result = match item.todo {
+            Ok(todo) => insert_imported_todo(conn, &todo).await,
+            Err(message) => Err(AppError::BadRequest(message)),
+        };
+        match result {
+            Ok(todo) => {
+                if let Some(parent) = item.parent.and_then(|parent| ids[parent]) {
+                    link_blocker(conn, &todo.id.to_string(), &parent.to_string()).await?;
+                }
+                imported += 1;
+                ids.push(Some(todo.id));
+            }
+            Err(AppError::BadRequest(message)) => {
+                errors.push(ImportRowError { line: item.line, message });
+                ids.push(None);
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    Ok((imported, errors))
+}
+
+/// Applies quick-add parsing to `text`, keeping `base`'s values for anything not found in it.
+fn parse_quick_add(text: &str, base: CreateTodo) -> ParsedTodo {
+    let parsed = quick_add::parse(text, Local::now());
+    ParsedTodo {
+        todo: CreateTodo {
+            title: parsed.title,
+            priority: parsed.priority.unwrap_or(base.priority),
+            tags: base.tags.iter().chain(&parsed.tags).cloned().collect(),
+            ..base
+        },
+        due: parsed.due,
+        tags: parsed.tags,
+        project: parsed.project,
+        recurrence: parsed.recurrence,
+        spans: parsed.spans,
+    }
+}
+
+/// The todo to create from parsed quick-add text. The due day goes into the `due` date field,
+/// without its time of day, and the project is looked up by name, creating it if needed.
+/// Recurrence has nowhere to go, so text using it is rejected rather than quietly losing it.
+async fn quick_add_todo(conn: &mut SqliteConnection, parsed: ParsedTodo) -> Result<CreateTodo, AppError> {
+    if parsed.recurrence.is_some() {
+        return Err(AppError::BadRequest("todos can't store recurrence yet".to_string()));
+    }
+
+    let mut todo = parsed.todo;
+    if let Some(project) = &parsed.project {
+        todo.project_id = Some(project_named(conn, project).await?);
+    }
+    if let Some(due) = parsed.due {
+        let fields = all_fields(conn).await?;
+        let field = due_field(&fields).ok_or_else(|| {
+            AppError::BadRequest("add a date custom field with the key `due` to store due dates".to_string())
+        })?;
+        let day = due.with_timezone(&Local).date_naive();
+        todo.custom_fields.0.insert(field.key.clone(), serde_json::Value::String(day.to_string()));
+    }
+    Ok(todo)
+}
+
+pub async fn parse_todo(Json(payload): Json<ParseTodo>) -> Json<ParsedTodo> {
+    let base = CreateTodo {
+        title: String::new(),
+        description: None,
+        priority: Priority::Medium,
+        status_id: None,
+        estimated_minutes: None,
+        custom_fields: Default::default(),
+        project_id: None,
+        tags: vec![],
+    };
+    Json(parse_quick_add(&payload.text, base))
+}
+
+pub async fn create_todo(
+    State(db): State<DB>,
+    State(events): State<Events>,
+    Query(options): Query<CreateOptions>,
+    Json(mut payload): Json<CreateTodo>,
+) -> Result<(StatusCode, [(HeaderName, String); 1], Json<Todo>), AppError> {
+    let mut tx = db.begin().await?;
+    if options.parse {
+        let text = std::mem::take(&mut payload.title);
+        payload = quick_add_todo(&mut tx, parse_quick_add(&text, payload)).await?;
+    }
+    let todo = insert_todo(&mut tx, &payload).await?;
+    events.commit(tx, DUMMY_USER_ID, vec![TodoEvent::Created { todo: todo.clone() }]).await?;
+
+    Ok((StatusCode::CREATED, [(header::ETAG, etag(todo.version))], Json(todo)))
 }
 
 pub async fn update_todo(
     State(db): State<DB>,
+    State(events): State<Events>,
     Path(id): Path<Uuid>,
-    Json(payload): Json<UpdateTodo>,
-) -> Result<Json<Todo>, AppError> {
-    let id_str = id.to_string();
-    let todo = sqlx::query_as!(
-        Todo,
-        r#"SELECT id as "id: Uuid", user_id as "user_id: Uuid", title, description, completed, priority as "priority: Priority", created_at as "created_at: DateTime<Utc>", updated_at as "updated_at: DateTime<Utc>" FROM todos WHERE id = $1 AND user_id = $2"#,
-        id_str,
-        DUMMY_USER_ID
-    )
-    .fetch_one(&db)
+    Query(options): Query<WriteOptions>,
+    headers: HeaderMap,
+    Json(payload): Json<ReplaceTodo>,
+) -> Result<([(HeaderName, String); 1], Json<Todo>), AppError> {
+    save_todo(&db, &events, id, &headers, options.force, |_| Ok(payload)).await
+}
+
+/// Partial update. The body is a JSON Patch when sent as `application/json-patch+json` and a
+/// JSON Merge Patch otherwise, so `{"description": null}` clears the description.
+pub async fn patch_todo(
+    State(db): State<DB>,
+    State(events): State<Events>,
+    Path(id): Path<Uuid>,
+    Query(options): Query<WriteOptions>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<([(HeaderName, String); 1], Json<Todo>), AppError> {
+    let is_json_patch = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with(JSON_PATCH_CONTENT_TYPE));
+
+    save_todo(&db, &events, id, &headers, options.force, |todo| {
+        let invalid = |e: serde_json::Error| AppError::InvalidPatch(e.to_string());
+        let mut doc = serde_json::to_value(ReplaceTodo::from(todo)).map_err(invalid)?;
+        if is_json_patch {
+            let operations: Vec<PatchOperation> = serde_json::from_slice(&body).map_err(invalid)?;
+            apply_json_patch(&mut doc, &operations).map_err(AppError::InvalidPatch)?;
+        } else {
+            let patch: serde_json::Value = serde_json::from_slice(&body).map_err(invalid)?;
+            merge_patch(&mut doc, &patch);
+        }
+        serde_json::from_value(doc).map_err(invalid)
+    })
     .await
-    .map_err(|_| AppError::NotFound)?;
-
-    let title = payload.title.unwrap_or(todo.title);
-    let description = payload.description.or(todo.description);
-    let completed = payload.completed.unwrap_or(todo.completed);
-    let priority = payload.priority.unwrap_or(todo.priority);
-    let priority_str = match priority {
-        Priority::Low => "low",
-        Priority::Medium => "medium",
-        Priority::High => "high",
+}
+
+/// Writes the fields produced by `build` from the current todo, guarded by `If-Match`.
+async fn save_todo(
+    db: &DB,
+    events: &Events,
+    id: Uuid,
+    headers: &HeaderMap,
+    force: bool,
+    build: impl FnOnce(&Todo) -> Result<ReplaceTodo, AppError>,
+) -> Result<([(HeaderName, String); 1], Json<Todo>), AppError> {
+    let expected = expected_version(headers)?;
+    let id_str = id.to_string();
+    let mut tx = db.begin().await?;
+    let before = fetch_todo(&mut tx, &id_str).await?;
+    if expected.is_some_and(|version| version != before.version) {
+        return Err(AppError::PreconditionFailed(Box::new(before)));
+    }
+
+    let replacement = build(&before)?;
+    let updated_todo = apply_replacement(&mut tx, &before, &replacement, force).await?;
+    events.commit(tx, DUMMY_USER_ID, vec![TodoEvent::Updated { todo: updated_todo.clone() }]).await?;
+
+    Ok(([(header::ETAG, etag(updated_todo.version))], Json(updated_todo)))
+}
+
+async fn neighbour_position(
+    conn: &mut SqliteConnection,
+    moving_id: &str,
+    position: &str,
+    upwards: bool,
+) -> Result<Option<String>, AppError> {
+    let query = if upwards {
+        "SELECT MAX(position) FROM todos WHERE user_id = $1 AND id != $2 AND position < $3"
+    } else {
+        "SELECT MIN(position) FROM todos WHERE user_id = $1 AND id != $2 AND position > $3"
     };
-    let now = Utc::now();
+    Ok(sqlx::query_scalar(query)
+        .bind(DUMMY_USER_ID)
+        .bind(moving_id)
+        .bind(position)
+        .fetch_one(&mut *conn)
+        .await?)
+}
 
+/// Places a todo between two others by giving it a rank key between theirs, so only the moved
+/// row is rewritten.
+pub async fn move_todo(
+    State(db): State<DB>,
+    State(events): State<Events>,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<MoveTodo>,
+) -> Result<([(HeaderName, String); 1], Json<Todo>), AppError> {
+    let id_str = id.to_string();
+    if payload.before == Some(id) || payload.after == Some(id) {
+        return Err(AppError::BadRequest("a todo cannot be moved relative to itself".to_string()));
+    }
+
+    let mut tx = db.begin().await?;
+    fetch_todo(&mut tx, &id_str).await?;
+
+    let after = match payload.after {
+        Some(after) => Some(fetch_todo(&mut tx, &after.to_string()).await?.position),
+        None => None,
+    };
+    let before = match payload.before {
+        Some(before) => Some(fetch_todo(&mut tx, &before.to_string()).await?.position),
+        None => None,
+    };
+
+    // With only one neighbour given, the other bound is whatever currently sits next to it.
+    let (lower, upper) = match (after, before) {
+        (Some(lower), Some(upper)) => (Some(lower), Some(upper)),
+        (Some(lower), None) => {
+            let upper = neighbour_position(&mut tx, &id_str, &lower, false).await?;
+            (Some(lower), upper)
+        }
+        (None, Some(upper)) => {
+            let lower = neighbour_position(&mut tx, &id_str, &upper, true).await?;
+            (lower, Some(upper))
+        }
+        (None, None) => return Err(AppError::BadRequest("`before` or `after` is required".to_string())),
+    };
+    if let (Some(lower), Some(upper)) = (&lower, &upper) {
+        if lower >= upper {
+            return Err(AppError::BadRequest("`after` must come before `before`".to_string()));
+        }
+    }
+
+    let position = key_between(lower.as_deref(), upper.as_deref());
+    let now = Utc::now();
     sqlx::query!(
-        "UPDATE todos SET title = $1, description = $2, completed = $3, priority = $4, updated_at = $5 WHERE id = $6",
-        title,
-        description,
-        completed,
-        priority_str,
+        "UPDATE todos SET position = $1, updated_at = $2, version = version + 1 WHERE id = $3",
+        position,
         now,
-        id_str
+        id_str,
     )
-    .execute(&db)
+    .execute(&mut *tx)
     .await?;
 
-    let updated_todo = sqlx::query_as!(
-        Todo,
-        r#"SELECT id as "id: Uuid", user_id as "user_id: Uuid", title, description, completed, priority as "priority: Priority", created_at as "created_at: DateTime<Utc>", updated_at as "updated_at: DateTime<Utc>" FROM todos WHERE id = $1"#,
-        id_str
-    )
-    .fetch_one(&db)
-    .await?;
+    let todo = fetch_todo(&mut tx, &id_str).await?;
+    events.commit(tx, DUMMY_USER_ID, vec![TodoEvent::Updated { todo: todo.clone() }]).await?;
 
-    Ok(Json(updated_todo))
+    Ok(([(header::ETAG, etag(todo.version))], Json(todo)))
 }
 
 pub async fn delete_todo(
     State(db): State<DB>,
+    State(events): State<Events>,
     Path(id): Path<Uuid>,
+    headers: HeaderMap,
 ) -> Result<StatusCode, AppError> {
+    let expected = expected_version(&headers)?;
     let id_str = id.to_string();
+    let mut tx = db.begin().await?;
+    let todo = fetch_todo(&mut tx, &id_str).await?;
+    let version = expected.unwrap_or(todo.version);
+
     let rows_affected = sqlx::query!(
-        "DELETE FROM todos WHERE id = $1 AND user_id = $2",
+        "DELETE FROM todos WHERE id = $1 AND user_id = $2 AND version = $3",
         id_str,
-        DUMMY_USER_ID
+        DUMMY_USER_ID,
+        version,
     )
-    .execute(&db)
+    .execute(&mut *tx)
     .await?
     .rows_affected();
 
     if rows_affected == 0 {
-        Err(AppError::NotFound)
-    } else {
-        Ok(StatusCode::NO_CONTENT)
+        return Err(AppError::PreconditionFailed(Box::new(todo)));
+    }
+
+    events.commit(tx, DUMMY_USER_ID, vec![TodoEvent::Deleted { id }]).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Overwrites the editable fields of `before` with `replacement`, bumping its version and
+/// recording a revision. Fails with `PreconditionFailed` if the row changed since `before` was read,
+/// and with `Blocked` when completing a todo that has open blockers unless `force` is set.
+pub(crate) async fn apply_replacement(
+    conn: &mut SqliteConnection,
+    before: &Todo,
+    replacement: &ReplaceTodo,
+    force: bool,
+) -> Result<Todo, AppError> {
+    let id_str = before.id.to_string();
+    if replacement.project_id != before.project_id {
+        check_project(conn, replacement.project_id).await?;
+    }
+    let (status_id, completed) =
+        resolve_status(conn, Some(before), replacement.status_id, replacement.completed, replacement.project_id).await?;
+
+    if completed && !before.completed && !force {
+        let blockers = open_blockers(conn, &id_str).await?;
+        if !blockers.is_empty() {
+            return Err(AppError::Blocked(blockers));
+        }
     }
-} 
\ No newline at end of file
+
+    // The version check is repeated in the WHERE clause so a concurrent writer cannot slip in
+    // between the caller's read and this write.
+    let now = Utc::now();
+    let status_id = status_id.to_string();
+    let project_id = replacement.project_id.map(|id| id.to_string());
+    let rows_affected = sqlx::query!(
+        "UPDATE todos SET title = $1, description = $2, completed = $3, priority = $4, status_id = $5, estimated_minutes = $6, updated_at = $7, version = version + 1, \
+            completed_at = CASE WHEN $3 THEN COALESCE(completed_at, $7) ELSE NULL END, \
+            archived_at = CASE WHEN $3 THEN archived_at ELSE NULL END, project_id = $10 WHERE id = $8 AND version = $9",
+        replacement.title,
+        replacement.description,
+        completed,
+        replacement.priority,
+        status_id,
+        replacement.estimated_minutes,
+        now,
+        id_str,
+        before.version,
+        project_id,
+    )
+    .execute(&mut *conn)
+    .await?
+    .rows_affected();
+
+    if rows_affected == 0 {
+        let current = fetch_todo(conn, &id_str).await?;
+        return Err(AppError::PreconditionFailed(Box::new(current)));
+    }
+    let mut custom_fields = replacement.custom_fields.clone();
+    if replacement.project_id != before.project_id {
+        retain_project_fields(conn, &mut custom_fields, replacement.project_id).await?;
+    }
+    if custom_fields != before.custom_fields {
+        save_field_values(conn, &id_str, &custom_fields, replacement.project_id).await?;
+    }
+    if normalize_tags(&replacement.tags) != before.tags {
+        save_tags(conn, &id_str, &replacement.tags).await?;
+    }
+
+    let updated_todo = fetch_todo(conn, &id_str).await?;
+    record_revision(conn, Some(before), &updated_todo, DUMMY_USER_ID).await?;
+    Ok(updated_todo)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{db::test_db, handlers::projects::all_projects};
+
+    fn base() -> CreateTodo {
+        CreateTodo {
+            title: String::new(),
+            description: None,
+            priority: Priority::Medium,
+            status_id: None,
+            estimated_minutes: None,
+            custom_fields: Default::default(),
+            project_id: None,
+            tags: vec![],
+        }
+    }
+
+    #[tokio::test]
+    async fn quick_add_stores_tags_and_project() {
+        let db = test_db().await;
+        let mut conn = db.acquire().await.unwrap();
+
+        let rent = quick_add_todo(&mut conn, parse_quick_add("Pay rent !high #finance +Home", base())).await.unwrap();
+        let rent = insert_todo(&mut conn, &rent).await.unwrap();
+        assert_eq!((rent.title.as_str(), rent.priority, rent.tags.clone()), ("Pay rent", Priority::High, vec!["finance".to_string()]));
+
+        // The project is found again by name, ignoring case.
+        let bins = quick_add_todo(&mut conn, parse_quick_add("Take out the bins +home", base())).await.unwrap();
+        assert_eq!(bins.project_id, rent.project_id);
+        let projects = all_projects(&mut conn).await.unwrap();
+        assert_eq!(projects.iter().map(|project| project.name.as_str()).collect::<Vec<_>>(), ["Home"]);
+        assert_eq!(rent.project_id, Some(projects[0].id));
+
+        let recurring = quick_add_todo(&mut conn, parse_quick_add("Water plants every week", base())).await;
+        assert!(matches!(recurring, Err(AppError::BadRequest(message)) if message.contains("recurrence")));
+    }
+}
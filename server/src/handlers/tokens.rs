@@ -0,0 +1,133 @@
+use crate::{
+    db::DB,
+    errors::AppError,
+    handlers::todos::DUMMY_USER_ID,
+    models::{ApiToken, CreateApiToken},
+};
+use axum::{
+    extract::{Path, State},
+    http::{header, HeaderMap, StatusCode},
+    Json,
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chrono::{DateTime, Utc};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+#[derive(sqlx::FromRow)]
+struct ApiTokenRow {
+    id: String,
+    name: String,
+    created_at: DateTime<Utc>,
+    last_used_at: Option<DateTime<Utc>>,
+}
+
+impl TryFrom<ApiTokenRow> for ApiToken {
+    type Error = AppError;
+
+    fn try_from(row: ApiTokenRow) -> Result<Self, Self::Error> {
+        Ok(ApiToken {
+            id: Uuid::parse_str(&row.id).map_err(|_| AppError::InternalServerError)?,
+            name: row.name,
+            created_at: row.created_at,
+            last_used_at: row.last_used_at,
+            token: None,
+        })
+    }
+}
+
+pub async fn list_tokens(State(db): State<DB>) -> Result<Json<Vec<ApiToken>>, AppError> {
+    let tokens = sqlx::query_as::<_, ApiTokenRow>(
+        "SELECT id, name, created_at, last_used_at FROM api_tokens WHERE user_id = $1 ORDER BY created_at",
+    )
+    .bind(DUMMY_USER_ID)
+    .fetch_all(&db)
+    .await?
+    .into_iter()
+    .map(ApiToken::try_from)
+    .collect::<Result<_, _>>()?;
+    Ok(Json(tokens))
+}
+
+/// Creates a token. This is the only response that includes the token itself.
+pub async fn create_token(
+    State(db): State<DB>,
+    Json(payload): Json<CreateApiToken>,
+) -> Result<(StatusCode, Json<ApiToken>), AppError> {
+    let name = payload.name.trim();
+    if name.is_empty() {
+        return Err(AppError::BadRequest("name must not be empty".to_string()));
+    }
+    let token = ApiToken {
+        id: Uuid::new_v4(),
+        name: name.to_string(),
+        created_at: Utc::now(),
+        last_used_at: None,
+        token: Some(format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple())),
+    };
+    sqlx::query("INSERT INTO api_tokens (id, user_id, name, token_hash, created_at) VALUES ($1, $2, $3, $4, $5)")
+        .bind(token.id.to_string())
+        .bind(DUMMY_USER_ID)
+        .bind(&token.name)
+        .bind(token.token.as_deref().map(hash_token))
+        .bind(token.created_at)
+        .execute(&db)
+        .await?;
+    Ok((StatusCode::CREATED, Json(token)))
+}
+
+pub async fn delete_token(State(db): State<DB>, Path(id): Path<Uuid>) -> Result<StatusCode, AppError> {
+    let rows_affected = sqlx::query("DELETE FROM api_tokens WHERE id = $1 AND user_id = $2")
+        .bind(id.to_string())
+        .bind(DUMMY_USER_ID)
+        .execute(&db)
+        .await?
+        .rows_affected();
+    if rows_affected == 0 {
+        return Err(AppError::NotFound);
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// Only the hash is stored, so a leaked database does not leak working tokens.
+fn hash_token(token: &str) -> String {
+    format!("{:x}", Sha256::digest(token.as_bytes()))
+}
+
+fn authorization<'a>(headers: &'a HeaderMap, scheme: &str) -> Option<&'a str> {
+    headers.get(header::AUTHORIZATION)?.to_str().ok()?.strip_prefix(scheme)
+}
+
+// The password of a Basic `Authorization` header. The user name is not checked, since clients
+// insist on one but the token alone identifies the user.
+fn basic_token(headers: &HeaderMap) -> Option<String> {
+    let credentials = STANDARD.decode(authorization(headers, "Basic ")?.trim()).ok()?;
+    let credentials = String::from_utf8(credentials).ok()?;
+    credentials.split_once(':').map(|(_, token)| token.to_string())
+}
+
+async fn token_user(db: &DB, token: &str) -> Result<String, AppError> {
+    sqlx::query_scalar("UPDATE api_tokens SET last_used_at = $1 WHERE token_hash = $2 RETURNING user_id")
+        .bind(Utc::now())
+        .bind(hash_token(token))
+        .fetch_optional(db)
+        .await?
+        .ok_or(AppError::AuthenticationError)
+}
+
+/// The user whose API token is the password of a Basic `Authorization` header.
+pub(crate) async fn basic_auth_user(db: &DB, headers: &HeaderMap) -> Result<String, AppError> {
+    let token = basic_token(headers).ok_or(AppError::AuthenticationError)?;
+    token_user(db, &token).await
+}
+
+/// The user an API token in the `Authorization` header or, since browsers cannot set headers on
+/// a WebSocket or EventSource, in `query_token` belongs to.
+pub(crate) async fn request_user(db: &DB, headers: &HeaderMap, query_token: Option<&str>) -> Result<String, AppError> {
+    let token = authorization(headers, "Bearer ")
+        .map(|token| token.trim().to_string())
+        .or_else(|| basic_token(headers))
+        .or_else(|| query_token.map(str::to_string))
+        .ok_or(AppError::AuthenticationError)?;
+    token_user(db, &token).await
+}
@@ -0,0 +1,167 @@
+use crate::{
+    db::DB,
+    errors::AppError,
+    handlers::todos::DUMMY_USER_ID,
+    models::{CreateSavedView, SavedView, UpdateSavedView},
+    view_query::{self, Expr},
+};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::{DateTime, Utc};
+use sqlx::{QueryBuilder, SqliteConnection};
+use uuid::Uuid;
+
+const VIEW_COLUMNS: &str = "id, user_id, name, query, position, created_at";
+
+#[derive(sqlx::FromRow)]
+struct SavedViewRow {
+    id: String,
+    user_id: String,
+    name: String,
+    query: String,
+    position: i64,
+    created_at: DateTime<Utc>,
+}
+
+/// Parses a filter expression, reporting syntax errors as a bad request.
+pub(crate) fn parse_query(query: &str) -> Result<Expr, AppError> {
+    view_query::parse(query, Utc::now()).map_err(AppError::BadRequest)
+}
+
+async fn fetch_row(conn: &mut SqliteConnection, id: Uuid) -> Result<SavedViewRow, AppError> {
+    sqlx::query_as::<_, SavedViewRow>(&format!(
+        "SELECT {VIEW_COLUMNS} FROM saved_views WHERE id = $1 AND user_id = $2"
+    ))
+    .bind(id.to_string())
+    .bind(DUMMY_USER_ID)
+    .fetch_optional(&mut *conn)
+    .await?
+    .ok_or(AppError::NotFound)
+}
+
+/// The parsed expression of a saved view, for filtering the todo list by it.
+pub(crate) async fn view_expression(conn: &mut SqliteConnection, id: Uuid) -> Result<Expr, AppError> {
+    let row = fetch_row(conn, id).await?;
+    parse_query(&row.query)
+}
+
+async fn count_matching(conn: &mut SqliteConnection, expr: &Expr) -> Result<i64, AppError> {
+    let mut builder = QueryBuilder::new("SELECT COUNT(*) FROM todos WHERE user_id = ");
+    builder.push_bind(DUMMY_USER_ID).push(" AND archived_at IS NULL AND ");
+    view_query::push_sql(expr, &mut builder);
+    Ok(builder.build_query_scalar::<i64>().fetch_one(&mut *conn).await?)
+}
+
+async fn with_count(conn: &mut SqliteConnection, row: SavedViewRow) -> Result<SavedView, AppError> {
+    let count = count_matching(conn, &parse_query(&row.query)?).await?;
+    let parse_id = |s: &str| Uuid::parse_str(s).map_err(|_| AppError::InternalServerError);
+    Ok(SavedView {
+        id: parse_id(&row.id)?,
+        user_id: parse_id(&row.user_id)?,
+        name: row.name,
+        query: row.query,
+        position: row.position,
+        created_at: row.created_at,
+        count,
+    })
+}
+
+pub(crate) async fn all_views(conn: &mut SqliteConnection) -> Result<Vec<SavedView>, AppError> {
+    let rows = sqlx::query_as::<_, SavedViewRow>(&format!(
+        "SELECT {VIEW_COLUMNS} FROM saved_views WHERE user_id = $1 ORDER BY position, created_at"
+    ))
+    .bind(DUMMY_USER_ID)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let mut views = Vec::with_capacity(rows.len());
+    for row in rows {
+        views.push(with_count(conn, row).await?);
+    }
+    Ok(views)
+}
+
+pub async fn list_views(State(db): State<DB>) -> Result<Json<Vec<SavedView>>, AppError> {
+    let mut conn = db.acquire().await?;
+    Ok(Json(all_views(&mut conn).await?))
+}
+
+pub async fn create_view(
+    State(db): State<DB>,
+    Json(payload): Json<CreateSavedView>,
+) -> Result<(StatusCode, Json<SavedView>), AppError> {
+    parse_query(&payload.query)?;
+    let mut tx = db.begin().await?;
+
+    let position = match payload.position {
+        Some(position) => position,
+        None => sqlx::query_scalar(
+            "SELECT COALESCE(MAX(position), -1) + 1 FROM saved_views WHERE user_id = $1",
+        )
+        .bind(DUMMY_USER_ID)
+        .fetch_one(&mut *tx)
+        .await?,
+    };
+
+    let id = Uuid::new_v4();
+    sqlx::query(
+        "INSERT INTO saved_views (id, user_id, name, query, position, created_at) VALUES ($1, $2, $3, $4, $5, $6)",
+    )
+    .bind(id.to_string())
+    .bind(DUMMY_USER_ID)
+    .bind(&payload.name)
+    .bind(&payload.query)
+    .bind(position)
+    .bind(Utc::now())
+    .execute(&mut *tx)
+    .await?;
+
+    let row = fetch_row(&mut tx, id).await?;
+    let view = with_count(&mut tx, row).await?;
+    tx.commit().await?;
+    Ok((StatusCode::CREATED, Json(view)))
+}
+
+pub async fn update_view(
+    State(db): State<DB>,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<UpdateSavedView>,
+) -> Result<Json<SavedView>, AppError> {
+    if let Some(query) = &payload.query {
+        parse_query(query)?;
+    }
+    let mut tx = db.begin().await?;
+    let row = fetch_row(&mut tx, id).await?;
+
+    sqlx::query("UPDATE saved_views SET name = $1, query = $2, position = $3 WHERE id = $4")
+        .bind(payload.name.unwrap_or(row.name))
+        .bind(payload.query.unwrap_or(row.query))
+        .bind(payload.position.unwrap_or(row.position))
+        .bind(id.to_string())
+        .execute(&mut *tx)
+        .await?;
+
+    let row = fetch_row(&mut tx, id).await?;
+    let view = with_count(&mut tx, row).await?;
+    tx.commit().await?;
+    Ok(Json(view))
+}
+
+pub async fn delete_view(
+    State(db): State<DB>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, AppError> {
+    let rows_affected = sqlx::query("DELETE FROM saved_views WHERE id = $1 AND user_id = $2")
+        .bind(id.to_string())
+        .bind(DUMMY_USER_ID)
+        .execute(&db)
+        .await?
+        .rows_affected();
+    if rows_affected == 0 {
+        return Err(AppError::NotFound);
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
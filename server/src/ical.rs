@@ -0,0 +1,336 @@
+//! The iCalendar format (RFC 5545), as much of it as VTODO export and import need.
+//!
+//! A file is a tree of `BEGIN:NAME` ... `END:NAME` components holding `NAME;PARAM=value:value`
+//! content lines. Lines longer than 75 octets are folded onto continuation lines that start with
+//! a space.
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+
+// Content lines are folded at this many octets, not counting the CRLF.
+const LINE_LIMIT: usize = 75;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Property {
+    pub name: String,
+    pub params: Vec<(String, String)>,
+    /// The raw value; TEXT values still carry their escapes (see `unescape_text`).
+    pub value: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Component {
+    pub name: String,
+    /// Line of the `BEGIN`, counting from 1.
+    pub line: usize,
+    pub properties: Vec<Property>,
+    pub components: Vec<Component>,
+}
+
+impl Component {
+    fn new(name: String, line: usize) -> Self {
+        Component { name, line, properties: vec![], components: vec![] }
+    }
+
+    pub fn property(&self, name: &str) -> Option<&Property> {
+        self.properties.iter().find(|property| property.name == name)
+    }
+}
+
+/// Joins folded lines, returning each content line with the number of the line it starts on.
+fn unfold(text: &str) -> Vec<(usize, String)> {
+    let mut lines: Vec<(usize, String)> = vec![];
+    for (index, line) in text.trim_start_matches('\u{feff}').lines().enumerate() {
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(rest), Some((_, previous))) => previous.push_str(rest),
+            _ if line.trim().is_empty() => {}
+            _ => lines.push((index + 1, line.to_string())),
+        }
+    }
+    lines
+}
+
+fn parse_property(line: &str) -> Option<Property> {
+    // The value starts at the first colon outside a quoted parameter value.
+    let mut quoted = false;
+    let colon = line.char_indices().find_map(|(i, c)| match c {
+        '"' => {
+            quoted = !quoted;
+            None
+        }
+        ':' if !quoted => Some(i),
+        _ => None,
+    })?;
+    let (head, value) = (&line[..colon], &line[colon + 1..]);
+
+    let mut parts = vec![];
+    let mut part = String::new();
+    quoted = false;
+    for c in head.chars() {
+        if c == '"' {
+            quoted = !quoted;
+        } else if c == ';' && !quoted {
+            parts.push(std::mem::take(&mut part));
+            continue;
+        }
+        part.push(c);
+    }
+    parts.push(part);
+
+    let mut parts = parts.into_iter();
+    let name = parts.next()?.trim().to_uppercase();
+    if name.is_empty() {
+        return None;
+    }
+    let params = parts
+        .map(|param| {
+            let (key, value) = param.split_once('=').unwrap_or((param.as_str(), ""));
+            (key.trim().to_uppercase(), value.trim_matches('"').to_string())
+        })
+        .collect();
+    Some(Property { name, params, value: value.to_string() })
+}
+
+/// Parses a file into its top-level components, usually a single VCALENDAR.
+pub fn parse(text: &str) -> Result<Vec<Component>, String> {
+    let mut roots = vec![];
+    let mut stack: Vec<Component> = vec![];
+    for (line, content) in unfold(text) {
+        let property = parse_property(&content).ok_or_else(|| format!("line {}: not a content line", line))?;
+        match property.name.as_str() {
+            "BEGIN" => stack.push(Component::new(property.value.trim().to_uppercase(), line)),
+            "END" => {
+                let name = property.value.trim().to_uppercase();
+                let component = stack
+                    .pop()
+                    .filter(|component| component.name == name)
+                    .ok_or_else(|| format!("line {}: END:{} without a matching BEGIN", line, name))?;
+                match stack.last_mut() {
+                    Some(parent) => parent.components.push(component),
+                    None => roots.push(component),
+                }
+            }
+            _ => stack
+                .last_mut()
+                .ok_or_else(|| format!("line {}: {} is outside of any component", line, property.name))?
+                .properties
+                .push(property),
+        }
+    }
+    match stack.pop() {
+        Some(open) => Err(format!("line {}: BEGIN:{} is never closed", open.line, open.name)),
+        None => Ok(roots),
+    }
+}
+
+/// Escapes a TEXT value.
+pub fn escape_text(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            ';' => out.push_str("\\;"),
+            ',' => out.push_str("\\,"),
+            '\n' => out.push_str("\\n"),
+            '\r' => {}
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+pub fn unescape_text(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n' | 'N') => out.push('\n'),
+            Some(escaped) => out.push(escaped),
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+/// Splits a list of TEXT values, such as CATEGORIES, on the commas that aren't escaped, and
+/// unescapes each one.
+pub fn split_text_list(value: &str) -> Vec<String> {
+    let mut items = vec![];
+    let mut start = 0;
+    let mut escaped = false;
+    for (i, c) in value.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            ',' => {
+                items.push(unescape_text(&value[start..i]));
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    items.push(unescape_text(&value[start..]));
+    items
+}
+
+/// Appends a content line, folded to the line limit and ended with CRLF.
+pub fn write_line(out: &mut String, line: &str) {
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > LINE_LIMIT {
+            out.push_str("\r\n ");
+            // The leading space counts towards the continuation line.
+            width = 1;
+        }
+        out.push(c);
+        width += c.len_utf8();
+    }
+    out.push_str("\r\n");
+}
+
+/// A UTC DATE-TIME value, e.g. `20240501T093000Z`.
+pub fn format_date_time(at: DateTime<Utc>) -> String {
+    at.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+/// A DATE value, e.g. `20240501`.
+pub fn format_date(date: NaiveDate) -> String {
+    date.format("%Y%m%d").to_string()
+}
+
+/// Reads a DATE or DATE-TIME value. Times without a `Z` are floating or tied to a `TZID`, and
+/// are read as UTC since there is no time zone database to resolve them with; dates are read as
+/// midnight.
+pub fn parse_date_time(value: &str) -> Option<DateTime<Utc>> {
+    let value = value.trim();
+    match value.len() {
+        8 => NaiveDate::parse_from_str(value, "%Y%m%d")
+            .ok()
+            .map(|date| date.and_time(NaiveTime::MIN).and_utc()),
+        15 | 16 => NaiveDateTime::parse_from_str(value.trim_end_matches('Z'), "%Y%m%dT%H%M%S")
+            .ok()
+            .map(|at| at.and_utc()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn parses_nested_components() {
+        let text = "\u{feff}BEGIN:VCALENDAR\r\n\
+                    VERSION:2.0\r\n\
+                    \r\n\
+                    begin:vtodo\r\n\
+                    SUMMARY;LANGUAGE=en:Call mom\\, then\r\n  dad\r\n\
+                    X-NOTE;X-URL=\"https://example.com;a=b\";ALTREP:a:b\r\n\
+                    END:VTODO\r\n\
+                    END:VCALENDAR\r\n";
+        let roots = parse(text).unwrap();
+        assert_eq!(roots.len(), 1);
+        let calendar = &roots[0];
+        assert_eq!((calendar.name.as_str(), calendar.line), ("VCALENDAR", 1));
+        assert_eq!(calendar.property("VERSION").unwrap().value, "2.0");
+
+        let todo = &calendar.components[0];
+        assert_eq!((todo.name.as_str(), todo.line), ("VTODO", 4));
+        let summary = todo.property("SUMMARY").unwrap();
+        assert_eq!(summary.params, vec![("LANGUAGE".to_string(), "en".to_string())]);
+        assert_eq!(summary.value, "Call mom\\, then dad");
+        assert_eq!(unescape_text(&summary.value), "Call mom, then dad");
+        assert_eq!(
+            todo.property("X-NOTE").unwrap(),
+            &Property {
+                name: "X-NOTE".to_string(),
+                params: vec![
+                    ("X-URL".to_string(), "https://example.com;a=b".to_string()),
+                    ("ALTREP".to_string(), String::new()),
+                ],
+                value: "a:b".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn malformed_files() {
+        let cases = [
+            ("BEGIN:VCALENDAR\nnot a property\nEND:VCALENDAR", "line 2: not a content line"),
+            ("BEGIN:VCALENDAR\n;X=1:value\nEND:VCALENDAR", "line 2: not a content line"),
+            ("SUMMARY:Call mom", "line 1: SUMMARY is outside of any component"),
+            ("END:VTODO", "line 1: END:VTODO without a matching BEGIN"),
+            ("BEGIN:VCALENDAR\nBEGIN:VTODO\nEND:VCALENDAR", "line 3: END:VCALENDAR without a matching BEGIN"),
+            ("BEGIN:VCALENDAR\n\nBEGIN:VTODO\nEND:VTODO", "line 1: BEGIN:VCALENDAR is never closed"),
+        ];
+        for (text, error) in cases {
+            assert_eq!(parse(text), Err(error.to_string()), "{:?}", text);
+        }
+        assert_eq!(parse(""), Ok(vec![]));
+    }
+
+    #[test]
+    fn text_escapes_round_trip() {
+        let cases = [
+            ("plain", "plain"),
+            ("a, b; c", "a\\, b\\; c"),
+            ("back\\slash", "back\\\\slash"),
+            ("two\nlines", "two\\nlines"),
+        ];
+        for (text, escaped) in cases {
+            assert_eq!(escape_text(text), escaped);
+            assert_eq!(unescape_text(escaped), text);
+        }
+        assert_eq!(escape_text("crlf\r\nline"), "crlf\\nline");
+        assert_eq!(unescape_text("upper\\Ncase and trailing\\"), "upper\ncase and trailing\\");
+    }
+
+    #[test]
+    fn text_lists() {
+        assert_eq!(split_text_list("work,home"), ["work", "home"]);
+        assert_eq!(split_text_list("a\\,b,c\\\\,d"), ["a,b", "c\\", "d"]);
+        assert_eq!(split_text_list(""), [""]);
+    }
+
+    #[test]
+    fn folds_long_lines() {
+        let line = format!("DESCRIPTION:{}", "añ€😀".repeat(40));
+        let mut out = String::new();
+        write_line(&mut out, &line);
+
+        let physical: Vec<_> = out.strip_suffix("\r\n").unwrap().split("\r\n").collect();
+        assert!(physical.len() > 1);
+        assert!(physical.iter().all(|part| part.len() <= LINE_LIMIT), "{:?}", physical);
+        assert!(physical[1..].iter().all(|part| part.starts_with(' ')));
+        assert_eq!(unfold(&out), vec![(1, line)]);
+
+        let mut out = String::new();
+        write_line(&mut out, "VERSION:2.0");
+        assert_eq!(out, "VERSION:2.0\r\n");
+    }
+
+    #[test]
+    fn dates() {
+        let at = Utc.with_ymd_and_hms(2024, 5, 1, 9, 30, 0).unwrap();
+        let midnight = Utc.with_ymd_and_hms(2024, 5, 1, 0, 0, 0).unwrap();
+        assert_eq!(format_date_time(at), "20240501T093000Z");
+        assert_eq!(format_date(at.date_naive()), "20240501");
+
+        let cases = [
+            ("20240501T093000Z", Some(at)),
+            ("20240501T093000", Some(at)),
+            (" 20240501 ", Some(midnight)),
+            ("2024-05-01", None),
+            ("20240501T0930", None),
+            ("20241301", None),
+            ("", None),
+        ];
+        for (value, expected) in cases {
+            assert_eq!(parse_date_time(value), expected, "{:?}", value);
+        }
+    }
+}
@@ -1,27 +1,96 @@
 pub mod errors;
+pub mod csv;
 pub mod db;
+pub mod events;
 pub mod handlers;
+pub mod ical;
+pub mod markdown;
 pub mod models;
+pub mod patch;
+pub mod quick_add;
+pub mod rank;
+pub mod todo_txt;
+pub mod view_query;
+pub mod webdav;
 
 use axum::{
-    routing::get,
+    extract::{DefaultBodyLimit, FromRef},
+    routing::{any, get},
     Router,
 };
 use handlers::{ 
-    todos::{ all_todos, create_todo, delete_todo, update_todo, get_todo }, 
-    auth::{ login, register, logout } 
+    todos::{ all_todos, create_todo, delete_todo, update_todo, patch_todo, get_todo, move_todo, parse_todo }, 
+    auth::{ login, register, logout },
+    backup::{export_data, import_data},
+    archive::{
+        auto_archive_job, list_archive, archive_todo, unarchive_todo, get_settings,
+        update_settings,
+    },
+    bulk::bulk_todos,
+    caldav::{caldav, well_known_caldav},
+    calendar::{
+        calendar_feed, get_calendar_feed, reset_calendar_feed, delete_calendar_feed, import_ics,
+    },
+    custom_fields::{ list_custom_fields, create_custom_field, update_custom_field, delete_custom_field },
+    dependencies::{ todo_dependencies, add_blocker, remove_blocker },
+    live::{todo_socket, todo_event_stream},
+    projects::{ list_projects, create_project, update_project, delete_project },
+    revisions::{ todo_history, revert_todo },
+    service_imports::{import_todoist, import_trello, import_google_tasks, import_microsoft_todo},
+    spreadsheets::{export_csv, import_csv},
+    text_formats::{export_todo_txt, import_todo_txt, export_markdown, import_markdown},
+    stats::stats,
+    statuses::{ list_statuses, create_status, update_status, delete_status },
+    sync::{pull_changes, push_changes},
+    tags::list_tags,
+    views::{ list_views, create_view, update_view, delete_view },
+    templates::{
+        list_templates, create_template_from_todo, render_template, instantiate_template,
+        delete_template,
+    },
+    tokens::{ list_tokens, create_token, delete_token },
+    time_entries::{
+        current_timer, start_timer, stop_timer, todo_time_entries, create_time_entry,
+        update_time_entry, delete_time_entry, time_report,
+    },
 };
-use db::init_db;
+use db::{init_db, DB};
+use events::{prune_log_job, Events};
 use tower_http::cors::CorsLayer;
-use axum::routing::post;
+use axum::routing::{delete, post, put};
 use std::net::SocketAddr;
 use tracing::info;
 
+// Exports are larger than the default 2 MB request body limit.
+const IMPORT_BODY_LIMIT: usize = 50 * 1024 * 1024;
+
+// Handlers take the parts they need, as `State<DB>` or `State<Events>`.
+#[derive(Clone)]
+struct AppState {
+    db: DB,
+    events: Events,
+}
+
+impl FromRef<AppState> for DB {
+    fn from_ref(state: &AppState) -> Self {
+        state.db.clone()
+    }
+}
+
+impl FromRef<AppState> for Events {
+    fn from_ref(state: &AppState) -> Self {
+        state.events.clone()
+    }
+}
+
 #[tokio::main]
 async fn main() {
     tracing_subscriber::fmt::init();
 
     let db_pool = init_db().await.expect("failed to initialize database");
+    let events = Events::default();
+    tokio::spawn(auto_archive_job(db_pool.clone(), events.clone()));
+    tokio::spawn(prune_log_job(db_pool.clone()));
 
     let app = Router::new()
         // authentication
@@ -30,9 +99,79 @@ async fn main() {
         .route("/api/auth/logout", post(logout))
         // todos
         .route("/api/todos", get(all_todos).post(create_todo))
-        .route("/api/todos/:id", get(get_todo).put(update_todo).delete(delete_todo))
+        .route("/api/todos/bulk", post(bulk_todos))
+        .route("/api/todos/parse", post(parse_todo))
+        .route("/api/todos/:id", get(get_todo).put(update_todo).patch(patch_todo).delete(delete_todo))
+        .route("/api/todos/:id/move", post(move_todo))
+        .route("/api/todos/:id/dependencies", get(todo_dependencies))
+        .route("/api/todos/:id/blockers", post(add_blocker))
+        .route("/api/todos/:id/blockers/:blocker_id", delete(remove_blocker))
+        .route("/api/todos/:id/history", get(todo_history))
+        .route("/api/todos/:id/revert/:rev", post(revert_todo))
+        .route("/api/todos/:id/timer/start", post(start_timer))
+        .route("/api/todos/:id/time-entries", get(todo_time_entries).post(create_time_entry))
+        .route("/api/todos/:id/template", post(create_template_from_todo))
+        .route("/api/todos/:id/archive", post(archive_todo))
+        .route("/api/todos/:id/unarchive", post(unarchive_todo))
+        // live updates
+        .route("/api/ws", get(todo_socket))
+        .route("/api/events", get(todo_event_stream))
+        // offline sync
+        .route("/api/sync", get(pull_changes).post(push_changes))
+        // archive and settings
+        .route("/api/archive", get(list_archive))
+        .route("/api/settings", get(get_settings).put(update_settings))
+        // export and import
+        .route("/api/export", get(export_data))
+        .route("/api/import", post(import_data).layer(DefaultBodyLimit::max(IMPORT_BODY_LIMIT)))
+        .route("/api/export/csv", get(export_csv))
+        .route("/api/import/csv", post(import_csv).layer(DefaultBodyLimit::max(IMPORT_BODY_LIMIT)))
+        .route("/api/export.txt", get(export_todo_txt))
+        .route("/api/import/todotxt", post(import_todo_txt).layer(DefaultBodyLimit::max(IMPORT_BODY_LIMIT)))
+        .route("/api/export.md", get(export_markdown))
+        .route("/api/import/markdown", post(import_markdown).layer(DefaultBodyLimit::max(IMPORT_BODY_LIMIT)))
+        .route("/api/import/todoist", post(import_todoist).layer(DefaultBodyLimit::max(IMPORT_BODY_LIMIT)))
+        .route("/api/import/trello", post(import_trello).layer(DefaultBodyLimit::max(IMPORT_BODY_LIMIT)))
+        .route("/api/import/google-tasks", post(import_google_tasks).layer(DefaultBodyLimit::max(IMPORT_BODY_LIMIT)))
+        .route("/api/import/microsoft-todo", post(import_microsoft_todo).layer(DefaultBodyLimit::max(IMPORT_BODY_LIMIT)))
+        // calendar feed
+        .route("/api/calendar.ics", get(calendar_feed))
+        .route("/api/calendar/feed", get(get_calendar_feed).post(reset_calendar_feed).delete(delete_calendar_feed))
+        .route("/api/import/ics", post(import_ics).layer(DefaultBodyLimit::max(IMPORT_BODY_LIMIT)))
+        // API tokens
+        .route("/api/tokens", get(list_tokens).post(create_token))
+        .route("/api/tokens/:id", delete(delete_token))
+        // time tracking
+        .route("/api/timer", get(current_timer))
+        .route("/api/timer/stop", post(stop_timer))
+        .route("/api/time-entries/:id", put(update_time_entry).delete(delete_time_entry))
+        .route("/api/reports/time", get(time_report))
+        .route("/api/stats", get(stats))
+        // workflow statuses
+        .route("/api/statuses", get(list_statuses).post(create_status))
+        .route("/api/statuses/:id", put(update_status).delete(delete_status))
+        // projects and tags
+        .route("/api/projects", get(list_projects).post(create_project))
+        .route("/api/projects/:id", put(update_project).delete(delete_project))
+        .route("/api/tags", get(list_tags))
+        // templates
+        .route("/api/templates", get(list_templates))
+        .route("/api/templates/:id", delete(delete_template))
+        .route("/api/templates/:id/render", post(render_template))
+        .route("/api/templates/:id/instantiate", post(instantiate_template))
+        // saved views
+        .route("/api/views", get(list_views).post(create_view))
+        .route("/api/views/:id", put(update_view).delete(delete_view))
+        // custom fields
+        .route("/api/custom-fields", get(list_custom_fields).post(create_custom_field))
+        .route("/api/custom-fields/:id", put(update_custom_field).delete(delete_custom_field))
         .layer(CorsLayer::very_permissive())
-        .with_state(db_pool);
+        // CalDAV, added after the CORS layer since it answers OPTIONS itself
+        .route("/.well-known/caldav", any(well_known_caldav))
+        .route("/dav", any(caldav))
+        .route("/dav/", any(caldav))
+        .route("/dav/*path", any(caldav))
+        .with_state(AppState { db: db_pool, events });
 
     let addr = SocketAddr::from(([127, 0, 0, 1], 3000));
     info!("listening on {}", addr);
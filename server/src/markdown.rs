@@ -0,0 +1,241 @@
+//! GitHub-flavoured Markdown task lists, as written by the Markdown export and read back by its
+//! importer.
+//!
+//! Tasks are `- [ ] text` and `- [x] text` list items, nested by indentation. Lines indented under
+//! a task that are not tasks themselves make up its description, and headings name the group the
+//! tasks below them belong to.
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Item {
+    /// Line of the task, counting from 1.
+    pub line: usize,
+    /// Index of the task this one is nested under.
+    pub parent: Option<usize>,
+    pub completed: bool,
+    pub text: String,
+    pub description: Option<String>,
+    /// The closest heading above the task.
+    pub heading: Option<Heading>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Heading {
+    /// 1 for `#`, 2 for `##` and so on.
+    pub level: usize,
+    pub text: String,
+}
+
+// Width of leading whitespace, with tabs as four spaces.
+fn indent(line: &str) -> usize {
+    line.chars()
+        .take_while(|c| c.is_whitespace())
+        .map(|c| if c == '\t' { 4 } else { 1 })
+        .sum()
+}
+
+// The text after a `-`, `*`, `+`, `1.` or `1)` list marker.
+fn list_item(line: &str) -> Option<&str> {
+    let digits = line.len() - line.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    let rest = match digits {
+        0 => line.strip_prefix(['-', '*', '+'])?,
+        _ => line[digits..].strip_prefix(['.', ')'])?,
+    };
+    match rest.strip_prefix([' ', '\t']) {
+        Some(text) => Some(text.trim()),
+        None if rest.is_empty() => Some(""),
+        None => None,
+    }
+}
+
+// Whether an item is a task, and its text if so.
+fn task(item: &str) -> Option<(bool, &str)> {
+    let completed = match item.get(..3)? {
+        "[ ]" => false,
+        "[x]" | "[X]" => true,
+        _ => return None,
+    };
+    let text = &item[3..];
+    (text.is_empty() || text.starts_with([' ', '\t'])).then(|| (completed, text.trim()))
+}
+
+/// Reads the tasks of a document in order. List items without a checkbox are kept as
+/// description lines when they sit under a task and are skipped otherwise.
+pub fn parse(text: &str) -> Vec<Item> {
+    let mut items: Vec<Item> = vec![];
+    // Indentation and index of the open tasks, outermost first.
+    let mut open: Vec<(usize, usize)> = vec![];
+    let mut heading = None;
+
+    for (index, line) in text.trim_start_matches('\u{feff}').lines().enumerate() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+        let width = indent(line);
+        // Headings are only read at the start of a line, so description lines may start with `#`.
+        if width == 0 && trimmed.starts_with('#') {
+            let text = trimmed.trim_start_matches('#');
+            let level = trimmed.len() - text.len();
+            heading = Some(text.trim()).filter(|h| !h.is_empty()).map(|text| Heading { level, text: text.to_string() });
+            open.clear();
+            continue;
+        }
+
+        if let Some((completed, text)) = list_item(trimmed).and_then(task) {
+            while open.last().is_some_and(|&(outer, _)| outer >= width) {
+                open.pop();
+            }
+            open.push((width, items.len()));
+            items.push(Item {
+                line: index + 1,
+                parent: open.iter().rev().nth(1).map(|&(_, parent)| parent),
+                completed,
+                text: text.to_string(),
+                description: None,
+                heading: heading.clone(),
+            });
+            continue;
+        }
+
+        // Anything else indented under a task belongs to the innermost task it is indented past;
+        // unindented text ends the list.
+        match open.iter().rev().find(|&&(outer, _)| outer < width) {
+            Some(&(_, owner)) => {
+                let description = items[owner].description.get_or_insert_with(String::new);
+                if !description.is_empty() {
+                    description.push('\n');
+                }
+                description.push_str(trimmed);
+            }
+            None => open.clear(),
+        }
+    }
+    items
+}
+
+pub fn write_heading(out: &mut String, level: usize, text: &str) {
+    if !out.is_empty() && !out.ends_with("\n\n") {
+        out.push('\n');
+    }
+    out.push_str(&format!("{} {}\n\n", "#".repeat(level), text));
+}
+
+/// Appends a task at the given nesting depth, with its description indented beneath it.
+pub fn write_item(out: &mut String, depth: usize, completed: bool, text: &str, description: Option<&str>) {
+    let indent = "  ".repeat(depth);
+    let mark = if completed { 'x' } else { ' ' };
+    // Line breaks would end the item early.
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    out.push_str(&format!("{}- [{}] {}\n", indent, mark, text));
+    for line in description.unwrap_or_default().lines().map(str::trim).filter(|line| !line.is_empty()) {
+        out.push_str(&format!("{}  {}\n", indent, line));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(line: usize, parent: Option<usize>, completed: bool, text: &str, heading: Option<(usize, &str)>) -> Item {
+        Item {
+            line,
+            parent,
+            completed,
+            text: text.to_string(),
+            description: None,
+            heading: heading.map(|(level, text)| Heading { level, text: text.to_string() }),
+        }
+    }
+
+    fn described(item: Item, description: &str) -> Item {
+        Item { description: Some(description.to_string()), ..item }
+    }
+
+    #[test]
+    fn reads_nested_tasks() {
+        let text = "\u{feff}# Home\n\
+                    \n\
+                    - [ ] Groceries\n  \
+                      - [x] Milk\n    \
+                        2% only\n  \
+                      - [ ] Eggs\n  \
+                      Before Friday\n\
+                    * [X] Laundry\n\
+                    1. [ ] Numbered\n\
+                    Loose paragraph\n  \
+                      - [ ] After the paragraph\n\
+                    ## Work\n\
+                    \t- [ ] Tabbed\n";
+        assert_eq!(
+            parse(text),
+            vec![
+                described(item(3, None, false, "Groceries", Some((1, "Home"))), "Before Friday"),
+                described(item(4, Some(0), true, "Milk", Some((1, "Home"))), "2% only"),
+                item(6, Some(0), false, "Eggs", Some((1, "Home"))),
+                item(8, None, true, "Laundry", Some((1, "Home"))),
+                item(9, None, false, "Numbered", Some((1, "Home"))),
+                item(11, None, false, "After the paragraph", Some((1, "Home"))),
+                item(13, None, false, "Tabbed", Some((2, "Work"))),
+            ]
+        );
+    }
+
+    #[test]
+    fn items_that_are_not_tasks() {
+        let text = "- [ ] Task\n  \
+                      - plain item\n  \
+                      - [y] odd mark\n  \
+                      - [x]no space\n  \
+                      -[ ] no space after the marker\n  \
+                      # not a heading\n\
+                    - plain item at the top\n\
+                    -\n\
+                    1) [ ]\n";
+        assert_eq!(
+            parse(text),
+            vec![
+                described(
+                    item(1, None, false, "Task", None),
+                    "- plain item\n- [y] odd mark\n- [x]no space\n-[ ] no space after the marker\n# not a heading"
+                ),
+                item(9, None, false, "", None),
+            ]
+        );
+        assert_eq!(parse(""), vec![]);
+        assert_eq!(parse("#\n\n- [ ] Untitled heading"), vec![item(3, None, false, "Untitled heading", None)]);
+    }
+
+    #[test]
+    fn written_tasks_read_back() {
+        let mut out = String::new();
+        write_heading(&mut out, 1, "Home");
+        write_item(&mut out, 0, false, "Groceries", Some("For the week\n\n  Before Friday  "));
+        write_item(&mut out, 1, true, "Milk\nand cream", None);
+        write_item(&mut out, 2, false, "Organic", Some(""));
+        write_heading(&mut out, 2, "Work");
+        write_item(&mut out, 0, false, "Report", None);
+        assert_eq!(
+            out,
+            "# Home\n\
+             \n\
+             - [ ] Groceries\n  \
+               For the week\n  \
+               Before Friday\n  \
+               - [x] Milk and cream\n    \
+                 - [ ] Organic\n\
+             \n\
+             ## Work\n\
+             \n\
+             - [ ] Report\n"
+        );
+        assert_eq!(
+            parse(&out),
+            vec![
+                described(item(3, None, false, "Groceries", Some((1, "Home"))), "For the week\nBefore Friday"),
+                item(6, Some(0), true, "Milk and cream", Some((1, "Home"))),
+                item(7, Some(1), false, "Organic", Some((1, "Home"))),
+                item(11, None, false, "Report", Some((2, "Work"))),
+            ]
+        );
+    }
+}
@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
 use chrono::{DateTime, Utc};
+use sqlx::{sqlite::{SqliteRow, SqliteTypeInfo, SqliteValueRef}, FromRow, Row, Sqlite};
+use std::collections::{BTreeMap, HashMap};
 use uuid::Uuid;
 
 #[derive(Debug, Serialize, Deserialize, Clone, sqlx::FromRow)]
@@ -10,7 +12,7 @@ pub struct User {
     pub created_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, sqlx::FromRow)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Todo {
     pub id: Uuid,
     pub user_id: Uuid,
@@ -20,9 +22,103 @@ pub struct Todo {
     pub priority: Priority,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    // Defaulted so revision snapshots written before these columns existed still deserialize.
+    #[serde(default)]
+    pub version: i64,
+    #[serde(default)]
+    pub position: String,
+    #[serde(default)]
+    pub status_id: Option<Uuid>,
+    #[serde(default)]
+    pub estimated_minutes: Option<i64>,
+    #[serde(default)]
+    pub custom_fields: FieldValues,
+    /// When the todo was last completed; cleared when it is reopened.
+    #[serde(default)]
+    pub completed_at: Option<DateTime<Utc>>,
+    /// Set while the todo is archived, which hides it from the list and its counts.
+    #[serde(default)]
+    pub archived_at: Option<DateTime<Utc>>,
+    /// Derived: whether any incomplete todo blocks this one.
+    #[serde(default)]
+    pub blocked: bool,
+    #[serde(default)]
+    pub project_id: Option<Uuid>,
+    /// Sorted, without duplicates.
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+impl FromRow<'_, SqliteRow> for Todo {
+    fn from_row(row: &SqliteRow) -> Result<Self, sqlx::Error> {
+        Ok(Todo {
+            id: uuid_column(row, "id")?,
+            user_id: uuid_column(row, "user_id")?,
+            title: row.try_get("title")?,
+            description: row.try_get("description")?,
+            completed: row.try_get("completed")?,
+            priority: row.try_get("priority")?,
+            created_at: row.try_get("created_at")?,
+            updated_at: row.try_get("updated_at")?,
+            version: row.try_get("version")?,
+            position: row.try_get("position")?,
+            status_id: optional_uuid_column(row, "status_id")?,
+            estimated_minutes: row.try_get("estimated_minutes")?,
+            custom_fields: row.try_get("custom_fields")?,
+            completed_at: row.try_get("completed_at")?,
+            archived_at: row.try_get("archived_at")?,
+            blocked: row.try_get("blocked")?,
+            project_id: optional_uuid_column(row, "project_id")?,
+            tags: json_column(row, "tags")?,
+        })
+    }
+}
+
+/// Reads an id column. Ids are stored as TEXT, while sqlx's `Uuid` decoding expects a 16-byte blob.
+pub(crate) fn uuid_column(row: &SqliteRow, column: &str) -> Result<Uuid, sqlx::Error> {
+    let value: String = row.try_get(column)?;
+    parse_uuid_column(column, &value)
+}
+
+pub(crate) fn optional_uuid_column(row: &SqliteRow, column: &str) -> Result<Option<Uuid>, sqlx::Error> {
+    let value: Option<String> = row.try_get(column)?;
+    value.map(|value| parse_uuid_column(column, &value)).transpose()
+}
+
+/// Reads a column holding JSON, such as one built with `json_group_array`.
+fn json_column<T: serde::de::DeserializeOwned>(row: &SqliteRow, column: &str) -> Result<T, sqlx::Error> {
+    let value: String = row.try_get(column)?;
+    serde_json::from_str(&value).map_err(|e| sqlx::Error::ColumnDecode { index: column.to_string(), source: Box::new(e) })
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, sqlx::Type)]
+fn parse_uuid_column(column: &str, value: &str) -> Result<Uuid, sqlx::Error> {
+    Uuid::parse_str(value).map_err(|e| sqlx::Error::ColumnDecode { index: column.to_string(), source: Box::new(e) })
+}
+
+/// Custom field values keyed by field key. Read from the database as the JSON object built by
+/// `json_group_object` in `TODO_COLUMNS`.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(transparent)]
+pub struct FieldValues(pub BTreeMap<String, serde_json::Value>);
+
+impl sqlx::Type<Sqlite> for FieldValues {
+    fn type_info() -> SqliteTypeInfo {
+        <String as sqlx::Type<Sqlite>>::type_info()
+    }
+
+    fn compatible(ty: &SqliteTypeInfo) -> bool {
+        <String as sqlx::Type<Sqlite>>::compatible(ty)
+    }
+}
+
+impl<'r> sqlx::Decode<'r, Sqlite> for FieldValues {
+    fn decode(value: SqliteValueRef<'r>) -> Result<Self, sqlx::error::BoxDynError> {
+        let json = <&str as sqlx::Decode<Sqlite>>::decode(value)?;
+        Ok(serde_json::from_str(json)?)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, sqlx::Type)]
 #[sqlx(type_name = "priority", rename_all = "lowercase")]
 pub enum Priority {
     Low,
@@ -30,19 +126,165 @@ pub enum Priority {
     High,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct CreateTodo {
     pub title: String,
     pub description: Option<String>,
     pub priority: Priority,
+    #[serde(default)]
+    pub status_id: Option<Uuid>,
+    #[serde(default)]
+    pub estimated_minutes: Option<i64>,
+    #[serde(default)]
+    pub custom_fields: FieldValues,
+    #[serde(default)]
+    pub project_id: Option<Uuid>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, sqlx::Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "category", rename_all = "lowercase")]
+pub enum StatusCategory {
+    Todo,
+    Doing,
+    Done,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Status {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub name: String,
+    pub position: i64,
+    pub category: StatusCategory,
+    pub created_at: DateTime<Utc>,
+    /// The project whose workflow this status is part of; `None` for the shared workflow.
+    #[serde(default)]
+    pub project_id: Option<Uuid>,
+}
+
+impl FromRow<'_, SqliteRow> for Status {
+    fn from_row(row: &SqliteRow) -> Result<Self, sqlx::Error> {
+        Ok(Status {
+            id: uuid_column(row, "id")?,
+            user_id: uuid_column(row, "user_id")?,
+            name: row.try_get("name")?,
+            position: row.try_get("position")?,
+            category: row.try_get("category")?,
+            created_at: row.try_get("created_at")?,
+            project_id: optional_uuid_column(row, "project_id")?,
+        })
+    }
 }
 
 #[derive(Debug, Deserialize)]
-pub struct UpdateTodo {
-    pub title: Option<String>,
+pub struct CreateStatus {
+    pub name: String,
+    pub category: StatusCategory,
+    /// Defaults to after the last status of its workflow.
+    pub position: Option<i64>,
+    /// Adds the status to this project's workflow, which starts as a copy of the shared one.
+    #[serde(default)]
+    pub project_id: Option<Uuid>,
+}
+
+/// Query for `GET /api/statuses`.
+#[derive(Debug, Deserialize, Default)]
+pub struct StatusQuery {
+    /// List the workflow this project's todos use instead of the shared one.
+    pub project_id: Option<Uuid>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateStatus {
+    pub name: Option<String>,
+    pub category: Option<StatusCategory>,
+    pub position: Option<i64>,
+}
+
+/// A named group of todos. Each todo is in at most one project.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Project {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub name: String,
+    pub position: i64,
+    pub created_at: DateTime<Utc>,
+}
+
+impl FromRow<'_, SqliteRow> for Project {
+    fn from_row(row: &SqliteRow) -> Result<Self, sqlx::Error> {
+        Ok(Project {
+            id: uuid_column(row, "id")?,
+            user_id: uuid_column(row, "user_id")?,
+            name: row.try_get("name")?,
+            position: row.try_get("position")?,
+            created_at: row.try_get("created_at")?,
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateProject {
+    pub name: String,
+    /// Defaults to after the user's last project.
+    pub position: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateProject {
+    pub name: Option<String>,
+    pub position: Option<i64>,
+}
+
+/// A tag with the number of unarchived todos carrying it.
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct TagCount {
+    pub tag: String,
+    pub count: i64,
+}
+
+/// Body of `PUT /api/todos/:id`. Every editable field is replaced, so an omitted
+/// `description` clears it. `PATCH` applies its patch to this shape as well.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ReplaceTodo {
+    pub title: String,
+    #[serde(default)]
     pub description: Option<String>,
-    pub completed: Option<bool>,
-    pub priority: Option<Priority>,
+    #[serde(default)]
+    pub completed: bool,
+    pub priority: Priority,
+    /// When omitted or unchanged, the status follows `completed`; otherwise `completed`
+    /// follows the new status's category.
+    #[serde(default)]
+    pub status_id: Option<Uuid>,
+    #[serde(default)]
+    pub estimated_minutes: Option<i64>,
+    #[serde(default)]
+    pub custom_fields: FieldValues,
+    #[serde(default)]
+    pub project_id: Option<Uuid>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+impl From<&Todo> for ReplaceTodo {
+    fn from(todo: &Todo) -> Self {
+        ReplaceTodo {
+            title: todo.title.clone(),
+            description: todo.description.clone(),
+            completed: todo.completed,
+            priority: todo.priority.clone(),
+            status_id: todo.status_id,
+            estimated_minutes: todo.estimated_minutes,
+            custom_fields: todo.custom_fields.clone(),
+            project_id: todo.project_id,
+            tags: todo.tags.clone(),
+        }
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -60,4 +302,722 @@ pub struct LoginUser {
 #[derive(Debug, Serialize)]
 pub struct AuthResponse {
     pub token: String,
-} 
\ No newline at end of file
+} 
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct FieldChange {
+    pub field: String,
+    pub old: serde_json::Value,
+    pub new: serde_json::Value,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct TodoRevision {
+    pub id: Uuid,
+    pub todo_id: Uuid,
+    pub revision: i64,
+    pub actor_id: Uuid,
+    pub changes: Vec<FieldChange>,
+    pub snapshot: Todo,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Body of `POST /api/todos/:id/move`. `after` is the todo that should precede the moved one and
+/// `before` the one that should follow it; either may be omitted at the ends of the list.
+#[derive(Debug, Deserialize)]
+pub struct MoveTodo {
+    pub before: Option<Uuid>,
+    pub after: Option<Uuid>,
+}
+
+/// Query options for `POST /api/todos`.
+#[derive(Debug, Deserialize, Default)]
+pub struct CreateOptions {
+    /// Parse the title as quick-add text, taking priority and other metadata out of it.
+    #[serde(default)]
+    pub parse: bool,
+}
+
+/// Query options for `PUT`/`PATCH /api/todos/:id`.
+#[derive(Debug, Deserialize, Default)]
+pub struct WriteOptions {
+    /// Complete the todo even if it still has open blockers.
+    #[serde(default)]
+    pub force: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LinkBlocker {
+    pub blocker_id: Uuid,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TodoDependencies {
+    pub blockers: Vec<Todo>,
+    pub dependents: Vec<Todo>,
+}
+
+#[derive(Debug, Deserialize, Default)]
+pub struct TodoFilter {
+    pub completed: Option<bool>,
+    pub priority: Option<Priority>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BulkSelection {
+    Ids(Vec<Uuid>),
+    Filter(TodoFilter),
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum BulkOperation {
+    Complete,
+    Uncomplete,
+    Delete,
+    SetPriority { priority: Priority },
+    /// `null` takes the todos out of their project.
+    MoveToProject { project_id: Option<Uuid> },
+    AddTag { tag: String },
+    RemoveTag { tag: String },
+}
+
+/// Body of `POST /api/todos/bulk`, e.g. `{"ids": [...], "operations": [{"op": "complete"}]}`
+/// or `{"filter": {"completed": true}, "operations": [{"op": "delete"}]}`.
+#[derive(Debug, Deserialize)]
+pub struct BulkRequest {
+    #[serde(flatten)]
+    pub selection: BulkSelection,
+    pub operations: Vec<BulkOperation>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BulkItemStatus {
+    Updated,
+    Deleted,
+    NotFound,
+    Conflict,
+    Blocked,
+    /// The item itself was fine but another item failed, so the whole batch was rolled back.
+    RolledBack,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BulkItemResult {
+    pub id: Uuid,
+    pub status: BulkItemStatus,
+    pub todo: Option<Todo>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BulkResponse {
+    pub applied: bool,
+    pub results: Vec<BulkItemResult>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TimeEntry {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub todo_id: Uuid,
+    pub started_at: DateTime<Utc>,
+    /// `None` while the timer is running.
+    pub ended_at: Option<DateTime<Utc>>,
+    pub note: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl FromRow<'_, SqliteRow> for TimeEntry {
+    fn from_row(row: &SqliteRow) -> Result<Self, sqlx::Error> {
+        Ok(TimeEntry {
+            id: uuid_column(row, "id")?,
+            user_id: uuid_column(row, "user_id")?,
+            todo_id: uuid_column(row, "todo_id")?,
+            started_at: row.try_get("started_at")?,
+            ended_at: row.try_get("ended_at")?,
+            note: row.try_get("note")?,
+            created_at: row.try_get("created_at")?,
+        })
+    }
+}
+
+impl TimeEntry {
+    /// Tracked seconds, counting a running timer up to `now`.
+    pub fn duration_seconds(&self, now: DateTime<Utc>) -> i64 {
+        (self.ended_at.unwrap_or(now) - self.started_at).num_seconds().max(0)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateTimeEntry {
+    pub started_at: DateTime<Utc>,
+    pub ended_at: DateTime<Utc>,
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateTimeEntry {
+    pub started_at: Option<DateTime<Utc>>,
+    pub ended_at: Option<DateTime<Utc>>,
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Default)]
+pub struct TimeReportQuery {
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    /// `csv` for a spreadsheet-friendly download; JSON otherwise.
+    pub format: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TimeReportRow {
+    pub day: chrono::NaiveDate,
+    pub todo_id: Uuid,
+    pub title: String,
+    pub project_id: Option<Uuid>,
+    /// The project's name, for the CSV export.
+    pub project: Option<String>,
+    pub tags: Vec<String>,
+    pub seconds: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DayTotal {
+    pub day: chrono::NaiveDate,
+    pub seconds: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TodoTotal {
+    pub todo_id: Uuid,
+    pub title: String,
+    pub seconds: i64,
+}
+
+/// Time on todos in a project, or outside any with no `project_id`.
+#[derive(Debug, Serialize)]
+pub struct ProjectTotal {
+    pub project_id: Option<Uuid>,
+    pub name: Option<String>,
+    pub seconds: i64,
+}
+
+/// Time on todos with a tag. A todo's time counts toward each of its tags.
+#[derive(Debug, Serialize)]
+pub struct TagTotal {
+    pub tag: String,
+    pub seconds: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TimeReport {
+    pub total_seconds: i64,
+    pub by_day: Vec<DayTotal>,
+    pub by_todo: Vec<TodoTotal>,
+    pub by_project: Vec<ProjectTotal>,
+    pub by_tag: Vec<TagTotal>,
+    /// One row per todo per day, which is also the shape of the CSV export.
+    pub rows: Vec<TimeReportRow>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, sqlx::Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "field_type", rename_all = "lowercase")]
+pub enum FieldType {
+    Text,
+    Number,
+    Date,
+    Select,
+    Checkbox,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CustomField {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    /// Stable identifier used in `Todo::custom_fields` and in `cf.<key>` filters.
+    pub key: String,
+    pub name: String,
+    pub field_type: FieldType,
+    /// Allowed values for `select` fields.
+    pub options: Vec<String>,
+    pub position: i64,
+    pub created_at: DateTime<Utc>,
+    /// Limits the field to todos in this project; `None` applies it to every todo.
+    #[serde(default)]
+    pub project_id: Option<Uuid>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateCustomField {
+    pub key: String,
+    pub name: String,
+    pub field_type: FieldType,
+    #[serde(default)]
+    pub options: Vec<String>,
+    pub position: Option<i64>,
+    #[serde(default)]
+    pub project_id: Option<Uuid>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateCustomField {
+    pub name: Option<String>,
+    pub options: Option<Vec<String>>,
+    pub position: Option<i64>,
+}
+
+/// Query for `GET /api/todos`.
+#[derive(Debug, Deserialize, Default)]
+pub struct ListTodosQuery {
+    /// A filter expression, see `view_query`.
+    pub q: Option<String>,
+    /// A saved view whose expression filters the list.
+    pub view: Option<Uuid>,
+    /// Only todos completed at or after this time (RFC 3339, or a `YYYY-MM-DD` date in UTC).
+    pub completed_after: Option<String>,
+    /// Only todos completed before this time, in the same formats.
+    pub completed_before: Option<String>,
+    /// `cf.<key>` to sort by a custom field, prefixed with `-` for descending order.
+    pub sort: Option<String>,
+    /// Custom field filters: `cf.<key>=<value>`, or `cf.<key>=<op>:<value>` with `op` one of
+    /// `gt`, `gte`, `lt`, `lte`.
+    #[serde(flatten)]
+    pub fields: HashMap<String, String>,
+}
+
+/// A reusable todo, instantiated with `{{placeholder}}` substitution.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Template {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub name: String,
+    pub todo: CreateTodo,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateTemplate {
+    pub name: String,
+}
+
+#[derive(Debug, Deserialize, Default)]
+pub struct InstantiateTemplate {
+    /// Placeholder values. `date` defaults to today and `name` to the template's name.
+    #[serde(default)]
+    pub variables: HashMap<String, String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ParseTodo {
+    pub text: String,
+}
+
+/// Result of parsing quick-add text. `todo` already carries the tags; creating with `?parse=true`
+/// also stores the due date in the `due` date field and files the todo under `project`.
+/// Recurrence is recognized but todos have no field for it.
+#[derive(Debug, Serialize)]
+pub struct ParsedTodo {
+    pub todo: CreateTodo,
+    pub due: Option<DateTime<Utc>>,
+    pub tags: Vec<String>,
+    pub project: Option<String>,
+    pub recurrence: Option<String>,
+    pub spans: Vec<crate::quick_add::Span>,
+}
+
+/// A named filter expression, listed with the number of todos it currently matches.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SavedView {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub name: String,
+    pub query: String,
+    pub position: i64,
+    pub created_at: DateTime<Utc>,
+    #[serde(default)]
+    pub count: i64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateSavedView {
+    pub name: String,
+    pub query: String,
+    pub position: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateSavedView {
+    pub name: Option<String>,
+    pub query: Option<String>,
+    pub position: Option<i64>,
+}
+
+/// Query for `GET /api/stats`.
+#[derive(Debug, Deserialize)]
+pub struct StatsQuery {
+    #[serde(default = "default_stats_days")]
+    pub days: i64,
+    #[serde(default = "default_stats_weeks")]
+    pub weeks: i64,
+}
+
+fn default_stats_days() -> i64 {
+    30
+}
+
+fn default_stats_weeks() -> i64 {
+    12
+}
+
+/// Completions in one day (`YYYY-MM-DD`) or week (the week's Monday).
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct PeriodCount {
+    pub period: String,
+    pub count: i64,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct PriorityCount {
+    pub priority: Priority,
+    pub count: i64,
+}
+
+/// Productivity statistics. Days and weeks are UTC, and every period in range is listed,
+/// including those without completions.
+#[derive(Debug, Serialize)]
+pub struct Stats {
+    pub completed_per_day: Vec<PeriodCount>,
+    pub completed_per_week: Vec<PeriodCount>,
+    /// Mean time from creation to completion over all completed todos.
+    pub average_completion_seconds: Option<f64>,
+    pub open_by_priority: Vec<PriorityCount>,
+    /// Open todos whose `due` date field is before today.
+    pub overdue: i64,
+    /// Consecutive days with at least one completion, ending today or yesterday.
+    pub current_streak: i64,
+    pub longest_streak: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, sqlx::FromRow)]
+pub struct UserSettings {
+    /// Archive completed todos this many days after completion. `None` turns it off.
+    pub auto_archive_days: Option<i64>,
+}
+
+/// Query for `GET /api/archive`.
+#[derive(Debug, Deserialize)]
+pub struct ArchiveQuery {
+    /// Matched against title and description.
+    pub q: Option<String>,
+    #[serde(default = "default_archive_limit")]
+    pub limit: i64,
+    #[serde(default)]
+    pub offset: i64,
+}
+
+fn default_archive_limit() -> i64 {
+    50
+}
+
+/// Query for `GET /api/sync`.
+#[derive(Debug, Deserialize)]
+pub struct SyncQuery {
+    /// Cursor from the previous sync; 0 fetches everything.
+    #[serde(default)]
+    pub since: i64,
+    #[serde(default = "default_sync_limit")]
+    pub limit: i64,
+}
+
+fn default_sync_limit() -> i64 {
+    500
+}
+
+/// The latest change to a todo. `todo` is its current state, left out when it was deleted.
+#[derive(Debug, Serialize)]
+pub struct SyncChange {
+    pub seq: i64,
+    pub id: Uuid,
+    pub deleted: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub todo: Option<Todo>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SyncChanges {
+    pub changes: Vec<SyncChange>,
+    /// `since` for the next request.
+    pub cursor: i64,
+    /// More changes are waiting past `cursor`.
+    pub has_more: bool,
+}
+
+/// A write made by a client while offline, against the version it last saw.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum SyncMutation {
+    /// Creates a todo with an id chosen by the client.
+    Create { id: Uuid, todo: CreateTodo },
+    Update { id: Uuid, version: i64, todo: ReplaceTodo },
+    Delete { id: Uuid, version: i64 },
+}
+
+impl SyncMutation {
+    pub fn id(&self) -> Uuid {
+        match self {
+            SyncMutation::Create { id, .. } | SyncMutation::Update { id, .. } | SyncMutation::Delete { id, .. } => *id,
+        }
+    }
+}
+
+/// Body of `POST /api/sync`.
+#[derive(Debug, Deserialize)]
+pub struct SyncRequest {
+    /// The client's cursor before these mutations.
+    #[serde(default)]
+    pub cursor: i64,
+    pub mutations: Vec<SyncMutation>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncStatus {
+    Applied,
+    /// The todo changed since `version`, or the id of a new todo is already in use.
+    Conflict,
+    NotFound,
+    /// Completing the todo was refused because open todos block it.
+    Blocked,
+    Invalid,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SyncResult {
+    pub id: Uuid,
+    pub status: SyncStatus,
+    /// The todo as it is now on the server, if it exists.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub todo: Option<Todo>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SyncResponse {
+    /// One result per mutation, in order.
+    pub results: Vec<SyncResult>,
+    /// Past this batch's own changes, or the request's cursor when other changes came in
+    /// between, so that a pull from it picks them up.
+    pub cursor: i64,
+}
+
+/// Version of the document written by `GET /api/export`. Bump it when the shape changes.
+pub const EXPORT_FORMAT_VERSION: u32 = 2;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub struct Dependency {
+    pub blocker_id: Uuid,
+    pub blocked_id: Uuid,
+}
+
+/// A user's full data set, as written by `GET /api/export` and read by `POST /api/import`.
+/// Ids are the exporting instance's; the importer maps them onto its own.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExportDocument {
+    pub version: u32,
+    pub exported_at: DateTime<Utc>,
+    #[serde(default)]
+    pub settings: UserSettings,
+    #[serde(default)]
+    pub projects: Vec<Project>,
+    #[serde(default)]
+    pub statuses: Vec<Status>,
+    #[serde(default)]
+    pub custom_fields: Vec<CustomField>,
+    #[serde(default)]
+    pub templates: Vec<Template>,
+    #[serde(default)]
+    pub saved_views: Vec<SavedView>,
+    #[serde(default)]
+    pub dependencies: Vec<Dependency>,
+    #[serde(default)]
+    pub time_entries: Vec<TimeEntry>,
+    // Last, and skipped when empty, so the export can stream todos after everything else.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub todos: Vec<Todo>,
+}
+
+/// What to do with an imported record that is already stored.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ConflictStrategy {
+    /// Keep the stored record.
+    #[default]
+    Skip,
+    /// Replace the stored record's fields with the imported ones.
+    Overwrite,
+    /// Import the record again under a new id.
+    Duplicate,
+}
+
+/// Query options for `POST /api/import`.
+#[derive(Debug, Deserialize, Default)]
+pub struct ImportOptions {
+    /// Run the import and report on it, then roll it back.
+    #[serde(default)]
+    pub dry_run: bool,
+    #[serde(default)]
+    pub strategy: ConflictStrategy,
+}
+
+#[derive(Debug, Serialize, Default)]
+pub struct ImportCounts {
+    pub created: u64,
+    pub updated: u64,
+    pub skipped: u64,
+}
+
+#[derive(Debug, Serialize, Default)]
+pub struct ImportReport {
+    pub dry_run: bool,
+    pub strategy: ConflictStrategy,
+    pub projects: ImportCounts,
+    pub statuses: ImportCounts,
+    pub custom_fields: ImportCounts,
+    pub todos: ImportCounts,
+    pub dependencies: ImportCounts,
+    pub time_entries: ImportCounts,
+    pub templates: ImportCounts,
+    pub saved_views: ImportCounts,
+    /// Imported ids that were stored under a different id, mapped to the stored one.
+    pub remapped_ids: BTreeMap<Uuid, Uuid>,
+}
+
+/// Query for `GET /api/export/csv`.
+#[derive(Debug, Deserialize, Default)]
+pub struct CsvExportQuery {
+    /// Comma-separated column names; see `spreadsheets::EXPORT_COLUMNS` plus `cf.<key>`.
+    pub columns: Option<String>,
+    /// A filter expression, see `view_query`.
+    pub q: Option<String>,
+}
+
+/// Body of `POST /api/import/csv`.
+#[derive(Debug, Deserialize)]
+pub struct CsvImport {
+    pub csv: String,
+    /// Column to todo field: `title`, `description`, `priority`, `completed` or `cf.<key>`.
+    /// Columns are named by header, or by 0-based index in a file without one. When empty,
+    /// header names that match a field are used.
+    #[serde(default)]
+    pub mapping: HashMap<String, String>,
+    /// Whether the first row is a header; detected when omitted.
+    pub has_header: Option<bool>,
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// A line of an import file that could not be imported.
+#[derive(Debug, Serialize)]
+pub struct ImportRowError {
+    /// 1-based line the row starts on, or for JSON files the 1-based position of the item.
+    pub line: usize,
+    pub message: String,
+}
+
+/// Result of a CSV import. Rows with errors are left out and the rest are imported.
+#[derive(Debug, Serialize)]
+pub struct CsvImportReport {
+    pub dry_run: bool,
+    pub header: Option<Vec<String>>,
+    /// The mapping that was applied, column to field.
+    pub mapping: BTreeMap<String, String>,
+    pub imported: u64,
+    pub errors: Vec<ImportRowError>,
+}
+
+/// Query options for live update connections.
+#[derive(Debug, Deserialize, Default)]
+pub struct LiveQuery {
+    /// API or login token, for clients that cannot send an `Authorization` header.
+    pub token: Option<String>,
+}
+
+/// Query options for importers that take a file as the request body.
+#[derive(Debug, Deserialize, Default)]
+pub struct DryRunQuery {
+    /// Run the import and report on it, then roll it back.
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// Result of importing a text file. Lines with errors are left out and the rest are imported.
+#[derive(Debug, Serialize)]
+pub struct TextImportReport {
+    pub dry_run: bool,
+    pub imported: u64,
+    pub errors: Vec<ImportRowError>,
+    /// What the file had that todos have no place for.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub unmapped: Vec<Unmapped>,
+}
+
+/// Data an importer read but left out, such as recurrence rules.
+#[derive(Debug, Serialize)]
+pub struct Unmapped {
+    /// What was left out, e.g. `card members (todos have no assignees)`.
+    pub what: String,
+    pub count: u64,
+}
+
+/// How `GET /api/export.md` groups todos under headings.
+#[derive(Debug, Deserialize, Default, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum MarkdownGroup {
+    #[default]
+    Priority,
+    /// A heading per project in project order, then one for todos without a project.
+    Project,
+    /// One list, in list order.
+    None,
+}
+
+/// Query for `GET /api/export.md`.
+#[derive(Debug, Deserialize, Default)]
+pub struct MarkdownExportQuery {
+    #[serde(default)]
+    pub group: MarkdownGroup,
+}
+
+/// A user's calendar subscription. The token is the only credential the feed URL carries.
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct CalendarFeed {
+    pub token: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Query for `GET /api/calendar.ics`.
+#[derive(Debug, Deserialize)]
+pub struct CalendarFeedQuery {
+    #[serde(default)]
+    pub token: String,
+}
+
+/// A personal API token. The token itself is only returned when it is created.
+#[derive(Debug, Serialize)]
+pub struct ApiToken {
+    pub id: Uuid,
+    pub name: String,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateApiToken {
+    pub name: String,
+}
@@ -0,0 +1,224 @@
+//! JSON Merge Patch (RFC 7396) and JSON Patch (RFC 6902) over `serde_json::Value`.
+
+use serde::Deserialize;
+use serde_json::{Map, Value};
+
+pub const JSON_PATCH_CONTENT_TYPE: &str = "application/json-patch+json";
+
+/// Applies an RFC 7396 merge patch: `null` members remove keys, objects merge recursively
+/// and anything else replaces the target outright.
+pub fn merge_patch(target: &mut Value, patch: &Value) {
+    let Value::Object(patch) = patch else {
+        *target = patch.clone();
+        return;
+    };
+    if !target.is_object() {
+        *target = Value::Object(Map::new());
+    }
+    let Value::Object(map) = target else {
+        unreachable!("target was just made an object");
+    };
+    for (key, value) in patch {
+        if value.is_null() {
+            map.remove(key);
+        } else {
+            merge_patch(map.entry(key.clone()).or_insert(Value::Null), value);
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum PatchOperation {
+    Add { path: String, value: Value },
+    Remove { path: String },
+    Replace { path: String, value: Value },
+    Move { from: String, path: String },
+    Copy { from: String, path: String },
+    Test { path: String, value: Value },
+}
+
+/// Applies an RFC 6902 patch. Operations run in order against a copy of `doc`, which is only
+/// updated if every operation succeeds.
+pub fn apply_json_patch(doc: &mut Value, operations: &[PatchOperation]) -> Result<(), String> {
+    let mut patched = doc.clone();
+    for operation in operations {
+        match operation {
+            PatchOperation::Add { path, value } => add(&mut patched, path, value.clone())?,
+            PatchOperation::Remove { path } => {
+                remove(&mut patched, path)?;
+            }
+            PatchOperation::Replace { path, value } => {
+                *patched
+                    .pointer_mut(path)
+                    .ok_or_else(|| format!("path `{}` does not exist", path))? = value.clone();
+            }
+            PatchOperation::Move { from, path } => {
+                if path.starts_with(&format!("{}/", from)) {
+                    return Err(format!("cannot move `{}` into its own child `{}`", from, path));
+                }
+                let value = remove(&mut patched, from)?;
+                add(&mut patched, path, value)?;
+            }
+            PatchOperation::Copy { from, path } => {
+                let value = patched
+                    .pointer(from)
+                    .cloned()
+                    .ok_or_else(|| format!("path `{}` does not exist", from))?;
+                add(&mut patched, path, value)?;
+            }
+            PatchOperation::Test { path, value } => {
+                if patched.pointer(path) != Some(value) {
+                    return Err(format!("test failed for path `{}`", path));
+                }
+            }
+        }
+    }
+    *doc = patched;
+    Ok(())
+}
+
+// Splits a JSON pointer into its parent pointer and unescaped last reference token.
+fn split_pointer(path: &str) -> Result<(&str, String), String> {
+    let index = path
+        .rfind('/')
+        .ok_or_else(|| format!("invalid JSON pointer `{}`", path))?;
+    let token = path[index + 1..].replace("~1", "/").replace("~0", "~");
+    Ok((&path[..index], token))
+}
+
+fn add(doc: &mut Value, path: &str, value: Value) -> Result<(), String> {
+    if path.is_empty() {
+        *doc = value;
+        return Ok(());
+    }
+    let (parent, token) = split_pointer(path)?;
+    match doc.pointer_mut(parent) {
+        Some(Value::Object(map)) => {
+            map.insert(token, value);
+            Ok(())
+        }
+        Some(Value::Array(items)) if token == "-" => {
+            items.push(value);
+            Ok(())
+        }
+        Some(Value::Array(items)) => match token.parse::<usize>() {
+            Ok(index) if index <= items.len() => {
+                items.insert(index, value);
+                Ok(())
+            }
+            _ => Err(format!("invalid array index in `{}`", path)),
+        },
+        _ => Err(format!("parent of `{}` does not exist", path)),
+    }
+}
+
+fn remove(doc: &mut Value, path: &str) -> Result<Value, String> {
+    let (parent, token) = split_pointer(path)?;
+    let removed = match doc.pointer_mut(parent) {
+        Some(Value::Object(map)) => map.remove(&token),
+        Some(Value::Array(items)) => match token.parse::<usize>() {
+            Ok(index) if index < items.len() => Some(items.remove(index)),
+            _ => None,
+        },
+        _ => None,
+    };
+    removed.ok_or_else(|| format!("path `{}` does not exist", path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn patched(doc: Value, operations: Value) -> Result<Value, String> {
+        let operations: Vec<PatchOperation> = serde_json::from_value(operations).unwrap();
+        let mut doc = doc;
+        apply_json_patch(&mut doc, &operations).map(|()| doc)
+    }
+
+    // The examples from RFC 7396, appendix A.
+    #[test]
+    fn merge_patch_examples() {
+        let cases = [
+            (json!({"a": "b"}), json!({"a": "c"}), json!({"a": "c"})),
+            (json!({"a": "b"}), json!({"b": "c"}), json!({"a": "b", "b": "c"})),
+            (json!({"a": "b"}), json!({"a": null}), json!({})),
+            (json!({"a": "b", "b": "c"}), json!({"a": null}), json!({"b": "c"})),
+            (json!({"a": ["b"]}), json!({"a": "c"}), json!({"a": "c"})),
+            (json!({"a": "c"}), json!({"a": ["b"]}), json!({"a": ["b"]})),
+            (json!({"a": {"b": "c"}}), json!({"a": {"b": "d", "c": null}}), json!({"a": {"b": "d"}})),
+            (json!({"a": [{"b": "c"}]}), json!({"a": [1]}), json!({"a": [1]})),
+            (json!(["a", "b"]), json!(["c", "d"]), json!(["c", "d"])),
+            (json!({"a": "b"}), json!(["c"]), json!(["c"])),
+            (json!({"a": "foo"}), json!(null), json!(null)),
+            (json!({"a": "foo"}), json!("bar"), json!("bar")),
+            (json!({"e": null}), json!({"a": 1}), json!({"e": null, "a": 1})),
+            (json!([1, 2]), json!({"a": "b", "c": null}), json!({"a": "b"})),
+            (json!({}), json!({"a": {"bb": {"ccc": null}}}), json!({"a": {"bb": {}}})),
+        ];
+        for (target, patch, expected) in cases {
+            let mut doc = target.clone();
+            merge_patch(&mut doc, &patch);
+            assert_eq!(doc, expected, "{} merged with {}", target, patch);
+        }
+    }
+
+    #[test]
+    fn json_patch_operations() {
+        let cases = [
+            (json!({"a": 1}), json!([{"op": "add", "path": "/b", "value": 2}]), json!({"a": 1, "b": 2})),
+            (json!({"a": [1, 3]}), json!([{"op": "add", "path": "/a/1", "value": 2}]), json!({"a": [1, 2, 3]})),
+            (json!({"a": [1, 2]}), json!([{"op": "add", "path": "/a/-", "value": 3}]), json!({"a": [1, 2, 3]})),
+            (json!({"a": [1, 2]}), json!([{"op": "add", "path": "/a/2", "value": 3}]), json!({"a": [1, 2, 3]})),
+            (json!({"a": 1}), json!([{"op": "add", "path": "", "value": [1]}]), json!([1])),
+            (json!({"a": 1, "b": 2}), json!([{"op": "remove", "path": "/a"}]), json!({"b": 2})),
+            (json!({"a": [1, 2, 3]}), json!([{"op": "remove", "path": "/a/1"}]), json!({"a": [1, 3]})),
+            (json!({"a": 1}), json!([{"op": "replace", "path": "/a", "value": null}]), json!({"a": null})),
+            (json!({"a": {"b": 1}}), json!([{"op": "move", "from": "/a/b", "path": "/c"}]), json!({"a": {}, "c": 1})),
+            (json!({"a": [1]}), json!([{"op": "copy", "from": "/a/0", "path": "/a/-"}]), json!({"a": [1, 1]})),
+            (json!({"a/b": 1, "m~n": 2}), json!([{"op": "remove", "path": "/a~1b"}, {"op": "remove", "path": "/m~0n"}]), json!({})),
+            (
+                json!({"a": "x"}),
+                json!([{"op": "test", "path": "/a", "value": "x"}, {"op": "replace", "path": "/a", "value": "y"}]),
+                json!({"a": "y"}),
+            ),
+        ];
+        for (doc, operations, expected) in cases {
+            assert_eq!(patched(doc, operations.clone()), Ok(expected), "{}", operations);
+        }
+    }
+
+    #[test]
+    fn json_patch_errors() {
+        let cases = [
+            json!([{"op": "test", "path": "/a", "value": 2}]),
+            json!([{"op": "test", "path": "/missing", "value": null}]),
+            json!([{"op": "replace", "path": "/missing", "value": 1}]),
+            json!([{"op": "remove", "path": "/missing"}]),
+            json!([{"op": "remove", "path": "/list/5"}]),
+            json!([{"op": "add", "path": "/list/5", "value": 1}]),
+            json!([{"op": "add", "path": "/list/x", "value": 1}]),
+            json!([{"op": "add", "path": "/missing/a", "value": 1}]),
+            json!([{"op": "add", "path": "no-slash", "value": 1}]),
+            json!([{"op": "move", "from": "/obj", "path": "/obj/inner"}]),
+            json!([{"op": "copy", "from": "/missing", "path": "/b"}]),
+        ];
+        for operations in cases {
+            let doc = json!({"a": 1, "list": [1], "obj": {}});
+            assert!(patched(doc, operations.clone()).is_err(), "{}", operations);
+        }
+    }
+
+    #[test]
+    fn failed_json_patch_leaves_the_document_alone() {
+        let mut doc = json!({"a": 1});
+        let operations: Vec<PatchOperation> = serde_json::from_value(json!([
+            {"op": "replace", "path": "/a", "value": 2},
+            {"op": "test", "path": "/a", "value": 1},
+        ]))
+        .unwrap();
+        assert!(apply_json_patch(&mut doc, &operations).is_err());
+        assert_eq!(doc, json!({"a": 1}));
+    }
+}
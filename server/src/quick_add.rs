@@ -0,0 +1,411 @@
+//! Natural-language quick-add parsing.
+//!
+//! Pulls metadata out of a one-line todo such as
+//! `Pay rent tomorrow 9am !high #finance +home every month`, recording the byte range of every
+//! recognized phrase so a client can highlight it. Whatever is not recognized becomes the title.
+//! A word starting with `\` is kept in the title as typed, without the backslash, so
+//! `\#1 fan \tomorrow` is just a title.
+
+use crate::models::Priority;
+use chrono::{DateTime, Datelike, Duration, Local, Months, NaiveDate, NaiveTime, TimeZone, Utc, Weekday};
+use serde::Serialize;
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SpanKind {
+    Date,
+    Time,
+    Priority,
+    Tag,
+    Project,
+    Recurrence,
+}
+
+/// A recognized phrase, as a byte range into the parsed text.
+#[derive(Debug, Serialize, Clone)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub kind: SpanKind,
+    pub text: String,
+}
+
+#[derive(Debug, Default)]
+pub struct QuickAdd {
+    pub title: String,
+    pub priority: Option<Priority>,
+    pub due: Option<DateTime<Utc>>,
+    pub tags: Vec<String>,
+    pub project: Option<String>,
+    pub recurrence: Option<String>,
+    pub spans: Vec<Span>,
+}
+
+enum When {
+    Day(NaiveDate),
+    /// A day with a time to use when none is given, e.g. "tonight".
+    DayAt(NaiveDate, NaiveTime),
+    After(Duration),
+}
+
+struct Token<'a> {
+    start: usize,
+    text: &'a str,
+    /// The token without trailing punctuation.
+    raw: &'a str,
+    word: String,
+}
+
+fn tokenize(text: &str) -> Vec<Token<'_>> {
+    let mut bounds = vec![];
+    let mut start = None;
+    for (i, c) in text.char_indices() {
+        match (c.is_whitespace(), start) {
+            (true, Some(s)) => {
+                bounds.push((s, i));
+                start = None;
+            }
+            (false, None) => start = Some(i),
+            _ => {}
+        }
+    }
+    if let Some(s) = start {
+        bounds.push((s, text.len()));
+    }
+
+    bounds
+        .into_iter()
+        .filter_map(|(start, end)| {
+            let raw = text[start..end].trim_end_matches([',', '.', ';']);
+            (!raw.is_empty()).then(|| Token { start, text: &text[start..end], raw, word: raw.to_lowercase() })
+        })
+        .collect()
+}
+
+pub(crate) fn priority_marker(word: &str) -> Option<Priority> {
+    match word {
+        "!high" | "!h" | "!1" | "!!!" => Some(Priority::High),
+        "!medium" | "!med" | "!m" | "!2" | "!!" => Some(Priority::Medium),
+        "!low" | "!l" | "!3" | "!" => Some(Priority::Low),
+        _ => None,
+    }
+}
+
+fn is_name(s: &str) -> bool {
+    !s.is_empty() && s.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '-' || c == '/')
+}
+
+// Full names only, so words like "sun" or "sat" in a title are left alone.
+fn weekday(word: &str) -> Option<Weekday> {
+    if word.len() < 6 {
+        return None;
+    }
+    word.parse().ok()
+}
+
+fn count(word: &str) -> Option<u32> {
+    match word {
+        "a" | "an" => Some(1),
+        _ => word.parse().ok().filter(|n| *n > 0),
+    }
+}
+
+/// `every day`, `every monday`, `every 2 weeks`, ...
+fn recurrence(words: &[&str]) -> Option<(usize, String)> {
+    if words.first() != Some(&"every") {
+        return None;
+    }
+    let unit = *words.get(1)?;
+    if matches!(unit, "day" | "weekday" | "week" | "month" | "year") || weekday(unit).is_some() {
+        return Some((2, format!("every {}", unit)));
+    }
+    let n = unit.parse::<u32>().ok().filter(|n| *n > 0)?;
+    let unit = words.get(2)?.trim_end_matches('s');
+    matches!(unit, "day" | "week" | "month" | "year").then(|| (3, format!("every {} {}s", n, unit)))
+}
+
+fn next_weekday(today: NaiveDate, target: Weekday) -> NaiveDate {
+    let mut day = today.succ_opt().unwrap_or(today);
+    while day.weekday() != target {
+        day = day.succ_opt().unwrap_or(day);
+    }
+    day
+}
+
+/// `today`, `tomorrow`, `friday`, `next week`, `in 3 days`, `2024-05-01`, optionally after
+/// `on`, `by` or `due`.
+fn date_phrase(words: &[&str], today: NaiveDate) -> Option<(usize, When)> {
+    if matches!(words.first(), Some(&("on" | "by" | "due"))) {
+        return date_phrase(&words[1..], today).map(|(n, when)| (n + 1, when));
+    }
+
+    let first = *words.first()?;
+    let single = match first {
+        "today" => Some(When::Day(today)),
+        "tonight" => NaiveTime::from_hms_opt(20, 0, 0).map(|time| When::DayAt(today, time)),
+        "tomorrow" => today.succ_opt().map(When::Day),
+        _ => weekday(first)
+            .map(|target| When::Day(next_weekday(today, target)))
+            .or_else(|| NaiveDate::parse_from_str(first, "%Y-%m-%d").ok().map(When::Day)),
+    };
+    if let Some(when) = single {
+        return Some((1, when));
+    }
+
+    match (first, words.get(1).copied()) {
+        ("next", Some("week")) => Some((2, When::Day(today + Duration::weeks(1)))),
+        ("next", Some("month")) => today.checked_add_months(Months::new(1)).map(|day| (2, When::Day(day))),
+        ("next", Some(word)) => weekday(word).map(|target| (2, When::Day(next_weekday(today, target)))),
+        ("in", Some(word)) => {
+            let n = count(word)?;
+            let when = match words.get(2)?.trim_end_matches('s') {
+                "minute" | "min" => When::After(Duration::minutes(n.into())),
+                "hour" | "hr" => When::After(Duration::hours(n.into())),
+                "day" => When::Day(today + Duration::days(n.into())),
+                "week" => When::Day(today + Duration::weeks(n.into())),
+                "month" => When::Day(today.checked_add_months(Months::new(n))?),
+                _ => return None,
+            };
+            Some((3, when))
+        }
+        _ => None,
+    }
+}
+
+fn parse_time(word: &str) -> Option<NaiveTime> {
+    match word {
+        "noon" => return NaiveTime::from_hms_opt(12, 0, 0),
+        "midnight" => return Some(NaiveTime::MIN),
+        _ => {}
+    }
+    let (digits, pm) = if let Some(digits) = word.strip_suffix("am") {
+        (digits, Some(false))
+    } else if let Some(digits) = word.strip_suffix("pm") {
+        (digits, Some(true))
+    } else {
+        (word, None)
+    };
+    let (hour, minute) = match digits.split_once(':') {
+        Some((hour, minute)) if minute.len() == 2 => (hour.parse::<u32>().ok()?, minute.parse().ok()?),
+        // A bare number is only a time with am/pm after it.
+        None if pm.is_some() => (digits.parse::<u32>().ok()?, 0),
+        _ => return None,
+    };
+    let hour = match pm {
+        Some(_) if hour == 0 || hour > 12 => return None,
+        Some(pm) => hour % 12 + if pm { 12 } else { 0 },
+        None => hour,
+    };
+    NaiveTime::from_hms_opt(hour, minute, 0)
+}
+
+/// `9am`, `9:30pm`, `17:00`, `noon`, optionally after `at`.
+fn time_phrase(words: &[&str]) -> Option<(usize, NaiveTime)> {
+    match words {
+        ["at", word, ..] => parse_time(word).map(|time| (2, time)),
+        [word, ..] => parse_time(word).map(|time| (1, time)),
+        [] => None,
+    }
+}
+
+fn local_to_utc(naive: chrono::NaiveDateTime) -> Option<DateTime<Utc>> {
+    Local.from_local_datetime(&naive).earliest().map(|dt| dt.with_timezone(&Utc))
+}
+
+/// Parses `text` relative to `now`. Dates and times are read in the server's local time zone.
+pub fn parse(text: &str, now: DateTime<Local>) -> QuickAdd {
+    let tokens = tokenize(text);
+    let words: Vec<&str> = tokens.iter().map(|t| t.word.as_str()).collect();
+    let mut result = QuickAdd::default();
+    let mut consumed = vec![false; tokens.len()];
+    let (mut day, mut time, mut offset) = (None, None, None);
+    let today = now.date_naive();
+
+    let mut i = 0;
+    while i < tokens.len() {
+        let rest = &words[i..];
+        let raw = tokens[i].raw;
+        let matched = if raw.starts_with('\\') {
+            None
+        } else if let Some(priority) = priority_marker(rest[0]) {
+            result.priority = Some(priority);
+            Some((1, SpanKind::Priority))
+        } else if let Some(tag) = raw.strip_prefix('#').filter(|t| is_name(t)) {
+            result.tags.push(tag.to_string());
+            Some((1, SpanKind::Tag))
+        } else if let Some(project) = raw.strip_prefix('+').filter(|p| is_name(p)) {
+            result.project = Some(project.to_string());
+            Some((1, SpanKind::Project))
+        } else if let Some((n, rule)) = recurrence(rest) {
+            result.recurrence = Some(rule);
+            Some((n, SpanKind::Recurrence))
+        } else if let Some((n, when)) = date_phrase(rest, today).filter(|_| day.is_none() && offset.is_none()) {
+            match when {
+                When::Day(date) => day = Some(date),
+                When::DayAt(date, default_time) => {
+                    day = Some(date);
+                    time = time.or(Some(default_time));
+                }
+                When::After(duration) => offset = Some(duration),
+            }
+            Some((n, SpanKind::Date))
+        } else if let Some((n, at)) = time_phrase(rest) {
+            time = Some(at);
+            Some((n, SpanKind::Time))
+        } else {
+            None
+        };
+
+        let Some((n, kind)) = matched else {
+            i += 1;
+            continue;
+        };
+        let last = &tokens[i + n - 1];
+        let (start, end) = (tokens[i].start, last.start + last.raw.len());
+        result.spans.push(Span { start, end, kind, text: text[start..end].to_string() });
+        consumed[i..i + n].iter_mut().for_each(|c| *c = true);
+        i += n;
+    }
+
+    result.due = match (day, time, offset) {
+        (_, _, Some(offset)) => Some((now + offset).with_timezone(&Utc)),
+        (Some(day), time, None) => local_to_utc(day.and_time(time.unwrap_or(NaiveTime::MIN))),
+        // A time on its own means the next time the clock reads it.
+        (None, Some(time), None) => {
+            let day = if time <= now.time() { today.succ_opt().unwrap_or(today) } else { today };
+            local_to_utc(day.and_time(time))
+        }
+        (None, None, None) => None,
+    };
+
+    result.title = tokens
+        .iter()
+        .zip(&consumed)
+        .filter(|(_, consumed)| !**consumed)
+        .map(|(token, _)| token.text.strip_prefix('\\').filter(|text| !text.is_empty()).unwrap_or(token.text))
+        .collect::<Vec<_>>()
+        .join(" ");
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A Wednesday.
+    fn now() -> DateTime<Local> {
+        Local.with_ymd_and_hms(2024, 5, 15, 10, 0, 0).unwrap()
+    }
+
+    fn local(month: u32, day: u32, hour: u32, minute: u32) -> Option<DateTime<Utc>> {
+        Some(Local.with_ymd_and_hms(2024, month, day, hour, minute, 0).unwrap().with_timezone(&Utc))
+    }
+
+    #[test]
+    fn reads_every_kind_of_metadata() {
+        let parsed = parse("Pay rent tomorrow 9am !high #finance +home every month", now());
+        assert_eq!(parsed.title, "Pay rent");
+        assert_eq!(parsed.priority, Some(Priority::High));
+        assert_eq!(parsed.due, local(5, 16, 9, 0));
+        assert_eq!(parsed.tags, vec!["finance"]);
+        assert_eq!(parsed.project.as_deref(), Some("home"));
+        assert_eq!(parsed.recurrence.as_deref(), Some("every month"));
+
+        let spans: Vec<_> = parsed.spans.iter().map(|span| (span.start, span.end, span.kind)).collect();
+        assert_eq!(
+            spans,
+            vec![
+                (9, 17, SpanKind::Date),
+                (18, 21, SpanKind::Time),
+                (22, 27, SpanKind::Priority),
+                (28, 36, SpanKind::Tag),
+                (37, 42, SpanKind::Project),
+                (43, 54, SpanKind::Recurrence),
+            ]
+        );
+    }
+
+    #[test]
+    fn relative_dates() {
+        let cases = [
+            ("call today", local(5, 15, 0, 0)),
+            ("call tomorrow", local(5, 16, 0, 0)),
+            ("call tonight", local(5, 15, 20, 0)),
+            ("call in 3 days", local(5, 18, 0, 0)),
+            ("call in 2 weeks", local(5, 29, 0, 0)),
+            ("call in a month", local(6, 15, 0, 0)),
+            ("call in 2 hours", local(5, 15, 12, 0)),
+            ("call in 30 minutes", local(5, 15, 10, 30)),
+            ("call next week", local(5, 22, 0, 0)),
+            ("call next month", local(6, 15, 0, 0)),
+            ("call on 2024-06-01", local(6, 1, 0, 0)),
+            ("call due tomorrow at noon", local(5, 16, 12, 0)),
+            ("call at 17:30", local(5, 15, 17, 30)),
+            // A time already past today means tomorrow.
+            ("call 8am", local(5, 16, 8, 0)),
+            ("call in 3 fortnights", None),
+        ];
+        for (text, due) in cases {
+            let parsed = parse(text, now());
+            assert_eq!(parsed.due, due, "{}", text);
+            if due.is_some() {
+                assert_eq!(parsed.title, "call", "{}", text);
+            }
+        }
+    }
+
+    #[test]
+    fn weekdays() {
+        let cases = [
+            ("call monday", local(5, 20, 0, 0)),
+            // Today's weekday means the next one, not today.
+            ("call wednesday", local(5, 22, 0, 0)),
+            ("call next sunday", local(5, 19, 0, 0)),
+            ("call by Friday, 5pm", local(5, 17, 17, 0)),
+            // Abbreviations are ordinary words.
+            ("call sun", None),
+        ];
+        for (text, due) in cases {
+            assert_eq!(parse(text, now()).due, due, "{}", text);
+        }
+    }
+
+    #[test]
+    fn priority_markers() {
+        let cases = [
+            ("!high", Some(Priority::High)),
+            ("!h", Some(Priority::High)),
+            ("!1", Some(Priority::High)),
+            ("!!!", Some(Priority::High)),
+            ("!medium", Some(Priority::Medium)),
+            ("!!", Some(Priority::Medium)),
+            ("!low", Some(Priority::Low)),
+            ("!", Some(Priority::Low)),
+            ("!urgent", None),
+        ];
+        for (marker, priority) in cases {
+            let parsed = parse(&format!("call {}", marker), now());
+            assert_eq!(parsed.priority, priority, "{}", marker);
+            let title = if priority.is_some() { "call".to_string() } else { format!("call {}", marker) };
+            assert_eq!(parsed.title, title, "{}", marker);
+        }
+    }
+
+    #[test]
+    fn escaped_words_stay_in_the_title() {
+        let cases = [
+            ("\\#1 fan", "#1 fan"),
+            ("call \\tomorrow", "call tomorrow"),
+            ("\\!high five", "!high five"),
+            ("read \\every day", "read every day"),
+            ("next \\friday", "next friday"),
+            ("a \\ b", "a \\ b"),
+        ];
+        for (text, title) in cases {
+            let parsed = parse(text, now());
+            assert_eq!(parsed.title, title, "{}", text);
+            assert!(parsed.spans.is_empty(), "{}", text);
+            assert_eq!(parsed.due, None, "{}", text);
+        }
+    }
+}
@@ -0,0 +1,240 @@
+//! Lexicographic rank keys for user-defined ordering.
+//!
+//! Keys are strings over a base-62 alphabet whose byte order matches their digit order, so
+//! SQLite can sort them with a plain `ORDER BY`. A key can always be generated strictly
+//! between two others, which means moving an item only ever rewrites that item's row.
+//!
+//! Each key is an integer followed by an optional fraction, as in fractional indexing. The first
+//! character of the integer gives its length, `a` to `z` for 2 to 27 characters counting up from
+//! `a0` and `Z` to `A` likewise counting down, so appending to or prepending to the list steps
+//! the integer and keys only grow logarithmically. Fractions are only needed between adjacent
+//! integers.
+
+const DIGITS: &[u8] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+
+// The key of the first item in an empty list, the integer zero.
+const FIRST_KEY: &str = "a0";
+
+// The lowest integer. Only fractions fit below it.
+const SMALLEST_INTEGER: &str = "A00000000000000000000000000";
+
+fn digit_value(byte: u8) -> usize {
+    DIGITS.iter().position(|&d| d == byte).unwrap_or(0)
+}
+
+// Length of an integer, head included, from its head.
+fn integer_length(head: u8) -> Option<usize> {
+    match head {
+        b'a'..=b'z' => Some((head - b'a') as usize + 2),
+        b'A'..=b'Z' => Some((b'Z' - head) as usize + 2),
+        _ => None,
+    }
+}
+
+// Splits a key into its integer and its fraction.
+fn split(key: &str) -> (&str, &str) {
+    let length = key.bytes().next().and_then(integer_length).unwrap_or(0);
+    key.split_at(length.min(key.len()))
+}
+
+fn integer(head: u8, digits: Vec<u8>) -> String {
+    let mut bytes = vec![head];
+    bytes.extend(digits);
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+// The next integer, or `None` past the largest one.
+fn increment(value: &str) -> Option<String> {
+    let (&head, digits) = value.as_bytes().split_first()?;
+    let mut digits = digits.to_vec();
+    for digit in digits.iter_mut().rev() {
+        let next = digit_value(*digit) + 1;
+        if next < DIGITS.len() {
+            *digit = DIGITS[next];
+            return Some(integer(head, digits));
+        }
+        *digit = DIGITS[0];
+    }
+    // Every digit carried, so the integer moves to the next length.
+    match head {
+        b'Z' => Some(FIRST_KEY.to_string()),
+        b'z' => None,
+        _ => {
+            let head = head + 1;
+            if head > b'a' {
+                digits.push(DIGITS[0]);
+            } else {
+                digits.pop();
+            }
+            Some(integer(head, digits))
+        }
+    }
+}
+
+// The previous integer, or `None` below the smallest one.
+fn decrement(value: &str) -> Option<String> {
+    let (&head, digits) = value.as_bytes().split_first()?;
+    let mut digits = digits.to_vec();
+    let last = DIGITS[DIGITS.len() - 1];
+    for digit in digits.iter_mut().rev() {
+        let value = digit_value(*digit);
+        if value > 0 {
+            *digit = DIGITS[value - 1];
+            return Some(integer(head, digits));
+        }
+        *digit = last;
+    }
+    match head {
+        b'a' => Some(integer(b'Z', vec![last])),
+        b'A' => None,
+        _ => {
+            let head = head - 1;
+            if head < b'Z' {
+                digits.push(last);
+            } else {
+                digits.pop();
+            }
+            Some(integer(head, digits))
+        }
+    }
+}
+
+/// Returns a key strictly between `lower` and `upper`. `None` stands for the start and end
+/// of the list respectively. Callers must pass keys produced by this function with `lower < upper`.
+pub fn key_between(lower: Option<&str>, upper: Option<&str>) -> String {
+    match (lower, upper) {
+        (None, None) => FIRST_KEY.to_string(),
+        (None, Some(upper)) => {
+            let (value, fraction) = split(upper);
+            if value == SMALLEST_INTEGER {
+                value.to_string() + &midpoint(b"", Some(fraction.as_bytes()))
+            } else if !fraction.is_empty() {
+                value.to_string()
+            } else {
+                decrement(value).unwrap_or_else(|| midpoint(b"", Some(upper.as_bytes())))
+            }
+        }
+        (Some(lower), None) => {
+            let (value, fraction) = split(lower);
+            increment(value).unwrap_or_else(|| value.to_string() + &midpoint(fraction.as_bytes(), None))
+        }
+        (Some(lower), Some(upper)) => {
+            let (lower_value, lower_fraction) = split(lower);
+            let (upper_value, upper_fraction) = split(upper);
+            if lower_value == upper_value {
+                return lower_value.to_string() + &midpoint(lower_fraction.as_bytes(), Some(upper_fraction.as_bytes()));
+            }
+            match increment(lower_value) {
+                Some(next) if next.as_str() < upper => next,
+                _ => lower_value.to_string() + &midpoint(lower_fraction.as_bytes(), None),
+            }
+        }
+    }
+}
+
+// Port of the fractional indexing midpoint: fractions are treated as base-62 fractions, so
+// trailing zeros are insignificant and never produced.
+fn midpoint(lower: &[u8], upper: Option<&[u8]>) -> String {
+    if let Some(upper) = upper {
+        let mut n = 0;
+        while n < upper.len() && lower.get(n).copied().unwrap_or(DIGITS[0]) == upper[n] {
+            n += 1;
+        }
+        if n > 0 {
+            let prefix = String::from_utf8_lossy(&upper[..n]).into_owned();
+            let rest = if n < lower.len() { &lower[n..] } else { &[][..] };
+            return prefix + &midpoint(rest, Some(&upper[n..]));
+        }
+    }
+
+    let digit_lower = lower.first().map_or(0, |&b| digit_value(b));
+    let digit_upper = upper.and_then(|u| u.first()).map_or(DIGITS.len(), |&b| digit_value(b));
+
+    if digit_upper - digit_lower > 1 {
+        let mid = (digit_lower + digit_upper).div_ceil(2);
+        (DIGITS[mid] as char).to_string()
+    } else if let Some(upper) = upper.filter(|u| u.len() > 1) {
+        (upper[0] as char).to_string()
+    } else {
+        let rest = if lower.is_empty() { &[][..] } else { &lower[1..] };
+        (DIGITS[digit_lower] as char).to_string() + &midpoint(rest, None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_between(lower: Option<&str>, upper: Option<&str>) -> String {
+        let key = key_between(lower, upper);
+        assert!(lower.is_none_or(|lower| lower < key.as_str()), "{:?} < {} < {:?}", lower, key, upper);
+        assert!(upper.is_none_or(|upper| key.as_str() < upper), "{:?} < {} < {:?}", lower, key, upper);
+        assert!(!split(&key).1.ends_with('0'), "{} has a trailing zero", key);
+        key
+    }
+
+    #[test]
+    fn keys() {
+        let cases = [
+            (None, None, "a0"),
+            (Some("a0"), None, "a1"),
+            (Some("az"), None, "b00"),
+            (Some("bzz"), None, "c000"),
+            (None, Some("a0"), "Zz"),
+            (None, Some("Z0"), "Yzz"),
+            (Some("Zz"), None, "a0"),
+            (Some("Yzz"), None, "Z0"),
+            (None, Some("b00"), "az"),
+            // An upper bound with a fraction has its integer just below it.
+            (None, Some("a1V"), "a1"),
+            (Some("a0"), Some("a2"), "a1"),
+            (Some("az"), Some("b01"), "b00"),
+            // Adjacent integers need a fraction.
+            (Some("a0"), Some("a1"), "a0V"),
+            (Some("a0V"), Some("a1"), "a0l"),
+            (Some("a0"), Some("a0V"), "a0G"),
+            (Some("a0"), Some("a01"), "a00V"),
+            (Some("a1"), Some("a1V"), "a1G"),
+            (Some("a1G"), Some("a1H"), "a1GV"),
+            (Some("a0zzV"), Some("a1"), "a0zzl"),
+        ];
+        for (lower, upper, expected) in cases {
+            assert_eq!(assert_between(lower, upper), expected, "{:?}..{:?}", lower, upper);
+        }
+    }
+
+    #[test]
+    fn largest_integer_falls_back_to_a_fraction() {
+        let largest = format!("z{}", "z".repeat(26));
+        assert_eq!(assert_between(Some(&largest), None), format!("{}V", largest));
+    }
+
+    #[test]
+    fn appending_and_prepending_keep_keys_short() {
+        let mut last = assert_between(None, None);
+        for _ in 0..1000 {
+            last = assert_between(Some(&last), None);
+        }
+        // 1000 appends only need integers of up to two digits.
+        assert_eq!(last.len(), 3, "{}", last);
+
+        let mut first = assert_between(None, None);
+        for _ in 0..1000 {
+            first = assert_between(None, Some(&first));
+        }
+        assert_eq!(first.len(), 3, "{}", first);
+    }
+
+    #[test]
+    fn repeated_inserts_between_adjacent_keys() {
+        // Always inserting right after the lower key, then right before the upper one.
+        let (lower, mut upper) = ("a0".to_string(), "a1".to_string());
+        for _ in 0..100 {
+            upper = assert_between(Some(&lower), Some(&upper));
+        }
+        let (mut lower, upper) = ("a0".to_string(), "a1".to_string());
+        for _ in 0..100 {
+            lower = assert_between(Some(&lower), Some(&upper));
+        }
+    }
+}
@@ -0,0 +1,242 @@
+//! The todo.txt line format (<https://github.com/todotxt/todo.txt>).
+//!
+//! `x 2024-05-02 2024-05-01 Call mom +family @phone due:2024-05-03 pri:A`: an optional `x`
+//! completion marker and completion date, or a `(A)` priority, then an optional creation date,
+//! then the text. Projects (`+name`), contexts (`@name`) and `key:value` extensions live in the
+//! text itself.
+
+use chrono::NaiveDate;
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Line {
+    pub completed: bool,
+    /// `A` to `Z`. Completed lines keep theirs as a `pri:` extension, as the format suggests.
+    pub priority: Option<char>,
+    pub completion_date: Option<NaiveDate>,
+    pub creation_date: Option<NaiveDate>,
+    pub text: String,
+}
+
+fn date(word: &str) -> Option<NaiveDate> {
+    // Exactly `YYYY-MM-DD`; chrono alone would also take unpadded fields.
+    (word.len() == 10).then(|| NaiveDate::parse_from_str(word, "%Y-%m-%d").ok()).flatten()
+}
+
+fn priority(word: &str) -> Option<char> {
+    match word.as_bytes() {
+        [b'(', p @ b'A'..=b'Z', b')'] => Some(*p as char),
+        _ => None,
+    }
+}
+
+/// Parses one line. Returns `None` for blank lines.
+pub fn parse(line: &str) -> Option<Line> {
+    let mut words = line.split_whitespace().peekable();
+    words.peek()?;
+    let mut parsed = Line::default();
+
+    if words.peek() == Some(&"x") {
+        words.next();
+        parsed.completed = true;
+        // The completion date comes first, then the creation date.
+        if let Some(first) = words.peek().and_then(|w| date(w)) {
+            words.next();
+            match words.peek().and_then(|w| date(w)) {
+                Some(second) => {
+                    words.next();
+                    parsed.completion_date = Some(first);
+                    parsed.creation_date = Some(second);
+                }
+                None => parsed.completion_date = Some(first),
+            }
+        }
+    } else if let Some(p) = words.peek().and_then(|w| priority(w)) {
+        words.next();
+        parsed.priority = Some(p);
+    }
+    if !parsed.completed {
+        if let Some(created) = words.peek().and_then(|w| date(w)) {
+            words.next();
+            parsed.creation_date = Some(created);
+        }
+    }
+
+    parsed.text = words.collect::<Vec<_>>().join(" ");
+    if parsed.priority.is_none() {
+        parsed.priority = extensions(&parsed.text)
+            .find(|(key, _)| *key == "pri")
+            .and_then(|(_, value)| value.chars().next())
+            .filter(|p| p.is_ascii_uppercase());
+    }
+    Some(parsed)
+}
+
+/// Whether `word` is a `key:value` extension. URLs such as `https://...` are not.
+pub fn extension(word: &str) -> Option<(&str, &str)> {
+    let (key, value) = word.split_once(':')?;
+    let valid = !key.is_empty()
+        && !value.is_empty()
+        && !value.starts_with("//")
+        && !key.starts_with(['+', '@'])
+        && key.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '-' || c == '.');
+    valid.then_some((key, value))
+}
+
+/// The name in a `+project` word.
+pub fn project(word: &str) -> Option<&str> {
+    word.strip_prefix('+').filter(|name| !name.is_empty())
+}
+
+/// The name in an `@context` word.
+pub fn context(word: &str) -> Option<&str> {
+    word.strip_prefix('@').filter(|name| !name.is_empty())
+}
+
+pub fn extensions(text: &str) -> impl Iterator<Item = (&str, &str)> {
+    text.split_whitespace().filter_map(extension)
+}
+
+/// Writes a line in the canonical order. A completed line's creation date is only written after
+/// its completion date, since a single date after `x` reads as the completion date.
+pub fn format(line: &Line) -> String {
+    let mut parts: Vec<String> = vec![];
+    if line.completed {
+        parts.push("x".to_string());
+        if let Some(completed) = line.completion_date {
+            parts.push(completed.to_string());
+        }
+    } else if let Some(p) = line.priority {
+        parts.push(format!("({})", p));
+    }
+    if let Some(created) = line.creation_date.filter(|_| !line.completed || line.completion_date.is_some()) {
+        parts.push(created.to_string());
+    }
+    parts.push(line.text.clone());
+    if line.completed {
+        if let Some(p) = line.priority.filter(|_| !extensions(&line.text).any(|(key, _)| key == "pri")) {
+            parts.push(format!("pri:{}", p));
+        }
+    }
+    parts.retain(|part| !part.is_empty());
+    parts.join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn day(month: u32, day: u32) -> Option<NaiveDate> {
+        NaiveDate::from_ymd_opt(2024, month, day)
+    }
+
+    #[test]
+    fn parses_lines() {
+        let cases = [
+            (
+                "x 2024-05-02 2024-05-01 Call mom +family @phone due:2024-05-03 pri:A",
+                Line {
+                    completed: true,
+                    priority: Some('A'),
+                    completion_date: day(5, 2),
+                    creation_date: day(5, 1),
+                    text: "Call mom +family @phone due:2024-05-03 pri:A".to_string(),
+                },
+            ),
+            (
+                "x 2024-05-02 Call mom",
+                Line { completed: true, completion_date: day(5, 2), text: "Call mom".to_string(), ..Line::default() },
+            ),
+            (
+                "(B) 2024-05-01 Call mom",
+                Line { priority: Some('B'), creation_date: day(5, 1), text: "Call mom".to_string(), ..Line::default() },
+            ),
+            ("  (C)   Call   mom ", Line { priority: Some('C'), text: "Call mom".to_string(), ..Line::default() }),
+            // A priority marker only counts at the start of an open line.
+            ("(A) x Call mom", Line { priority: Some('A'), text: "x Call mom".to_string(), ..Line::default() }),
+            (
+                "x (A) Call mom",
+                Line { completed: true, text: "(A) Call mom".to_string(), ..Line::default() },
+            ),
+        ];
+        for (input, line) in cases {
+            assert_eq!(parse(input), Some(line), "{}", input);
+        }
+    }
+
+    #[test]
+    fn malformed_markers_stay_in_the_text() {
+        let cases = [
+            "(a) lowercase priority",
+            "(AB) long priority",
+            "(A)no space",
+            "2024-5-1 unpadded date",
+            "2024-13-01 impossible date",
+            "X uppercase marker",
+            "xylophone practice",
+            "pri:a lowercase extension",
+        ];
+        for input in cases {
+            assert_eq!(
+                parse(input),
+                Some(Line { text: input.to_string(), ..Line::default() }),
+                "{}",
+                input
+            );
+        }
+        assert_eq!(parse(""), None);
+        assert_eq!(parse("  \t "), None);
+    }
+
+    #[test]
+    fn canonical_lines_round_trip() {
+        let cases = [
+            "Call mom",
+            "(A) Call mom",
+            "(A) 2024-05-01 Call mom +family @phone",
+            "2024-05-01 Call mom due:2024-05-03",
+            "x Call mom",
+            "x 2024-05-02 Call mom",
+            "x 2024-05-02 2024-05-01 Call mom pri:A",
+        ];
+        for input in cases {
+            assert_eq!(format(&parse(input).unwrap()), input);
+        }
+    }
+
+    #[test]
+    fn formats_completed_lines() {
+        let done = |priority, completion_date, text: &str| Line {
+            completed: true,
+            priority,
+            completion_date,
+            creation_date: day(5, 1),
+            text: text.to_string(),
+        };
+        // The creation date can't be written without a completion date in front of it.
+        assert_eq!(format(&done(None, None, "Call mom")), "x Call mom");
+        assert_eq!(format(&done(Some('B'), day(5, 2), "Call mom")), "x 2024-05-02 2024-05-01 Call mom pri:B");
+        assert_eq!(format(&done(Some('B'), day(5, 2), "Call mom pri:B")), "x 2024-05-02 2024-05-01 Call mom pri:B");
+        assert_eq!(parse("x 2024-05-02 2024-05-01 Call mom pri:B").unwrap(), done(Some('B'), day(5, 2), "Call mom pri:B"));
+    }
+
+    #[test]
+    fn recognises_extensions() {
+        let cases = [
+            ("due:2024-05-03", Some(("due", "2024-05-03"))),
+            ("time:12:30", Some(("time", "12:30"))),
+            ("rec.every_day:1", Some(("rec.every_day", "1"))),
+            ("https://example.com", None),
+            ("+project:x", None),
+            ("@context:x", None),
+            (":value", None),
+            ("key:", None),
+            ("no-colon", None),
+            ("we!rd:key", None),
+        ];
+        for (word, expected) in cases {
+            assert_eq!(extension(word), expected, "{}", word);
+        }
+        let found: Vec<_> = extensions("Call due:2024-05-03 mom pri:A http://x").collect();
+        assert_eq!(found, vec![("due", "2024-05-03"), ("pri", "A")]);
+    }
+}
@@ -0,0 +1,558 @@
+//! Filter expressions for saved views, e.g. `priority:high AND NOT completed:true`.
+//!
+//! An expression is a list of `field<op>value` terms joined with `AND` (also implied between
+//! adjacent terms), `OR` and `NOT`, with parentheses for grouping. Operators are `:` and `=`
+//! (equal, or contains for `title`), `!=`, `<`, `<=`, `>` and `>=`. Expressions are parsed into
+//! an AST up front and compiled into a `WHERE` fragment with every value bound as a parameter.
+//!
+//! Fields: `priority`, `completed`, `blocked`, `status` (name or category), `project` (name),
+//! `tag`, `title`, `estimate`
+//! (minutes), `created`, `updated` and `completed_at` (a `YYYY-MM-DD` date or an age such as
+//! `7d`, `2w`, `12h`) and `cf.<key>` for custom fields.
+
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use sqlx::{QueryBuilder, Sqlite};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Op {
+    fn sql(self) -> &'static str {
+        match self {
+            Op::Eq => "=",
+            Op::Ne => "!=",
+            Op::Lt => "<",
+            Op::Le => "<=",
+            Op::Gt => ">",
+            Op::Ge => ">=",
+        }
+    }
+
+    // `created<7d` means "less than 7 days old", i.e. a timestamp after seven days ago.
+    fn flipped(self) -> Op {
+        match self {
+            Op::Lt => Op::Gt,
+            Op::Le => Op::Ge,
+            Op::Gt => Op::Lt,
+            Op::Ge => Op::Le,
+            op => op,
+        }
+    }
+
+    fn is_equality(self) -> bool {
+        matches!(self, Op::Eq | Op::Ne)
+    }
+}
+
+/// A custom field value as written in a query, with each way it can be read. Which one is
+/// compared depends on the field's declared type, so `cf.ticket:123` matches the text `"123"`
+/// in a text field and the number `123` in a number field.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldValue {
+    pub text: String,
+    pub number: Option<f64>,
+    pub checked: Option<bool>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Condition {
+    /// Compared by rank, so `priority>=medium` means medium or high.
+    Priority(Op, i64),
+    Completed(Op, bool),
+    Blocked(Op, bool),
+    Status(Op, String),
+    Project(Op, String),
+    Tag(Op, String),
+    Title(Op, String),
+    Estimate(Op, i64),
+    Created(Op, DateTime<Utc>),
+    Updated(Op, DateTime<Utc>),
+    CompletedAt(Op, DateTime<Utc>),
+    CustomField(String, Op, FieldValue),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Term(Condition),
+}
+
+const OPERATORS: &[(&str, Op)] = &[
+    ("<=", Op::Le),
+    (">=", Op::Ge),
+    ("!=", Op::Ne),
+    (":", Op::Eq),
+    ("=", Op::Eq),
+    ("<", Op::Lt),
+    (">", Op::Gt),
+];
+
+fn tokenize(input: &str) -> Result<Vec<String>, String> {
+    let mut tokens = vec![];
+    let mut current = String::new();
+    let mut quoted = false;
+    for c in input.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            c if quoted => current.push(c),
+            '(' | ')' => {
+                if !current.is_empty() {
+                    tokens.push(std::mem::take(&mut current));
+                }
+                tokens.push(c.to_string());
+            }
+            c if c.is_whitespace() => {
+                if !current.is_empty() {
+                    tokens.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+    if quoted {
+        return Err("unterminated quote".to_string());
+    }
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+    Ok(tokens)
+}
+
+fn parse_bool(field: &str, value: &str) -> Result<bool, String> {
+    match value.to_lowercase().as_str() {
+        "true" | "yes" => Ok(true),
+        "false" | "no" => Ok(false),
+        _ => Err(format!("`{}` expects true or false", field)),
+    }
+}
+
+fn parse_time(field: &str, op: Op, value: &str, now: DateTime<Utc>) -> Result<(Op, DateTime<Utc>), String> {
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return Ok((op, date.and_time(chrono::NaiveTime::MIN).and_utc()));
+    }
+    let split = value.char_indices().last().map_or(0, |(index, _)| index);
+    let (amount, unit) = value.split_at(split);
+    let amount: i64 = amount
+        .parse()
+        .map_err(|_| format!("`{}` expects a date or an age like 7d", field))?;
+    let age = match unit {
+        "h" => Duration::hours(amount),
+        "d" => Duration::days(amount),
+        "w" => Duration::weeks(amount),
+        _ => return Err(format!("unknown unit `{}` for `{}`, use h, d or w", unit, field)),
+    };
+    Ok((op.flipped(), now - age))
+}
+
+fn parse_condition(token: &str, now: DateTime<Utc>) -> Result<Condition, String> {
+    let (index, symbol, op) = OPERATORS
+        .iter()
+        .filter_map(|(symbol, op)| token.find(symbol).map(|index| (index, *symbol, *op)))
+        .min_by_key(|(index, symbol, _)| (*index, std::cmp::Reverse(symbol.len())))
+        .ok_or_else(|| format!("expected a condition like field:value, found `{}`", token))?;
+    let field = token[..index].to_lowercase();
+    let value = &token[index + symbol.len()..];
+    if value.is_empty() {
+        return Err(format!("missing value for `{}`", field));
+    }
+    let equality_only = |condition: Condition| {
+        if op.is_equality() {
+            Ok(condition)
+        } else {
+            Err(format!("`{}` can only be compared with : or !=", field))
+        }
+    };
+
+    match field.as_str() {
+        "priority" => {
+            let rank = match value.to_lowercase().as_str() {
+                "low" => 1,
+                "medium" => 2,
+                "high" => 3,
+                _ => return Err("`priority` expects low, medium or high".to_string()),
+            };
+            Ok(Condition::Priority(op, rank))
+        }
+        "completed" | "done" => equality_only(Condition::Completed(op, parse_bool(&field, value)?)),
+        "blocked" => equality_only(Condition::Blocked(op, parse_bool(&field, value)?)),
+        "status" => equality_only(Condition::Status(op, value.to_string())),
+        "project" => equality_only(Condition::Project(op, value.to_string())),
+        "tag" => equality_only(Condition::Tag(op, value.trim_start_matches('#').to_string())),
+        "title" => equality_only(Condition::Title(op, value.to_string())),
+        "estimate" => value
+            .parse()
+            .map(|minutes| Condition::Estimate(op, minutes))
+            .map_err(|_| "`estimate` expects a number of minutes".to_string()),
+        "created" => parse_time(&field, op, value, now).map(|(op, at)| Condition::Created(op, at)),
+        "updated" => parse_time(&field, op, value, now).map(|(op, at)| Condition::Updated(op, at)),
+        "completed_at" => parse_time(&field, op, value, now).map(|(op, at)| Condition::CompletedAt(op, at)),
+        _ => {
+            // Custom field keys keep their case.
+            let key = Some(&token[..index])
+                .filter(|name| field.starts_with("cf.") && name.len() > 3)
+                .map(|name| &name[3..])
+                .ok_or_else(|| format!("unknown field `{}`", field))?;
+            let value = FieldValue {
+                text: value.to_string(),
+                number: value.parse().ok(),
+                checked: parse_bool(&field, value).ok(),
+            };
+            Ok(Condition::CustomField(key.to_string(), op, value))
+        }
+    }
+}
+
+struct Parser<'a> {
+    tokens: &'a [String],
+    pos: usize,
+    now: DateTime<Utc>,
+}
+
+impl Parser<'_> {
+    fn peek_keyword(&self, keyword: &str) -> bool {
+        self.tokens.get(self.pos).is_some_and(|t| t.eq_ignore_ascii_case(keyword))
+    }
+
+    fn or(&mut self) -> Result<Expr, String> {
+        let mut left = self.and()?;
+        while self.peek_keyword("OR") {
+            self.pos += 1;
+            left = Expr::Or(Box::new(left), Box::new(self.and()?));
+        }
+        Ok(left)
+    }
+
+    fn and(&mut self) -> Result<Expr, String> {
+        let mut left = self.not()?;
+        loop {
+            if self.peek_keyword("AND") {
+                self.pos += 1;
+            } else if self.pos >= self.tokens.len() || self.peek_keyword("OR") || self.peek_keyword(")") {
+                return Ok(left);
+            }
+            left = Expr::And(Box::new(left), Box::new(self.not()?));
+        }
+    }
+
+    fn not(&mut self) -> Result<Expr, String> {
+        let token = self.tokens.get(self.pos).ok_or("unexpected end of expression")?;
+        self.pos += 1;
+        if token.eq_ignore_ascii_case("NOT") {
+            return Ok(Expr::Not(Box::new(self.not()?)));
+        }
+        if token == "(" {
+            let inner = self.or()?;
+            if !self.peek_keyword(")") {
+                return Err("missing `)`".to_string());
+            }
+            self.pos += 1;
+            return Ok(inner);
+        }
+        parse_condition(token, self.now).map(Expr::Term)
+    }
+}
+
+/// Parses an expression, resolving relative ages against `now`.
+pub fn parse(input: &str, now: DateTime<Utc>) -> Result<Expr, String> {
+    let tokens = tokenize(input)?;
+    if tokens.is_empty() {
+        return Err("empty expression".to_string());
+    }
+    let mut parser = Parser { tokens: &tokens, pos: 0, now };
+    let expr = parser.or()?;
+    match tokens.get(parser.pos) {
+        Some(token) => Err(format!("unexpected `{}`", token)),
+        None => Ok(expr),
+    }
+}
+
+/// Appends `expr` as a parenthesized condition on `todos`. The query must select `FROM todos`
+/// without an alias.
+pub fn push_sql(expr: &Expr, builder: &mut QueryBuilder<'_, Sqlite>) {
+    builder.push("(");
+    match expr {
+        Expr::And(left, right) | Expr::Or(left, right) => {
+            push_sql(left, builder);
+            builder.push(if matches!(expr, Expr::And(..)) { " AND " } else { " OR " });
+            push_sql(right, builder);
+        }
+        Expr::Not(inner) => {
+            builder.push("NOT ");
+            push_sql(inner, builder);
+        }
+        Expr::Term(condition) => push_condition(condition, builder),
+    }
+    builder.push(")");
+}
+
+fn push_condition(condition: &Condition, builder: &mut QueryBuilder<'_, Sqlite>) {
+    match condition {
+        Condition::Priority(op, rank) => {
+            builder
+                .push(format!(
+                    "(CASE priority WHEN 'low' THEN 1 WHEN 'medium' THEN 2 WHEN 'high' THEN 3 END) {} ",
+                    op.sql()
+                ))
+                .push_bind(*rank);
+        }
+        Condition::Completed(op, completed) => {
+            builder.push(format!("completed {} ", op.sql())).push_bind(*completed);
+        }
+        Condition::Blocked(op, blocked) => {
+            builder
+                .push("EXISTS (SELECT 1 FROM todo_dependencies d JOIN todos b ON b.id = d.blocker_id WHERE d.blocked_id = todos.id AND b.completed = FALSE) ")
+                .push(op.sql())
+                .push(" ")
+                .push_bind(*blocked);
+        }
+        Condition::Status(op, name) => {
+            builder
+                .push(if *op == Op::Eq { "" } else { "NOT " })
+                .push("EXISTS (SELECT 1 FROM statuses s WHERE s.id = todos.status_id AND (lower(s.name) = lower(")
+                .push_bind(name.clone())
+                .push(") OR s.category = lower(")
+                .push_bind(name.clone())
+                .push(")))");
+        }
+        Condition::Project(op, name) => {
+            builder
+                .push(if *op == Op::Eq { "" } else { "NOT " })
+                .push("EXISTS (SELECT 1 FROM projects p WHERE p.id = todos.project_id AND p.name = ")
+                .push_bind(name.clone())
+                .push(")");
+        }
+        Condition::Tag(op, tag) => {
+            builder
+                .push(if *op == Op::Eq { "" } else { "NOT " })
+                .push("EXISTS (SELECT 1 FROM todo_tags t WHERE t.todo_id = todos.id AND t.tag = ")
+                .push_bind(tag.clone())
+                .push(" COLLATE NOCASE)");
+        }
+        Condition::Title(op, text) => {
+            let pattern = format!("%{}%", text.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_"));
+            builder
+                .push(if *op == Op::Eq { "title LIKE " } else { "title NOT LIKE " })
+                .push_bind(pattern)
+                .push(" ESCAPE '\\'");
+        }
+        Condition::Estimate(op, minutes) => {
+            builder.push(format!("estimated_minutes {} ", op.sql())).push_bind(*minutes);
+        }
+        Condition::Created(op, at) | Condition::Updated(op, at) | Condition::CompletedAt(op, at) => {
+            let column = match condition {
+                Condition::Created(..) => "created_at",
+                Condition::Updated(..) => "updated_at",
+                _ => "completed_at",
+            };
+            builder
+                .push(format!("julianday({}) {} julianday(", column, op.sql()))
+                .push_bind(*at)
+                .push(")");
+        }
+        Condition::CustomField(key, op, value) => {
+            builder
+                .push("EXISTS (SELECT 1 FROM todo_field_values v JOIN custom_fields f ON f.id = v.field_id WHERE v.todo_id = todos.id AND f.key = ")
+                .push_bind(key.clone())
+                .push(format!(" AND json_extract(v.value, '$') {} CASE f.field_type WHEN 'number' THEN ", op.sql()))
+                .push_bind(value.number)
+                .push(" WHEN 'checkbox' THEN ")
+                .push_bind(value.checked.map(i64::from))
+                .push(" ELSE ")
+                .push_bind(value.text.clone())
+                .push(" END)");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        db::test_db,
+        handlers::todos::{insert_todo, DUMMY_USER_ID},
+        models::{CreateTodo, FieldValues, Priority},
+    };
+    use chrono::TimeZone;
+
+    fn now() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 5, 15, 10, 0, 0).unwrap()
+    }
+
+    fn field_value(text: &str, number: Option<f64>, checked: Option<bool>) -> FieldValue {
+        FieldValue { text: text.to_string(), number, checked }
+    }
+
+    fn term(condition: Condition) -> Expr {
+        Expr::Term(condition)
+    }
+
+    fn and(left: Expr, right: Expr) -> Expr {
+        Expr::And(Box::new(left), Box::new(right))
+    }
+
+    fn or(left: Expr, right: Expr) -> Expr {
+        Expr::Or(Box::new(left), Box::new(right))
+    }
+
+    fn not(inner: Expr) -> Expr {
+        Expr::Not(Box::new(inner))
+    }
+
+    #[test]
+    fn conditions() {
+        let midnight = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+        let cases = [
+            ("priority:high", Condition::Priority(Op::Eq, 3)),
+            ("Priority>=medium", Condition::Priority(Op::Ge, 2)),
+            ("completed:true", Condition::Completed(Op::Eq, true)),
+            ("done!=no", Condition::Completed(Op::Ne, false)),
+            ("blocked=yes", Condition::Blocked(Op::Eq, true)),
+            ("status:Doing", Condition::Status(Op::Eq, "Doing".to_string())),
+            ("project:Home", Condition::Project(Op::Eq, "Home".to_string())),
+            ("tag!=#errand", Condition::Tag(Op::Ne, "errand".to_string())),
+            ("title:\"buy milk\"", Condition::Title(Op::Eq, "buy milk".to_string())),
+            ("estimate<=30", Condition::Estimate(Op::Le, 30)),
+            ("created>=2024-01-01", Condition::Created(Op::Ge, midnight)),
+            // Ages flip the comparison: younger than 7 days means created after 7 days ago.
+            ("created<7d", Condition::Created(Op::Gt, now() - Duration::days(7))),
+            ("updated>2w", Condition::Updated(Op::Lt, now() - Duration::weeks(2))),
+            ("completed_at<=12h", Condition::CompletedAt(Op::Ge, now() - Duration::hours(12))),
+            ("cf.Points>3", Condition::CustomField("Points".to_string(), Op::Gt, field_value("3", Some(3.0), None))),
+            ("cf.urgent:yes", Condition::CustomField("urgent".to_string(), Op::Eq, field_value("yes", None, Some(true)))),
+            ("cf.owner!=alice", Condition::CustomField("owner".to_string(), Op::Ne, field_value("alice", None, None))),
+        ];
+        for (input, condition) in cases {
+            assert_eq!(parse(input, now()), Ok(term(condition)), "{}", input);
+        }
+    }
+
+    #[test]
+    fn boolean_structure() {
+        let high = || term(Condition::Priority(Op::Eq, 3));
+        let low = || term(Condition::Priority(Op::Eq, 1));
+        let done = || term(Condition::Completed(Op::Eq, true));
+        let cases = [
+            ("priority:high done:true", and(high(), done())),
+            ("priority:high AND done:true", and(high(), done())),
+            ("priority:low OR priority:high and done:true", or(low(), and(high(), done()))),
+            ("(priority:low OR priority:high) done:true", and(or(low(), high()), done())),
+            ("NOT done:true", not(done())),
+            ("not not done:true", not(not(done()))),
+            ("NOT (priority:low OR done:true)", not(or(low(), done()))),
+        ];
+        for (input, expr) in cases {
+            assert_eq!(parse(input, now()), Ok(expr), "{}", input);
+        }
+    }
+
+    #[test]
+    fn malformed_queries() {
+        let cases = [
+            ("", "empty expression"),
+            ("   ", "empty expression"),
+            ("priority:(", "missing value for `priority`"),
+            ("title:\"buy milk", "unterminated quote"),
+            ("(priority:high", "missing `)`"),
+            ("priority:high)", "unexpected `)`"),
+            ("priority:high AND", "unexpected end of expression"),
+            ("NOT", "unexpected end of expression"),
+            ("milk", "expected a condition like field:value, found `milk`"),
+            ("colour:red", "unknown field `colour`"),
+            ("cf.:red", "unknown field `cf.`"),
+            ("priority:urgent", "`priority` expects low, medium or high"),
+            ("completed:maybe", "`completed` expects true or false"),
+            ("completed<true", "`completed` can only be compared with : or !="),
+            ("title>milk", "`title` can only be compared with : or !="),
+            ("estimate:lots", "`estimate` expects a number of minutes"),
+            ("created:soon", "`created` expects a date or an age like 7d"),
+            ("created<7y", "unknown unit `y` for `created`, use h, d or w"),
+        ];
+        for (input, error) in cases {
+            assert_eq!(parse(input, now()), Err(error.to_string()), "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn compiles_to_bound_sql() {
+        let sql = |input: &str| {
+            let mut builder = QueryBuilder::<Sqlite>::new("");
+            push_sql(&parse(input, now()).unwrap(), &mut builder);
+            builder.into_sql()
+        };
+        assert_eq!(
+            sql("NOT done:true OR title:milk"),
+            "((NOT (completed = ?)) OR (title LIKE ? ESCAPE '\\'))"
+        );
+        assert_eq!(
+            sql("priority>=medium estimate<60"),
+            "(((CASE priority WHEN 'low' THEN 1 WHEN 'medium' THEN 2 WHEN 'high' THEN 3 END) >= ?) AND (estimated_minutes < ?))"
+        );
+        assert_eq!(sql("created<7d"), "(julianday(created_at) > julianday(?))");
+        assert_eq!(
+            sql("status!=done"),
+            "(NOT EXISTS (SELECT 1 FROM statuses s WHERE s.id = todos.status_id AND (lower(s.name) = lower(?) OR s.category = lower(?))))"
+        );
+    }
+
+    #[tokio::test]
+    async fn custom_fields_compare_by_declared_type() {
+        let db = test_db().await;
+        let mut conn = db.acquire().await.unwrap();
+        for (key, field_type) in [("ticket", "text"), ("points", "number"), ("urgent", "checkbox")] {
+            sqlx::query("INSERT INTO custom_fields (id, user_id, key, name, field_type) VALUES ($1, $2, $3, $3, $4)")
+                .bind(uuid::Uuid::new_v4().to_string())
+                .bind(DUMMY_USER_ID)
+                .bind(key)
+                .bind(field_type)
+                .execute(&mut *conn)
+                .await
+                .unwrap();
+        }
+        let todos = [
+            ("text digits", serde_json::json!({"ticket": "123"})),
+            ("text words", serde_json::json!({"ticket": "abc"})),
+            ("number", serde_json::json!({"points": 123})),
+            ("checked", serde_json::json!({"urgent": true})),
+        ];
+        for (title, fields) in todos {
+            let todo = CreateTodo {
+                title: title.to_string(),
+                description: None,
+                priority: Priority::Medium,
+                status_id: None,
+                estimated_minutes: None,
+                custom_fields: serde_json::from_value::<FieldValues>(fields).unwrap(),
+                project_id: None,
+                tags: vec![],
+            };
+            insert_todo(&mut conn, &todo).await.unwrap();
+        }
+
+        let cases = [
+            ("cf.ticket:123", vec!["text digits"]),
+            ("cf.ticket:abc", vec!["text words"]),
+            ("cf.ticket!=123", vec!["text words"]),
+            ("cf.points:123", vec!["number"]),
+            ("cf.points>=100", vec!["number"]),
+            ("cf.points:abc", vec![]),
+            ("cf.urgent:true", vec!["checked"]),
+            ("cf.urgent:123", vec![]),
+        ];
+        for (input, expected) in cases {
+            let mut builder = QueryBuilder::<Sqlite>::new("SELECT title FROM todos WHERE ");
+            push_sql(&parse(input, now()).unwrap(), &mut builder);
+            builder.push(" ORDER BY position");
+            let titles: Vec<String> = builder.build_query_scalar().fetch_all(&mut *conn).await.unwrap();
+            assert_eq!(titles, expected, "{}", input);
+        }
+    }
+}
@@ -0,0 +1,252 @@
+//! Just enough XML for WebDAV (RFC 4918) and CalDAV (RFC 4791): reading the small request
+//! bodies of PROPFIND and REPORT, and writing `multistatus` responses.
+//!
+//! Elements are identified by namespace URI and local name, so `<d:prop xmlns:d="DAV:">` and
+//! `<prop xmlns="DAV:">` are the same element. DTDs and processing instructions are skipped.
+
+pub const DAV: &str = "DAV:";
+pub const CALDAV: &str = "urn:ietf:params:xml:ns:caldav";
+pub const CALENDAR_SERVER: &str = "http://calendarserver.org/ns/";
+
+// Prefixes used in responses, declared once on the `multistatus` root.
+const PREFIXES: &[(&str, &str)] = &[("D", DAV), ("C", CALDAV), ("CS", CALENDAR_SERVER)];
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Element {
+    pub namespace: String,
+    pub name: String,
+    pub attributes: Vec<(String, String)>,
+    pub children: Vec<Element>,
+    pub text: String,
+}
+
+impl Element {
+    pub fn is(&self, namespace: &str, name: &str) -> bool {
+        self.namespace == namespace && self.name == name
+    }
+
+    pub fn child(&self, namespace: &str, name: &str) -> Option<&Element> {
+        self.children.iter().find(|child| child.is(namespace, name))
+    }
+
+    /// Every element below this one with the given name, in document order.
+    pub fn descendants<'a>(&'a self, namespace: &str, name: &str) -> Vec<&'a Element> {
+        let mut found = vec![];
+        for child in &self.children {
+            if child.is(namespace, name) {
+                found.push(child);
+            }
+            found.extend(child.descendants(namespace, name));
+        }
+        found
+    }
+
+    pub fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes.iter().find(|(key, _)| key == name).map(|(_, value)| value.as_str())
+    }
+}
+
+// An open element with the namespace declarations in scope for it.
+struct Open {
+    element: Element,
+    tag: String,
+    scope: Vec<(String, String)>,
+}
+
+fn resolve(scope: &[(String, String)], prefix: &str) -> Option<String> {
+    scope.iter().rev().find(|(p, _)| p == prefix).map(|(_, uri)| uri.clone())
+}
+
+fn split_name(name: &str) -> (&str, &str) {
+    name.split_once(':').unwrap_or(("", name))
+}
+
+fn parse_attributes(mut rest: &str) -> Result<Vec<(String, String)>, String> {
+    let mut attributes = vec![];
+    loop {
+        rest = rest.trim_start();
+        if rest.is_empty() {
+            return Ok(attributes);
+        }
+        let eq = rest.find('=').ok_or_else(|| format!("malformed attribute `{}`", rest))?;
+        let key = rest[..eq].trim().to_string();
+        let value = rest[eq + 1..].trim_start();
+        let quote = value.chars().next().filter(|c| *c == '"' || *c == '\'');
+        let quote = quote.ok_or_else(|| format!("unquoted value for attribute `{}`", key))?;
+        let end = value[1..].find(quote).ok_or_else(|| format!("unterminated value for attribute `{}`", key))?;
+        attributes.push((key, unescape(&value[1..end + 1])));
+        rest = &value[end + 2..];
+    }
+}
+
+/// Parses a document and returns its root element.
+pub fn parse(xml: &str) -> Result<Element, String> {
+    let mut stack: Vec<Open> = vec![];
+    let mut root = None;
+    let mut rest = xml.trim_start_matches('\u{feff}');
+
+    while let Some(start) = rest.find('<') {
+        if let Some(open) = stack.last_mut() {
+            open.element.text.push_str(&unescape(&rest[..start]));
+        }
+        rest = &rest[start..];
+
+        let skip_to = |rest: &str, end: &str| -> Result<usize, String> {
+            rest.find(end).map(|i| i + end.len()).ok_or_else(|| format!("unterminated `{}`", &rest[..rest.len().min(9)]))
+        };
+        if rest.starts_with("<?") {
+            rest = &rest[skip_to(rest, "?>")?..];
+        } else if rest.starts_with("<!--") {
+            rest = &rest[skip_to(rest, "-->")?..];
+        } else if let Some(cdata) = rest.strip_prefix("<![CDATA[") {
+            let end = cdata.find("]]>").ok_or("unterminated CDATA section")?;
+            if let Some(open) = stack.last_mut() {
+                open.element.text.push_str(&cdata[..end]);
+            }
+            rest = &cdata[end + 3..];
+        } else if rest.starts_with("<!") {
+            rest = &rest[skip_to(rest, ">")?..];
+        } else if let Some(end_tag) = rest.strip_prefix("</") {
+            let close = end_tag.find('>').ok_or("unterminated end tag")?;
+            let name = end_tag[..close].trim();
+            let open = stack.pop().ok_or_else(|| format!("unexpected `</{}>`", name))?;
+            if name != open.tag {
+                return Err(format!("`</{}>` does not close `<{}>`", name, open.tag));
+            }
+            match stack.last_mut() {
+                Some(parent) => parent.element.children.push(open.element),
+                None => root = Some(open.element),
+            }
+            rest = &end_tag[close + 1..];
+        } else {
+            // A `>` inside a quoted attribute value does not end the tag.
+            let mut quote = None;
+            let close = rest
+                .char_indices()
+                .find(|&(_, c)| match quote {
+                    Some(q) if c == q => {
+                        quote = None;
+                        false
+                    }
+                    Some(_) => false,
+                    None if c == '"' || c == '\'' => {
+                        quote = Some(c);
+                        false
+                    }
+                    None => c == '>',
+                })
+                .map(|(i, _)| i)
+                .ok_or("unterminated start tag")?;
+            let tag = &rest[1..close];
+            let (tag, empty) = match tag.strip_suffix('/') {
+                Some(tag) => (tag, true),
+                None => (tag, false),
+            };
+            let name_end = tag.find(char::is_whitespace).unwrap_or(tag.len());
+            let name = &tag[..name_end];
+            let (prefix, local) = split_name(name);
+
+            let mut scope = stack.last().map(|open| open.scope.clone()).unwrap_or_default();
+            let mut attributes = vec![];
+            for (key, value) in parse_attributes(&tag[name_end..])? {
+                match key.as_str() {
+                    "xmlns" => scope.push((String::new(), value)),
+                    _ if key.starts_with("xmlns:") => scope.push((key["xmlns:".len()..].to_string(), value)),
+                    _ => attributes.push((key, value)),
+                }
+            }
+            let namespace = resolve(&scope, prefix)
+                .or_else(|| prefix.is_empty().then(String::new))
+                .ok_or_else(|| format!("undeclared namespace prefix `{}`", prefix))?;
+            let open = Open {
+                element: Element { namespace, name: local.to_string(), attributes, ..Element::default() },
+                tag: name.to_string(),
+                scope,
+            };
+            rest = &rest[close + 1..];
+
+            if empty {
+                match stack.last_mut() {
+                    Some(parent) => parent.element.children.push(open.element),
+                    None => root = Some(open.element),
+                }
+            } else {
+                stack.push(open);
+            }
+        }
+        if root.is_some() && stack.is_empty() {
+            break;
+        }
+    }
+
+    match (root, stack.pop()) {
+        (Some(root), None) => Ok(root),
+        (_, Some(open)) => Err(format!("`<{}>` is never closed", open.tag)),
+        (None, None) => Err("the document has no root element".to_string()),
+    }
+}
+
+pub fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+pub fn unescape(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+/// A property name as it is written in a response, e.g. `D:getetag`. Names outside the known
+/// namespaces get their own default namespace declaration.
+pub fn qualified(namespace: &str, name: &str) -> (String, String) {
+    match PREFIXES.iter().find(|(_, uri)| *uri == namespace) {
+        Some((prefix, _)) => (format!("{}:{}", prefix, name), String::new()),
+        None => (name.to_string(), format!(" xmlns=\"{}\"", escape(namespace))),
+    }
+}
+
+/// `<name/>` or `<name>value</name>`, where `value` is already-escaped XML.
+pub fn property(namespace: &str, name: &str, value: &str) -> String {
+    let (tag, declaration) = qualified(namespace, name);
+    if value.is_empty() {
+        format!("<{}{}/>", tag, declaration)
+    } else {
+        format!("<{}{}>{}</{}>", tag, declaration, value, tag)
+    }
+}
+
+/// The `response` for one resource: the properties that were found, then the names of those
+/// that were not, which get a `404 Not Found` propstat of their own.
+pub fn response(href: &str, found: &[String], missing: &[(String, String)]) -> String {
+    let mut out = format!("<D:response><D:href>{}</D:href>", escape(href));
+    if !found.is_empty() {
+        out.push_str("<D:propstat><D:prop>");
+        found.iter().for_each(|prop| out.push_str(prop));
+        out.push_str("</D:prop><D:status>HTTP/1.1 200 OK</D:status></D:propstat>");
+    }
+    if !missing.is_empty() {
+        out.push_str("<D:propstat><D:prop>");
+        missing.iter().for_each(|(namespace, name)| out.push_str(&property(namespace, name, "")));
+        out.push_str("</D:prop><D:status>HTTP/1.1 404 Not Found</D:status></D:propstat>");
+    }
+    out.push_str("</D:response>");
+    out
+}
+
+/// A `response` for an href that does not exist, as in a multiget for a deleted resource.
+pub fn status_response(href: &str, status: &str) -> String {
+    format!("<D:response><D:href>{}</D:href><D:status>HTTP/1.1 {}</D:status></D:response>", escape(href), status)
+}
+
+pub fn multistatus(responses: &[String]) -> String {
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<D:multistatus");
+    for (prefix, uri) in PREFIXES {
+        out.push_str(&format!(" xmlns:{}=\"{}\"", prefix, uri));
+    }
+    out.push('>');
+    responses.iter().for_each(|response| out.push_str(response));
+    out.push_str("</D:multistatus>");
+    out
+}